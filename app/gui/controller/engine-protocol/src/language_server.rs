@@ -214,6 +214,39 @@ trait API {
 
 
 
+// ====================
+// === Reconnecting ===
+// ====================
+
+/// The policy of re-establishing the lost connection with the Language Server.
+///
+/// After reconnecting, the protocol connection is initialized again with the same client id, and
+/// the requests only reading the server state are repeated.
+pub fn reconnect_policy() -> json_rpc::transport::reconnecting::Policy {
+    use json_rpc::RemoteMethodCall;
+    let idempotent_methods = [
+        FileExistsInput::NAME,
+        FileListInput::NAME,
+        ReadFileInput::NAME,
+        FileInfoInput::NAME,
+        FileChecksumInput::NAME,
+        GetSuggestionsDatabaseInput::NAME,
+        GetSuggestionsDatabaseVersionInput::NAME,
+        CompletionInput::NAME,
+        GetComponentGroups::NAME,
+        VcsListInput::NAME,
+        VcsStatusInput::NAME,
+    ];
+    let session_init_methods = [InitProtocolInput::NAME];
+    json_rpc::transport::reconnecting::Policy {
+        idempotent_methods: idempotent_methods.into_iter().map(Into::into).collect(),
+        session_init_methods: session_init_methods.into_iter().map(Into::into).collect(),
+        ..default()
+    }
+}
+
+
+
 // ==============
 // === Errors ===
 // ==============
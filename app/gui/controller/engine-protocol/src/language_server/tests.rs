@@ -604,3 +604,37 @@ fn test_execution_context() {
         (),
     );
 }
+
+#[test]
+fn test_in_process_server() {
+    use json_rpc::expect_call;
+
+    let main = Path { root_id: Uuid::nil(), segments: vec!["Main.enso".into()] };
+    let implementation = MockClient::default();
+    let exists = response::FileExists { exists: true };
+    let read = response::Read { contents: "main = 2 + 2".into() };
+    expect_call!(implementation.file_exists(path=main.clone()) => Ok(exists));
+    expect_call!(implementation.read_file(path=main.clone()) => Ok(read.clone()));
+    implementation.require_all_calls();
+
+    let (client, mut server) = server::connect_in_process(Rc::new(implementation));
+    let mut executor = futures::executor::LocalPool::new();
+    executor.spawner().spawn_local(client.runner()).unwrap();
+    executor.spawner().spawn_local(server.runner()).unwrap();
+    let mut events = Box::pin(client.events());
+
+    let mut exists_future = client.file_exists(&main);
+    let mut read_future = client.read_file(&main);
+    executor.run_until_stalled();
+    assert_eq!(exists_future.expect_ok(), exists);
+    assert_eq!(read_future.expect_ok(), read);
+
+    let event = FileEvent { path: main, kind: FileEventKind::Added };
+    server.notify("file/event", &event).unwrap();
+    executor.run_until_stalled();
+    if let Event::Notification(n) = events.expect_next() {
+        assert_eq!(n, Notification::FileEvent(event));
+    } else {
+        panic!("expected notification event");
+    }
+}
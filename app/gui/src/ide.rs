@@ -15,6 +15,7 @@ use std::time::Duration;
 // ==============
 
 pub mod initializer;
+pub mod keymap;
 
 pub use initializer::Initializer;
 
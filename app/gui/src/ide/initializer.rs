@@ -4,6 +4,7 @@ use crate::prelude::*;
 
 use crate::config;
 use crate::config::ProjectToOpen;
+use crate::ide::keymap;
 use crate::ide::Ide;
 use crate::transport::web::WebSocket;
 use crate::FailedIde;
@@ -75,6 +76,8 @@ impl Initializer {
         ensogl_app.display.set_pixel_read_period(pixel_read_period as usize);
         register_views(&ensogl_app);
         let view = ensogl_app.new_view::<ide_view::root::View>();
+        let app = ensogl_app.clone_ref();
+        executor::global::spawn(async move { keymap::load(&app).await });
 
         // IDE was opened with `project` argument, we should skip the Welcome Screen.
         // We are doing it early, because Controllers initialization
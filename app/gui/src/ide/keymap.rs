@@ -0,0 +1,51 @@
+//! Loading of the user-defined keymap, which rebinds the default shortcuts of the application. See
+//! [`ensogl::application::shortcut::keymap`] to learn more.
+
+use crate::prelude::*;
+
+use ensogl::application::shortcut::keymap;
+use ensogl::application::Application;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+
+
+// ============
+// === Load ===
+// ============
+
+/// Load the keymap from the URL given in the `startup.keymap` option, and apply it over the default
+/// shortcuts of the application, replacing the previously loaded keymap. Does nothing if the option
+/// is not set. The problems found in the keymap are logged.
+pub async fn load(app: &Application) {
+    let url = &enso_config::ARGS.groups.startup.options.keymap.value;
+    if url.is_empty() {
+        return;
+    }
+    let source = match fetch(url).await {
+        Ok(source) => source,
+        Err(error) => {
+            error!("Failed to load the keymap from {url}: {error:?}");
+            return;
+        }
+    };
+    match app.shortcuts.load_keymap(&source, keymap::Format::from_path(url)) {
+        Ok(issues) =>
+            for issue in issues {
+                warn!("Keymap {url}: {issue}");
+            },
+        Err(error) => error!("Invalid keymap {url}: {error}"),
+    }
+}
+
+async fn fetch(url: &str) -> Result<String, JsValue> {
+    let window = web_sys::window().ok_or("No window object.")?;
+    let response = JsFuture::from(window.fetch_with_str(url)).await?;
+    let response: web_sys::Response = response.dyn_into()?;
+    if !response.ok() {
+        return Err(response.status_text().into());
+    }
+    let text = JsFuture::from(response.text()?).await?;
+    text.as_string().ok_or_else(|| "The response is not a text.".into())
+}
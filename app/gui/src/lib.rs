@@ -167,6 +167,7 @@ thread_local! {
 #[profile(Objective)]
 #[allow(dead_code)]
 pub fn main() {
+    set_log_filter();
     // Logging of build information.
     #[cfg(debug_assertions)]
     let debug_mode = true;
@@ -190,6 +191,15 @@ pub fn main() {
     });
 }
 
+/// Set the filter of the displayed log messages, provided in the `debug.logFilter` option.
+fn set_log_filter() {
+    let log_filter = &enso_config::ARGS.groups.debug.options.log_filter.value;
+    match log_filter.parse() {
+        Ok(filter) => logging::set_filter(filter),
+        Err(error) => error!("{error}"),
+    }
+}
+
 
 
 // ================
@@ -217,3 +227,22 @@ pub fn drop() {
         }
     })
 }
+
+
+
+// ==================
+// === IDE Keymap ===
+// ==================
+
+/// Load the user-defined keymap again, replacing the applied one. See [`ide::keymap::load`].
+#[wasm_bindgen]
+pub fn reload_keymap() {
+    let app = IDE.with(|ide| match &*ide.borrow() {
+        Some(Ok(ide)) => Some(ide.ensogl_app.clone_ref()),
+        _ => None,
+    });
+    match app {
+        Some(app) => executor::global::spawn(async move { ide::keymap::load(&app).await }),
+        None => error!("Cannot reload the keymap before the IDE is initialized."),
+    }
+}
@@ -26,6 +26,7 @@ use engine_protocol::project_manager::MissingComponentAction;
 use engine_protocol::project_manager::ProjectName;
 use flo_stream::Subscriber;
 use json_rpc::error::RpcError;
+use json_rpc::transport::ReconnectingTransport;
 use parser::Parser;
 
 
@@ -379,9 +380,20 @@ impl Project {
     ) -> FallibleResult<model::Project> {
         let wrap = UnsupportedEngineVersion::error_wrapper(&properties);
         let client_id = Uuid::new_v4();
-        let json_ws = WebSocket::new_opened(&language_server_rpc).await?;
+        let connect_json = move || {
+            let url = language_server_rpc.clone();
+            async move {
+                let mut json_ws = WebSocket::new_opened(&url).await?;
+                json_ws.disable_auto_reconnect();
+                FallibleResult::Ok(json_ws)
+            }
+        };
+        let json_ws = connect_json().await?;
+        let policy = language_server::reconnect_policy();
+        let json_transport = ReconnectingTransport::new(json_ws, policy, connect_json);
+        crate::executor::global::spawn(json_transport.runner());
         let binary_ws = WebSocket::new_opened(&language_server_bin).await?;
-        let client_json = language_server::Client::new(json_ws);
+        let client_json = language_server::Client::new(json_transport);
         let client_binary = binary::Client::new(binary_ws);
         crate::executor::global::spawn(client_json.runner());
         crate::executor::global::spawn(client_binary.runner());
@@ -326,6 +326,16 @@ impl WebSocket {
         }
     }
 
+    /// Do not re-establish the connection when it is lost, reporting the closed connection
+    /// instead. Used when reconnecting is handled by the owner of the socket, like
+    /// [`json_rpc::transport::ReconnectingTransport`].
+    pub fn disable_auto_reconnect(&mut self) {
+        self.with_borrow_mut_model(|model| {
+            model.auto_reconnect = false;
+            model.on_close_internal.clear_callback();
+        });
+    }
+
     /// Checks the current state of the connection.
     pub fn state(&self) -> State {
         State::query_ws(&self.model.borrow().socket)
@@ -11,7 +11,8 @@ ide_ci::define_env_var! {
     /// https://github.com/enso-org/design/blob/main/epics/profiling/implementation.md)
     ENSO_MAX_PROFILING_LEVEL, ProfilingLevel;
 
-    /// Set the level of logging detail that will be enabled at compile-time.
+    /// Set the most detailed level of logging that will be compiled in. The levels actually
+    /// displayed are selected at runtime.
     ENSO_MAX_LOG_LEVEL, LogLevel;
     /// Set the level of logging detail that will be displayed initially-open in hierarchical views,
     /// such as the Web Console.
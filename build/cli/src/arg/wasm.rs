@@ -65,8 +65,10 @@ pub struct BuildInput {
     #[clap(long, arg_enum, enso_env())]
     pub profiling_level: Option<ProfilingLevel>,
 
-    /// Compiles Enso with given log level.
-    #[clap(long, arg_enum, enso_env(), default_value_t)]
+    /// Compiles Enso with given maximum log level. The `debug.logFilter` application option can
+    /// narrow the displayed levels down at runtime. Debug logs are compiled in by default, so they
+    /// can be enabled in shipped builds, while only warnings and errors are displayed by default.
+    #[clap(long, arg_enum, enso_env(), default_value_t = LogLevel::Debug)]
     pub wasm_log_level: LogLevel,
 
     /// Compiles Enso with given uncollapsed log level.
@@ -9,6 +9,8 @@ use crate::nfa::Nfa;
 use crate::state;
 use crate::symbol::Symbol;
 
+use std::collections::BTreeSet;
+
 
 
 // =============
@@ -165,6 +167,199 @@ impl From<&Nfa> for Dfa {
 
 
 
+// ====================
+// === Minimization ===
+// ====================
+
+impl Dfa {
+    /// Return the minimal DFA equivalent to this one, computed with
+    /// [Hopcroft's algorithm](https://en.wikipedia.org/wiki/DFA_minimization#Hopcroft's_algorithm).
+    ///
+    /// Only states with the same [`Dfa::sources`] are merged, so the result preserves the sources
+    /// of every reachable state. Unreachable states, and states from which no state with non-empty
+    /// sources can be reached, are removed. The states are numbered in breadth-first order from
+    /// the start state, so equivalent automata over the same alphabet minimize to equal values.
+    pub fn minimize(&self) -> Dfa {
+        self.minimize_by(|sources| sources.to_vec())
+    }
+
+    /// Return the minimal DFA equivalent to this one, where two states are considered to have the
+    /// same output if `key` returns the same value for their sources. Merged states have the union
+    /// of the sources of the states they were built from.
+    ///
+    /// For example, when a DFA was created from an NFA in which each rule ends in a separate state,
+    /// a key returning the rule accepted in the given sources produces the smallest automaton
+    /// recognizing the same rules.
+    pub fn minimize_by<K: Eq + Hash>(&self, key: impl Fn(&[nfa::State]) -> K) -> Dfa {
+        let block_of = self.equivalence_classes(key);
+        let sink_block = block_of[self.sink()];
+        let start_block = block_of[Self::START_STATE.id()];
+        let mut new_state = HashMap::<usize, State>::new();
+        let mut representatives = vec![Self::START_STATE.id()];
+        new_state.insert(start_block, Self::START_STATE);
+        let mut links = Matrix::new(0, self.links.columns);
+        let mut i = 0;
+        while i < representatives.len() {
+            links.new_row();
+            let source = representatives[i];
+            for column in 0..self.links.columns {
+                let target = self.target(source, column);
+                let block = block_of[target];
+                if block == sink_block {
+                    continue;
+                }
+                links[(i, column)] = *new_state.entry(block).or_insert_with(|| {
+                    representatives.push(target);
+                    State::new(representatives.len() - 1)
+                });
+            }
+            i += 1;
+        }
+        let mut sources = vec![BTreeSet::new(); representatives.len()];
+        for (state, block) in block_of.iter().enumerate().take(self.links.rows) {
+            if let Some(new) = new_state.get(block) {
+                sources[new.id()].extend(self.sources[state].iter().copied());
+            }
+        }
+        let sources = sources.into_iter().map(|sources| sources.into_iter().collect()).collect();
+        let alphabet = self.alphabet.clone();
+        Dfa { alphabet, links, sources }
+    }
+
+    /// Partition the states into the classes of states with the same output for every input. The
+    /// result maps every state, including the implicit sink state (see [`Dfa::sink`]), to the
+    /// index of its class.
+    fn equivalence_classes<K: Eq + Hash>(&self, key: impl Fn(&[nfa::State]) -> K) -> Vec<usize> {
+        let columns = self.links.columns;
+        let count = self.sink() + 1;
+        let mut inverse = vec![vec![vec![]; count]; columns];
+        for state in 0..count {
+            for (column, sources) in inverse.iter_mut().enumerate() {
+                sources[self.target(state, column)].push(state);
+            }
+        }
+        let mut block_of = Vec::with_capacity(count);
+        let mut blocks: Vec<Vec<usize>> = vec![];
+        let mut block_by_key = HashMap::new();
+        for state in 0..count {
+            let key = key(self.sources.get(state).map_or(&[][..], |sources| &sources[..]));
+            let block = *block_by_key.entry(key).or_insert_with(|| {
+                blocks.push(vec![]);
+                blocks.len() - 1
+            });
+            block_of.push(block);
+            blocks[block].push(state);
+        }
+        let mut pending = vec![];
+        let mut is_pending = HashSet::new();
+        for block in 0..blocks.len() {
+            for column in 0..columns {
+                pending.push((block, column));
+                is_pending.insert((block, column));
+            }
+        }
+        while let Some((splitter, column)) = pending.pop() {
+            is_pending.remove(&(splitter, column));
+            let mut marked = BTreeMap::<usize, Vec<usize>>::new();
+            for &state in &blocks[splitter] {
+                for &source in &inverse[column][state] {
+                    marked.entry(block_of[source]).or_default().push(source);
+                }
+            }
+            for (block, marked) in marked {
+                if marked.len() == blocks[block].len() {
+                    continue;
+                }
+                let marked_set: HashSet<usize> = marked.iter().copied().collect();
+                blocks[block].retain(|state| !marked_set.contains(state));
+                let new_block = blocks.len();
+                for &state in &marked {
+                    block_of[state] = new_block;
+                }
+                blocks.push(marked);
+                for column in 0..columns {
+                    let splitter = if is_pending.contains(&(block, column))
+                        || blocks[new_block].len() <= blocks[block].len()
+                    {
+                        new_block
+                    } else {
+                        block
+                    };
+                    if is_pending.insert((splitter, column)) {
+                        pending.push((splitter, column));
+                    }
+                }
+            }
+        }
+        block_of
+    }
+
+    /// The index of the implicit sink state, which every invalid transition leads to.
+    fn sink(&self) -> usize {
+        self.links.rows
+    }
+
+    /// The target of the transition from the given state on the symbols of the given column,
+    /// treating invalid transitions as transitions to the sink state.
+    fn target(&self, state: usize, column: usize) -> usize {
+        match self.links.safe_index(state, column) {
+            Some(target) if !target.is_invalid() => target.id(),
+            _ => self.sink(),
+        }
+    }
+}
+
+
+
+// ===================
+// === Equivalence ===
+// ===================
+
+impl Dfa {
+    /// Check whether this DFA and `other` produce the same output for every input sequence. The
+    /// output of a state of this DFA is given by `key` applied to its sources, and the output of a
+    /// state of `other` by `other_key`. Invalid states have empty sources.
+    ///
+    /// The automata don't need to share an alphabet. The check explores the product of both
+    /// automata, so its complexity is linear in the product of their sizes.
+    pub fn is_equivalent_by<K: PartialEq>(
+        &self,
+        other: &Dfa,
+        key: impl Fn(&[nfa::State]) -> K,
+        other_key: impl Fn(&[nfa::State]) -> K,
+    ) -> bool {
+        let output = |dfa: &Dfa, key: &dyn Fn(&[nfa::State]) -> K, state: usize| {
+            key(dfa.sources.get(state).map_or(&[][..], |sources| &sources[..]))
+        };
+        let symbols: BTreeSet<&Symbol> =
+            self.alphabet.keys().chain(other.alphabet.keys()).collect();
+        let columns = symbols
+            .into_iter()
+            .map(|symbol| {
+                (self.alphabet.index_of_symbol(symbol), other.alphabet.index_of_symbol(symbol))
+            })
+            .collect_vec();
+        let start = (Self::START_STATE.id(), Self::START_STATE.id());
+        let mut visited = HashSet::new();
+        let mut pending = vec![start];
+        visited.insert(start);
+        while let Some((state, other_state)) = pending.pop() {
+            if output(self, &key, state) != output(other, &other_key, other_state) {
+                return false;
+            }
+            for &(column, other_column) in &columns {
+                let next = (self.target(state, column), other.target(other_state, other_column));
+                if visited.insert(next) {
+                    pending.push(next);
+                }
+            }
+        }
+        true
+    }
+}
+
+
+
 // =============
 // === Tests ===
 // =============
@@ -175,6 +370,7 @@ pub mod tests {
     use super::*;
     use crate::nfa;
     use crate::nfa::tests::NfaTest;
+    use crate::pattern::Pattern;
     use test::Bencher;
 
 
@@ -323,6 +519,54 @@ pub mod tests {
         assert_eq!(get_name(&nfa, &dfa, make_state(4)), Some(&String::from("rule_2")));
     }
 
+    fn accepting(pattern: &Pattern) -> (Dfa, impl Fn(&[nfa::State]) -> bool) {
+        let mut nfa = Nfa::new();
+        let end = nfa.new_pattern(nfa.start, pattern);
+        (Dfa::from(&nfa), move |sources: &[nfa::State]| sources.contains(&end))
+    }
+
+    #[test]
+    fn dfa_minimize_merges_equivalent_states() {
+        let pattern = (Pattern::char('a') | Pattern::char('b')).many();
+        let (dfa, accepts) = accepting(&pattern);
+        assert!(dfa.links.rows > 1);
+        let minimal = dfa.minimize_by(&accepts);
+        assert_eq!(minimal.links.rows, 1);
+        assert!(dfa.is_equivalent_by(&minimal, &accepts, &accepts));
+    }
+
+    #[test]
+    fn dfa_minimize_removes_dead_states() {
+        let pattern = Pattern::all_of("ab") | (Pattern::char('c') >> Pattern::never());
+        let (dfa, accepts) = accepting(&pattern);
+        let minimal = dfa.minimize_by(&accepts);
+        assert_eq!(minimal.links.rows, 3);
+        assert!(dfa.is_equivalent_by(&minimal, &accepts, &accepts));
+    }
+
+    #[test]
+    fn dfa_minimize_preserves_sources() {
+        for nfa in [nfa::tests::simple_rules(), nfa::tests::complex_rules()] {
+            let dfa = Dfa::from(&nfa.nfa);
+            let minimal = dfa.minimize();
+            assert!(minimal.links.rows <= dfa.links.rows);
+            let sources = |sources: &[nfa::State]| sources.to_vec();
+            assert!(dfa.is_equivalent_by(&minimal, sources, sources));
+            assert_eq!(minimal.minimize(), minimal);
+        }
+    }
+
+    #[test]
+    fn dfa_equivalence() {
+        let (dfa, accepts) = accepting(&Pattern::char('a').many1());
+        let (other, other_accepts) = accepting(&(Pattern::char('a') >> Pattern::char('a').many()));
+        assert!(dfa.is_equivalent_by(&other, &accepts, &other_accepts));
+        let (other, other_accepts) = accepting(&Pattern::char('a').many());
+        assert!(!dfa.is_equivalent_by(&other, &accepts, &other_accepts));
+        let (other, other_accepts) = accepting(&Pattern::range('a'..='b').many1());
+        assert!(!dfa.is_equivalent_by(&other, &accepts, &other_accepts));
+    }
+
     // === The Benchmarks ===
 
     #[bench]
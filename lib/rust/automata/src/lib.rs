@@ -27,6 +27,7 @@ pub mod data;
 pub mod dfa;
 pub mod nfa;
 pub mod pattern;
+pub mod regex;
 pub mod state;
 pub mod symbol;
 
@@ -2,6 +2,10 @@
 
 use crate::prelude::*;
 
+use crate::dfa::Dfa;
+use crate::nfa;
+use crate::nfa::Nfa;
+use crate::regex;
 use crate::symbol::Symbol;
 
 use core::iter;
@@ -193,6 +197,25 @@ impl Pattern {
     pub fn repeat_between(pat: &Pattern, min: usize, max: usize) -> Self {
         (min..max).fold(Self::never(), |p, n| p | Self::repeat(pat, n))
     }
+
+    /// Parse a conventional regular expression, like `[a-z_][a-z0-9_]*`. See the [`crate::regex`]
+    /// module for the supported syntax.
+    pub fn regex(regex: &str) -> regex::Result<Self> {
+        regex::parse(regex)
+    }
+
+    /// Check whether this pattern triggers on exactly the same sequences of symbols as `other`.
+    pub fn is_equivalent(&self, other: &Pattern) -> bool {
+        let to_dfa = |pattern: &Pattern| {
+            let mut nfa = Nfa::new();
+            let end = nfa.new_pattern(nfa.start, pattern);
+            (Dfa::from(&nfa), end)
+        };
+        let (dfa, end) = to_dfa(self);
+        let (other_dfa, other_end) = to_dfa(other);
+        let accepts = |end| move |sources: &[nfa::State]| sources.contains(&end);
+        dfa.is_equivalent_by(&other_dfa, accepts(end), accepts(other_end))
+    }
 }
 
 
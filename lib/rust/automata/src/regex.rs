@@ -0,0 +1,423 @@
+//! A parser of conventional regular expression strings into [`Pattern`]s.
+//!
+//! The supported syntax is:
+//! - Literal characters, and characters escaped with a backslash (`\.`, `\(`, `\\`, ...).
+//! - The escapes `\n`, `\r`, `\t`, `\0`, and `\u{...}` with a hexadecimal code point.
+//! - The character classes `\d`, `\w`, `\s`, and their negations `\D`, `\W`, `\S`.
+//! - Bracket expressions such as `[abc]`, `[a-z_]`, and negated bracket expressions like `[^0-9]`.
+//! - `.`, which matches any character but a newline.
+//! - Grouping with `(...)` or `(?:...)`. Groups don't capture, as patterns have no captures.
+//! - Alternation `a|b`.
+//! - The repetitions `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`. A repetition cannot be directly
+//!   repeated again, like `a+?` or `a**`; use a group instead, like `(?:a+)?`.
+//!
+//! As with [`Pattern::none_of`], negated classes never match [`Symbol::null`] and [`Symbol::eof`].
+//! Anchors, lookaround, backreferences and lazy repetitions are not supported.
+
+use crate::prelude::*;
+
+use crate::pattern::Pattern;
+use crate::symbol::Symbol;
+
+use std::iter::Peekable;
+use std::ops::RangeInclusive;
+use std::str::CharIndices;
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// An error encountered while parsing a regular expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// The byte offset in the regular expression at which the error was found.
+    pub offset:  usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The result of parsing a regular expression.
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+
+// =============
+// === Parse ===
+// =============
+
+/// Parse a regular expression into a [`Pattern`]. See the module documentation for the supported
+/// syntax.
+pub fn parse(regex: &str) -> Result<Pattern> {
+    let mut parser = Parser { chars: regex.char_indices().peekable(), len: regex.len() };
+    let pattern = parser.alternation()?;
+    match parser.chars.peek() {
+        None => Ok(pattern),
+        Some(&(offset, _)) => Err(Error { offset, message: "Unmatched `)`".into() }),
+    }
+}
+
+/// The code point ranges of the class given by a letter of an escape like `\d`, and whether the
+/// class is negated.
+fn escape_class(letter: char) -> Option<(Vec<RangeInclusive<u64>>, bool)> {
+    let ranges = |ranges: &[(char, char)]| {
+        ranges.iter().map(|&(start, end)| start as u64..=end as u64).collect_vec()
+    };
+    let digit = || ranges(&[('0', '9')]);
+    let word = || ranges(&[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]);
+    let space = || ranges(&[('\t', '\r'), (' ', ' ')]);
+    match letter {
+        'd' => Some((digit(), false)),
+        'D' => Some((digit(), true)),
+        'w' => Some((word(), false)),
+        'W' => Some((word(), true)),
+        's' => Some((space(), false)),
+        'S' => Some((space(), true)),
+        _ => None,
+    }
+}
+
+/// A pattern matching the characters in the given ranges, or, if `negated`, all other characters.
+fn class(mut ranges: Vec<RangeInclusive<u64>>, negated: bool) -> Pattern {
+    ranges.sort_by_key(|range| *range.start());
+    let mut merged: Vec<RangeInclusive<u64>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if *range.start() <= last.end().saturating_add(1) =>
+                *last = *last.start()..=*last.end().max(range.end()),
+            _ => merged.push(range),
+        }
+    }
+    if negated {
+        let mut gaps = vec![];
+        let mut start = Symbol::null().index + 1;
+        for range in merged {
+            if *range.start() > start {
+                gaps.push(start..=range.start() - 1);
+            }
+            start = start.max(range.end().saturating_add(1));
+        }
+        let end = Symbol::eof().index - 1;
+        if start <= end {
+            gaps.push(start..=end);
+        }
+        merged = gaps;
+    }
+    let mut patterns = merged
+        .into_iter()
+        .map(|range| Pattern::symbols(Symbol::from(*range.start())..=Symbol::from(*range.end())))
+        .collect_vec();
+    match patterns.len() {
+        0 => Pattern::never(),
+        1 => patterns.pop().unwrap(),
+        _ => Pattern::Or(patterns),
+    }
+}
+
+
+// === Parser ===
+
+/// A recursive-descent parser of regular expressions.
+#[derive(Debug)]
+struct Parser<'s> {
+    chars: Peekable<CharIndices<'s>>,
+    len:   usize,
+}
+
+impl<'s> Parser<'s> {
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |&(offset, _)| offset)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, char)| char)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matches = self.peek() == Some(expected);
+        if matches {
+            self.chars.next();
+        }
+        matches
+    }
+
+    fn error<T>(&mut self, message: impl Into<String>) -> Result<T> {
+        let offset = self.offset();
+        Err(Error { offset, message: message.into() })
+    }
+
+    fn next(&mut self, context: &str) -> Result<char> {
+        match self.chars.next() {
+            Some((_, char)) => Ok(char),
+            None => self.error(format!("Unexpected end of {context}")),
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Pattern> {
+        let mut branches = vec![self.sequence()?];
+        while self.eat('|') {
+            branches.push(self.sequence()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Pattern::Or(branches) })
+    }
+
+    fn sequence(&mut self) -> Result<Pattern> {
+        let mut items = vec![];
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            items.push(self.repetition()?);
+        }
+        Ok(match items.len() {
+            0 => Pattern::always(),
+            1 => items.pop().unwrap(),
+            _ => Pattern::Seq(items),
+        })
+    }
+
+    fn repetition(&mut self) -> Result<Pattern> {
+        let pattern = self.atom()?;
+        let offset = self.offset();
+        let quantifier = match self.peek() {
+            Some(quantifier @ ('*' | '+' | '?' | '{')) => quantifier,
+            _ => return Ok(pattern),
+        };
+        self.chars.next();
+        let pattern = match quantifier {
+            '*' => pattern.many(),
+            '+' => pattern.many1(),
+            '?' => pattern.opt(),
+            _ => {
+                let min = self.number()?;
+                let max = if self.eat(',') {
+                    if self.peek() == Some('}') {
+                        None
+                    } else {
+                        Some(self.number()?)
+                    }
+                } else {
+                    Some(min)
+                };
+                if !self.eat('}') {
+                    return self.error("Expected `}`");
+                }
+                match max {
+                    None => Pattern::repeat(&pattern, min) >> pattern.many(),
+                    Some(max) if max < min => {
+                        let message = "Repetition bounds are out of order".into();
+                        return Err(Error { offset, message });
+                    }
+                    Some(max) => Pattern::repeat_between(&pattern, min, max + 1),
+                }
+            }
+        };
+        // Otherwise, lazy repetitions like `a+?` would be silently parsed as `(a+)?`.
+        match self.peek() {
+            Some('*' | '+' | '?' | '{') =>
+                self.error("A quantifier cannot directly follow another quantifier"),
+            _ => Ok(pattern),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        let mut digits = String::new();
+        while let Some(digit) = self.peek().filter(char::is_ascii_digit) {
+            digits.push(digit);
+            self.chars.next();
+        }
+        match digits.parse() {
+            Ok(number) => Ok(number),
+            Err(_) => self.error("Expected a repetition count"),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Pattern> {
+        let offset = self.offset();
+        match self.next("regular expression")? {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return self.error("Only non-capturing groups `(?:...)` are supported");
+                }
+                let pattern = self.alternation()?;
+                if !self.eat(')') {
+                    return self.error("Expected `)`");
+                }
+                Ok(pattern)
+            }
+            '[' => self.bracket(),
+            '.' => Ok(Pattern::none_of("\n")),
+            '\\' => match self.escape()? {
+                Escape::Char(char) => Ok(Pattern::char(char)),
+                Escape::Class(ranges, negated) => Ok(class(ranges, negated)),
+            },
+            '^' | '$' => Err(Error { offset, message: "Anchors are not supported".into() }),
+            '*' | '+' | '?' | '{' => Err(Error { offset, message: "Nothing to repeat".into() }),
+            char => Ok(Pattern::char(char)),
+        }
+    }
+
+    /// Parse a bracket expression, after the opening `[`.
+    fn bracket(&mut self) -> Result<Pattern> {
+        let negated = self.eat('^');
+        let mut ranges = vec![];
+        let mut first = true;
+        loop {
+            let start = match self.next("bracket expression")? {
+                ']' if !first => break,
+                '\\' => match self.escape()? {
+                    Escape::Char(char) => char,
+                    Escape::Class(class, false) => {
+                        ranges.extend(class);
+                        first = false;
+                        continue;
+                    }
+                    Escape::Class(_, true) =>
+                        return self.error("Negated classes are not supported in brackets"),
+                },
+                char => char,
+            };
+            first = false;
+            let mut end = start;
+            let mut lookahead = self.chars.clone();
+            if lookahead.next().map(|(_, char)| char) == Some('-')
+                && !matches!(lookahead.next(), None | Some((_, ']')))
+            {
+                self.chars.next();
+                end = match self.next("bracket expression")? {
+                    '\\' => match self.escape()? {
+                        Escape::Char(char) => char,
+                        Escape::Class(..) => return self.error("Invalid range end"),
+                    },
+                    char => char,
+                };
+                if end < start {
+                    return self.error("Range bounds are out of order");
+                }
+            }
+            ranges.push(start as u64..=end as u64);
+        }
+        Ok(class(ranges, negated))
+    }
+
+    /// Parse an escape sequence, after the backslash.
+    fn escape(&mut self) -> Result<Escape> {
+        let char = self.next("escape sequence")?;
+        if let Some((ranges, negated)) = escape_class(char) {
+            return Ok(Escape::Class(ranges, negated));
+        }
+        Ok(Escape::Char(match char {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                if !self.eat('{') {
+                    return self.error("Expected `{`");
+                }
+                let mut digits = String::new();
+                while let Some(digit) = self.peek().filter(char::is_ascii_hexdigit) {
+                    digits.push(digit);
+                    self.chars.next();
+                }
+                if !self.eat('}') {
+                    return self.error("Expected `}`");
+                }
+                let code = u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32);
+                match code {
+                    Some(char) => char,
+                    None => return self.error("Invalid code point"),
+                }
+            }
+            char if char.is_ascii_alphanumeric() =>
+                return self.error(format!("Unknown escape sequence `\\{char}`")),
+            char => char,
+        }))
+    }
+}
+
+/// A parsed escape sequence.
+#[derive(Debug)]
+enum Escape {
+    Char(char),
+    Class(Vec<RangeInclusive<u64>>, bool),
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_equivalent(regex: &str, expected: Pattern) {
+        let pattern = parse(regex).unwrap();
+        assert!(pattern.is_equivalent(&expected), "{regex:?} parsed as {pattern:?}");
+    }
+
+    fn assert_error(regex: &str, offset: usize) {
+        assert_eq!(parse(regex).map_err(|e| e.offset), Err(offset), "{regex:?}");
+    }
+
+    #[test]
+    fn literals_and_escapes() {
+        assert_equivalent("abc", Pattern::all_of("abc"));
+        assert_equivalent("a\\.\\n", Pattern::all_of("a.\n"));
+        assert_equivalent("\\u{1F600}", Pattern::char('😀'));
+        assert_equivalent("", Pattern::always());
+    }
+
+    #[test]
+    fn alternation_and_groups() {
+        assert_equivalent("a|bc", Pattern::char('a') | Pattern::all_of("bc"));
+        assert_equivalent("a(b|c)", Pattern::all_of("ab") | Pattern::all_of("ac"));
+        assert_equivalent("(?:ab)*", Pattern::all_of("ab").many());
+        assert_equivalent("a|", Pattern::char('a').opt());
+    }
+
+    #[test]
+    fn repetitions() {
+        let a = Pattern::char('a');
+        assert_equivalent("a*", a.many());
+        assert_equivalent("a+", a.many1());
+        assert_equivalent("a?", a.opt());
+        assert_equivalent("a{3}", Pattern::all_of("aaa"));
+        assert_equivalent("a{1,3}", a.clone() | Pattern::all_of("aa") | Pattern::all_of("aaa"));
+        assert_equivalent("a{2,}", Pattern::all_of("aa") >> a.many());
+    }
+
+    #[test]
+    fn classes() {
+        assert_equivalent("[a-c]", Pattern::range('a'..='c'));
+        assert_equivalent("[]a]", Pattern::any_of("]a"));
+        assert_equivalent("[a-]", Pattern::any_of("a-"));
+        assert_equivalent("[cab]", Pattern::range('a'..='c'));
+        assert_equivalent("\\d", Pattern::range('0'..='9'));
+        assert_equivalent("[\\d_]", Pattern::range('0'..='9') | Pattern::char('_'));
+        assert_equivalent("[^be]", Pattern::none_of("be"));
+        assert_equivalent(".", Pattern::not('\n'));
+    }
+
+    #[test]
+    fn errors() {
+        assert_error("*", 0);
+        assert_error("a)", 1);
+        assert_error("(a", 2);
+        assert_error("[a", 2);
+        assert_error("[z-a]", 4);
+        assert_error("a{3,1}", 1);
+        assert_error("^a", 0);
+        assert_error("a+?", 2);
+        assert_error("a{2}*", 4);
+        assert_error("\\q", 2);
+    }
+}
@@ -5,7 +5,6 @@ use crate::index::*;
 use crate::prelude::*;
 use enso_text::unit::*;
 
-use crate::buffer::formatting::Formatting;
 use crate::buffer::rope::formatted::FormattedRope;
 use crate::buffer::selection::Selection;
 
@@ -20,6 +19,7 @@ use ensogl_text_font_family::NonVariableFaceHeader;
 // ==============
 
 pub mod formatting;
+pub mod history;
 pub mod index;
 pub mod movement;
 pub mod rope;
@@ -33,6 +33,7 @@ pub mod traits {
 }
 
 pub use formatting::*;
+pub use history::History;
 pub use movement::*;
 
 pub use enso_text::index::*;
@@ -43,27 +44,6 @@ pub use enso_text::RopeCell;
 
 
 
-// ===============
-// === History ===
-// ===============
-
-/// Modifications history. Contains data used by undo / redo mechanism.
-#[derive(Debug, Clone, CloneRef, Default)]
-pub struct History {
-    data: Rc<RefCell<HistoryData>>,
-}
-
-/// Internal representation of `History`.
-#[derive(Debug, Clone, Default)]
-pub struct HistoryData {
-    undo_stack: Vec<(Rope, Formatting, selection::Group)>,
-    #[allow(dead_code)]
-    /// Not yet implemented.
-    redo_stack: Vec<(Rope, Formatting, selection::Group)>,
-}
-
-
-
 // ====================
 // === Modification ===
 // ====================
@@ -231,6 +211,7 @@ impl Buffer {
 
             sel_on_remove_all <- input.remove_all_cursors.map(|_| default());
             sel_on_undo <= input.undo.map(f_!(m.undo()));
+            sel_on_redo <= input.redo.map(f_!(m.redo()));
 
             eval input.set_property (((range,value)) m.set_property(range,*value));
             eval input.mod_property (((range,value)) m.mod_property(range,*value));
@@ -238,6 +219,7 @@ impl Buffer {
 
             output.source.selection_edit_mode <+ any_mod;
             output.source.selection_non_edit_mode <+ sel_on_undo;
+            output.source.selection_non_edit_mode <+ sel_on_redo;
             output.source.selection_non_edit_mode <+ sel_on_move;
             output.source.selection_non_edit_mode <+ sel_on_mod;
             output.source.selection_non_edit_mode <+ sel_on_clear;
@@ -258,6 +240,7 @@ impl Buffer {
 
             eval output.source.selection_edit_mode ((t) m.set_selection(&t.selection_group));
             eval output.source.selection_non_edit_mode ((t) m.set_selection(t));
+            eval_ output.source.selection_non_edit_mode (m.history.seal());
 
             // === Buffer Area Management ===
 
@@ -582,7 +565,7 @@ impl BufferModel {
     /// applying modification, what is useful when handling delete operations.
     fn modify_selections<I>(&self, mut iter: I, transform: Option<Transform>) -> Modification
     where I: Iterator<Item = Rope> {
-        self.commit_history();
+        let initial_selection = self.selections();
         let mut modification = Modification::default();
         for rel_byte_selection in self.byte_selections() {
             let text = iter.next().unwrap_or_default();
@@ -590,6 +573,7 @@ impl BufferModel {
             let selection = Selection::<Location>::from_in_context_snapped(self, byte_selection);
             modification.merge(self.modify_selection(selection, text, transform));
         }
+        self.history.commit(&self.rope, initial_selection);
         modification
     }
 
@@ -616,6 +600,7 @@ impl BufferModel {
             Selection::<ViewLocation>::from_in_context_snapped(self, byte_selection);
         let line_selection = line_selection.map_shape(|s| s.normalized());
         let range = byte_selection.range();
+        self.history.record(&self.rope, range, &text);
         self.rope.replace(range, &text);
 
         let new_byte_cursor_pos = range.start + text_byte_size;
@@ -649,6 +634,7 @@ impl BufferModel {
         if let Some(property) = property {
             for range in ranges {
                 let range = self.crop_byte_range(range);
+                self.history.record_formatting(&self.rope, range);
                 self.formatting.set_property(range, property)
             }
         }
@@ -658,6 +644,7 @@ impl BufferModel {
         if let Some(property) = property {
             for range in ranges {
                 let range = self.crop_byte_range(range);
+                self.history.record_formatting(&self.rope, range);
                 self.formatting.mod_property(range, property)
             }
         }
@@ -727,20 +714,12 @@ impl BufferModel {
 // === Undo / Redo ===
 
 impl BufferModel {
-    fn commit_history(&self) {
-        let text = self.rope.text();
-        let style = self.rope.style();
-        let selection = self.selection.borrow().clone();
-        self.history.data.borrow_mut().undo_stack.push((text, style, selection));
+    fn undo(&self) -> Option<selection::Group> {
+        self.history.undo(&self.rope, self.selections())
     }
 
-    fn undo(&self) -> Option<selection::Group> {
-        let item = self.history.data.borrow_mut().undo_stack.pop();
-        item.map(|(text, style, selection)| {
-            self.rope.set_text(text);
-            self.rope.set_style(style);
-            selection
-        })
+    fn redo(&self) -> Option<selection::Group> {
+        self.history.redo(&self.rope, self.selections())
     }
 }
 
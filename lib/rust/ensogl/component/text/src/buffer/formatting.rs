@@ -234,6 +234,12 @@ macro_rules! define_formatting {
                 $(self.$field.replace_resize(range,len,None);)*
             }
 
+            /// Replace the provided `range` with the given formatting. The formatting should span
+            /// over exactly the same number of bytes as the text inserted in place of the range.
+            pub fn replace(&mut self, range:Range<Byte>, formatting:Formatting) {
+                $(self.$field.spans.replace(range,formatting.$field.spans);)*
+            }
+
             /// Return all span ranges of default values for the given property.
             pub fn span_ranges_of_default_values(&self, tag:PropertyTag) -> Vec<Range<Byte>> {
                 match tag {
@@ -588,6 +594,12 @@ impl FormattingCell {
         self.cell.borrow_mut().set_resize_with_default(range, len)
     }
 
+    /// Replace the provided `range` with the given formatting. See [`Formatting::replace`] to
+    /// learn more.
+    pub fn replace(&self, range: Range<Byte>, formatting: Formatting) {
+        self.cell.borrow_mut().replace(range, formatting)
+    }
+
     /// Set the property for the given range.
     pub fn set_property(&self, range: Range<Byte>, property: Property) {
         self.cell.borrow_mut().set_property(range, property)
@@ -0,0 +1,593 @@
+//! Modifications history, used by the undo / redo mechanism. Instead of snapshots of the whole
+//! buffer, the history keeps an operation log of inverse deltas – the replacements reverting the
+//! performed modifications.
+
+use crate::prelude::*;
+use enso_text::index::*;
+use enso_text::unit::*;
+
+use crate::buffer::formatting::Formatting;
+use crate::buffer::rope::formatted::FormattedRope;
+use crate::buffer::selection;
+
+use enso_text::Range;
+use enso_text::Rope;
+use std::collections::VecDeque;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The default memory budget of the history, in bytes.
+pub const DEFAULT_MEMORY_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Approximated memory footprint of a single delta, excluding the text it contains.
+const DELTA_COST: usize = 64;
+
+
+
+// =============
+// === Delta ===
+// =============
+
+/// Replacement of a text range with a formatted text.
+#[derive(Clone, Debug)]
+pub struct Delta {
+    /// The range to be replaced.
+    pub range:      Range<Byte>,
+    /// The text to be put in place of the range.
+    pub text:       Rope,
+    /// The formatting of the text. It spans over the whole text.
+    pub formatting: Formatting,
+}
+
+impl Delta {
+    /// Apply the delta to the rope. Returns the delta reverting this operation.
+    pub fn apply(self, rope: &FormattedRope) -> Delta {
+        let range = rope.crop_byte_range(self.range);
+        let inverse = Self::reverting(rope, range, &self.text);
+        rope.replace_formatted(range, self.text, self.formatting);
+        inverse
+    }
+
+    /// The delta reverting the replacement of `range` with `text`. Must be called before the
+    /// replacement is applied to the rope.
+    fn reverting(rope: &FormattedRope, range: Range<Byte>, text: &Rope) -> Delta {
+        let text_range = Range::new(range.start, range.start + text.len());
+        Delta {
+            range:      text_range,
+            text:       rope.text.sub(range),
+            formatting: rope.sub_style(range),
+        }
+    }
+
+    /// Move the delta reverting a formatting change over the insertions reverted by the
+    /// `insertions` deltas, which must be sorted and expressed in the coordinates after all the
+    /// insertions. The text inserted inside the delta range is added to the delta with its current
+    /// formatting, so the moved delta can be applied before the insertions are reverted.
+    fn shifted_over(self, insertions: &[Delta], rope: &FormattedRope) -> Delta {
+        let spliced = FormattedRope::new();
+        spliced.replace_formatted(.., self.text, self.formatting);
+        let mut range = self.range;
+        let mut shift = Bytes(0);
+        for insertion in insertions {
+            let size = insertion.inserted_bytes();
+            let position = insertion.range.start.value - shift.value;
+            if position <= self.range.start.value {
+                range.start += size;
+                range.end += size;
+            } else if position < self.range.end.value {
+                let offset = Byte(insertion.range.start.value - range.start.value);
+                let text = rope.text.sub(insertion.range);
+                spliced.replace_formatted(offset..offset, text, rope.sub_style(insertion.range));
+                range.end += size;
+            }
+            shift += size;
+        }
+        Delta { range, text: spliced.text(), formatting: spliced.style() }
+    }
+
+    fn inserted_bytes(&self) -> Bytes {
+        Bytes(self.range.end.value - self.range.start.value)
+    }
+
+    fn cost(&self) -> usize {
+        self.text.len().value + DELTA_COST
+    }
+}
+
+
+
+// ============
+// === Step ===
+// ============
+
+/// A single undo step. Contains the deltas reverting a single modification of the buffer, which
+/// may span multiple selections.
+#[derive(Clone, Debug)]
+struct Step {
+    /// The deltas in the order they were recorded. They need to be applied in the reversed order.
+    deltas:          Vec<Delta>,
+    /// The deltas reverting the formatting changes done since the previous step, in the order
+    /// they were recorded. They are applied in the reversed order after the `deltas`.
+    formatting:      Vec<Delta>,
+    /// The deltas reverting the formatting changes done between the coalesced typing steps,
+    /// moved over the later insertions. They are applied in the reversed order before the
+    /// `deltas`.
+    late_formatting: Vec<Delta>,
+    /// The selection to be restored after applying this step.
+    selection:       selection::Group,
+    /// Whether the step reverts typing only, that is, inserting single-line text in place of
+    /// cursors. Consecutive typing steps are coalesced.
+    typing:          bool,
+    cost:            usize,
+}
+
+impl Step {
+    fn new(
+        deltas: Vec<Delta>,
+        formatting: Vec<Delta>,
+        selection: selection::Group,
+        typing: bool,
+    ) -> Self {
+        let late_formatting = default();
+        let mut step = Self { deltas, formatting, late_formatting, selection, typing, cost: 0 };
+        step.update_cost();
+        step
+    }
+
+    fn update_cost(&mut self) {
+        let deltas = self.deltas.iter().chain(&self.formatting).chain(&self.late_formatting);
+        self.cost = deltas.map(Delta::cost).sum();
+    }
+
+    /// Apply the step to the rope. Returns the step reverting this operation.
+    fn apply(self, rope: &FormattedRope, selection: selection::Group) -> Step {
+        let late_formatting = self.late_formatting.into_iter().rev();
+        let deltas = self.deltas.into_iter().rev();
+        let formatting = self.formatting.into_iter().rev();
+        let all_deltas = late_formatting.chain(deltas).chain(formatting);
+        let inverse = all_deltas.map(|delta| delta.apply(rope)).collect();
+        Step::new(inverse, default(), selection, false)
+    }
+
+    /// Merge the next typing step into this one, if it continues typing at the same cursors.
+    ///
+    /// The deltas are recorded in the order of the sorted selections, so every insertion shifts
+    /// the ranges of the subsequent ones by its length. The formatting changes done before the
+    /// next step are moved over its insertions, to be reverted before all the typing is.
+    fn coalesce(&mut self, next: Step, rope: &FormattedRope) -> Result<(), Step> {
+        let same_cursors = self.deltas.len() == next.deltas.len();
+        let can_coalesce = self.typing && next.typing && same_cursors && {
+            let mut shift = Bytes(0);
+            self.deltas.iter().zip(&next.deltas).all(|(prev, next)| {
+                let continues = next.range.start == prev.range.end + shift;
+                shift += next.inserted_bytes();
+                continues
+            })
+        };
+        if !can_coalesce {
+            return Err(next);
+        }
+        let mut shift = Bytes(0);
+        for (prev, next) in self.deltas.iter_mut().zip(&next.deltas) {
+            prev.range.start += shift;
+            shift += next.inserted_bytes();
+            prev.range.end += shift;
+        }
+        let late_formatting = mem::take(&mut self.late_formatting).into_iter();
+        let formatting = late_formatting.chain(next.formatting);
+        let shifted = formatting.map(|delta| delta.shifted_over(&next.deltas, rope));
+        self.late_formatting = shifted.collect();
+        self.update_cost();
+        Ok(())
+    }
+}
+
+
+
+// ===============
+// === History ===
+// ===============
+
+/// Modifications history. Contains data used by undo / redo mechanism.
+///
+/// All replacements done during a single buffer modification are recorded with [`History::record`]
+/// and grouped into a single undo step by [`History::commit`]. Consecutive typing is coalesced
+/// into a single step until the history is sealed with [`History::seal`]. The oldest steps are
+/// dropped once the history exceeds its memory budget.
+///
+/// Formatting changes, recorded with [`History::record_formatting`], do not form steps on their
+/// own, as they are mostly done programmatically, for example by animations. They are folded into
+/// the step of the next modification instead, and undone together with it.
+#[derive(Debug, Clone, CloneRef, Default)]
+pub struct History {
+    data: Rc<RefCell<HistoryData>>,
+}
+
+/// Internal representation of `History`.
+#[derive(Debug, Clone)]
+pub struct HistoryData {
+    undo_stack:         VecDeque<Step>,
+    redo_stack:         VecDeque<Step>,
+    pending:            Vec<Delta>,
+    pending_formatting: Vec<Delta>,
+    pending_typing:     bool,
+    sealed:             bool,
+    memory_budget:      usize,
+    memory_usage:       usize,
+}
+
+impl Default for HistoryData {
+    fn default() -> Self {
+        Self {
+            undo_stack:         default(),
+            redo_stack:         default(),
+            pending:            default(),
+            pending_formatting: default(),
+            pending_typing:     true,
+            sealed:             false,
+            memory_budget:      DEFAULT_MEMORY_BUDGET,
+            memory_usage:       0,
+        }
+    }
+}
+
+impl History {
+    /// Constructor.
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Constructor of a history with the provided memory budget. See [`Self::set_memory_budget`].
+    pub fn with_memory_budget(memory_budget: usize) -> Self {
+        let history = Self::new();
+        history.set_memory_budget(memory_budget);
+        history
+    }
+
+    /// Set the approximated number of bytes the history may use. The oldest steps are dropped
+    /// when the budget is exceeded.
+    pub fn set_memory_budget(&self, memory_budget: usize) {
+        let mut data = self.data.borrow_mut();
+        data.memory_budget = memory_budget;
+        data.enforce_memory_budget();
+    }
+
+    /// The approximated number of bytes used by the history.
+    pub fn memory_usage(&self) -> usize {
+        self.data.borrow().memory_usage
+    }
+
+    /// Check whether there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.data.borrow().undo_stack.is_empty()
+    }
+
+    /// Check whether there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.data.borrow().redo_stack.is_empty()
+    }
+
+    /// Prevent the next modification from being coalesced with the last undo step. Should be
+    /// called when the cursors are moved without modifying the text.
+    pub fn seal(&self) {
+        self.data.borrow_mut().sealed = true;
+    }
+
+    /// Remove all the steps from the history.
+    pub fn clear(&self) {
+        let memory_budget = self.data.borrow().memory_budget;
+        *self.data.borrow_mut() = HistoryData { memory_budget, ..default() };
+    }
+
+    /// Record the replacement of the `range` with the `text`. Must be called before the
+    /// replacement is applied to the rope. All the recorded replacements will form a single undo
+    /// step once [`Self::commit`] is called.
+    pub fn record(&self, rope: &FormattedRope, range: Range<Byte>, text: &Rope) {
+        let range = rope.crop_byte_range(range);
+        if range.is_empty() && text.is_empty() {
+            return;
+        }
+        let typing = range.is_empty() && text.last_line_index() == Line(0);
+        let delta = Delta::reverting(rope, range, text);
+        let mut data = self.data.borrow_mut();
+        data.pending.push(delta);
+        data.pending_typing &= typing;
+    }
+
+    /// Record the formatting change of the `range`. Must be called before the formatting is
+    /// modified. The change is recorded as a replacement of the range with its own text, which
+    /// restores the current formatting once reverted. It will be a part of the next committed
+    /// step, so it neither clears the redo steps nor prevents coalescing. Changes of a range
+    /// already recorded since the last commit are skipped, as reverting the earlier record
+    /// restores the older formatting anyway.
+    pub fn record_formatting(&self, rope: &FormattedRope, range: Range<Byte>) {
+        let range = rope.crop_byte_range(range);
+        let mut data = self.data.borrow_mut();
+        let recorded = data
+            .pending_formatting
+            .iter()
+            .any(|delta| delta.range.start <= range.start && range.end <= delta.range.end);
+        if !range.is_empty() && !recorded {
+            let delta = Delta::reverting(rope, range, &rope.text.sub(range));
+            data.pending_formatting.push(delta);
+        }
+    }
+
+    /// Create a new undo step from the replacements recorded since the last commit. The
+    /// `selection` is the selection from before the modification, which will be restored on
+    /// undo, and the `rope` is the already modified one. Clears the redo steps if any replacement
+    /// was recorded. The recorded formatting changes become a part of the step.
+    pub fn commit(&self, rope: &FormattedRope, selection: selection::Group) {
+        let mut data = self.data.borrow_mut();
+        let data = &mut *data;
+        let deltas = mem::take(&mut data.pending);
+        let typing = mem::replace(&mut data.pending_typing, true);
+        if !deltas.is_empty() {
+            let redo_cost: usize = data.redo_stack.drain(..).map(|step| step.cost).sum();
+            data.memory_usage -= redo_cost;
+            let formatting = mem::take(&mut data.pending_formatting);
+            let step = Step::new(deltas, formatting, selection, typing);
+            let coalesced = match data.undo_stack.back_mut() {
+                Some(last) if !data.sealed => {
+                    let last_cost = last.cost;
+                    last.coalesce(step, rope).map(|()| last.cost - last_cost)
+                }
+                _ => Err(step),
+            };
+            match coalesced {
+                Ok(added_cost) => data.memory_usage += added_cost,
+                Err(step) => {
+                    data.memory_usage += step.cost;
+                    data.undo_stack.push_back(step);
+                }
+            }
+            data.sealed = false;
+            data.enforce_memory_budget();
+        }
+    }
+
+    /// Revert the last undo step. The `selection` is the current selection, which will be restored
+    /// on redo. Returns the selection to be set after the undo.
+    pub fn undo(
+        &self,
+        rope: &FormattedRope,
+        selection: selection::Group,
+    ) -> Option<selection::Group> {
+        self.data.borrow_mut().revert(rope, selection, false)
+    }
+
+    /// Revert the last undone step. The `selection` is the current selection, which will be
+    /// restored on undo. Returns the selection to be set after the redo.
+    pub fn redo(
+        &self,
+        rope: &FormattedRope,
+        selection: selection::Group,
+    ) -> Option<selection::Group> {
+        self.data.borrow_mut().revert(rope, selection, true)
+    }
+}
+
+impl HistoryData {
+    fn revert(
+        &mut self,
+        rope: &FormattedRope,
+        selection: selection::Group,
+        redo: bool,
+    ) -> Option<selection::Group> {
+        let (source, target) = if redo {
+            (&mut self.redo_stack, &mut self.undo_stack)
+        } else {
+            (&mut self.undo_stack, &mut self.redo_stack)
+        };
+        let step = source.pop_back()?;
+        // The recorded formatting changes refer to the text before reverting, so they cannot be
+        // undone anymore.
+        self.pending_formatting.clear();
+        let restored_selection = step.selection.clone();
+        self.memory_usage -= step.cost;
+        let reverting_step = step.apply(rope, selection);
+        self.memory_usage += reverting_step.cost;
+        target.push_back(reverting_step);
+        self.sealed = true;
+        self.enforce_memory_budget();
+        Some(restored_selection)
+    }
+
+    /// Drop the oldest undo steps, and then the farthest redo steps, until the history fits in
+    /// the memory budget.
+    fn enforce_memory_budget(&mut self) {
+        while self.memory_usage > self.memory_budget {
+            let dropped = self.undo_stack.pop_front().or_else(|| self.redo_stack.pop_front());
+            match dropped {
+                Some(step) => self.memory_usage -= step.cost,
+                None => break,
+            }
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::buffer::formatting::color;
+    use crate::buffer::formatting::PropertyTag;
+
+    fn rope(text: &str) -> FormattedRope {
+        let rope = FormattedRope::new();
+        rope.replace(.., text);
+        rope
+    }
+
+    fn modify(history: &History, rope: &FormattedRope, edits: &[(usize, usize, &str)]) {
+        for &(start, end, text) in edits {
+            let range = Range::new(Byte(start), Byte(end));
+            let text = Rope::from(text);
+            history.record(rope, range, &text);
+            rope.replace(range, text);
+        }
+        history.commit(rope, default());
+    }
+
+    fn make_red(history: &History, rope: &FormattedRope, start: usize, end: usize) {
+        let range = Range::new(Byte(start), Byte(end));
+        history.record_formatting(rope, range);
+        rope.formatting.set_property(range, color::Rgba::new(1.0, 0.0, 0.0, 1.0).into());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let history = History::new();
+        let rope = rope("hello world");
+        modify(&history, &rope, &[(0, 5, "goodbye")]);
+        modify(&history, &rope, &[(8, 13, "moon\n")]);
+        assert_eq!(rope.text().to_string(), "goodbye moon\n");
+        assert!(history.undo(&rope, default()).is_some());
+        assert_eq!(rope.text().to_string(), "goodbye world");
+        assert!(history.undo(&rope, default()).is_some());
+        assert_eq!(rope.text().to_string(), "hello world");
+        assert!(history.undo(&rope, default()).is_none());
+        assert!(history.redo(&rope, default()).is_some());
+        assert_eq!(rope.text().to_string(), "goodbye world");
+        modify(&history, &rope, &[(0, 0, "> ")]);
+        assert!(!history.can_redo());
+        assert!(history.undo(&rope, default()).is_some());
+        assert_eq!(rope.text().to_string(), "goodbye world");
+    }
+
+    #[test]
+    fn coalescing_typing() {
+        let history = History::new();
+        let rope = rope("");
+        modify(&history, &rope, &[(0, 0, "a")]);
+        modify(&history, &rope, &[(1, 1, "b")]);
+        modify(&history, &rope, &[(2, 2, "c")]);
+        modify(&history, &rope, &[(3, 3, "\n")]);
+        modify(&history, &rope, &[(4, 4, "d")]);
+        history.seal();
+        modify(&history, &rope, &[(5, 5, "e")]);
+        modify(&history, &rope, &[(0, 0, "f")]);
+        let mut steps = vec![];
+        while history.can_undo() {
+            history.undo(&rope, default());
+            steps.push(rope.text().to_string());
+        }
+        assert_eq!(steps, vec!["abc\nde", "abc\nd", "abc\n", "abc", ""]);
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), "abc");
+    }
+
+    #[test]
+    fn coalescing_typing_with_multiple_cursors() {
+        let history = History::new();
+        let rope = rope("ab");
+        modify(&history, &rope, &[(0, 0, "x"), (3, 3, "x")]);
+        modify(&history, &rope, &[(1, 1, "y"), (5, 5, "y")]);
+        assert_eq!(rope.text().to_string(), "xyabxy");
+        history.undo(&rope, default());
+        assert_eq!(rope.text().to_string(), "ab");
+        assert!(!history.can_undo());
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), "xyabxy");
+    }
+
+    #[test]
+    fn restoring_formatting() {
+        let history = History::new();
+        let rope = rope("abcd");
+        let red = color::Rgba::new(1.0, 0.0, 0.0, 1.0);
+        rope.formatting.set_property(Range::new(Byte(1), Byte(3)), red.into());
+        let default_ranges = || rope.style().span_ranges_of_default_values(PropertyTag::Color);
+        let expected = default_ranges();
+        modify(&history, &rope, &[(0, 4, "xyz")]);
+        assert_ne!(default_ranges(), expected);
+        history.undo(&rope, default());
+        assert_eq!(rope.text().to_string(), "abcd");
+        assert_eq!(default_ranges(), expected);
+    }
+
+    #[test]
+    fn undoing_formatting_change() {
+        let history = History::new();
+        let rope = rope("abcd");
+        let default_ranges = || rope.style().span_ranges_of_default_values(PropertyTag::Color);
+        modify(&history, &rope, &[(4, 4, "\n")]);
+        let expected = default_ranges();
+        make_red(&history, &rope, 1, 3);
+        make_red(&history, &rope, 1, 2);
+        modify(&history, &rope, &[(0, 5, "xyz")]);
+        let formatted = default_ranges();
+        history.undo(&rope, default());
+        assert_eq!(rope.text().to_string(), "abcd\n");
+        assert_eq!(default_ranges(), expected);
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), "xyz");
+        assert_eq!(default_ranges(), formatted);
+        history.undo(&rope, default());
+        history.undo(&rope, default());
+        assert_eq!(rope.text().to_string(), "abcd");
+    }
+
+    #[test]
+    fn formatting_keeps_redo() {
+        let history = History::new();
+        let rope = rope("xy");
+        modify(&history, &rope, &[(1, 1, "a")]);
+        history.undo(&rope, default());
+        make_red(&history, &rope, 0, 2);
+        assert!(history.can_redo());
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), "xay");
+    }
+
+    #[test]
+    fn formatting_keeps_typing_coalesced() {
+        let history = History::new();
+        let rope = rope("xy");
+        let default_ranges = || rope.style().span_ranges_of_default_values(PropertyTag::Color);
+        let expected = default_ranges();
+        modify(&history, &rope, &[(1, 1, "a")]);
+        make_red(&history, &rope, 0, 3);
+        modify(&history, &rope, &[(2, 2, "b")]);
+        make_red(&history, &rope, 3, 4);
+        modify(&history, &rope, &[(3, 3, "c")]);
+        assert_eq!(rope.text().to_string(), "xabcy");
+        let formatted = default_ranges();
+        history.undo(&rope, default());
+        assert_eq!(rope.text().to_string(), "xy");
+        assert_eq!(default_ranges(), expected);
+        assert!(!history.can_undo());
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), "xabcy");
+        assert_eq!(default_ranges(), formatted);
+    }
+
+    #[test]
+    fn memory_budget() {
+        let line = "line\n";
+        let budget = 3 * (DELTA_COST + line.len());
+        let history = History::with_memory_budget(budget);
+        let rope = rope("");
+        for _ in 0..10 {
+            modify(&history, &rope, &[(0, 0, line)]);
+        }
+        assert_eq!(history.memory_usage(), 3 * DELTA_COST);
+        while history.undo(&rope, default()).is_some() {}
+        assert_eq!(rope.text().to_string(), line.repeat(7));
+        assert_eq!(history.memory_usage(), budget);
+        history.set_memory_budget(budget - 1);
+        assert_eq!(history.memory_usage(), 2 * (DELTA_COST + line.len()));
+        history.redo(&rope, default());
+        assert_eq!(rope.text().to_string(), line.repeat(8));
+    }
+}
@@ -34,6 +34,19 @@ impl FormattedRope {
         self.text.replace(range, text);
         self.formatting.set_resize_with_default(range, size);
     }
+
+    /// Replace the content of the buffer with the provided text, styled with the provided
+    /// formatting. The formatting should span over the whole inserted text.
+    pub fn replace_formatted(
+        &self,
+        range: impl enso_text::RangeBounds,
+        text: impl Into<Rope>,
+        formatting: Formatting,
+    ) {
+        let range = self.crop_byte_range(range);
+        self.text.replace(range, text);
+        self.formatting.replace(range, formatting);
+    }
 }
 
 
@@ -673,6 +673,7 @@ impl Text {
             eval_ input.undo (m.buffer.frp.undo());
             eval_ input.undo (m.redraw());
             eval_ input.redo (m.buffer.frp.redo());
+            eval_ input.redo (m.redraw());
         }
     }
 }
@@ -78,9 +78,14 @@ where OnFrame: RawOnFrameCallback
     fn new(on_frame: OnFrame) -> Self {
         let data = Rc::new(RefCell::new(JsLoopData::new(on_frame)));
         let weak_data = Rc::downgrade(&data);
+        let virtual_weak_data = weak_data.clone();
         let js_on_frame =
             move |time: f64| weak_data.upgrade().for_each(|t| t.borrow_mut().run(time));
+        let virtual_on_frame = move |time: f64| {
+            virtual_weak_data.upgrade().for_each(|t| t.borrow_mut().run_virtual(time))
+        };
         data.borrow_mut().js_on_frame = Some(Closure::new(js_on_frame));
+        data.borrow_mut().virtual_frame_handle = frp::virtual_time::on_frame(virtual_on_frame);
         let js_on_frame_handle_id = web::window.request_animation_frame_with_closure_or_panic(
             data.borrow_mut().js_on_frame.as_ref().unwrap(),
         );
@@ -97,6 +102,8 @@ struct JsLoopData<OnFrame> {
     on_frame:              OnFrame,
     js_on_frame:           Option<Closure<dyn FnMut(f64)>>,
     js_on_frame_handle_id: i32,
+    /// Handle of the frame callback used when the [`frp::virtual_time`] is active in tests.
+    virtual_frame_handle:  callback::Handle,
 }
 
 impl<OnFrame> JsLoopData<OnFrame> {
@@ -104,7 +111,8 @@ impl<OnFrame> JsLoopData<OnFrame> {
     fn new(on_frame: OnFrame) -> Self {
         let js_on_frame = default();
         let js_on_frame_handle_id = default();
-        Self { on_frame, js_on_frame, js_on_frame_handle_id }
+        let virtual_frame_handle = default();
+        Self { on_frame, js_on_frame, js_on_frame_handle_id, virtual_frame_handle }
     }
 
     // FIXME: We are converting `f64` to `f32` here which is a mistake. We should revert to `f64`
@@ -113,10 +121,18 @@ impl<OnFrame> JsLoopData<OnFrame> {
     where OnFrame: FnMut(Duration) {
         let on_frame = &mut self.on_frame;
         self.js_on_frame_handle_id = self.js_on_frame.as_ref().map_or(default(), |js_on_frame| {
-            on_frame((current_time_ms as f32).ms());
+            // With the virtual time, the frames are driven by the test instead of the browser.
+            if !frp::virtual_time::is_active() {
+                on_frame((current_time_ms as f32).ms());
+            }
             web::window.request_animation_frame_with_closure_or_panic(js_on_frame)
         })
     }
+
+    fn run_virtual(&mut self, current_time_ms: f64)
+    where OnFrame: FnMut(Duration) {
+        (self.on_frame)((current_time_ms as f32).ms());
+    }
 }
 
 impl<OnFrame> Drop for JsLoopData<OnFrame> {
@@ -302,8 +318,12 @@ fn on_frame_closure(
     let mut time_info = InitializedTimeInfo::default();
     let h_cell = Rc::new(Cell::new(callback::Handle::default()));
     let fixed_fps_sampler = Rc::new(RefCell::new(FixedFrameRateSampler::default()));
+    // The frame callbacks of the FRP time nodes are run by this loop, instead of a separate
+    // `requestAnimationFrame` loop.
+    let frp_frame_driver = frp::time::drive_frames_externally();
 
     move |frame_time: Duration| {
+        frp_frame_driver.run_frame(frame_time.unchecked_raw() as f64);
         let time_info = time_info.next_frame(frame_time);
         let on_frame_start = output.on_frame_start.clone_ref();
         let on_before_animations = output.on_before_animations.clone_ref();
@@ -15,6 +15,8 @@ use enso_shortcuts as shortcuts;
 // === Export ===
 // ==============
 
+pub mod keymap;
+
 pub use shortcuts::ActionType;
 
 
@@ -24,7 +26,7 @@ pub use shortcuts::ActionType;
 // ============
 
 /// Shortcut action rule, a combination of `ActionType`, like `Press` and a pattern, like
-/// "ctrl shift s".
+/// "ctrl shift s", or a key sequence, like "ctrl k, ctrl c".
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 #[allow(missing_docs)]
 pub struct Rule {
@@ -220,13 +222,20 @@ impl Shortcut {
 /// dropped, the shortcut will be lazily removed. This is useful when defining shortcuts by GUI
 /// components. When a component is unloaded, all its default shortcuts should be removed as well.
 ///
+/// The shortcuts added by components are the defaults, over which a user-defined [`keymap::Keymap`]
+/// can be applied with [`RegistryModel::set_keymap`].
+///
 /// ## Implementation Notes
 /// There should be a layer for user shortcuts which will remember handles permanently until a
 /// shortcut is unregistered.
 #[derive(Clone, CloneRef, Debug)]
 pub struct Registry {
-    model:   RegistryModel,
-    network: frp::Network,
+    model:             RegistryModel,
+    network:           frp::Network,
+    /// The strokes of the partially entered key sequence, like `["ctrl k"]` when waiting for the
+    /// second stroke of "ctrl k, ctrl c". Updated on every input event, and cleared when the
+    /// sequence times out.
+    pub pending_chord: frp::Stream<Vec<String>>,
 }
 
 /// Internal representation of `Registry`.
@@ -236,6 +245,10 @@ pub struct RegistryModel {
     mouse:              Mouse_DEPRECATED,
     command_registry:   command::Registry,
     shortcuts_registry: shortcuts::HashSetRegistry<Shortcut>,
+    defaults:           Rc<RefCell<Vec<Shortcut>>>,
+    keymap:             Rc<RefCell<keymap::Keymap>>,
+    active:             Rc<RefCell<Vec<Shortcut>>>,
+    chord_timer:        Rc<RefCell<Option<frp::time::Timer>>>,
 }
 
 impl Deref for Registry {
@@ -262,8 +275,15 @@ impl Registry {
             mouse_up   <- mouse.up.map      (f!((t) model.shortcuts_registry.on_release(t.simple_name())));
             event      <- any(kb_down,kb_up,mouse_down,mouse_up);
             eval event ((m) model.process_rules(m));
+
+            chord_timed_out <- source_();
+            eval_ event ([model, chord_timed_out] model.restart_chord_timer(&chord_timed_out));
+            pending_chord <- event.map(f_!(model.shortcuts_registry.pending_strokes()));
+            pending_chord <- any(pending_chord, chord_timed_out.constant(default()));
+            pending_chord <- pending_chord.on_change();
         }
-        Self { model, network }
+        let pending_chord = pending_chord.into();
+        Self { model, network, pending_chord }
     }
 }
 
@@ -278,7 +298,73 @@ impl RegistryModel {
         let mouse = mouse.clone_ref();
         let command_registry = command_registry.clone_ref();
         let shortcuts_registry = default();
-        Self { keyboard, mouse, command_registry, shortcuts_registry }
+        let defaults = default();
+        let keymap = default();
+        let active = default();
+        let chord_timer = default();
+        Self {
+            keyboard,
+            mouse,
+            command_registry,
+            shortcuts_registry,
+            defaults,
+            keymap,
+            active,
+            chord_timer,
+        }
+    }
+
+    /// Set the maximum time between the strokes of a key sequence. The default is
+    /// [`shortcuts::CHORD_TIMEOUT_MS`].
+    pub fn set_chord_timeout(&self, timeout_ms: f32) {
+        self.shortcuts_registry.set_chord_timeout(timeout_ms);
+    }
+
+    /// Emit `timed_out` once the chord timeout passes, unless this function is called again
+    /// before. The timeout is read on every call, so it follows [`Self::set_chord_timeout`].
+    fn restart_chord_timer(&self, timed_out: &frp::Source) {
+        let timed_out = timed_out.clone_ref();
+        let timeout_ms = self.shortcuts_registry.chord_timeout().into();
+        let timer = frp::time::set_timeout(timeout_ms, move || timed_out.emit(()));
+        self.chord_timer.replace(Some(timer));
+    }
+
+    /// Apply the user-defined keymap over the default shortcuts, replacing the previously applied
+    /// keymap. Returns the problems found in the keymap. See the [`keymap`] module to learn more.
+    pub fn set_keymap(&self, keymap: keymap::Keymap) -> Vec<keymap::Issue> {
+        let merged = keymap.merge(&self.defaults.borrow(), &self.command_registry);
+        for shortcut in mem::take(&mut *self.active.borrow_mut()) {
+            self.shortcuts_registry.remove(shortcut.rule.tp, &shortcut.rule.pattern, &shortcut);
+        }
+        *self.keymap.borrow_mut() = keymap;
+        for shortcut in merged.shortcuts {
+            self.register(shortcut);
+        }
+        merged.issues
+    }
+
+    /// Parse a keymap in the given format and apply it with [`Self::set_keymap`].
+    pub fn load_keymap(
+        &self,
+        source: &str,
+        format: keymap::Format,
+    ) -> Result<Vec<keymap::Issue>, keymap::Error> {
+        Ok(self.set_keymap(keymap::Keymap::parse(source, format)?))
+    }
+
+    /// Remove the user-defined keymap, restoring the default shortcuts.
+    pub fn reset_keymap(&self) {
+        self.set_keymap(default());
+    }
+
+    /// The shortcuts in effect: the default shortcuts with the user-defined keymap applied.
+    pub fn shortcuts(&self) -> Vec<Shortcut> {
+        self.active.borrow().clone()
+    }
+
+    fn register(&self, shortcut: Shortcut) {
+        self.shortcuts_registry.add(shortcut.rule.tp, &shortcut.rule.pattern, shortcut.clone());
+        self.active.borrow_mut().push(shortcut);
     }
 
     fn process_rules(&self, rules: &[Shortcut]) {
@@ -327,10 +413,112 @@ impl RegistryModel {
 impl Add<Shortcut> for &Registry {
     type Output = ();
     fn add(self, shortcut: Shortcut) {
-        self.model.shortcuts_registry.add(
-            shortcut.rule.tp,
-            &shortcut.rule.pattern,
-            shortcut.clone(),
-        );
+        self.model.defaults.borrow_mut().push(shortcut.clone());
+        if !self.model.keymap.borrow().overrides(&shortcut) {
+            self.model.register(shortcut);
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::frp::io::keyboard::KeyWithCode;
+
+    use crate::frp::virtual_time::VirtualTime;
+
+    #[test]
+    fn reloading_keymap_removes_bindings() {
+        let mouse = Mouse_DEPRECATED::default();
+        let keyboard = keyboard::Keyboard::new();
+        let registry = Registry::new(&mouse, &keyboard, &command::Registry::create());
+        let rule = Rule::new(ActionType::Press, "ctrl z");
+        registry.add(Shortcut::new(rule, "TextEditor", "undo"));
+        let commands = |key: &str| {
+            let shortcuts_registry = &registry.shortcuts_registry;
+            shortcuts_registry.on_press("ctrl-left");
+            let shortcuts = shortcuts_registry.on_press(key);
+            shortcuts_registry.on_release(key);
+            shortcuts_registry.on_release("ctrl-left");
+            shortcuts.into_iter().map(|shortcut| shortcut.command.name.clone()).collect_vec()
+        };
+        let none = Vec::<String>::new();
+        assert_eq!(commands("z"), vec!["undo".to_string()]);
+        let rebind = r#"
+            [[bindings]]
+            key = "ctrl y"
+            target = "TextEditor"
+            command = "undo"
+        "#;
+        assert_eq!(registry.load_keymap(rebind, keymap::Format::Toml), Ok(vec![]));
+        assert_eq!(commands("z"), none);
+        assert_eq!(commands("y"), vec!["undo".to_string()]);
+        let remove = r#"
+            [[bindings]]
+            target = "TextEditor"
+            command = "undo"
+            remove = true
+        "#;
+        assert_eq!(registry.load_keymap(remove, keymap::Format::Toml), Ok(vec![]));
+        assert_eq!(commands("y"), none);
+        assert_eq!(commands("z"), none);
+        registry.reset_keymap();
+        assert_eq!(commands("z"), vec!["undo".to_string()]);
+    }
+
+    #[test]
+    fn pending_chord_cleared_on_timeout() {
+        let time = VirtualTime::new();
+        let mouse = Mouse_DEPRECATED::default();
+        let keyboard = keyboard::Keyboard::new();
+        let registry = Registry::new(&mouse, &keyboard, &command::Registry::create());
+        let rule = Rule::new(ActionType::Press, "ctrl k, ctrl c");
+        registry.add(Shortcut::new(rule, "TextEditor", "comment"));
+        let network = frp::Network::new("test");
+        frp::extend! { network
+            pending_chord <- registry.pending_chord.sampler();
+        }
+        let key = |key: &str, code: &str| KeyWithCode::new(key.into(), code.into());
+        keyboard.source.down.emit(key("Control", "ControlLeft"));
+        keyboard.source.down.emit(key("k", "KeyK"));
+        keyboard.source.up.emit(key("k", "KeyK"));
+        assert_eq!(pending_chord.value(), vec!["ctrl-left k".to_string()]);
+        let chord_timeout = shortcuts::CHORD_TIMEOUT_MS as f64;
+        time.advance_time(chord_timeout - 1.0);
+        assert_eq!(pending_chord.value(), vec!["ctrl-left k".to_string()]);
+        time.advance_time(1.0);
+        assert_eq!(pending_chord.value(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn pending_chord_cleared_on_custom_timeout() {
+        let time = VirtualTime::new();
+        let mouse = Mouse_DEPRECATED::default();
+        let keyboard = keyboard::Keyboard::new();
+        let registry = Registry::new(&mouse, &keyboard, &command::Registry::create());
+        let rule = Rule::new(ActionType::Press, "ctrl k, ctrl c");
+        registry.add(Shortcut::new(rule, "TextEditor", "comment"));
+        let chord_timeout = 200.0;
+        registry.set_chord_timeout(chord_timeout);
+        let network = frp::Network::new("test");
+        frp::extend! { network
+            pending_chord <- registry.pending_chord.sampler();
+        }
+        let key = |key: &str, code: &str| KeyWithCode::new(key.into(), code.into());
+        keyboard.source.down.emit(key("Control", "ControlLeft"));
+        keyboard.source.down.emit(key("k", "KeyK"));
+        keyboard.source.up.emit(key("k", "KeyK"));
+        assert_eq!(pending_chord.value(), vec!["ctrl-left k".to_string()]);
+        time.advance_time(chord_timeout as f64 - 1.0);
+        assert_eq!(pending_chord.value(), vec!["ctrl-left k".to_string()]);
+        time.advance_time(1.0);
+        assert_eq!(pending_chord.value(), Vec::<String>::new());
     }
 }
@@ -0,0 +1,436 @@
+//! User-defined keymaps, which rebind the default shortcuts of views.
+//!
+//! A keymap is a TOML or JSON document with a list of bindings:
+//!
+//! ```text
+//! [[bindings]]
+//! key = "cmd shift z"
+//! target = "TextEditor"
+//! command = "redo"
+//!
+//! [[bindings]]
+//! key = "ctrl d"
+//! action = "press-and-repeat"
+//! target = "TextEditor"
+//! command = "delete_right"
+//! when = "focused & !read_only"
+//!
+//! [[bindings]]
+//! target = "TextEditor"
+//! command = "undo"
+//! remove = true
+//! ```
+//!
+//! The same keymap in JSON is an object with a `bindings` array:
+//!
+//! ```text
+//! { "bindings": [{ "key": "cmd shift z", "target": "TextEditor", "command": "redo" }, ...] }
+//! ```
+//!
+//! The `action` defaults to `press`, and the `when` condition uses the syntax of [`Condition`].
+//! Binding a command replaces all default shortcuts of that command. A binding with `"remove":
+//! true` only removes the default shortcuts of the command, or, if it has a `key`, the default
+//! shortcuts of the command bound to that key.
+
+use crate::prelude::*;
+
+use super::ActionType;
+use super::Command;
+use super::Condition;
+use super::Rule;
+use super::Shortcut;
+use crate::application::command;
+
+use enso_shortcuts as shortcuts;
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// An error in the syntax of a keymap file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    /// The index of the binding containing the error, if the error is in a binding.
+    pub binding: Option<usize>,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.binding {
+            Some(index) => write!(f, "Binding {index}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+
+
+// ==============
+// === Format ===
+// ==============
+
+/// The format of a keymap file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// The format of the file with the given path or URL, determined by its extension. Files
+    /// without the `.toml` extension are assumed to be JSON.
+    pub fn from_path(path: &str) -> Self {
+        match path.to_lowercase().ends_with(".toml") {
+            true => Format::Toml,
+            false => Format::Json,
+        }
+    }
+}
+
+
+
+// ===============
+// === Binding ===
+// ===============
+
+/// A single entry of a keymap.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct Binding {
+    /// The action type and key expression. It is [`None`] only for bindings removing all default
+    /// shortcuts of a command.
+    pub rule:      Option<Rule>,
+    pub target:    String,
+    pub command:   Command,
+    pub condition: Condition,
+    /// Whether this binding removes default shortcuts instead of adding a new one.
+    pub remove:    bool,
+}
+
+impl Binding {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let object = value.as_object().ok_or("Expected an object.")?;
+        let string = |field: &str| match object.get(field) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(format!("The field '{field}' must be a string.")),
+        };
+        let fields = ["key", "action", "target", "command", "when", "remove"];
+        if let Some(field) = object.keys().find(|field| !fields.contains(&field.as_str())) {
+            return Err(format!("Unknown field '{field}'."));
+        }
+        let remove = match object.get("remove") {
+            None => false,
+            Some(Value::Bool(remove)) => *remove,
+            Some(_) => return Err("The field 'remove' must be a boolean.".into()),
+        };
+        let target = string("target")?.ok_or("Missing the field 'target'.")?;
+        let command = string("command")?.ok_or("Missing the field 'command'.")?.into();
+        let condition = string("when")?.map_or(Condition::Always, Condition::parse);
+        let action_type = string("action")?.map(|action| action.parse()).transpose()?;
+        let rule = match string("key")? {
+            Some(key) if shortcuts::normalize_expr(&key).is_empty() =>
+                return Err("The field 'key' must not be empty.".into()),
+            Some(key) => Some(Rule::new(action_type.unwrap_or(ActionType::Press), key)),
+            None if remove => None,
+            None => return Err("Missing the field 'key'.".into()),
+        };
+        Ok(Self { rule, target, command, condition, remove })
+    }
+
+    /// Check whether this binding replaces or removes the given default shortcut.
+    fn overrides(&self, shortcut: &Shortcut) -> bool {
+        let same_command =
+            self.target == shortcut.target && self.command.name == shortcut.command.name;
+        let same_rule = || match &self.rule {
+            None => true,
+            Some(rule) => rule.tp == shortcut.rule.tp && same_keys(&rule.pattern, &shortcut.rule),
+        };
+        same_command && (!self.remove || same_rule())
+    }
+
+    /// The shortcut added by this binding, if any.
+    fn shortcut(&self) -> Option<Shortcut> {
+        let rule = self.rule.as_ref().filter(|_| !self.remove)?;
+        let rule = Rule::new(rule.tp, rule.pattern.to_lowercase());
+        let target = self.target.clone();
+        let command = self.command.clone();
+        Some(Shortcut::new_when(rule, target, command, self.condition.clone()))
+    }
+}
+
+fn same_keys(pattern: &str, rule: &Rule) -> bool {
+    shortcuts::normalize_expr(pattern) == shortcuts::normalize_expr(&rule.pattern)
+}
+
+
+
+// ==============
+// === Keymap ===
+// ==============
+
+/// A set of user-defined bindings, applied over the default shortcuts of views. See the module
+/// docs to learn more.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// Parse a keymap in the given format.
+    pub fn parse(source: &str, format: Format) -> Result<Self, Error> {
+        match format {
+            Format::Toml => Self::from_toml(source),
+            Format::Json => Self::from_json(source),
+        }
+    }
+
+    /// Parse a keymap from its TOML representation.
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        let error = |message: String| Error { binding: None, message };
+        let value: toml::Value = toml::from_str(toml).map_err(|e| error(e.to_string()))?;
+        Self::from_value(serde_json::to_value(value).map_err(|e| error(e.to_string()))?)
+    }
+
+    /// Parse a keymap from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let error = |message: String| Error { binding: None, message };
+        Self::from_value(serde_json::from_str(json).map_err(|e| error(e.to_string()))?)
+    }
+
+    fn from_value(value: Value) -> Result<Self, Error> {
+        let error = |binding, message| Error { binding, message };
+        let bindings = match value.get("bindings") {
+            Some(Value::Array(bindings)) => bindings,
+            _ => return Err(error(None, "Expected an object with a 'bindings' array.".into())),
+        };
+        let bindings = bindings.iter().enumerate().map(|(index, binding)| {
+            Binding::from_json(binding).map_err(|message| error(Some(index), message))
+        });
+        Ok(Self { bindings: bindings.collect::<Result<_, _>>()? })
+    }
+
+    /// Check whether a binding of this keymap replaces or removes the given default shortcut.
+    pub fn overrides(&self, shortcut: &Shortcut) -> bool {
+        self.bindings.iter().any(|binding| binding.overrides(shortcut))
+    }
+
+    /// Apply the keymap over the given default shortcuts. The commands known to the command
+    /// registry, and the commands of the default shortcuts, are used to detect bindings of unknown
+    /// commands. Note that commands of views which were not instantiated yet are only known if they
+    /// have a default shortcut.
+    pub fn merge(&self, defaults: &[Shortcut], commands: &command::Registry) -> Merged {
+        let mut issues = vec![];
+        let mut known = HashMap::<String, HashSet<String>>::new();
+        for (target, instances) in commands.name_map.borrow().iter() {
+            let names = known.entry(target.clone()).or_default();
+            for instance in instances {
+                names.extend(instance.command_map.borrow().keys().cloned());
+            }
+        }
+        for shortcut in defaults {
+            let names = known.entry(shortcut.target.clone()).or_default();
+            names.insert(shortcut.command.name.clone());
+        }
+        for (index, binding) in self.bindings.iter().enumerate() {
+            let target = binding.target.clone();
+            let command = binding.command.name.clone();
+            match known.get(&target) {
+                None => issues.push(Issue::UnknownTarget { binding: index, target }),
+                Some(names) if !names.contains(&command) =>
+                    issues.push(Issue::UnknownCommand { binding: index, target, command }),
+                Some(_) => {}
+            }
+        }
+        let defaults = defaults.iter().filter(|shortcut| !self.overrides(shortcut)).cloned();
+        let shortcuts = defaults.chain(self.bindings.iter().filter_map(Binding::shortcut));
+        let shortcuts = shortcuts.collect_vec();
+        let mut bound = HashMap::<_, &Shortcut>::new();
+        for shortcut in &shortcuts {
+            let rule = &shortcut.rule;
+            let keys = shortcuts::normalize_expr(&rule.pattern);
+            let key = (rule.tp, keys, &shortcut.target, &shortcut.condition);
+            match bound.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(shortcut);
+                }
+                Entry::Occupied(entry) =>
+                    if entry.get().command != shortcut.command {
+                        let first = (*entry.get()).clone();
+                        let second = shortcut.clone();
+                        issues.push(Issue::Conflict { first, second });
+                    },
+            }
+        }
+        Merged { shortcuts, issues }
+    }
+}
+
+
+// === Merged ===
+
+/// The result of applying a [`Keymap`] over the default shortcuts.
+#[derive(Clone, Debug, Default)]
+#[allow(missing_docs)]
+pub struct Merged {
+    pub shortcuts: Vec<Shortcut>,
+    pub issues:    Vec<Issue>,
+}
+
+
+// === Issue ===
+
+/// A problem found when applying a [`Keymap`]. Bindings with issues are applied anyway.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum Issue {
+    /// The binding with the given index targets a view which is not registered.
+    UnknownTarget { binding: usize, target: String },
+    /// The binding with the given index targets a command the target view doesn't have.
+    UnknownCommand { binding: usize, target: String, command: String },
+    /// Two shortcuts with the same keys, action type, target and condition trigger different
+    /// commands.
+    Conflict { first: Shortcut, second: Shortcut },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnknownTarget { binding, target } =>
+                write!(f, "Binding {binding}: unknown target '{target}'."),
+            Issue::UnknownCommand { binding, target, command } =>
+                write!(f, "Binding {binding}: unknown command '{command}' of '{target}'."),
+            Issue::Conflict { first, second } => write!(
+                f,
+                "The shortcut '{}' ({}) of '{}' triggers both '{}' and '{}'.",
+                first.rule.pattern,
+                first.rule.tp,
+                first.target,
+                first.command.name,
+                second.command.name
+            ),
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Vec<Shortcut> {
+        vec![
+            Shortcut::new(Rule::new(ActionType::Press, "ctrl z"), "TextEditor", "undo"),
+            Shortcut::new(Rule::new(ActionType::Press, "ctrl y"), "TextEditor", "redo"),
+            Shortcut::new(Rule::new(ActionType::Press, "ctrl shift z"), "TextEditor", "redo"),
+            Shortcut::new(Rule::new(ActionType::Press, "ctrl a"), "TextEditor", "select_all"),
+        ]
+    }
+
+    fn commands(shortcuts: &[Shortcut]) -> Vec<(&str, &str)> {
+        let command = |s: &Shortcut| (s.rule.pattern.as_str(), s.command.name.as_str());
+        shortcuts.iter().map(command).collect()
+    }
+
+    #[test]
+    fn parse_keymap() {
+        let keymap = Keymap::from_json(
+            r#"{ "bindings": [
+                { "key": "alt z", "action": "press-and-repeat", "target": "TextEditor",
+                  "command": "undo", "when": "focused" },
+                { "target": "TextEditor", "command": "redo", "remove": true }
+            ] }"#,
+        )
+        .unwrap();
+        let undo = &keymap.bindings[0];
+        assert_eq!(undo.rule, Some(Rule::new(ActionType::PressAndRepeat, "alt z")));
+        assert_eq!(undo.condition, Condition::when("focused"));
+        assert!(!undo.remove);
+        let redo = &keymap.bindings[1];
+        assert_eq!(redo.rule, None);
+        assert!(redo.remove);
+    }
+
+    #[test]
+    fn parse_toml_keymap() {
+        let toml = r#"
+            [[bindings]]
+            key = "alt z"
+            action = "press-and-repeat"
+            target = "TextEditor"
+            command = "undo"
+            when = "focused"
+
+            [[bindings]]
+            target = "TextEditor"
+            command = "redo"
+            remove = true
+        "#;
+        let json = r#"{ "bindings": [
+            { "key": "alt z", "action": "press-and-repeat", "target": "TextEditor",
+              "command": "undo", "when": "focused" },
+            { "target": "TextEditor", "command": "redo", "remove": true }
+        ] }"#;
+        assert_eq!(Keymap::parse(toml, Format::from_path("keymap.toml")), Keymap::from_json(json));
+        assert_eq!(Format::from_path("keymap.json"), Format::Json);
+        let invalid = "[[bindings]]\nkey = 1\ntarget = \"A\"\ncommand = \"b\"";
+        assert_eq!(Keymap::from_toml(invalid).unwrap_err().binding, Some(0));
+        assert_eq!(Keymap::from_toml("bindings = ").unwrap_err().binding, None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let binding = |json: &str| {
+            Keymap::from_json(&format!(r#"{{ "bindings": [{json}] }}"#)).unwrap_err().binding
+        };
+        assert_eq!(binding(r#"{ "target": "A", "command": "b" }"#), Some(0));
+        assert_eq!(binding(r#"{ "key": "a", "target": "A" }"#), Some(0));
+        assert_eq!(binding(r#"{ "key": "", "target": "A", "command": "b" }"#), Some(0));
+        assert_eq!(binding(r#"{ "key": "a", "target": "A", "command": 1 }"#), Some(0));
+        let unknown_action = r#"{ "key": "a", "action": "x", "target": "A", "command": "b" }"#;
+        assert_eq!(binding(unknown_action), Some(0));
+        assert_eq!(binding(r#"{ "kye": "a", "target": "A", "command": "b" }"#), Some(0));
+        assert_eq!(Keymap::from_json("[]").unwrap_err().binding, None);
+    }
+
+    #[test]
+    fn merge_keymap() {
+        let keymap = Keymap::from_json(
+            r#"{ "bindings": [
+                { "key": "ctrl u", "target": "TextEditor", "command": "undo" },
+                { "key": "ctrl y", "target": "TextEditor", "command": "redo", "remove": true },
+                { "key": "ctrl a", "target": "TextEditor", "command": "copy" },
+                { "key": "ctrl b", "target": "TextEditor", "command": "bold" },
+                { "key": "ctrl b", "target": "Graph", "command": "undo" }
+            ] }"#,
+        )
+        .unwrap();
+        let merged = keymap.merge(&defaults(), &command::Registry::create());
+        assert_eq!(commands(&merged.shortcuts), vec![
+            ("ctrl shift z", "redo"),
+            ("ctrl a", "select_all"),
+            ("ctrl u", "undo"),
+            ("ctrl a", "copy"),
+            ("ctrl b", "bold"),
+            ("ctrl b", "undo"),
+        ]);
+        assert_eq!(merged.issues.len(), 4);
+        assert!(matches!(&merged.issues[0], Issue::UnknownCommand { binding: 2, .. }));
+        assert!(matches!(&merged.issues[1], Issue::UnknownCommand { binding: 3, .. }));
+        assert!(matches!(&merged.issues[2], Issue::UnknownTarget { binding: 4, .. }));
+        assert!(matches!(&merged.issues[3], Issue::Conflict { .. }));
+    }
+}
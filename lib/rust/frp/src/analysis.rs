@@ -0,0 +1,319 @@
+//! Static analysis of FRP networks, detecting the most common mistakes in the network definitions.
+//!
+//! The [`analyze`] function (or the [`crate::Network::analyze`] method) inspects a
+//! [`NetworkInfo`] snapshot and reports the following issues:
+//! - [`Issue::Cycle`]: a set of nodes emitting events to each other synchronously. Emitting an
+//!   event to any of them results in an infinite recursion. A cycle is correct only if it contains
+//!   a node which does not pass the events synchronously, like [`crate::Network::debounce`], or
+//!   which was designed to be used in loops, like [`crate::Network::sampler`] or
+//!   [`crate::Network::previous`].
+//! - [`Issue::Unconsumed`]: a node whose output is neither passed to another node nor read as a
+//!   behavior. Such nodes are usually leftovers after refactoring. Nodes of the `()` output type
+//!   are not reported, as they are used to perform side effects, like [`crate::Network::map_`].
+//! - [`Issue::NeverEmitted`]: a source node which did not emit any event so far. As this is checked
+//!   using the runtime statistics of the network, it is meaningful only after the code using the
+//!   network was run, for example at the end of a test. The events are counted in debug builds
+//!   only.
+//!
+//! In debug builds, the cycles are also reported automatically, as warnings logged once the network
+//! construction is finished.
+//!
+//! The network outputs which are meant to be connected to other networks are reported as
+//! unconsumed until they are connected, so the analysis should be performed once all the networks
+//! of a component are set up. The issues are reported with the source code locations of the nodes,
+//! for example:
+//!
+//! ```text
+//! let network = frp::Network::new("network");
+//! ...
+//! network.analyze().assert_no_issues();
+//! ```
+
+use crate::prelude::*;
+
+use crate::debug::NetworkInfo;
+use crate::debug::NodeInfo;
+use crate::network::LinkType;
+
+
+
+// =============
+// === Issue ===
+// =============
+
+/// A potential mistake found in the network definition. See the module docs to learn more.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    /// Nodes emitting events to each other synchronously, in the order they were registered in.
+    Cycle(Vec<NodeInfo>),
+    /// Node whose output is never consumed.
+    Unconsumed(NodeInfo),
+    /// Source node which did not emit any event.
+    NeverEmitted(NodeInfo),
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Cycle(nodes) => {
+                write!(f, "Synchronous cycle of {} nodes:", nodes.len())?;
+                for node in nodes {
+                    write!(f, "\n    {node}")?;
+                }
+                Ok(())
+            }
+            Issue::Unconsumed(node) => write!(f, "Output of {node} is never consumed."),
+            Issue::NeverEmitted(node) => write!(f, "Source {node} has never emitted an event."),
+        }
+    }
+}
+
+
+
+// ==============
+// === Report ===
+// ==============
+
+/// The result of the network analysis.
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// Label of the analyzed network.
+    pub network: String,
+    /// All the issues found, cycles first.
+    pub issues:  Vec<Issue>,
+}
+
+impl Report {
+    /// Check whether no issues were found.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Panic with the description of all the found issues, if any. To be used in tests.
+    #[track_caller]
+    pub fn assert_no_issues(&self) {
+        if !self.is_empty() {
+            panic!("{self}");
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = self.issues.len();
+        write!(f, "Found {count} issue(s) in the FRP network \"{}\".", self.network)?;
+        for issue in &self.issues {
+            write!(f, "\n{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+
+
+// ===============
+// === Analyze ===
+// ===============
+
+/// Analyze the network structure and statistics. See the module docs to learn more.
+pub fn analyze(info: &NetworkInfo) -> Report {
+    let network = info.label.clone();
+    let mut issues = vec![];
+    let cycles = find_cycles(info);
+    issues.extend(cycles.into_iter().map(|cycle| {
+        Issue::Cycle(cycle.into_iter().map(|index| info.nodes[index].clone()).collect())
+    }));
+    for node in &info.nodes {
+        let has_unit_output = node.output_type == "()";
+        let is_consumed = node.target_count > 0 || node.is_watched || node.has_side_effects;
+        if !is_consumed && (!has_unit_output || node.is_source) {
+            issues.push(Issue::Unconsumed(node.clone()));
+        }
+    }
+    for node in &info.nodes {
+        // The events are counted in debug builds only.
+        if cfg!(debug_assertions) && node.is_source && node.emit_count == 0 {
+            issues.push(Issue::NeverEmitted(node.clone()));
+        }
+    }
+    Report { network, issues }
+}
+
+/// Find all the synchronous cycles of the network, as sorted lists of node indexes. Only the
+/// event links are taken into account, as reading a behavior does not propagate events.
+fn find_cycles(info: &NetworkInfo) -> Vec<Vec<usize>> {
+    let index_of: HashMap<_, _> =
+        info.nodes.iter().enumerate().map(|(index, node)| (node.id, index)).collect();
+    let mut edges = vec![vec![]; info.nodes.len()];
+    for link in &info.links {
+        let is_event_link = !matches!(link.tp, LinkType::Behavior);
+        let source = index_of.get(&link.source).copied();
+        let target = index_of.get(&link.target).copied();
+        if let (true, Some(source), Some(target)) = (is_event_link, source, target) {
+            if !info.nodes[source].breaks_cycles {
+                edges[source].push(target);
+            }
+        }
+    }
+    let components = strongly_connected_components(&edges);
+    let is_cycle =
+        |component: &Vec<usize>| component.len() > 1 || edges[component[0]].contains(&component[0]);
+    let mut cycles = components.into_iter().filter(is_cycle).collect_vec();
+    cycles.iter_mut().for_each(|cycle| cycle.sort_unstable());
+    cycles.sort_unstable();
+    cycles
+}
+
+/// The strongly connected components of the graph given as adjacency lists, computed by the
+/// Tarjan's algorithm. The recursion is replaced with an explicit stack, as the FRP networks can
+/// have long chains of nodes.
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let count = edges.len();
+    let mut order: Vec<Option<usize>> = vec![None; count];
+    let mut low_link = vec![0; count];
+    let mut on_stack = vec![false; count];
+    let mut stack = vec![];
+    let mut next_order = 0;
+    let mut components = vec![];
+    for root in 0..count {
+        if order[root].is_some() {
+            continue;
+        }
+        let mut call_stack = vec![(root, 0)];
+        while let Some((node, edge_index)) = call_stack.pop() {
+            if edge_index == 0 {
+                order[node] = Some(next_order);
+                low_link[node] = next_order;
+                next_order += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&target) = edges[node].get(edge_index) {
+                call_stack.push((node, edge_index + 1));
+                match order[target] {
+                    None => call_stack.push((target, 0)),
+                    Some(target_order) if on_stack[target] =>
+                        low_link[node] = low_link[node].min(target_order),
+                    Some(_) => {}
+                }
+            } else {
+                if Some(low_link[node]) == order[node] {
+                    let mut component = vec![];
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_link[parent] = low_link[parent].min(low_link[node]);
+                }
+            }
+        }
+    }
+    components
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::network::Network;
+
+    fn labels(nodes: &[NodeInfo]) -> Vec<&str> {
+        nodes.iter().map(|node| node.label).collect()
+    }
+
+    #[test]
+    fn cycles() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let looped = network.any_mut::<i32>("looped");
+        looped.attach(&source);
+        let incremented = network.map("incremented", &looped, |t| t + 1);
+        let positive = network.filter("positive", &incremented, |t| *t > 0);
+        looped.attach(&positive);
+        let self_loop = network.any_mut::<i32>("self_loop");
+        self_loop.attach(&self_loop);
+        let report = network.analyze();
+        let cycles = report.issues.iter().filter_map(|issue| match issue {
+            Issue::Cycle(nodes) => Some(labels(nodes)),
+            _ => None,
+        });
+        let cycles = cycles.collect_vec();
+        assert_eq!(cycles, [vec!["looped", "incremented", "positive"], vec!["self_loop"]]);
+    }
+
+    #[test]
+    fn cycles_broken_by_async_nodes() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let looped = network.any_mut::<i32>("looped");
+        looped.attach(&source);
+        let incremented = network.map("incremented", &looped, |t| t + 1);
+        let debounced = network.debounce("debounced", &incremented);
+        looped.attach(&debounced);
+        let report = network.analyze();
+        assert!(!report.issues.iter().any(|issue| matches!(issue, Issue::Cycle(_))));
+    }
+
+    #[test]
+    fn cycles_broken_by_sampler() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let looped = network.any_mut::<i32>("looped");
+        looped.attach(&source);
+        let sampled = network.sampler("sampled", &looped);
+        let incremented = network.map("incremented", &sampled, |t| t + 1);
+        looped.attach(&incremented);
+        let report = network.analyze();
+        assert!(!report.issues.iter().any(|issue| matches!(issue, Issue::Cycle(_))));
+    }
+
+    #[test]
+    fn unconsumed_and_never_emitted_nodes() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let unused = network.source::<i32>("unused");
+        let _orphan = network.map("orphan", &source, |t| t + 1);
+        let _effect = network.map_("effect", &source, |_| ());
+        let sampled = network.sampler("sampled", &source);
+        let _watching = network.map2("watching", &unused, &sampled, |a, b| a + b);
+        source.emit(1);
+        let report = network.analyze();
+        let unconsumed = report.issues.iter().filter_map(|issue| match issue {
+            Issue::Unconsumed(node) => Some(node.label),
+            _ => None,
+        });
+        assert_eq!(unconsumed.collect_vec(), ["orphan", "watching"]);
+        let never_emitted = report.issues.iter().filter_map(|issue| match issue {
+            Issue::NeverEmitted(node) => Some(node.label),
+            _ => None,
+        });
+        assert_eq!(never_emitted.collect_vec(), ["unused"]);
+    }
+
+    #[test]
+    fn reporting_locations() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        source.emit(1);
+        let report = network.analyze();
+        assert!(matches!(&report.issues[..], [Issue::Unconsumed(_)]));
+        let message = report.to_string();
+        assert!(message.starts_with("Found 1 issue(s) in the FRP network \"network\"."));
+        assert!(message.contains("\"source\" (Source: i32) at "));
+        assert!(message.contains("analysis.rs:"));
+        let result = std::panic::catch_unwind(|| report.assert_no_issues());
+        assert!(result.is_err());
+    }
+}
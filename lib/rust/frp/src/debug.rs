@@ -1,18 +1,270 @@
-//! This module defines FRP Graphviz bindings. It allows visualizing the FRP network as Graphviz
-//! diagram.
-//! WARNING
-//! THIS MODULE IS IN A VERY WORK-IN-PROGRESS STATE. IT WILL BE CHANGED SOON.
-
-// === Non-Standard Linter Configuration ===
-#![allow(missing_docs)]
-#![allow(dead_code)]
+//! Introspection and debugging utilities of FRP networks.
+//!
+//! The [`crate::Network::introspect`] method returns a [`NetworkInfo`] snapshot of the network
+//! structure: all of its nodes with their labels and types, and the links between them. The
+//! snapshot can be exported to the Graphviz Dot (see [`GraphvizBuilder`]) or JSON format. The
+//! [`crate::Network::trace_events`] method enables an [`EventTracer`], recording all the events
+//! emitted by the network nodes, so they can be dumped once a bug happens:
+//!
+//! ```text
+//! let tracer = network.trace_events(1000);
+//! ...
+//! warn!("{}", tracer.dump());
+//! ```
 
 use crate::prelude::*;
 
+use crate::network::LinkType;
+use crate::node::Id;
+use crate::node::Label;
+use crate::time;
+
+use std::collections::VecDeque;
+use std::panic::Location;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Maximum number of characters of a traced event value. Longer values are truncated.
+pub const MAX_TRACED_VALUE_LENGTH: usize = 256;
+
+
+
+// ===================
+// === NetworkInfo ===
+// ===================
+
+/// Description of a single FRP node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// Unique identifier of the node.
+    pub id:               Id,
+    /// Label provided when the node was created.
+    pub label:            Label,
+    /// Kind of the node, like `Map2` or `Sampler`.
+    pub kind:             Label,
+    /// Name of the output type of the node, with the module paths stripped.
+    pub output_type:      String,
+    /// The source code location the node was created at. It is known in debug builds only.
+    pub location:         Option<&'static Location<'static>>,
+    /// The number of events emitted by the node so far. The events are counted in debug builds
+    /// only.
+    pub emit_count:       usize,
+    /// The number of nodes receiving the events emitted by this node, including the nodes of other
+    /// networks.
+    pub target_count:     usize,
+    /// Whether any other node is reading the value of this node.
+    pub is_watched:       bool,
+    /// See [`crate::stream::NodeRole::is_source`].
+    pub is_source:        bool,
+    /// See [`crate::stream::NodeRole::breaks_cycles`].
+    pub breaks_cycles:    bool,
+    /// See [`crate::stream::NodeRole::has_side_effects`].
+    pub has_side_effects: bool,
+}
+
+impl Display for NodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\" ({}: {})", self.label, self.kind, self.output_type)?;
+        if let Some(location) = self.location {
+            write!(f, " at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Description of a link between two FRP nodes.
+#[derive(Clone, Copy, Debug)]
+pub struct LinkInfo {
+    /// Node which the events or values are coming from.
+    pub source: Id,
+    /// Node receiving the events or reading the values.
+    pub target: Id,
+    /// Type of the link.
+    pub tp:     LinkType,
+}
+
+/// A snapshot of the structure of an FRP network. Only the links between the nodes of the network
+/// are included.
+#[derive(Clone, Debug)]
+pub struct NetworkInfo {
+    /// Label of the network.
+    pub label: String,
+    /// All the nodes, in the order they were registered in.
+    pub nodes: Vec<NodeInfo>,
+    /// All the links between the nodes.
+    pub links: Vec<LinkInfo>,
+}
+
+impl NetworkInfo {
+    /// Find the first node of the given label.
+    pub fn node(&self, label: &str) -> Option<&NodeInfo> {
+        self.nodes.iter().find(|node| node.label == label)
+    }
+
+    /// The nodes the node of the given id receives the events or values from.
+    pub fn inputs(&self, id: Id) -> impl Iterator<Item = &NodeInfo> {
+        let sources = self.links.iter().filter(move |link| link.target == id);
+        sources.filter_map(|link| self.nodes.iter().find(|node| node.id == link.source))
+    }
+
+    /// The nodes receiving the events or values from the node of the given id.
+    pub fn outputs(&self, id: Id) -> impl Iterator<Item = &NodeInfo> {
+        let targets = self.links.iter().filter(move |link| link.source == id);
+        targets.filter_map(|link| self.nodes.iter().find(|node| node.id == link.target))
+    }
+
+    /// Export the network to JSON, in the following format:
+    ///
+    /// ```text
+    /// { "label": "network",
+    ///   "nodes": [{ "id": 1, "label": "src", "kind": "Source", "outputType": "i32",
+    ///               "location": "src/lib.rs:10:5", "emitCount": 0 }],
+    ///   "links": [{ "source": 1, "target": 2, "type": "event" }] }
+    /// ```
+    pub fn to_json(&self) -> String {
+        let nodes = self.nodes.iter().map(|node| {
+            let id = usize::from(node.id);
+            let label = json_string(node.label);
+            let kind = json_string(node.kind);
+            let output_type = json_string(&node.output_type);
+            let location = node.location.map(|location| json_string(&location.to_string()));
+            let location = location.unwrap_or_else(|| "null".into());
+            let emit_count = node.emit_count;
+            let fields =
+                format!(r#""id":{id},"label":{label},"kind":{kind},"outputType":{output_type}"#);
+            format!(r#"{{{fields},"location":{location},"emitCount":{emit_count}}}"#)
+        });
+        let links = self.links.iter().map(|link| {
+            let source = usize::from(link.source);
+            let target = usize::from(link.target);
+            let tp = match link.tp {
+                LinkType::Event => "event",
+                LinkType::Behavior => "behavior",
+                LinkType::Mixed => "mixed",
+            };
+            format!(r#"{{"source":{source},"target":{target},"type":"{tp}"}}"#)
+        });
+        let label = json_string(&self.label);
+        let nodes = nodes.join(",");
+        let links = links.join(",");
+        format!(r#"{{"label":{label},"nodes":[{nodes}],"links":[{links}]}}"#)
+    }
+}
+
+/// Quote and escape the string to be used in JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for char in s.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
 
 
-//use crate::DataType;
-//
+
+// ===================
+// === EventTracer ===
+// ===================
+
+/// A single event recorded by the [`EventTracer`].
+#[derive(Clone, Debug)]
+pub struct TracedEvent {
+    /// Id of the node which emitted the event.
+    pub node:  Id,
+    /// Label of the node which emitted the event.
+    pub label: Label,
+    /// The emitted value, formatted with [`Debug`].
+    pub value: String,
+    /// The time the event was emitted at, in milliseconds. See [`time::now`].
+    pub time:  f64,
+}
+
+impl Display for TracedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>10.3}ms] {}: {}", self.time, self.label, self.value)
+    }
+}
+
+/// Whether any [`EventTracer`] was created. It is checked on every emitted event before accessing
+/// the tracer of the node, so the tracing does not slow down the networks unless it is used.
+static TRACING_USED: AtomicBool = AtomicBool::new(false);
+
+/// Check whether any [`EventTracer`] was created.
+pub(crate) fn is_tracing_used() -> bool {
+    TRACING_USED.load(Ordering::Relaxed)
+}
+
+/// Recorder of the events emitted by FRP nodes. It keeps only the given number of the most recent
+/// events, so it can be enabled for a long time without exhausting the memory.
+#[derive(Clone, CloneRef, Debug)]
+pub struct EventTracer {
+    data: Rc<RefCell<EventTracerData>>,
+}
+
+#[derive(Debug)]
+struct EventTracerData {
+    capacity: usize,
+    events:   VecDeque<TracedEvent>,
+}
+
+impl EventTracer {
+    /// Constructor. The tracer keeps at most `capacity` most recent events.
+    pub fn new(capacity: usize) -> Self {
+        let events = VecDeque::with_capacity(capacity.min(1024));
+        TRACING_USED.store(true, Ordering::Relaxed);
+        Self { data: Rc::new(RefCell::new(EventTracerData { capacity, events })) }
+    }
+
+    /// Record a new event.
+    pub fn record(&self, node: Id, label: Label, value: &dyn Debug) {
+        let mut value = format!("{value:?}");
+        if let Some((index, _)) = value.char_indices().nth(MAX_TRACED_VALUE_LENGTH) {
+            value.truncate(index);
+            value.push('…');
+        }
+        let time = time::now();
+        let mut data = self.data.borrow_mut();
+        if data.capacity == 0 {
+            return;
+        }
+        if data.events.len() == data.capacity {
+            data.events.pop_front();
+        }
+        data.events.push_back(TracedEvent { node, label, value, time });
+    }
+
+    /// All the recorded events, from the oldest to the newest.
+    pub fn events(&self) -> Vec<TracedEvent> {
+        self.data.borrow().events.iter().cloned().collect()
+    }
+
+    /// Remove all the recorded events.
+    pub fn clear(&self) {
+        self.data.borrow_mut().events.clear();
+    }
+
+    /// Format all the recorded events, one per line, from the oldest to the newest.
+    pub fn dump(&self) -> String {
+        self.data.borrow().events.iter().map(|event| event.to_string()).join("\n")
+    }
+}
+
 
 
 // ================
@@ -22,14 +274,15 @@ use crate::prelude::*;
 /// Visualization data for a nodes.
 #[derive(Debug, Clone)]
 pub struct VizNode {
+    id:      usize,
     variant: String,
     label:   String,
 }
 
 impl VizNode {
     /// Constructor
-    pub fn new(variant: String, label: String) -> Self {
-        VizNode { variant, label }
+    pub fn new(id: usize, variant: String, label: String) -> Self {
+        VizNode { id, variant, label }
     }
 }
 
@@ -37,16 +290,15 @@ impl VizNode {
 /// Visualization data for a link between nodes.
 #[derive(Debug, Clone)]
 pub struct VizLink {
-    source_display_id: usize,
-    target_display_id: usize,
-    //    message_type      : DataType,
-    data_type:         String,
+    source: usize,
+    target: usize,
+    tp:     LinkType,
 }
 
 impl VizLink {
     /// Constructor.
-    pub fn new(source_display_id: usize, target_display_id: usize, data_type: String) -> Self {
-        Self { source_display_id, target_display_id, data_type }
+    pub fn new(source: usize, target: usize, tp: LinkType) -> Self {
+        Self { source, target, tp }
     }
 }
 
@@ -54,89 +306,52 @@ impl VizLink {
 /// Graphviz FRP system visualizer.
 #[derive(Debug, Default)]
 pub struct Graphviz {
-    nodes:  HashMap<usize, VizNode>,
-    labels: HashMap<usize, String>,
-    links:  Vec<VizLink>,
+    nodes: Vec<VizNode>,
+    links: Vec<VizLink>,
 }
 
 impl Graphviz {
     /// Defines a new node.
-    pub fn add_node<Tp: Str, Label: Str>(&mut self, id: usize, tp: Tp, label: Label) {
-        let tp = tp.into();
-        let label = label.into();
-        self.nodes.insert(id, VizNode::new(tp, label.clone()));
-        self.labels.insert(id, label);
-    }
-    //
-    //    /// Defines a new link between nodes.
-    //    pub fn add_link<S:Str>
-    //    (&mut self, source:usize, target:usize, message_type:DataType, data_type:S) {
-    //        let link = VizLink::new(source,target,message_type,data_type.into());
-    //        self.links.push(link);
-    //    }
-    //
-    //    /// Checks if a node with the given id is already registered in the node map.
-    //    pub fn contains(&mut self, id:usize) -> bool {
-    //        self.nodes.contains_key(&id)
-    //    }
-    //
-    //    /// Takes a set of nodes and outputs a map from `display_id` to a particular node. In case
-    // the    /// `display_id` points to several nodes, the node types `Hold` and `Recursive`
-    // has weaker    /// preference.
-    //    fn create_node_map(&self) -> HashMap<usize,VizNode> {
-    //        let mut node_map : HashMap<usize,VizNode> = default();
-    //        for node in self.nodes.values() {
-    //            let entry        = node_map.entry(node.display_id);
-    //            let merged_entry = entry.and_modify(|node2|{
-    //                let variant = &node2.variant;
-    //                if variant == "Hold" || variant == "Recursive" {
-    //                    *node2 = node.clone();
-    //                }
-    //            });
-    //            merged_entry.or_insert_with(|| node.clone());
-    //        }
-    //        node_map
-    //    }
-    //
-    //    /// Outputs a Graphviz Dot code.
+    pub fn add_node<Tp: Str, L: Str>(&mut self, id: usize, tp: Tp, label: L) {
+        self.nodes.push(VizNode::new(id, tp.into(), label.into()));
+    }
+
+    /// Defines a new link between nodes.
+    pub fn add_link(&mut self, source: usize, target: usize, tp: LinkType) {
+        self.links.push(VizLink::new(source, target, tp));
+    }
+
+    /// Outputs a Graphviz Dot code.
     pub fn to_code(&self) -> String {
         let mut code = String::default();
-        //        let node_map = self.create_node_map();
-        //
-        for (idx, node) in self.nodes.iter() {
-            let color = match node.variant.as_str() {
+        for node in &self.nodes {
+            let kind = node.variant.split(':').next().unwrap_or_default();
+            let color = match kind {
                 "Toggle" => "534666",
-                "Gate" => "e69d45",
-                "Hold" => "308695",
-                "Lambda" => "d45769",
-                "Lambda2" => "d45769",
+                "Gate" | "GateNot" | "BufferedGate" => "e69d45",
+                "Sampler" => "308695",
+                "Map" | "Map2" | "Map3" | "Map4" => "d45769",
                 _ => "455054",
             };
             let fill = format!("[fillcolor=\"#{color}\"]");
             let spacing = "<br/><FONT POINT-SIZE=\"5\"> </FONT><br/>";
-            let variant = format!("<FONT POINT-SIZE=\"9\">{}</FONT>", node.variant);
-            let label = format!("[label=< {} {spacing} {variant} >]", node.label);
-            let line = format!("\n{idx} {fill} {label}");
+            let variant = html_escape(&node.variant);
+            let variant = format!("<FONT POINT-SIZE=\"9\">{variant}</FONT>");
+            let label = format!("[label=< {} {spacing} {variant} >]", html_escape(&node.label));
+            let line = format!("\n{} {fill} {label}", node.id);
+            code.push_str(&line);
+        }
+        for link in &self.links {
+            let source = link.source;
+            let target = link.target;
+            let style = match link.tp {
+                LinkType::Event => "",
+                LinkType::Behavior => "[style=\"dashed\"]",
+                LinkType::Mixed => "[style=\"bold\"]",
+            };
+            let line = format!("\n{source} -> {target} {style}");
             code.push_str(&line);
         }
-        //
-        //        for link in &self.links {
-        //            let source    = &link.source_display_id;
-        //            let target    = &link.target_display_id;
-        //            let data_type = &link.data_type;
-        //            let not_loop  = source != target;
-        //            if not_loop {
-        //                let style = match link.message_type {
-        //                    DataType::Behavior => "[style=\"dashed\"]",
-        //                    _ => ""
-        //                };
-        //                let label = if data_type == "()" { "" } else { &data_type };
-        //                let label = format!("[label=\"  {label}\"]");
-        //                let line  = format!("\n{source} -> {target} {style} {label}");
-        //                code.push_str(&line);
-        //            }
-        //        }
-        //
         let fonts = "[fontname=\"Helvetica Neue\" fontsize=11]";
         let node_shape = "[shape=box penwidth=0 margin=0.12 style=\"rounded,filled\"]";
         let node_style = "[fontcolor=white fillcolor=\"#5397dc\"]";
@@ -154,6 +369,11 @@ impl From<Graphviz> for String {
     }
 }
 
+/// Escape the text to be used in the Graphviz HTML-like labels.
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
 
 
 // =======================
@@ -174,15 +394,13 @@ pub trait GraphvizBuilder {
 
     /// Converts the current object to Graphviz and displays it in a new tab in a web browser.
     fn display_graphviz(&self) {
-        let code = self.to_graphviz();
-        let url = percent_encoding::utf8_percent_encode(&code, percent_encoding::NON_ALPHANUMERIC);
-        let url = format!("https://dreampuf.github.io/GraphvizOnline/#{url}");
-        crate::web::window.open_with_url_and_target(&url, "_blank").unwrap();
+        display_graphviz(self.to_graphviz());
     }
 }
 
-pub fn display_graphviz(viz: Graphviz) {
-    let code: String = viz.into();
+/// Display the Graphviz Dot code in a new tab in a web browser.
+pub fn display_graphviz(code: impl Into<String>) {
+    let code: String = code.into();
     let url = percent_encoding::utf8_percent_encode(&code, percent_encoding::NON_ALPHANUMERIC);
     let url = format!("https://dreampuf.github.io/GraphvizOnline/#{url}");
     crate::web::window.open_with_url_and_target(&url, "_blank").unwrap();
@@ -198,3 +416,98 @@ where
         self.content().graphviz_build(builder)
     }
 }
+
+/// Exports the network to the Graphviz Dot code. The behavior links are drawn dashed, and the
+/// links being both event and behavior ones are drawn bold.
+impl GraphvizBuilder for NetworkInfo {
+    fn graphviz_build(&self, builder: &mut Graphviz) {
+        for node in &self.nodes {
+            let variant = format!("{}: {}", node.kind, node.output_type);
+            builder.add_node(node.id.into(), variant, node.label);
+        }
+        for link in &self.links {
+            builder.add_link(link.source.into(), link.target.into(), link.tp);
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::network::Network;
+    use crate::virtual_time::VirtualTime;
+
+    #[test]
+    fn introspection() {
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let flag = network.source::<bool>("flag");
+        let doubled = network.map("doubled", &source, |t| t * 2);
+        let gated = network.gate("gated", &doubled, &flag);
+        let info = network.introspect();
+        assert_eq!(info.label, "network");
+        let labels = info.nodes.iter().map(|node| node.label).collect_vec();
+        assert_eq!(labels, ["source", "flag", "doubled", "gated"]);
+        let doubled_info = info.node("doubled").unwrap();
+        assert_eq!(doubled_info.kind, "Map");
+        assert_eq!(doubled_info.output_type, "i32");
+        let gated_id = gated.id();
+        let inputs = info.inputs(gated_id).map(|node| node.label).sorted().collect_vec();
+        assert_eq!(inputs, ["doubled", "flag"]);
+        let outputs = info.outputs(source.id()).map(|node| node.label).collect_vec();
+        assert_eq!(outputs, ["doubled"]);
+        let flag_link = info.links.iter().find(|link| link.source == flag.id()).unwrap();
+        assert!(matches!(flag_link.tp, LinkType::Behavior));
+    }
+
+    #[test]
+    fn exporting() {
+        let network = Network::new("net\"work");
+        let source = network.source::<Vec<i32>>("source");
+        let count = network.count("count", &source);
+        let info = network.introspect();
+        let source_id = usize::from(source.id());
+        let count_id = usize::from(count.id());
+        let graphviz = info.to_graphviz();
+        assert!(graphviz.contains(&format!("\n{source_id} -> {count_id} ")));
+        assert!(graphviz.contains("Source: Vec&lt;i32&gt;"));
+        let json = info.to_json();
+        let expected_link =
+            format!(r#"{{"source":{source_id},"target":{count_id},"type":"event"}}"#);
+        assert!(json.starts_with(r#"{"label":"net\"work","nodes":[{"id":"#));
+        assert!(json.contains(r#""label":"count","kind":"Count","outputType":"usize","location""#));
+        assert!(json.contains(r#"debug.rs:"#));
+        assert!(json.contains(&expected_link));
+    }
+
+    #[test]
+    fn tracing_events() {
+        let time = VirtualTime::new();
+        let network = Network::new("network");
+        let source = network.source::<i32>("source");
+        let tracer = network.trace_events(3);
+        let doubled = network.map("doubled", &source, |t| t * 2);
+        source.emit(1);
+        time.advance_time(10.0);
+        source.emit(2);
+        let events = tracer.events();
+        let recorded = events.iter().map(|e| (e.label, e.value.as_str(), e.time)).collect_vec();
+        assert_eq!(recorded, [
+            ("doubled", "2", 0.0),
+            ("source", "2", 10.0),
+            ("doubled", "4", 10.0)
+        ]);
+        assert_eq!(events[2].node, doubled.id());
+        assert!(tracer.dump().ends_with("doubled: 4"));
+        network.stop_tracing_events();
+        source.emit(3);
+        assert_eq!(tracer.events().len(), 3);
+    }
+}
@@ -166,6 +166,7 @@
 #![feature(downcast_unchecked)]
 #![recursion_limit = "512"]
 
+pub mod analysis;
 pub mod any_data;
 pub mod data;
 pub mod debug;
@@ -173,11 +174,14 @@ pub mod fan;
 pub mod future;
 pub mod io;
 pub mod macros;
+pub mod marbles;
 pub mod microtasks;
 pub mod network;
 pub mod node;
 pub mod nodes;
 pub mod stream;
+pub mod time;
+pub mod virtual_time;
 
 pub use network::*;
 pub use node::*;
@@ -0,0 +1,449 @@
+//! Marble-diagram assertions for time-dependent FRP networks.
+//!
+//! A marble diagram describes the events flowing through the network in time, using the same
+//! notation as the diagrams in the [`crate::nodes`] docs:
+//!
+//! ```text
+//! Input:       ───────1──2─3───────────
+//! Microtasks:  ── ▶──── ▶──── ▶──── ▶──
+//! Output:      ─────────1─────3────────
+//! ```
+//!
+//! Every row starts with a label, followed by a colon. The rest of the row is a timeline, in which
+//! every character column is a single step. The `─`, `-` and space characters denote steps
+//! without events. Any other run of characters is an event, which happens at the column of its
+//! first character. Brackets are balanced, so `(1,-2)` or `[1, 2]` are single events.
+//!
+//! The rows are interpreted as follows:
+//! - `Microtasks` – the microtasks are flushed at every marked column.
+//! - `Frames` – a single animation frame is performed at every marked column.
+//! - An input row – the event value is emitted to the input at its column.
+//! - An output row – the events emitted by the output at the given column. The values are formatted
+//!   with [`Debug`], with all whitespace removed. Multiple values emitted in the same column are
+//!   concatenated, so the row also asserts the order of the events.
+//!
+//! In every column, the inputs are emitted first, then the microtasks are flushed, and then the
+//! frame is performed. The steps are driven by the [`VirtualTime`], so the diagrams are fully
+//! deterministic.
+//!
+//! By default, the time does not pass between the columns. To test nodes measuring the wall-clock
+//! time, like [`Network::throttle`], use [`Marbles::column_duration`]. The time is then advanced
+//! at the beginning of every column, firing all the timers that are due at that column.
+
+use crate::prelude::*;
+
+use crate::network::Network;
+use crate::node::Data;
+use crate::node::Output;
+use crate::nodes::Source;
+use crate::stream::EventOutput;
+use crate::virtual_time::VirtualTime;
+
+use std::str::FromStr;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Label of the row marking the microtask flushes.
+pub const MICROTASKS_LABEL: &str = "Microtasks";
+
+/// Label of the row marking the animation frames.
+pub const FRAMES_LABEL: &str = "Frames";
+
+
+
+// ===========
+// === Row ===
+// ===========
+
+/// A single, parsed row of a marble diagram.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Row {
+    label:  String,
+    events: Vec<(usize, String)>,
+}
+
+impl Row {
+    fn parse(line: &str) -> Row {
+        let line = line.trim_start();
+        let (label, _) = line
+            .split_once(':')
+            .unwrap_or_else(|| panic!("Marble diagram row \"{line}\" has no label."));
+        let timeline = line.chars().enumerate().skip(label.chars().count() + 1);
+        let mut events: Vec<(usize, String)> = default();
+        let mut current: Option<(usize, String)> = None;
+        let mut depth = 0_usize;
+        for (column, char) in timeline {
+            let is_blank = depth == 0 && matches!(char, '─' | '-' | ' ');
+            if is_blank {
+                events.extend(current.take());
+            } else {
+                match char {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                current.get_or_insert_with(|| (column, default())).1.push(char);
+            }
+        }
+        events.extend(current);
+        Row { label: label.trim().into(), events }
+    }
+
+    fn event_at(&self, column: usize) -> Option<&str> {
+        self.events.iter().find(|(c, _)| *c == column).map(|(_, event)| event.as_str())
+    }
+
+    fn end(&self) -> usize {
+        self.events.iter().map(|(column, event)| column + event.chars().count()).max().unwrap_or(0)
+    }
+
+    /// Render the timeline of the row, starting at the given column.
+    fn render(&self, start: usize) -> String {
+        let mut out = String::new();
+        let mut column = start;
+        for (event_column, event) in &self.events {
+            let padding = event_column.saturating_sub(column);
+            out.extend(iter::repeat('─').take(padding));
+            out.push_str(event);
+            column = column.max(*event_column) + event.chars().count();
+        }
+        out
+    }
+}
+
+
+
+// ===============
+// === Marbles ===
+// ===============
+
+type Recorded = Rc<RefCell<Vec<String>>>;
+
+/// Marble-diagram test of an FRP network. See the module docs to learn more.
+///
+/// ```text
+/// let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+/// marbles.assert("
+///     Input:       ───────1──2─3───────────
+///     Microtasks:  ── ▶──── ▶──── ▶──── ▶──
+///     Output:      ─────────1─────3────────
+/// ");
+/// ```
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct Marbles {
+    network:         Network,
+    time:            VirtualTime,
+    column_duration: Option<f64>,
+    #[derivative(Debug = "ignore")]
+    inputs:          Vec<(String, Box<dyn Fn(&str)>)>,
+    outputs:         Vec<(String, Recorded)>,
+}
+
+impl Marbles {
+    /// Constructor. Activates the virtual time for the lifetime of the test.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let network = Network::new("marbles");
+        let time = VirtualTime::new();
+        let column_duration = default();
+        Self { network, time, column_duration, inputs: default(), outputs: default() }
+    }
+
+    /// Set the time passing between two columns of the diagram, in milliseconds.
+    pub fn column_duration(mut self, duration_ms: f64) -> Self {
+        self.column_duration = Some(duration_ms);
+        self
+    }
+
+    /// The virtual time driving the test.
+    pub fn time(&self) -> &VirtualTime {
+        &self.time
+    }
+
+    /// Register an input row. The events of the row are parsed with [`FromStr`] and emitted to
+    /// the `source`.
+    pub fn input<T>(self, label: &str, source: &Source<T>) -> Self
+    where
+        T: Data + FromStr,
+        T::Err: Debug, {
+        let source = source.clone_ref();
+        self.input_with(label, move |event| {
+            let value = event.parse::<T>();
+            let value = value.unwrap_or_else(|e| panic!("Cannot parse event \"{event}\": {e:?}."));
+            source.emit(value)
+        })
+    }
+
+    /// Register an input row. The `f` function is called with every event of the row.
+    pub fn input_with(mut self, label: &str, f: impl Fn(&str) + 'static) -> Self {
+        self.inputs.push((label.into(), Box::new(f)));
+        self
+    }
+
+    /// Register an output row, recording all the events emitted by the `stream`.
+    pub fn output<T>(mut self, label: &str, stream: &T) -> Self
+    where T: EventOutput {
+        let recorded: Recorded = default();
+        let events = recorded.clone_ref();
+        self.network.map("marbles_output", stream, move |value: &Output<T>| {
+            let event = format!("{value:?}").split_whitespace().collect::<String>();
+            events.borrow_mut().push(event);
+        });
+        self.outputs.push((label.into(), recorded));
+        self
+    }
+
+    /// Run the diagram and assert that the outputs emitted the events described by their rows.
+    /// The registered outputs not present in the diagram are not checked.
+    ///
+    /// # Panics
+    /// Panics if the outputs do not match the diagram, or if the diagram contains a row of unknown
+    /// label.
+    #[track_caller]
+    pub fn assert(&self, diagram: &str) {
+        let rows = diagram.lines().filter(|line| !line.trim().is_empty()).map(Row::parse);
+        let rows = rows.collect_vec();
+        let is_registered = |label: &str| {
+            let is_input = self.inputs.iter().any(|(input, _)| input == label);
+            let is_output = self.outputs.iter().any(|(output, _)| output == label);
+            is_input || is_output || label == MICROTASKS_LABEL || label == FRAMES_LABEL
+        };
+        if let Some(row) = rows.iter().find(|row| !is_registered(&row.label)) {
+            panic!("Unknown marble diagram row \"{}\".", row.label);
+        }
+        let row = |label: &str| rows.iter().find(|row| row.label == label);
+        let end = rows.iter().map(Row::end).max().unwrap_or(0);
+        let mut actual = self
+            .outputs
+            .iter()
+            .map(|(label, _)| Row { label: label.clone(), events: default() })
+            .collect_vec();
+        for (_, recorded) in &self.outputs {
+            recorded.borrow_mut().clear();
+        }
+        for column in 0..end {
+            if let Some(duration) = self.column_duration.filter(|_| column > 0) {
+                self.time.advance_time(duration);
+            }
+            for (label, emit) in &self.inputs {
+                if let Some(event) = row(label).and_then(|row| row.event_at(column)) {
+                    emit(event);
+                }
+            }
+            if row(MICROTASKS_LABEL).and_then(|row| row.event_at(column)).is_some() {
+                self.time.flush_microtasks();
+            }
+            if row(FRAMES_LABEL).and_then(|row| row.event_at(column)).is_some() {
+                self.time.advance_frames(1);
+            }
+            for ((_, recorded), actual) in self.outputs.iter().zip(&mut actual) {
+                let events = mem::take(&mut *recorded.borrow_mut());
+                if !events.is_empty() {
+                    actual.events.push((column, events.concat()));
+                }
+            }
+        }
+        let start = rows.iter().filter_map(|row| row.events.first()).map(|(c, _)| *c).min();
+        let start = start.unwrap_or(0);
+        let mismatches = actual.iter().filter_map(|actual| {
+            let expected = row(&actual.label)?;
+            (expected.events != actual.events).then(|| {
+                let label = &actual.label;
+                let expected = expected.render(start);
+                let actual = actual.render(start);
+                format!("{label}:\n  expected: {expected}\n  actual:   {actual}")
+            })
+        });
+        let mismatches = mismatches.collect_vec();
+        if !mismatches.is_empty() {
+            panic!("Marble diagram mismatch.\n{}", mismatches.join("\n"));
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::virtual_time;
+
+    #[test]
+    fn parsing_rows() {
+        let row = Row::parse("  Input:  ──1─(1,-2)──[2, 3] x");
+        let expected = vec![(10, "1".into()), (12, "(1,-2)".into()), (20, "[2, 3]".into())];
+        assert_eq!(row.label, "Input");
+        assert_eq!(row.events[..3], expected);
+        assert_eq!(row.events[3], (27, "x".into()));
+    }
+
+    #[test]
+    fn debounce() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.debounce("output", &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.assert(
+            "
+            Input:       ───────1──2─3───────────
+            Microtasks:  ── ▶──── ▶──── ▶──── ▶──
+            Output:      ─────────1─────3────────
+            ",
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.batch("output", &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.assert(
+            "
+            Input:       ───────1────2─3────────────
+            Microtasks:  ── ▶───── ▶───── ▶───── ▶──
+            Output:      ──────────[1]────[2,3]─────
+            ",
+        );
+    }
+
+    #[test]
+    fn throttle() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.throttle("output", 30.0, &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.column_duration(10.0).assert(
+            "
+            Input:   ─1─2────────3─4─5─────
+            Output:  ─1──2───────3──4──5───
+            ",
+        );
+    }
+
+    #[test]
+    fn delay() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.delay("output", 30.0, &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.column_duration(10.0).assert(
+            "
+            Input:   ─1─2─────3─────
+            Output:  ────1─2─────3──
+            ",
+        );
+    }
+
+    #[test]
+    fn debounce_time() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.debounce_time("output", 30.0, &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.column_duration(10.0).assert(
+            "
+            Input:   ─1─2─────3─4─5─────
+            Output:  ──────2─────────5──
+            ",
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.timeout("output", 30.0, &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.column_duration(10.0).assert(
+            "
+            Input:   ─1─2─────3─4─5─────
+            Output:  ──────()────────()─
+            ",
+        );
+    }
+
+    #[test]
+    fn sample_every() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.sample_every("output", 2, &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.assert(
+            "
+            Input:   ─1──2─────3───4──────
+            Frames:  ───|──|──|──|──|──|──
+            Output:  ──────2─────3─────4──
+            ",
+        );
+    }
+
+    #[test]
+    fn sample_every_counts_frames_only_when_needed() {
+        let time = VirtualTime::new();
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let _output = network.sample_every("output", 2, &input);
+        time.advance_frames(1);
+        assert!(!crate::time::has_frame_callbacks());
+        input.emit(1);
+        assert!(crate::time::has_frame_callbacks());
+        time.advance_frames(6);
+        assert!(!crate::time::has_frame_callbacks());
+    }
+
+    #[test]
+    fn dropping_network_cancels_timers() {
+        let time = VirtualTime::new();
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.delay("output", 10.0, &input);
+        let recorded: Rc<RefCell<Vec<i32>>> = default();
+        network.map("record", &output, f!((value) recorded.borrow_mut().push(*value)));
+        input.emit(1);
+        drop(network);
+        time.advance_time(20.0);
+        assert!(recorded.borrow().is_empty());
+    }
+
+    #[test]
+    fn frames() {
+        let network = Network::new("network");
+        let frame = network.source::<f64>("frame");
+        let _handle = virtual_time::on_frame(f!((time) frame.emit(time)));
+        let marbles = Marbles::new().output("Frame", &frame);
+        marbles.time().set_frame_duration(10.0);
+        marbles.assert(
+            "
+            Frames: ──|─────|─────|──
+            Frame:  ──10.0──20.0──30.0
+            ",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Marble diagram mismatch")]
+    fn mismatch() {
+        let network = Network::new("network");
+        let input = network.source::<i32>("input");
+        let output = network.debounce("output", &input);
+        let marbles = Marbles::new().input("Input", &input).output("Output", &output);
+        marbles.assert(
+            "
+            Input:       ───1─2───
+            Microtasks:  ───────▶─
+            Output:      ───────1─
+            ",
+        );
+    }
+}
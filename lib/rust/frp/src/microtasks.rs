@@ -73,10 +73,18 @@
 //! including all tasks enqueued within those tasks. That means all FRP nodes that use the scheduler
 //! (e.g. `debounce` or `batch`) will be guaranteed to be fully processed before the rendering logic
 //! is executed.
+//!
+//! # Testing
+//!
+//! In tests, the scheduler can be driven by the [`crate::virtual_time::VirtualTime`] instead of
+//! the JavaScript event loop. The scheduled tasks are then performed only when the test explicitly
+//! flushes them or advances the virtual time.
 
 use crate::prelude::*;
 use enso_callback::traits::*;
 
+use crate::virtual_time;
+
 use enso_callback as callback;
 use enso_generics::Cons;
 use enso_generics::Nil;
@@ -214,6 +222,10 @@ struct SchedulerData {
 
 impl SchedulerData {
     fn schedule_task(&self) {
+        // With the virtual time, the tasks are performed only when explicitly flushed.
+        if virtual_time::is_active() {
+            return;
+        }
         if !self.is_scheduled.replace(true) {
             // Result left unused on purpose. We only care about `closure` being run in the next
             // microtask, which is a guaranteed side effect of providing it to [`Promise::then`]
@@ -228,6 +240,10 @@ impl SchedulerData {
     }
 
     fn run_all(&self) {
+        if virtual_time::is_active() {
+            self.is_scheduled.set(false);
+            return;
+        }
         let current_count = self.schedule_depth.get();
         let task_limit_reached = current_count >= MAX_RECURSIVE_MICROTASKS;
         if task_limit_reached {
@@ -3,10 +3,18 @@
 use crate::node::*;
 use crate::prelude::*;
 
+use crate::analysis;
 use crate::debug;
+use crate::debug::GraphvizBuilder;
 use crate::stream;
+use crate::stream::HasOutputTypeLabel;
+use crate::stream::InputBehaviors;
+use crate::stream::Introspect;
+use crate::stream::NodeRole;
 use crate::stream::Stream;
 
+use std::panic::Location;
+
 
 
 // ==========
@@ -40,21 +48,26 @@ pub struct WeakNetwork {
 }
 
 /// Network item.
-pub trait Item: HasId + HasLabel + stream::HasOutputTypeLabel {}
-impl<T> Item for T where T: HasId + HasLabel + stream::HasOutputTypeLabel {}
+pub trait Item: HasId + HasLabel + HasOutputTypeLabel + InputBehaviors + Introspect {}
+impl<T> Item for T where T: HasId + HasLabel + HasOutputTypeLabel + InputBehaviors + Introspect {}
 
 /// Internal data of `Network`.
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct NetworkData {
     /// Label of the network.
-    pub label: String,
+    pub label:                String,
     #[derivative(Debug = "ignore")]
-    nodes:     RefCell<Vec<Box<dyn Item>>>,
-    links:     RefCell<HashMap<Id, Link>>,
-    bridges:   RefCell<Vec<BridgeNetwork>>,
+    nodes:                    RefCell<Vec<Box<dyn Item>>>,
+    links:                    RefCell<HashMap<Id, Link>>,
+    bridges:                  RefCell<Vec<BridgeNetwork>>,
     /// Used as a convenient storage of data associated with network, like animation instances.
-    storage:   RefCell<Vec<Box<dyn Any>>>,
+    storage:                  RefCell<Vec<Box<dyn Any>>>,
+    /// The tracer recording events of all nodes, if enabled with [`Network::trace_events`].
+    tracer:                   RefCell<Option<debug::EventTracer>>,
+    /// Whether the cycle analysis is scheduled, see [`Network::schedule_cycle_analysis`].
+    #[cfg(debug_assertions)]
+    cycle_analysis_scheduled: Cell<bool>,
 }
 
 
@@ -67,7 +80,17 @@ impl NetworkData {
         let links = default();
         let bridges = default();
         let storage = default();
-        Self { label, nodes, links, bridges, storage }
+        let tracer = default();
+        Self {
+            label,
+            nodes,
+            links,
+            bridges,
+            storage,
+            tracer,
+            #[cfg(debug_assertions)]
+            cycle_analysis_scheduled: default(),
+        }
     }
 }
 
@@ -120,17 +143,21 @@ impl Network {
         self.data.storage.borrow_mut().push(item);
     }
 
-    /// Register the node and return its weak reference.
+    /// Register the node and return its weak reference. In debug builds, the caller location is
+    /// remembered as the node location, see [`Introspect::location`].
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn register_raw<T: HasOutputStatic>(&self, node: stream::Node<T>) -> stream::WeakNode<T> {
         let weak = node.downgrade();
-        self.register_boxed(Box::new(node));
+        self.register_boxed(Box::new(node), caller_location());
         weak
     }
 
-    /// Register the node and return a new `Stream` reference.
+    /// Register the node and return a new `Stream` reference. In debug builds, the caller location
+    /// is remembered as the node location, see [`Introspect::location`].
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn register<Def: HasOutputStatic>(&self, node: stream::Node<Def>) -> Stream<Output<Def>> {
         let stream = node.clone_ref().into();
-        self.register_boxed(Box::new(node));
+        self.register_boxed(Box::new(node), caller_location());
         stream
     }
 
@@ -139,8 +166,38 @@ impl Network {
     /// Force the compiler to never inline this function, so the generated code (including two panic
     /// handlers) can be shared across all registered nodes independent of their type.
     #[inline(never)]
-    fn register_boxed(&self, node: Box<dyn Item>) {
+    fn register_boxed(&self, node: Box<dyn Item>, location: Option<&'static Location<'static>>) {
+        if let Some(location) = location {
+            node.set_location(location);
+        }
+        if let Some(tracer) = &*self.data.tracer.borrow() {
+            node.set_tracer(Some(tracer.clone_ref()));
+        }
         self.data.nodes.borrow_mut().push(node);
+        #[cfg(debug_assertions)]
+        self.schedule_cycle_analysis();
+    }
+
+    /// Log a warning describing the synchronous cycles of this network in the next microtask, that
+    /// is, once the network construction is finished. Other issues found by [`Network::analyze`]
+    /// are not reported automatically, as they are often intended, like unused inputs of
+    /// component APIs. Scheduled on every node registration in debug builds.
+    #[cfg(debug_assertions)]
+    fn schedule_cycle_analysis(&self) {
+        if !self.data.cycle_analysis_scheduled.replace(true) {
+            let weak = self.downgrade();
+            let handle = crate::microtasks::next_microtask(move || {
+                if let Some(network) = weak.upgrade() {
+                    network.data.cycle_analysis_scheduled.set(false);
+                    let mut report = network.analyze();
+                    report.issues.retain(|issue| matches!(issue, analysis::Issue::Cycle(_)));
+                    if !report.is_empty() {
+                        warn!("{report}");
+                    }
+                }
+            });
+            handle.forget();
+        }
     }
 
     /// Register a new link between nodes. Visualization purposes only.
@@ -155,12 +212,98 @@ impl Network {
 
     /// Draw the network using GraphViz.
     pub fn draw(&self) {
-        let mut viz = debug::Graphviz::default();
-        self.data.nodes.borrow().iter().for_each(|node| {
-            viz.add_node(node.id().into(), node.output_type_label(), node.label());
-        });
-        debug::display_graphviz(viz);
+        self.introspect().display_graphviz();
+    }
+
+    /// Get a snapshot of the network structure: all of its nodes and the links between them. See
+    /// the [`debug`] module to learn more.
+    pub fn introspect(&self) -> debug::NetworkInfo {
+        let label = self.data.label.clone();
+        let nodes = self.data.nodes.borrow();
+        let ids: HashSet<Id> = nodes.iter().map(|node| node.id()).collect();
+        let mut links: Vec<debug::LinkInfo> = default();
+        for node in nodes.iter() {
+            let source = node.id();
+            let tp = LinkType::Event;
+            let targets = node.target_ids().into_iter().filter(|target| ids.contains(target));
+            links.extend(targets.map(|target| debug::LinkInfo { source, target, tp }));
+        }
+        for node in nodes.iter() {
+            let target = node.id();
+            for link in node.input_behaviors() {
+                let existing =
+                    links.iter_mut().find(|l| l.source == link.source && l.target == target);
+                match existing {
+                    Some(existing) => existing.tp = link.tp,
+                    None if ids.contains(&link.source) =>
+                        links.push(debug::LinkInfo { source: link.source, target, tp: link.tp }),
+                    None => {}
+                }
+            }
+        }
+        let nodes = nodes
+            .iter()
+            .map(|node| debug::NodeInfo {
+                id:               node.id(),
+                label:            node.label(),
+                kind:             node.output_type_label(),
+                output_type:      node.output_type_name(),
+                location:         node.location(),
+                emit_count:       node.emit_count(),
+                target_count:     node.target_ids().len(),
+                is_watched:       node.is_watched(),
+                is_source:        node.is_source(),
+                breaks_cycles:    node.breaks_cycles(),
+                has_side_effects: node.has_side_effects(),
+            })
+            .collect();
+        debug::NetworkInfo { label, nodes, links }
+    }
+
+    /// Record all the events emitted by the nodes of this network, including the nodes registered
+    /// later. Only the `capacity` most recent events are kept. Replaces the previously enabled
+    /// tracer, if any. See the [`debug`] module to learn more.
+    pub fn trace_events(&self, capacity: usize) -> debug::EventTracer {
+        let tracer = debug::EventTracer::new(capacity);
+        self.set_tracer(Some(tracer.clone_ref()));
+        tracer
     }
+
+    /// Stop recording the events enabled with [`Network::trace_events`].
+    pub fn stop_tracing_events(&self) {
+        self.set_tracer(None);
+    }
+
+    /// Find the synchronous cycles, unconsumed nodes, and never emitted sources of this network.
+    /// See the [`analysis`] module to learn more.
+    pub fn analyze(&self) -> analysis::Report {
+        analysis::analyze(&self.introspect())
+    }
+
+    /// Log a warning describing the issues found by [`Network::analyze`], if any. Does nothing in
+    /// the release builds, so it can be called after setting up every network.
+    pub fn warn_about_issues(&self) {
+        if cfg!(debug_assertions) {
+            let report = self.analyze();
+            if !report.is_empty() {
+                warn!("{report}");
+            }
+        }
+    }
+
+    fn set_tracer(&self, tracer: Option<debug::EventTracer>) {
+        for node in self.data.nodes.borrow().iter() {
+            node.set_tracer(tracer.clone());
+        }
+        *self.data.tracer.borrow_mut() = tracer;
+    }
+}
+
+/// The location of the caller of the registering function. Known in debug builds only, as
+/// tracking the caller of every node constructor makes the release builds larger and slower.
+#[cfg_attr(debug_assertions, track_caller)]
+fn caller_location() -> Option<&'static Location<'static>> {
+    cfg!(debug_assertions).then_some(Location::caller())
 }
 
 impl WeakNetwork {
@@ -21,8 +21,10 @@ use crate::stream::EventOutput;
 use crate::stream::OwnedStream;
 use crate::stream::Stream;
 use crate::stream::ValueProvider;
+use crate::time;
 
 use enso_generics as generics;
+use std::collections::VecDeque;
 
 
 
@@ -34,11 +36,13 @@ impl Network {
     /// Begin point in the FRP network. It does not accept inputs, but it is able to emit events.
     /// Often it is used to indicate that something happened, like a button was pressed. In such
     /// case its type parameter is set to an empty tuple.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn source<T: Data>(&self, label: Label) -> Source<T> {
         self.register_raw(OwnedSource::new(label))
     }
 
     /// Starting point in the FRP network. Specialized version of `source`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn source_(&self, label: Label) -> Source {
         self.register_raw(OwnedSource::new(label))
     }
@@ -47,11 +51,13 @@ impl Network {
     /// node, so its drop timing can be precisely controlled. It is not automatically retained by
     /// the network. When the source is cloned, the event will only be emitted after all clones are
     /// dropped.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn on_drop(&self, label: Label) -> DropSource {
         DropSource::new(label)
     }
 
     /// Remember the last event value and allow sampling it anytime.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn sampler<T, Out>(&self, label: Label, src: &T) -> Sampler<Out>
     where
         T: EventOutput<Output = Out>,
@@ -60,11 +66,13 @@ impl Network {
     }
 
     /// Print the incoming events to console and pass them to output.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn trace<T: EventOutput>(&self, label: Label, src: &T) -> Stream<Output<T>> {
         self.register(OwnedTrace::new(label, src))
     }
 
     /// Print the incoming events to console and pass them to output.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn trace_if<B, T>(&self, label: Label, src: &T, gate: &B) -> Stream<Output<T>>
     where
         B: EventOutput<Output = bool>,
@@ -74,6 +82,7 @@ impl Network {
 
     /// Profile the event resolution from this node onwards and log the result in the profiling
     /// framework.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn profile<T: EventOutput>(&self, label: Label, src: &T) -> Stream<Output<T>> {
         self.register(OwnedProfile::new(label, src))
     }
@@ -88,6 +97,7 @@ impl Network {
     /// ```
     /// Now, we can safely attach any events to `mouse_on_up` and we can be sure that all these
     /// events will be handled before events attached to `mouse_on_up_cleaning_phase`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn identity<T, V>(&self, label: Label, t: &T) -> Stream<V>
     where
         T: EventOutput<Output = V>,
@@ -97,33 +107,39 @@ impl Network {
 
     /// Emits `true`, `false`, `true`, `false`, ... on every incoming event. Initialized with false
     /// value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn toggle<T: EventOutput>(&self, label: Label, src: &T) -> Stream<bool> {
         self.register(OwnedToggle::new(label, src))
     }
 
     /// Emits `false`, `true`, `false`, `true`, ... on every incoming event. Initialized with true
     /// value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn toggle_true<T: EventOutput>(&self, label: Label, src: &T) -> Stream<bool> {
         self.register(OwnedToggle::new_with(label, src, true))
     }
 
     /// Count the incoming events.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn count<T: EventOutput>(&self, label: Label, src: &T) -> Stream<usize> {
         self.register(OwnedCount::new(label, src))
     }
 
     /// Replace the incoming event with the predefined value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn constant<X: Data, T: EventOutput>(&self, label: Label, src: &T, value: X) -> Stream<X> {
         self.register(OwnedConstant::new(label, src, value))
     }
 
     /// Remembers the value of the input stream and outputs the previously received one.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn previous<T: EventOutput>(&self, label: Label, src: &T) -> Stream<Output<T>> {
         self.register(OwnedPrevious::new(label, src))
     }
 
     /// Samples the first stream (behavior) on every incoming event of the second stream. The
     /// incoming event is dropped and a new event with the behavior's value is emitted.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn sample<T1: EventOutput, T2: EventOutput>(
         &self,
         label: Label,
@@ -135,6 +151,7 @@ impl Network {
 
     /// Passes the incoming event of the first stream only if the value of the second stream is
     /// true.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn gate<T1, T2>(&self, label: Label, event: &T1, behavior: &T2) -> Stream<Output<T1>>
     where
         T1: EventOutput,
@@ -142,6 +159,7 @@ impl Network {
         self.register(OwnedGate::new(label, event, behavior))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn sampled_gate<T1, T2>(
         &self,
         label: Label,
@@ -158,6 +176,7 @@ impl Network {
         self.any(label, &value, &value2)
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn sampled_gate_not<T1, T2>(
         &self,
         label: Label,
@@ -175,6 +194,7 @@ impl Network {
     }
 
     /// Like `gate` but passes the value when the condition is `false`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn gate_not<T1, T2>(&self, label: Label, event: &T1, behavior: &T2) -> Stream<Output<T1>>
     where
         T1: EventOutput,
@@ -190,6 +210,7 @@ impl Network {
     /// Behavior: T---F---T-----F-------T---T---F---T--
     /// Event:    --1--2-----3---4-5-6-----------------
     /// Output:   --1-----2--3----------6--------------
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn buffered_gate<T1, T2>(
         &self,
         label: Label,
@@ -209,6 +230,7 @@ impl Network {
     /// Event:  1---2---3-----4-------5---6---7---8--
     /// Sync:   --|--------|---|---|-----------------
     /// Output: ----2---------4-------5--------------
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn sync_gate<T, T2>(&self, label: Label, event: &T, sync: &T2) -> Stream<Output<T>>
     where
         T: EventOutput,
@@ -219,6 +241,7 @@ impl Network {
     }
 
     /// Unwraps the value of incoming events and emits the unwrapped values.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn unwrap<T, S>(&self, label: Label, event: &T) -> Stream<S>
     where
         T: EventOutput<Output = Option<S>>,
@@ -227,6 +250,7 @@ impl Network {
     }
 
     /// On every incoming event, iterate over its value and emit each element separately.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn iter<T1, X>(&self, label: Label, event: &T1) -> Stream<X>
     where
         T1: EventOutput,
@@ -274,6 +298,7 @@ impl Network {
     /// ```
     ///
     /// Note: See documentation of [`crate::microtasks`] module for more details about microtasks.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn debounce<T>(&self, label: Label, event: &T) -> Stream<Output<T>>
     where T: EventOutput {
         self.register(OwnedDebounce::new(label, event))
@@ -293,13 +318,102 @@ impl Network {
     /// ```
     ///
     /// Note: See documentation of [`crate::microtasks`] module for more details about microtasks.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn batch<T>(&self, label: Label, input: &T) -> Stream<Vec<Output<T>>>
     where T: EventOutput {
         self.register(OwnedBatch::new(label, input))
     }
 
+    /// Emit the incoming event immediately and ignore the following ones for `duration_ms`
+    /// milliseconds. The last event received in that period is emitted once it ends, starting a
+    /// new period. In the diagram below, every column is 10ms and `duration_ms` is 30.
+    ///
+    /// ```text
+    /// Input:   ─1─2────────3─4─5─────
+    /// Output:  ─1──2───────3──4──5───
+    /// ```
+    ///
+    /// Note: See documentation of [`crate::time`] module for more details about the time sources.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn throttle<T>(&self, label: Label, duration_ms: f64, event: &T) -> Stream<Output<T>>
+    where T: EventOutput {
+        self.register(OwnedThrottle::new(label, duration_ms, event))
+    }
+
+    /// Emit every incoming event after `duration_ms` milliseconds. The order of events is
+    /// preserved. In the diagram below, every column is 10ms and `duration_ms` is 30.
+    ///
+    /// ```text
+    /// Input:   ─1─2─────3─────
+    /// Output:  ────1─2─────3──
+    /// ```
+    ///
+    /// Note: See documentation of [`crate::time`] module for more details about the time sources.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn delay<T>(&self, label: Label, duration_ms: f64, event: &T) -> Stream<Output<T>>
+    where T: EventOutput {
+        self.register(OwnedDelay::new(label, duration_ms, event))
+    }
+
+    /// Emit the last incoming event once no new events were received for `duration_ms`
+    /// milliseconds. Unlike [`Network::debounce`], which waits for the current microtask only,
+    /// this node measures the wall-clock time. In the diagram below, every column is 10ms and
+    /// `duration_ms` is 30.
+    ///
+    /// ```text
+    /// Input:   ─1─2─────3─4─5─────
+    /// Output:  ──────2─────────5──
+    /// ```
+    ///
+    /// Note: See documentation of [`crate::time`] module for more details about the time sources.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn debounce_time<T>(&self, label: Label, duration_ms: f64, event: &T) -> Stream<Output<T>>
+    where T: EventOutput {
+        self.register(OwnedDebounceTime::new(label, duration_ms, event))
+    }
+
+    /// Emit an event when no new events were received for `duration_ms` milliseconds after the
+    /// last incoming event. In the diagram below, every column is 10ms and `duration_ms` is 30.
+    ///
+    /// ```text
+    /// Input:   ─1─2─────3─4─5─────
+    /// Output:  ──────()────────()─
+    /// ```
+    ///
+    /// Note: See documentation of [`crate::time`] module for more details about the time sources.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn timeout<T>(&self, label: Label, duration_ms: f64, event: &T) -> Stream<()>
+    where T: EventOutput {
+        self.register(OwnedTimeout::new(label, duration_ms, event))
+    }
+
+    /// Emit the last incoming event on every `frame_count`-th animation frame. Nothing is emitted
+    /// if no event was received since the previous sample. In the diagram below, `frame_count` is
+    /// 2.
+    ///
+    /// ```text
+    /// Input:   ─1──2─────3───4──────
+    /// Frames:  ───|──|──|──|──|──|──
+    /// Output:  ──────2─────3─────4──
+    /// ```
+    ///
+    /// Note: See documentation of [`crate::time`] module for more details about the time sources.
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn sample_every<T>(
+        &self,
+        label: Label,
+        frame_count: usize,
+        event: &T,
+    ) -> Stream<Output<T>>
+    where
+        T: EventOutput,
+    {
+        self.register(OwnedSampleEvery::new(label, frame_count, event))
+    }
+
 
     /// Fold the incoming value using [`Monoid`] implementation.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn fold<T1, X>(&self, label: Label, event: &T1) -> Stream<X>
     where
         T1: EventOutput,
@@ -309,6 +423,7 @@ impl Network {
     }
 
     /// Get the 0-based index of the incoming event.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn _0<T1>(&self, label: Label, event: &T1) -> Stream<generics::ItemAt0<Output<T1>>>
     where
         T1: EventOutput,
@@ -318,6 +433,7 @@ impl Network {
     }
 
     /// Get the 1-based index of the incoming event.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn _1<T1>(&self, label: Label, event: &T1) -> Stream<generics::ItemAt1<Output<T1>>>
     where
         T1: EventOutput,
@@ -327,6 +443,7 @@ impl Network {
     }
 
     /// Get the 2-based index of the incoming event.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn _2<T1>(&self, label: Label, event: &T1) -> Stream<generics::ItemAt2<Output<T1>>>
     where
         T1: EventOutput,
@@ -337,6 +454,7 @@ impl Network {
 
     /// Only if the input event has changed, emit the input event. This will hide multiple
     /// consecutive events with the same value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn on_change<T, V>(&self, label: Label, t: &T) -> Stream<V>
     where
         T: EventOutput<Output = V>,
@@ -347,6 +465,7 @@ impl Network {
     }
 
     /// Just like [`value.into()`] on the reference of the incoming value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn ref_into<T, V, S>(&self, label: Label, t: &T) -> Stream<S>
     where
         T: EventOutput<Output = V>,
@@ -356,6 +475,7 @@ impl Network {
     }
 
     /// Just like [`value.clone().into()`] on the incoming value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn cloned_into<T, V, S>(&self, label: Label, t: &T) -> Stream<S>
     where
         T: EventOutput<Output = V>,
@@ -365,6 +485,7 @@ impl Network {
     }
 
     /// Just like [`Some(value.into())`] on the reference of the incoming value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn ref_into_some<T, V, S>(&self, label: Label, t: &T) -> Stream<Option<S>>
     where
         T: EventOutput<Output = V>,
@@ -374,6 +495,7 @@ impl Network {
     }
 
     /// Just like [`Some(value.clone().into())`] on the incoming value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn cloned_into_some<T, V, S>(&self, label: Label, t: &T) -> Stream<Option<S>>
     where
         T: EventOutput<Output = V>,
@@ -384,6 +506,7 @@ impl Network {
 
     /// Converts the incoming values to [`AnyData`] hiding their types. This can be used to create
     /// FRP inputs accepting different types, not known at compile time.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any_data<T>(&self, label: Label, t: &T) -> Stream<crate::AnyData>
     where
         T: EventOutput,
@@ -395,16 +518,19 @@ impl Network {
     // === Bool Utils ===
 
     /// Replace the incoming event with `true`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn to_true<T: EventOutput>(&self, label: Label, src: &T) -> Stream<bool> {
         self.constant(label, src, true)
     }
 
     /// Replace the incoming event with `false`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn to_false<T: EventOutput>(&self, label: Label, src: &T) -> Stream<bool> {
         self.constant(label, src, false)
     }
 
     /// Whenever the input event is `true`, emit the output event.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn on_true<T>(&self, label: Label, t: &T) -> Stream
     where T: EventOutput<Output = bool> {
         let t_ = self.constant(label, t, ());
@@ -412,6 +538,7 @@ impl Network {
     }
 
     /// Whenever the input event is `false`, emit the output event.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn on_false<T>(&self, label: Label, t: &T) -> Stream
     where T: EventOutput<Output = bool> {
         let t_ = self.constant(label, t, ());
@@ -419,6 +546,7 @@ impl Network {
     }
 
     /// Replace the incoming event from first input with `false` and from second with `true`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn bool<T1, T2>(&self, label: Label, src1: &T1, src2: &T2) -> Stream<bool>
     where
         T1: EventOutput,
@@ -429,12 +557,14 @@ impl Network {
     }
 
     /// On every input event, output its negation.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn not<T>(&self, label: Label, src: &T) -> Stream<bool>
     where T: EventOutput<Output = bool> {
         self.map(label, src, |t| !t)
     }
 
     /// On every input event, sample all input streams and output their `or` value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn or<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<bool>
     where
         T1: EventOutput<Output = bool>,
@@ -443,6 +573,7 @@ impl Network {
     }
 
     /// On every input event, sample all input streams and output their `and` value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn and<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<bool>
     where
         T1: EventOutput<Output = bool>,
@@ -450,11 +581,13 @@ impl Network {
         self.all_with(label, t1, t2, |a, b| *a && *b)
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn is_some<T, X>(&self, label: Label, src: &T) -> Stream<bool>
     where T: EventOutput<Output = Option<X>> {
         self.map(label, src, |t| t.is_some())
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn is_none<T, X>(&self, label: Label, src: &T) -> Stream<bool>
     where T: EventOutput<Output = Option<X>> {
         self.map(label, src, |t| t.is_none())
@@ -464,6 +597,7 @@ impl Network {
     /// `true` respectively. The redirection is persistent. The first input doesn't have to fire to
     /// propagate the events fromm second and third input streams. Moreover, when first input
     /// changes, an output event will be emitted with the updated value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn switch<T1, T2, T3, T>(&self, label: Label, check: &T1, t2: &T2, t3: &T3) -> Stream<T>
     where
         T1: EventOutput<Output = bool>,
@@ -481,6 +615,7 @@ impl Network {
 
     /// On every `true` event from the first input, emit the second parameter. On every `false`
     /// event from the first input, emit the third parameter.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn switch_constant<Cond, T>(&self, label: Label, check: &Cond, t1: T, t2: T) -> Stream<T>
     where
         Cond: EventOutput<Output = bool>,
@@ -488,6 +623,7 @@ impl Network {
         self.map(label, check, move |check| if !check { t1.clone() } else { t2.clone() })
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn default_or<Cond, T>(&self, label: Label, check: &Cond, t: T) -> Stream<T>
     where
         Cond: EventOutput<Output = bool>,
@@ -497,6 +633,7 @@ impl Network {
 
     /// Map the incoming value into a stream and connect the resulting stream to the output. When a
     /// new value is emitted, the previous stream is disconnected and the new one is connected.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn flat_map<T, F, Out>(&self, label: Label, src: &T, f: F) -> Stream<Out>
     where
         T: EventOutput,
@@ -518,6 +655,7 @@ impl Network {
     }
 
     /// Whenever the incoming value is `true`, emit constant value. Otherwise, emit `None`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn then_constant<Cond, T>(&self, label: Label, check: &Cond, t: T) -> Stream<Option<T>>
     where
         Cond: EventOutput<Output = bool>,
@@ -526,6 +664,7 @@ impl Network {
     }
 
     /// Emit the first input value if it is `Some`. Otherwise, emit the second input value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn unwrap_or<T, T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<T>
     where
         T1: EventOutput<Output = Option<T>>,
@@ -541,12 +680,14 @@ impl Network {
     /// networks by creating an empty `any` and using the `attach` method to attach new streams to
     /// it. When a recursive network is created, `any_mut` breaks the cycle. After passing the first
     /// event, no more events will be passed till the end of the current FRP network resolution.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any_mut<T: Data>(&self, label: Label) -> Any<T> {
         self.register_raw(OwnedAny::new(label))
     }
 
     /// Merges multiple input streams into a single output stream. All input streams have to share
     /// the same output data type.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any<T1, T2, T: Data>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<T>
     where
         T1: EventOutput<Output = T>,
@@ -555,6 +696,7 @@ impl Network {
     }
 
     /// Specialized version of `any`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any2<T1, T2, T: Data>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<T>
     where
         T1: EventOutput<Output = T>,
@@ -563,6 +705,7 @@ impl Network {
     }
 
     /// Specialized version of `any`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any3<T1, T2, T3, T: Data>(&self, label: Label, t1: &T1, t2: &T2, t3: &T3) -> Stream<T>
     where
         T1: EventOutput<Output = T>,
@@ -572,6 +715,7 @@ impl Network {
     }
 
     /// Specialized version of `any`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any4<T1, T2, T3, T4, T: Data>(
         &self,
         label: Label,
@@ -590,6 +734,7 @@ impl Network {
     }
 
     /// Specialized version of `any`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any5<T1, T2, T3, T4, T5, T: Data>(
         &self,
         label: Label,
@@ -613,11 +758,13 @@ impl Network {
     // === Any_ ===
 
     /// Like `any_mut` but drops the incoming data. You can attach streams of different types.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any_mut_(&self, label: Label) -> Any_ {
         self.register_raw(OwnedAny_::new(label))
     }
 
     /// Like `any` but drops the incoming data. You can attach streams of different types.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any_<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<()>
     where
         T1: EventOutput,
@@ -626,6 +773,7 @@ impl Network {
     }
 
     /// Specialized version of `any_`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any2_<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<()>
     where
         T1: EventOutput,
@@ -634,6 +782,7 @@ impl Network {
     }
 
     /// Specialized version of `any_`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any3_<T1, T2, T3>(&self, label: Label, t1: &T1, t2: &T2, t3: &T3) -> Stream<()>
     where
         T1: EventOutput,
@@ -643,6 +792,7 @@ impl Network {
     }
 
     /// Specialized version of `any_`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any4_<T1, T2, T3, T4>(
         &self,
         label: Label,
@@ -661,6 +811,7 @@ impl Network {
     }
 
     /// Specialized version of `any_`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn any5_<T1, T2, T3, T4, T5>(
         &self,
         label: Label,
@@ -685,10 +836,12 @@ impl Network {
 
     /// Merges input streams into a stream containing values from all of them. On event from any of
     /// the input streams, all streams are sampled and the final event is produced.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_mut<T: Data>(&self, label: Label) -> AllMut<T> {
         self.register_raw(OwnedAllMut::new(label))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec2<Out, T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<Vec<Out>>
     where
         Out: Data,
@@ -697,6 +850,7 @@ impl Network {
         self.register(OwnedAllMut::new(label).with(t1).with(t2))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec3<Out, T1, T2, T3>(
         &self,
         label: Label,
@@ -713,6 +867,7 @@ impl Network {
         self.register(OwnedAllMut::new(label).with(t1).with(t2).with(t3))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec4<Out, T1, T2, T3, T4>(
         &self,
         label: Label,
@@ -731,6 +886,7 @@ impl Network {
         self.register(OwnedAllMut::new(label).with(t1).with(t2).with(t3).with(t4))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec5<Out, T1, T2, T3, T4, T5>(
         &self,
         label: Label,
@@ -751,6 +907,7 @@ impl Network {
         self.register(OwnedAllMut::new(label).with(t1).with(t2).with(t3).with(t4).with(t5))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec6<Out, T1, T2, T3, T4, T5, T6>(
         &self,
         label: Label,
@@ -773,6 +930,7 @@ impl Network {
         self.register(OwnedAllMut::new(label).with(t1).with(t2).with(t3).with(t4).with(t5).with(t6))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec7<Out, T1, T2, T3, T4, T5, T6, T7>(
         &self,
         label: Label,
@@ -799,6 +957,7 @@ impl Network {
         )
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec8<Out, T1, T2, T3, T4, T5, T6, T7, T8>(
         &self,
         label: Label,
@@ -835,6 +994,7 @@ impl Network {
         )
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_vec9<Out, T1, T2, T3, T4, T5, T6, T7, T8, T9>(
         &self,
         label: Label,
@@ -876,6 +1036,7 @@ impl Network {
 
     /// Merges input streams into a stream containing values from all of them. On event from any of
     /// the input streams, all streams are sampled and the final event is produced.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<(Output<T1>, Output<T2>)>
     where
         T1: EventOutput,
@@ -884,6 +1045,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all2<T1, T2>(&self, label: Label, t1: &T1, t2: &T2) -> Stream<(Output<T1>, Output<T2>)>
     where
         T1: EventOutput,
@@ -892,6 +1054,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all3<T1, T2, T3>(
         &self,
         label: Label,
@@ -908,6 +1071,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all4<T1, T2, T3, T4>(
         &self,
         label: Label,
@@ -926,6 +1090,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all5<T1, T2, T3, T4, T5>(
         &self,
         label: Label,
@@ -946,6 +1111,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all6<T1, T2, T3, T4, T5, T6>(
         &self,
         label: Label,
@@ -968,6 +1134,7 @@ impl Network {
     }
 
     /// Specialized version of `all`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all7<T1, T2, T3, T4, T5, T6, T7>(
         &self,
         label: Label,
@@ -995,6 +1162,7 @@ impl Network {
     // === Filter ===
 
     /// Passes exactly those incoming events that satisfy the predicate `p`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn filter<T, P>(&self, label: Label, src: &T, p: P) -> Stream<Output<T>>
     where
         T: EventOutput,
@@ -1007,6 +1175,7 @@ impl Network {
 
     /// Applies the function `f` to the value of all incoming events. If the resulting `Option`
     /// caries a value then this value is passed on. Otherwise, nothing happens.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn filter_map<T, F, Out>(&self, label: Label, src: &T, f: F) -> Stream<Out>
     where
         T: EventOutput,
@@ -1021,6 +1190,7 @@ impl Network {
     /// On every event from the first input stream, sample all other input streams and run the
     /// provided function on all gathered values. If you want to run the function on event from any
     /// input stream, use the `all_with` function family instead.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map<T, F, Out>(&self, label: Label, src: &T, f: F) -> Stream<Out>
     where
         T: EventOutput,
@@ -1029,6 +1199,7 @@ impl Network {
         self.register(OwnedMap::new(label, src, f))
     }
 
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map_<'a, T, F, Out>(&self, label: Label, src: &T, f: F) -> Stream<()>
     where
         T: EventOutput,
@@ -1040,12 +1211,14 @@ impl Network {
     }
 
     /// A shortcut for `.map(|v| Some(v.clone()))`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn some<T>(&self, label: Label, src: &T) -> Stream<Option<Output<T>>>
     where T: EventOutput {
         self.map(label, src, |value| Some(value.clone()))
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map2<T1, T2, F, T>(&self, label: Label, t1: &T1, t2: &T2, f: F) -> Stream<T>
     where
         T1: EventOutput,
@@ -1056,6 +1229,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map3<T1, T2, T3, F, T>(
         &self,
         label: Label,
@@ -1075,6 +1249,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map4<T1, T2, T3, T4, F, T>(
         &self,
         label: Label,
@@ -1096,6 +1271,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map5<T1, T2, T3, T4, T5, F, T>(
         &self,
         label: Label,
@@ -1119,6 +1295,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map6<T1, T2, T3, T4, T5, T6, F, T>(
         &self,
         label: Label,
@@ -1145,6 +1322,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map7<T1, T2, T3, T4, T5, T6, T7, F, T>(
         &self,
         label: Label,
@@ -1181,6 +1359,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map8<T1, T2, T3, T4, T5, T6, T7, T8, F, T>(
         &self,
         label: Label,
@@ -1220,6 +1399,7 @@ impl Network {
     }
 
     /// Specialized version of `map`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn map9<T1, T2, T3, T4, T5, T6, T7, T8, T9, F, T>(
         &self,
         label: Label,
@@ -1267,6 +1447,7 @@ impl Network {
     /// On every input event sample all input streams and run the provided function on all gathered
     /// values. If you want to run the function only on event on the first input, use the `map`
     /// function family instead.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with<T1, T2, F, T>(&self, label: Label, t1: &T1, t2: &T2, f: F) -> Stream<T>
     where
         T1: EventOutput,
@@ -1277,6 +1458,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with3<T1, T2, T3, F, T>(
         &self,
         label: Label,
@@ -1296,6 +1478,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with4<T1, T2, T3, T4, F, T>(
         &self,
         label: Label,
@@ -1317,6 +1500,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with5<T1, T2, T3, T4, T5, F, T>(
         &self,
         label: Label,
@@ -1340,6 +1524,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with6<T1, T2, T3, T4, T5, T6, F, T>(
         &self,
         label: Label,
@@ -1366,6 +1551,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with7<T1, T2, T3, T4, T5, T6, T7, F, T>(
         &self,
         label: Label,
@@ -1402,6 +1588,7 @@ impl Network {
     }
 
     /// Specialized version `all_with`.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn all_with8<T1, T2, T3, T4, T5, T6, T7, T8, F, T>(
         &self,
         label: Label,
@@ -1445,6 +1632,7 @@ impl Network {
 
     /// Repeat node listens for input events of type [`usize`] and emits events in number equal to
     /// the input event value.
+    #[cfg_attr(debug_assertions, track_caller)]
     pub fn repeat<T>(&self, label: Label, src: &T) -> Stream<()>
     where T: EventOutput<Output = usize> {
         self.register(OwnedRepeat::new(label, src))
@@ -1980,6 +2168,12 @@ impl<Out: Data> HasOutput for SourceData<Out> {
     type Output = Out;
 }
 
+impl<Out: Data> stream::NodeRole for SourceData<Out> {
+    fn is_source(&self) -> bool {
+        true
+    }
+}
+
 impl<Out: Data> OwnedSource<Out> {
     /// Constructor.
     pub fn new(label: Label) -> Self {
@@ -2094,6 +2288,12 @@ impl<Out: Data> HasOutput for SamplerData<Out> {
     type Output = Out;
 }
 
+impl<Out: Data> stream::NodeRole for SamplerData<Out> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
 impl<Out: Data> OwnedSampler<Out> {
     /// Constructor.
     pub fn new<T1>(label: Label, src1: &T1) -> Self
@@ -2166,6 +2366,12 @@ impl<T: EventOutput> HasOutput for TraceData<T> {
     type Output = Output<T>;
 }
 
+impl<T: EventOutput> stream::NodeRole for TraceData<T> {
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+}
+
 impl<T: EventOutput> OwnedTrace<T> {
     /// Constructor.
     pub fn new(label: Label, src1: &T) -> Self {
@@ -2254,6 +2460,12 @@ impl<T: EventOutput> HasOutput for ProfileData<T> {
     type Output = Output<T>;
 }
 
+impl<T: EventOutput> stream::NodeRole for ProfileData<T> {
+    fn has_side_effects(&self) -> bool {
+        true
+    }
+}
+
 impl<T: EventOutput> OwnedProfile<T> {
     /// Constructor.
     pub fn new(label: Label, src1: &T) -> Self {
@@ -2416,6 +2628,12 @@ impl<T: EventOutput> HasOutput for PreviousData<T> {
     type Output = Output<T>;
 }
 
+impl<T: EventOutput> stream::NodeRole for PreviousData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
 impl<T: EventOutput> OwnedPrevious<T> {
     /// Constructor.
     pub fn new(label: Label, src1: &T) -> Self {
@@ -2669,6 +2887,10 @@ where
         self.gate.is_dropped()
     }
 
+    fn target_id(&self) -> Id {
+        self.gate.id()
+    }
+
     fn on_event_if_exists(&self, stack: CallStack, new_active: &bool) -> bool {
         if let Some(gate) = self.gate.upgrade() {
             match (gate.state.get(), new_active) {
@@ -2769,6 +2991,12 @@ impl<T: HasOutput> HasOutput for BatchData<T> {
     type Output = Vec<Output<T>>;
 }
 
+impl<T: HasOutput> stream::NodeRole for BatchData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
 impl<T: EventOutput> OwnedBatch<T> {
     /// Constructor.
     pub fn new(label: Label, input: &T) -> Self {
@@ -2823,6 +3051,12 @@ impl<T: HasOutput> HasOutput for DebounceData<T> {
     type Output = Output<T>;
 }
 
+impl<T: HasOutput> stream::NodeRole for DebounceData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
 impl<T: EventOutput> OwnedDebounce<T> {
     /// Constructor.
     pub fn new(label: Label, input: &T) -> Self {
@@ -2858,6 +3092,304 @@ impl<T: EventOutput> stream::InputBehaviors for DebounceData<T> {
 }
 
 
+// ================
+// === Throttle ===
+// ================
+
+#[derive(Debug)]
+pub struct ThrottleData<T: HasOutput> {
+    duration_ms: f64,
+    next_value:  RefCell<Option<Output<T>>>,
+    timer:       RefCell<Option<time::Timer>>,
+}
+
+pub type OwnedThrottle<T> = stream::Node<ThrottleData<T>>;
+pub type Throttle<T> = stream::WeakNode<ThrottleData<T>>;
+
+impl<T: HasOutput> HasOutput for ThrottleData<T> {
+    type Output = Output<T>;
+}
+
+impl<T: EventOutput> OwnedThrottle<T> {
+    /// Constructor.
+    pub fn new(label: Label, duration_ms: f64, input: &T) -> Self {
+        let definition = ThrottleData { duration_ms, next_value: default(), timer: default() };
+        Self::construct_and_connect(label, input, definition)
+    }
+
+    /// Start the period in which the incoming events are not emitted immediately.
+    fn start_period(&self) {
+        let weak = self.downgrade();
+        let timer = time::set_timeout(self.duration_ms, move || {
+            if let Some(node) = weak.upgrade() {
+                let next_value = node.next_value.borrow_mut().take();
+                if let Some(value) = next_value {
+                    node.start_period();
+                    node.emit_event(&default(), &value);
+                } else {
+                    node.timer.take();
+                }
+            }
+        });
+        self.timer.replace(Some(timer));
+    }
+}
+
+impl<T: EventOutput> stream::EventConsumer<Output<T>> for OwnedThrottle<T> {
+    fn on_event(&self, stack: CallStack, value: &Output<T>) {
+        let is_throttled = self.timer.borrow().is_some();
+        if is_throttled {
+            self.next_value.replace(Some(value.clone()));
+        } else {
+            self.start_period();
+            self.emit_event(stack, value);
+        }
+    }
+}
+
+impl<T: EventOutput> stream::InputBehaviors for ThrottleData<T> {
+    fn input_behaviors(&self) -> Vec<Link> {
+        vec![]
+    }
+}
+
+
+// =============
+// === Delay ===
+// =============
+
+#[derive(Debug)]
+pub struct DelayData<T: HasOutput> {
+    duration_ms: f64,
+    scheduled:   RefCell<VecDeque<(Output<T>, time::Timer)>>,
+}
+
+pub type OwnedDelay<T> = stream::Node<DelayData<T>>;
+pub type Delay<T> = stream::WeakNode<DelayData<T>>;
+
+impl<T: HasOutput> HasOutput for DelayData<T> {
+    type Output = Output<T>;
+}
+
+impl<T: HasOutput> stream::NodeRole for DelayData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
+impl<T: EventOutput> OwnedDelay<T> {
+    /// Constructor.
+    pub fn new(label: Label, duration_ms: f64, input: &T) -> Self {
+        let definition = DelayData { duration_ms, scheduled: default() };
+        Self::construct_and_connect(label, input, definition)
+    }
+}
+
+impl<T: EventOutput> stream::EventConsumer<Output<T>> for OwnedDelay<T> {
+    fn on_event(&self, _stack: CallStack, value: &Output<T>) {
+        let weak = self.downgrade();
+        // All the timers have the same duration, so they fire in the order of the events.
+        let timer = time::set_timeout(self.duration_ms, move || {
+            if let Some(node) = weak.upgrade() {
+                let scheduled = node.scheduled.borrow_mut().pop_front();
+                if let Some((value, _timer)) = scheduled {
+                    node.emit_event(&default(), &value);
+                }
+            }
+        });
+        self.scheduled.borrow_mut().push_back((value.clone(), timer));
+    }
+}
+
+impl<T: EventOutput> stream::InputBehaviors for DelayData<T> {
+    fn input_behaviors(&self) -> Vec<Link> {
+        vec![]
+    }
+}
+
+
+// ====================
+// === DebounceTime ===
+// ====================
+
+#[derive(Debug)]
+pub struct DebounceTimeData<T: HasOutput> {
+    duration_ms: f64,
+    next_value:  RefCell<Option<Output<T>>>,
+    timer:       RefCell<Option<time::Timer>>,
+}
+
+pub type OwnedDebounceTime<T> = stream::Node<DebounceTimeData<T>>;
+pub type DebounceTime<T> = stream::WeakNode<DebounceTimeData<T>>;
+
+impl<T: HasOutput> HasOutput for DebounceTimeData<T> {
+    type Output = Output<T>;
+}
+
+impl<T: HasOutput> stream::NodeRole for DebounceTimeData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
+impl<T: EventOutput> OwnedDebounceTime<T> {
+    /// Constructor.
+    pub fn new(label: Label, duration_ms: f64, input: &T) -> Self {
+        let definition = DebounceTimeData { duration_ms, next_value: default(), timer: default() };
+        Self::construct_and_connect(label, input, definition)
+    }
+}
+
+impl<T: EventOutput> stream::EventConsumer<Output<T>> for OwnedDebounceTime<T> {
+    fn on_event(&self, _stack: CallStack, value: &Output<T>) {
+        self.next_value.replace(Some(value.clone()));
+        let weak = self.downgrade();
+        let timer = time::set_timeout(self.duration_ms, move || {
+            if let Some(node) = weak.upgrade() {
+                let next_value = node.next_value.borrow_mut().take();
+                if let Some(value) = next_value {
+                    node.emit_event(&default(), &value);
+                }
+            }
+        });
+        self.timer.replace(Some(timer));
+    }
+}
+
+impl<T: EventOutput> stream::InputBehaviors for DebounceTimeData<T> {
+    fn input_behaviors(&self) -> Vec<Link> {
+        vec![]
+    }
+}
+
+
+// ===============
+// === Timeout ===
+// ===============
+
+#[derive(Debug)]
+pub struct TimeoutData<T> {
+    duration_ms: f64,
+    timer:       RefCell<Option<time::Timer>>,
+    phantom:     PhantomData<T>,
+}
+
+pub type OwnedTimeout<T> = stream::Node<TimeoutData<T>>;
+pub type Timeout<T> = stream::WeakNode<TimeoutData<T>>;
+
+impl<T> HasOutput for TimeoutData<T> {
+    type Output = ();
+}
+
+impl<T> stream::NodeRole for TimeoutData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
+impl<T: EventOutput> OwnedTimeout<T> {
+    /// Constructor.
+    pub fn new(label: Label, duration_ms: f64, input: &T) -> Self {
+        let definition = TimeoutData { duration_ms, timer: default(), phantom: default() };
+        Self::construct_and_connect(label, input, definition)
+    }
+}
+
+impl<T: EventOutput> stream::EventConsumer<Output<T>> for OwnedTimeout<T> {
+    fn on_event(&self, _stack: CallStack, _value: &Output<T>) {
+        let weak = self.downgrade();
+        let timer = time::set_timeout(self.duration_ms, move || {
+            if let Some(node) = weak.upgrade() {
+                node.emit_event(&default(), &());
+            }
+        });
+        self.timer.replace(Some(timer));
+    }
+}
+
+impl<T: EventOutput> stream::InputBehaviors for TimeoutData<T> {
+    fn input_behaviors(&self) -> Vec<Link> {
+        vec![]
+    }
+}
+
+
+// ===================
+// === SampleEvery ===
+// ===================
+
+#[derive(Debug)]
+pub struct SampleEveryData<T: HasOutput> {
+    frame_count:  usize,
+    frames_left:  Cell<usize>,
+    next_value:   RefCell<Option<Output<T>>>,
+    frame_handle: RefCell<Option<enso_callback::Handle>>,
+}
+
+pub type OwnedSampleEvery<T> = stream::Node<SampleEveryData<T>>;
+pub type SampleEvery<T> = stream::WeakNode<SampleEveryData<T>>;
+
+impl<T: HasOutput> HasOutput for SampleEveryData<T> {
+    type Output = Output<T>;
+}
+
+impl<T: HasOutput> stream::NodeRole for SampleEveryData<T> {
+    fn breaks_cycles(&self) -> bool {
+        true
+    }
+}
+
+impl<T: EventOutput> OwnedSampleEvery<T> {
+    /// Constructor. The `frame_count` of zero is treated as one.
+    pub fn new(label: Label, frame_count: usize, input: &T) -> Self {
+        let frame_count = frame_count.max(1);
+        let frames_left = Cell::new(frame_count);
+        let next_value = default();
+        let frame_handle = default();
+        let definition = SampleEveryData { frame_count, frames_left, next_value, frame_handle };
+        Self::construct_and_connect(label, input, definition)
+    }
+
+    /// The frames are counted only while there are values to be sampled, so the idle node does not
+    /// keep the animation frames running. After the value is emitted, the node waits for the
+    /// whole period before it stops counting, so the next value is not emitted too early.
+    fn on_frame(&self) {
+        let frames_left = self.frames_left.get() - 1;
+        if frames_left > 0 {
+            self.frames_left.set(frames_left);
+        } else {
+            self.frames_left.set(self.frame_count);
+            let next_value = self.next_value.borrow_mut().take();
+            match next_value {
+                Some(value) => self.emit_event(&default(), &value),
+                None => drop(self.frame_handle.take()),
+            }
+        }
+    }
+}
+
+impl<T: EventOutput> stream::EventConsumer<Output<T>> for OwnedSampleEvery<T> {
+    fn on_event(&self, _stack: CallStack, value: &Output<T>) {
+        self.next_value.replace(Some(value.clone()));
+        if self.frame_handle.borrow().is_none() {
+            let weak = self.downgrade();
+            let handle = time::on_frame(move |_| {
+                if let Some(node) = weak.upgrade() {
+                    node.on_frame();
+                }
+            });
+            self.frame_handle.replace(Some(handle));
+        }
+    }
+}
+
+impl<T: EventOutput> stream::InputBehaviors for SampleEveryData<T> {
+    fn input_behaviors(&self) -> Vec<Link> {
+        vec![]
+    }
+}
+
+
 // ===========
 // === Any ===
 // ===========
@@ -2875,6 +3407,12 @@ impl<Out: Data> HasOutput for AnyData<Out> {
     type Output = Out;
 }
 
+impl<Out: Data> stream::NodeRole for AnyData<Out> {
+    fn is_source(&self) -> bool {
+        self.srcs.borrow().is_empty()
+    }
+}
+
 impl<Out: Data> OwnedAny<Out> {
     /// Constructor.
     pub fn new(label: Label) -> Self {
@@ -3003,6 +3541,12 @@ impl HasOutput for AnyData_ {
     type Output = ();
 }
 
+impl stream::NodeRole for AnyData_ {
+    fn is_source(&self) -> bool {
+        self.srcs.borrow().is_empty()
+    }
+}
+
 impl OwnedAny_ {
     /// Constructor.
     pub fn new(label: Label) -> Self {
@@ -5,6 +5,10 @@ use crate::node::*;
 use crate::prelude::*;
 
 use crate::data::watch;
+use crate::debug;
+use crate::debug::EventTracer;
+
+use std::panic::Location;
 
 
 
@@ -94,6 +98,67 @@ pub trait HasOutputTypeLabel {
 
 
 
+// ==================
+// === Introspect ===
+// ==================
+
+/// Debugging capabilities of an FRP node, used by the network introspection. See the
+/// [`crate::debug`] module to learn more.
+pub trait Introspect: NodeRole {
+    /// Name of the output type of this node, with the module paths stripped.
+    fn output_type_name(&self) -> String;
+    /// Ids of the nodes receiving the events emitted by this node.
+    fn target_ids(&self) -> Vec<Id>;
+    /// Check whether any other node is reading the value of this node.
+    fn is_watched(&self) -> bool;
+    /// The number of events emitted by this node so far. The events are counted in debug builds
+    /// only, as counting them slows down every emitted event.
+    fn emit_count(&self) -> usize;
+    /// The source code location this node was created at. It is known in debug builds only.
+    fn location(&self) -> Option<&'static Location<'static>>;
+    /// Set the source code location this node was created at. Ignored in release builds.
+    fn set_location(&self, location: &'static Location<'static>);
+    /// Record all the events emitted by this node with the provided tracer, or stop recording
+    /// them if `None` was provided.
+    fn set_tracer(&self, tracer: Option<EventTracer>);
+}
+
+
+
+// ================
+// === NodeRole ===
+// ================
+
+/// The role of the node in the network, used by the network analysis. See the
+/// [`crate::analysis`] module to learn more.
+pub trait NodeRole {
+    /// Whether the node is an entry point of the events emitted from outside of the network, like
+    /// [`crate::Network::source`] or [`crate::Network::any_mut`] without attached inputs.
+    fn is_source(&self) -> bool;
+    /// Whether the node does not emit the incoming events synchronously, or is designed to be used
+    /// in FRP loops, like [`crate::Network::debounce`] or [`crate::Network::sampler`].
+    fn breaks_cycles(&self) -> bool;
+    /// Whether the node performs side effects, so its output does not need to be consumed, like
+    /// [`crate::Network::trace`].
+    fn has_side_effects(&self) -> bool;
+}
+
+impl<T> NodeRole for T {
+    default fn is_source(&self) -> bool {
+        false
+    }
+
+    default fn breaks_cycles(&self) -> bool {
+        false
+    }
+
+    default fn has_side_effects(&self) -> bool {
+        false
+    }
+}
+
+
+
 // ======================
 // === InputBehaviors ===
 // ======================
@@ -148,6 +213,9 @@ pub trait WeakEventConsumer<T> {
     /// Returns true is the consumer is already dropped.
     fn is_dropped(&self) -> bool;
 
+    /// Id of the node consuming the events. For debugging purposes only.
+    fn target_id(&self) -> Id;
+
     /// Callback for a new incoming event. Returns true if the event was consumed or false if it was
     /// not. Not consuming an event means that the event receiver was already dropped.
     fn on_event_if_exists(&self, stack: CallStack, value: &T) -> bool;
@@ -251,6 +319,11 @@ pub struct NodeData<Out = ()> {
     ongoing_evaluations: Cell<usize>,
     watch_counter:       watch::Counter,
     label:               Label,
+    tracer:              RefCell<Option<EventTracer>>,
+    #[cfg(debug_assertions)]
+    emit_count:          Cell<usize>,
+    #[cfg(debug_assertions)]
+    location:            Cell<Option<&'static Location<'static>>>,
 }
 
 impl<Out: Default> NodeData<Out> {
@@ -261,6 +334,7 @@ impl<Out: Default> NodeData<Out> {
         let value_cache = default();
         let evaluations = default();
         let watch_counter = default();
+        let tracer = default();
         Self {
             targets,
             new_targets,
@@ -268,12 +342,61 @@ impl<Out: Default> NodeData<Out> {
             ongoing_evaluations: evaluations,
             watch_counter,
             label,
+            tracer,
+            #[cfg(debug_assertions)]
+            emit_count: default(),
+            #[cfg(debug_assertions)]
+            location: default(),
         }
     }
 
     fn use_caching(&self) -> bool {
         !self.watch_counter.is_zero()
     }
+
+    /// The id of this node, the same as the id of the [`Stream`] pointing to it.
+    fn id(&self) -> Id {
+        let ptr: *const Self = self;
+        (ptr as *const () as usize).into()
+    }
+
+    fn target_ids(&self) -> Vec<Id> {
+        let new_targets = self.new_targets.borrow();
+        let new_targets = new_targets.iter();
+        let ids = match self.targets.try_borrow() {
+            Ok(targets) => targets.iter().chain(new_targets).map(|t| t.data.target_id()).collect(),
+            Err(_) => new_targets.map(|t| t.data.target_id()).collect_vec(),
+        };
+        ids.into_iter().unique().collect()
+    }
+
+    #[cfg(debug_assertions)]
+    fn emit_count(&self) -> usize {
+        self.emit_count.get()
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn emit_count(&self) -> usize {
+        0
+    }
+
+    #[cfg(debug_assertions)]
+    fn location(&self) -> Option<&'static Location<'static>> {
+        self.location.get()
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    #[cfg(debug_assertions)]
+    fn set_location(&self, location: &'static Location<'static>) {
+        self.location.set(Some(location));
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn set_location(&self, _location: &'static Location<'static>) {}
 }
 
 impl<Out: Data> HasOutput for NodeData<Out> {
@@ -290,6 +413,13 @@ impl<Out: Data> EventEmitter for NodeData<Out> {
             warn!("{}", backtrace())
         } else {
             self.ongoing_evaluations.set(self.ongoing_evaluations.get() + 1);
+            #[cfg(debug_assertions)]
+            self.emit_count.set(self.emit_count.get() + 1);
+            if debug::is_tracing_used() {
+                if let Some(tracer) = &*self.tracer.borrow() {
+                    tracer.record(self.id(), self.label, value);
+                }
+            }
             if self.use_caching() {
                 *self.value_cache.borrow_mut() = value.clone();
             }
@@ -623,6 +753,10 @@ where
         self.definition.strong_count() == 0
     }
 
+    fn target_id(&self) -> Id {
+        self.id()
+    }
+
     fn on_event_if_exists(&self, stack: CallStack, value: &T) -> bool {
         self.upgrade()
             .map(|node| {
@@ -746,8 +880,7 @@ impl<Def: HasOutputStatic> InputBehaviors for Node<Def>
 where Def: InputBehaviors
 {
     fn input_behaviors(&self) -> Vec<Link> {
-        vec![] // FIXME
-               //        self.data.input_behaviors()
+        self.definition.input_behaviors()
     }
 }
 
@@ -755,7 +888,74 @@ impl<Def: HasOutputStatic> InputBehaviors for WeakNode<Def>
 where Def: InputBehaviors
 {
     fn input_behaviors(&self) -> Vec<Link> {
-        self.stream.input_behaviors()
+        self.upgrade().map(|node| node.input_behaviors()).unwrap_or_default()
+    }
+}
+
+
+// === Introspect ===
+
+impl<Def: HasOutputStatic> Introspect for Node<Def> {
+    fn output_type_name(&self) -> String {
+        short_type_name(type_name::<Output<Def>>())
+    }
+
+    fn target_ids(&self) -> Vec<Id> {
+        self.stream.data.target_ids()
+    }
+
+    fn is_watched(&self) -> bool {
+        self.stream.data.use_caching()
+    }
+
+    fn emit_count(&self) -> usize {
+        self.stream.data.emit_count()
+    }
+
+    fn location(&self) -> Option<&'static Location<'static>> {
+        self.stream.data.location()
+    }
+
+    fn set_location(&self, location: &'static Location<'static>) {
+        self.stream.data.set_location(location);
+    }
+
+    fn set_tracer(&self, tracer: Option<EventTracer>) {
+        *self.stream.data.tracer.borrow_mut() = tracer;
+    }
+}
+
+/// Strip the module paths from the type name, so `alloc::vec::Vec<core::option::Option<i32>>`
+/// becomes `Vec<Option<i32>>`.
+#[inline(never)]
+fn short_type_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut rest = name;
+    while let Some((head, tail)) = rest.split_once("::") {
+        out.push_str(head);
+        let is_path_char = |c: char| c.is_alphanumeric() || c == '_';
+        let path_start = out.rfind(|c| !is_path_char(c)).map_or(0, |index| index + 1);
+        out.truncate(path_start);
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+
+// === NodeRole ===
+
+impl<Def: HasOutputStatic> NodeRole for Node<Def> {
+    fn is_source(&self) -> bool {
+        self.definition.is_source()
+    }
+
+    fn breaks_cycles(&self) -> bool {
+        self.definition.breaks_cycles()
+    }
+
+    fn has_side_effects(&self) -> bool {
+        self.definition.has_side_effects()
     }
 }
 
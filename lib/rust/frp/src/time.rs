@@ -0,0 +1,286 @@
+//! Wall-clock and animation frame time sources used by the time-based FRP nodes, like
+//! [`crate::Network::throttle`] or [`crate::Network::sample_every`].
+//!
+//! All the functions of this module are driven by the browser (`setTimeout` and
+//! `requestAnimationFrame`), unless the [`crate::virtual_time`] is active. In such a case, they are
+//! driven by the virtual time instead, which makes the time-based FRP logic testable. An
+//! application having its own animation loop should drive the frames with a [`FrameDriver`]
+//! instead of running a separate `requestAnimationFrame` loop.
+
+use crate::prelude::*;
+use enso_callback::traits::*;
+use enso_web::traits::*;
+
+use crate::microtasks::next_microtask;
+use crate::virtual_time;
+
+use enso_callback as callback;
+use enso_web::window;
+use enso_web::Closure;
+
+
+
+// ===========
+// === Now ===
+// ===========
+
+/// The current time, in milliseconds. If the virtual time is active, the virtual time is returned.
+pub fn now() -> f64 {
+    if virtual_time::is_active() {
+        virtual_time::now()
+    } else {
+        enso_web::time_from_start()
+    }
+}
+
+
+
+// =============
+// === Timer ===
+// =============
+
+/// Closure type alias for use in `setTimeout` call.
+type TimerClosure = Closure<dyn FnMut()>;
+
+/// A handle to a callback scheduled with [`set_timeout`]. Dropping the handle cancels the timer if
+/// it did not fire yet.
+#[derive(Debug)]
+pub struct Timer {
+    raw: RawTimer,
+}
+
+#[derive(Debug)]
+enum RawTimer {
+    Web { handle: i32, closure: Option<TimerClosure>, fired: Rc<Cell<bool>> },
+    Virtual { id: usize },
+}
+
+/// Call `f` once, after `delay_ms` milliseconds. The timer is cancelled when the returned handle is
+/// dropped.
+///
+/// The timer is based on `setTimeout` browser API. That means there is no guarantee about the
+/// exact time the callback will be called. It might be delayed if the browser event loop is busy.
+pub fn set_timeout(delay_ms: f64, f: impl FnOnce() + 'static) -> Timer {
+    let raw = if virtual_time::is_active() {
+        RawTimer::Virtual { id: virtual_time::schedule_timer(delay_ms, f) }
+    } else {
+        let fired: Rc<Cell<bool>> = default();
+        let mut f = Some(f);
+        let closure: TimerClosure = Closure::new(f!([fired] () {
+            fired.set(true);
+            if let Some(f) = f.take() {
+                f()
+            }
+        }));
+        let js_func = closure.as_js_function();
+        let delay = delay_ms.round() as i32;
+        let result = window.set_timeout_with_callback_and_timeout_and_arguments_0(js_func, delay);
+        let handle = result.expect("setTimeout should never fail when callback is a function.");
+        RawTimer::Web { handle, closure: Some(closure), fired }
+    };
+    Timer { raw }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        match &mut self.raw {
+            RawTimer::Virtual { id } => virtual_time::cancel_timer(*id),
+            RawTimer::Web { handle, closure, fired } =>
+                if fired.get() {
+                    // The timer may be dropped by its own callback, so the closure can be still
+                    // running. It is released after the current task instead.
+                    let closure = closure.take();
+                    next_microtask(move || drop(closure)).forget();
+                } else {
+                    window.clear_timeout_with_handle(*handle);
+                },
+        }
+    }
+}
+
+
+
+// ================
+// === on_frame ===
+// ================
+
+/// Closure type alias for use in `requestAnimationFrame` call.
+type FrameClosure = Closure<dyn FnMut(f64)>;
+
+thread_local! {
+    static FRAME_LOOP: FrameLoop = FrameLoop::new();
+}
+
+/// Register a callback to be called on every animation frame, with the frame time in milliseconds.
+/// Dropping the returned handle unregisters the callback.
+///
+/// The animation frames are requested from the browser only as long as there are callbacks
+/// registered and there is no [`FrameDriver`]. If the virtual time is active, the callbacks are
+/// called on the virtual frames only.
+pub fn on_frame(f: impl FnMut(f64) + 'static) -> callback::Handle {
+    FRAME_LOOP.with(|frame_loop| frame_loop.add(f))
+}
+
+/// Check whether there are any [`on_frame`] callbacks registered, which keep the animation frames
+/// running.
+pub fn has_frame_callbacks() -> bool {
+    FRAME_LOOP.with(|frame_loop| !frame_loop.data.callbacks.is_empty())
+}
+
+/// Run the [`on_frame`] callbacks by the returned [`FrameDriver`] instead of requesting the
+/// animation frames from the browser, until the driver is dropped.
+pub fn drive_frames_externally() -> FrameDriver {
+    FRAME_LOOP.with(|frame_loop| {
+        let data = frame_loop.data.clone_ref();
+        data.external_drivers.set(data.external_drivers.get() + 1);
+        data.cancel_frame_request();
+        FrameDriver { data }
+    })
+}
+
+/// A handle of an external animation loop driving the [`on_frame`] callbacks. See
+/// [`drive_frames_externally`].
+#[derive(Debug)]
+pub struct FrameDriver {
+    data: Rc<FrameLoopData>,
+}
+
+impl FrameDriver {
+    /// Run the [`on_frame`] callbacks with the frame time in milliseconds. Should be called on
+    /// every animation frame. Does nothing if the virtual time is active, as the callbacks are
+    /// called on the virtual frames then.
+    pub fn run_frame(&self, time: f64) {
+        if !virtual_time::is_active() {
+            self.data.callbacks.run_all(time);
+        }
+    }
+}
+
+impl Drop for FrameDriver {
+    fn drop(&mut self) {
+        self.data.external_drivers.set(self.data.external_drivers.get() - 1);
+        self.data.request_frame();
+    }
+}
+
+/// A `requestAnimationFrame` loop running the [`on_frame`] callbacks.
+#[derive(Debug)]
+struct FrameLoop {
+    data: Rc<FrameLoopData>,
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct FrameLoopData {
+    callbacks:             callback::registry::Copy1<f64>,
+    request_handle:        Cell<Option<i32>>,
+    external_drivers:      Cell<usize>,
+    #[derivative(Debug = "ignore")]
+    closure:               FrameClosure,
+    _virtual_frame_handle: callback::Handle,
+}
+
+impl FrameLoop {
+    fn new() -> Self {
+        let data = Rc::new_cyclic(|weak: &Weak<FrameLoopData>| {
+            let callbacks: callback::registry::Copy1<f64> = default();
+            let closure = Closure::new(f!([weak] (time: f64) {
+                if let Some(data) = weak.upgrade() {
+                    data.request_handle.take();
+                    if !virtual_time::is_active() {
+                        data.callbacks.run_all(time);
+                    }
+                    data.request_frame();
+                }
+            }));
+            let _virtual_frame_handle =
+                virtual_time::on_frame(f!([callbacks] (time) callbacks.run_all(time)));
+            let request_handle = default();
+            let external_drivers = default();
+            FrameLoopData {
+                callbacks,
+                request_handle,
+                external_drivers,
+                closure,
+                _virtual_frame_handle,
+            }
+        });
+        Self { data }
+    }
+
+    fn add(&self, f: impl FnMut(f64) + 'static) -> callback::Handle {
+        let handle = self.data.callbacks.add(f);
+        self.data.request_frame();
+        handle
+    }
+}
+
+impl FrameLoopData {
+    /// Request the next animation frame, unless it was already requested, the frames are driven
+    /// externally, or there are no callbacks to be run.
+    fn request_frame(&self) {
+        let is_driven = self.external_drivers.get() > 0;
+        if self.request_handle.get().is_none() && !is_driven && !self.callbacks.is_empty() {
+            let handle = window.request_animation_frame_with_closure_or_panic(&self.closure);
+            self.request_handle.set(Some(handle));
+        }
+    }
+
+    fn cancel_frame_request(&self) {
+        if let Some(handle) = self.request_handle.take() {
+            window.cancel_animation_frame_or_warn(handle);
+        }
+    }
+}
+
+impl Drop for FrameLoopData {
+    fn drop(&mut self) {
+        self.cancel_frame_request();
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::virtual_time::VirtualTime;
+
+    #[test]
+    fn timers() {
+        let time = VirtualTime::new();
+        let log: Rc<RefCell<Vec<f64>>> = default();
+        let _timer = set_timeout(10.0, f!(log.borrow_mut().push(now())));
+        let cancelled = set_timeout(5.0, f!(log.borrow_mut().push(now())));
+        drop(cancelled);
+        time.advance_time(20.0);
+        assert_eq!(*log.borrow(), vec![10.0]);
+    }
+
+    #[test]
+    fn frames() {
+        let time = VirtualTime::new();
+        time.set_frame_duration(10.0);
+        let frames: Rc<RefCell<Vec<f64>>> = default();
+        let handle = on_frame(f!((frame_time) frames.borrow_mut().push(frame_time)));
+        time.advance_frames(2);
+        drop(handle);
+        time.advance_frames(1);
+        assert_eq!(*frames.borrow(), vec![10.0, 20.0]);
+    }
+
+    #[test]
+    fn externally_driven_frames() {
+        let frames: Rc<RefCell<Vec<f64>>> = default();
+        let _handle = on_frame(f!((frame_time) frames.borrow_mut().push(frame_time)));
+        let driver = drive_frames_externally();
+        driver.run_frame(16.0);
+        driver.run_frame(32.0);
+        assert_eq!(*frames.borrow(), vec![16.0, 32.0]);
+    }
+}
@@ -0,0 +1,325 @@
+//! Deterministic, virtual-time backend of the [`crate::microtasks`] scheduler and the animation
+//! loop, to be used in tests of time-dependent FRP logic.
+//!
+//! While a [`VirtualTime`] instance is alive, the microtasks scheduled with
+//! [`crate::microtasks::next_microtask`] and [`crate::microtasks::next_microtask_late`] are no
+//! longer run by the JavaScript event loop. Instead, they are performed only when the test
+//! explicitly asks for it, by calling [`VirtualTime::flush_microtasks`] or by advancing the time.
+//! Similarly, the animation frames are not requested from the browser. Every frame callback
+//! registered with [`on_frame`] is called only when the test advances the time with
+//! [`VirtualTime::advance_frames`] or [`VirtualTime::advance_time`]. The timers set with
+//! [`crate::time::set_timeout`] are fired when the time passes their due time, before the frame
+//! scheduled at the same time.
+//!
+//! ```text
+//! advance_frames(2):  flush ─ frame 1 ─ flush ─ flush ─ frame 2 ─ flush
+//! time:               0ms     16.7ms                    33.3ms
+//! ```
+//!
+//! See the [`crate::marbles`] module for marble-diagram assertions built on top of it.
+
+use crate::prelude::*;
+use enso_callback::traits::*;
+
+use crate::microtasks;
+
+use enso_callback as callback;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The default duration of a single virtual animation frame, in milliseconds.
+pub const DEFAULT_FRAME_DURATION_MS: f64 = 1000.0 / 60.0;
+
+
+
+// =============
+// === Clock ===
+// =============
+
+thread_local! {
+    static CLOCK: Clock = Clock::default();
+}
+
+/// The state of the virtual time, shared by all [`VirtualTime`] instances of the current thread.
+#[derive(Debug, Derivative)]
+#[derivative(Default)]
+struct Clock {
+    active_instances: Cell<usize>,
+    time:             Cell<f64>,
+    last_frame_time:  Cell<f64>,
+    frame:            Cell<usize>,
+    #[derivative(Default(value = "Cell::new(DEFAULT_FRAME_DURATION_MS)"))]
+    frame_duration:   Cell<f64>,
+    frame_callbacks:  callback::registry::Copy1<f64>,
+    next_timer_id:    Cell<usize>,
+    timers:           RefCell<Vec<VirtualTimer>>,
+}
+
+/// A callback scheduled to be called once the virtual time reaches the `due_time`.
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct VirtualTimer {
+    id:       usize,
+    due_time: f64,
+    #[derivative(Debug = "ignore")]
+    callback: Box<dyn FnOnce()>,
+}
+
+/// Check whether the virtual time is active in the current thread, that is, whether there is a
+/// living [`VirtualTime`] instance.
+pub fn is_active() -> bool {
+    CLOCK.with(|clock| clock.active_instances.get() > 0)
+}
+
+/// Register a callback to be called on every virtual animation frame, with the frame time in
+/// milliseconds. Dropping the returned handle unregisters the callback.
+///
+/// The callbacks are called only when the time is advanced by [`VirtualTime`]. This is the hook
+/// used by the animation loops to be driven by the virtual time in tests.
+pub fn on_frame(f: impl FnMut(f64) + 'static) -> callback::Handle {
+    CLOCK.with(|clock| clock.frame_callbacks.add(f))
+}
+
+/// The current virtual time, in milliseconds.
+pub(crate) fn now() -> f64 {
+    CLOCK.with(|clock| clock.time.get())
+}
+
+/// Schedule the `callback` to be called after the virtual time is advanced by `delay_ms`
+/// milliseconds. Returns the timer id, which can be passed to [`cancel_timer`].
+pub(crate) fn schedule_timer(delay_ms: f64, callback: impl FnOnce() + 'static) -> usize {
+    CLOCK.with(|clock| {
+        let id = clock.next_timer_id.get();
+        clock.next_timer_id.set(id + 1);
+        let due_time = clock.time.get() + delay_ms.max(0.0);
+        let callback = Box::new(callback);
+        clock.timers.borrow_mut().push(VirtualTimer { id, due_time, callback });
+        id
+    })
+}
+
+/// Cancel the timer scheduled with [`schedule_timer`]. Does nothing if the timer already fired.
+pub(crate) fn cancel_timer(id: usize) {
+    let timer = CLOCK.with(|clock| {
+        let mut timers = clock.timers.borrow_mut();
+        let index = timers.iter().position(|timer| timer.id == id);
+        index.map(|index| timers.remove(index))
+    });
+    // The callback is dropped outside of the borrow, as it may own other timers.
+    drop(timer);
+}
+
+/// Remove the earliest timer due not later than `time` from the queue. The timers of the same due
+/// time are returned in the order they were scheduled in.
+fn take_timer_due(time: f64) -> Option<VirtualTimer> {
+    CLOCK.with(|clock| {
+        let mut timers = clock.timers.borrow_mut();
+        let due = timers.iter().enumerate().filter(|(_, timer)| timer.due_time <= time);
+        let earliest =
+            due.min_by(|(_, a), (_, b)| a.due_time.total_cmp(&b.due_time).then(a.id.cmp(&b.id)));
+        let index = earliest.map(|(index, _)| index);
+        index.map(|index| timers.remove(index))
+    })
+}
+
+
+
+// ===================
+// === VirtualTime ===
+// ===================
+
+/// A handle to the virtual time. See the module docs to learn more.
+///
+/// Creating the first instance resets the time to zero. The virtual time stays active until all
+/// the instances are dropped.
+#[derive(Debug)]
+pub struct VirtualTime {
+    /// The virtual time is thread-local, so the handle must not be sent between threads.
+    not_send: PhantomData<*const ()>,
+}
+
+impl VirtualTime {
+    /// Activate the virtual time in the current thread.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let stale_timers = CLOCK.with(|clock| {
+            let is_first = clock.active_instances.get() == 0;
+            clock.active_instances.set(clock.active_instances.get() + 1);
+            is_first.then(|| {
+                clock.time.set(0.0);
+                clock.last_frame_time.set(0.0);
+                clock.frame.set(0);
+                clock.frame_duration.set(DEFAULT_FRAME_DURATION_MS);
+                mem::take(&mut *clock.timers.borrow_mut())
+            })
+        });
+        drop(stale_timers);
+        Self { not_send: default() }
+    }
+
+    /// The current virtual time, in milliseconds.
+    pub fn now(&self) -> f64 {
+        CLOCK.with(|clock| clock.time.get())
+    }
+
+    /// The number of the frames performed since the virtual time was activated.
+    pub fn frame(&self) -> usize {
+        CLOCK.with(|clock| clock.frame.get())
+    }
+
+    /// Set the duration of a single animation frame, in milliseconds.
+    pub fn set_frame_duration(&self, duration_ms: f64) {
+        CLOCK.with(|clock| clock.frame_duration.set(duration_ms));
+    }
+
+    /// Perform all the scheduled microtasks, including the ones scheduled by the performed tasks.
+    /// The tasks are performed in the same order as they would be by the JavaScript event loop.
+    pub fn flush_microtasks(&self) {
+        microtasks::flush_microtasks();
+    }
+
+    /// Advance the time by the given number of animation frames, firing all the timers due before
+    /// or at the last frame. The microtasks are flushed before and after each frame and timer.
+    pub fn advance_frames(&self, count: usize) {
+        for _ in 0..count {
+            let frame_time = self.next_frame_time();
+            while let Some(timer) = take_timer_due(frame_time) {
+                self.run_timer(timer);
+            }
+            self.run_frame(frame_time);
+        }
+    }
+
+    /// Advance the time by the given number of milliseconds, performing all the animation frames
+    /// and firing all the timers that fall into that period. The microtasks are flushed before and
+    /// after each frame and timer, and once the time is advanced.
+    pub fn advance_time(&self, duration_ms: f64) {
+        let target_time = self.now() + duration_ms;
+        loop {
+            let frame_time = self.next_frame_time();
+            if let Some(timer) = take_timer_due(frame_time.min(target_time)) {
+                self.run_timer(timer);
+            } else if frame_time <= target_time {
+                self.run_frame(frame_time);
+            } else {
+                break;
+            }
+        }
+        CLOCK.with(|clock| clock.time.set(target_time));
+        self.flush_microtasks();
+    }
+
+    fn next_frame_time(&self) -> f64 {
+        CLOCK.with(|clock| clock.last_frame_time.get() + clock.frame_duration.get())
+    }
+
+    fn run_timer(&self, timer: VirtualTimer) {
+        self.flush_microtasks();
+        CLOCK.with(|clock| clock.time.set(clock.time.get().max(timer.due_time)));
+        (timer.callback)();
+        self.flush_microtasks();
+    }
+
+    fn run_frame(&self, frame_time: f64) {
+        self.flush_microtasks();
+        let frame_callbacks = CLOCK.with(|clock| {
+            clock.time.set(frame_time);
+            clock.last_frame_time.set(frame_time);
+            clock.frame.set(clock.frame.get() + 1);
+            clock.frame_callbacks.clone_ref()
+        });
+        frame_callbacks.run_all(frame_time);
+        self.flush_microtasks();
+    }
+}
+
+impl Drop for VirtualTime {
+    fn drop(&mut self) {
+        CLOCK.with(|clock| clock.active_instances.set(clock.active_instances.get() - 1));
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::microtasks::next_microtask;
+
+    #[test]
+    fn microtasks_wait_for_flush() {
+        let time = VirtualTime::new();
+        assert!(is_active());
+        let log: Rc<RefCell<Vec<usize>>> = default();
+        next_microtask(f!(log.borrow_mut().push(1))).forget();
+        assert!(log.borrow().is_empty());
+        time.flush_microtasks();
+        assert_eq!(*log.borrow(), vec![1]);
+        drop(time);
+        assert!(!is_active());
+    }
+
+    #[test]
+    fn advancing_frames() {
+        let time = VirtualTime::new();
+        time.set_frame_duration(10.0);
+        let log: Rc<RefCell<Vec<String>>> = default();
+        let _handle = on_frame(f!([log] (frame_time) {
+            log.borrow_mut().push(format!("frame {frame_time}"));
+            next_microtask(f!(log.borrow_mut().push(format!("task {frame_time}")))).forget();
+        }));
+        next_microtask(f!(log.borrow_mut().push("initial task".into()))).forget();
+        time.advance_frames(2);
+        assert_eq!(time.frame(), 2);
+        assert_eq!(time.now(), 20.0);
+        let expected = ["initial task", "frame 10", "task 10", "frame 20", "task 20"];
+        assert_eq!(*log.borrow(), expected);
+    }
+
+    #[test]
+    fn advancing_time() {
+        let time = VirtualTime::new();
+        time.set_frame_duration(10.0);
+        let frames: Rc<RefCell<Vec<f64>>> = default();
+        let _handle = on_frame(f!((frame_time) frames.borrow_mut().push(frame_time)));
+        time.advance_time(25.0);
+        assert_eq!(*frames.borrow(), vec![10.0, 20.0]);
+        assert_eq!(time.now(), 25.0);
+        time.advance_time(5.0);
+        assert_eq!(*frames.borrow(), vec![10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn firing_timers() {
+        let time = VirtualTime::new();
+        time.set_frame_duration(10.0);
+        let log: Rc<RefCell<Vec<String>>> = default();
+        let _handle =
+            on_frame(f!((frame_time) log.borrow_mut().push(format!("frame {frame_time}"))));
+        let log_timer = |label: &'static str| {
+            let log = log.clone_ref();
+            move || log.borrow_mut().push(format!("{label} {}", now()))
+        };
+        schedule_timer(20.0, log_timer("timer"));
+        let nested_timer = log_timer("nested timer");
+        let first_timer = log_timer("timer");
+        schedule_timer(5.0, move || {
+            first_timer();
+            schedule_timer(0.0, nested_timer);
+        });
+        let cancelled = schedule_timer(15.0, log_timer("cancelled"));
+        cancel_timer(cancelled);
+        time.advance_time(25.0);
+        let expected = ["timer 5", "nested timer 5", "frame 10", "timer 20", "frame 20"];
+        assert_eq!(*log.borrow(), expected);
+    }
+}
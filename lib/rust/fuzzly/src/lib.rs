@@ -7,6 +7,9 @@
 //!
 //! The metrics used for scoring may be adjusted by implementing `Metric` trait, or by customizing
 //! parameters of metrics defined in `metric` module.
+//!
+//! When the pattern may contain typos or its words may be reordered, the `tolerant` module provides
+//! an alternative scoring, along with a preprocessed list of candidates for scoring many of them.
 
 // === Features ===
 #![feature(option_result_contains)]
@@ -32,6 +35,7 @@
 pub mod metric;
 pub mod score;
 pub mod subsequence_graph;
+pub mod tolerant;
 
 pub use enso_prelude as prelude;
 pub use metric::Metric;
@@ -39,3 +43,4 @@ pub use score::find_best_subsequence;
 pub use score::matches;
 pub use score::Subsequence;
 pub use subsequence_graph::Graph as SubsequenceGraph;
+pub use tolerant::Index as TolerantIndex;
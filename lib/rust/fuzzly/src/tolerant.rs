@@ -0,0 +1,567 @@
+//! Typo-tolerant scoring of how given text matches the query.
+//!
+//! Unlike `find_best_subsequence`, the query does not have to be a subsequence of the text:
+//! - The query is split into space-separated words, each matched against a different word of the
+//!   text, in any order. Thus "csv read" matches `read_csv`.
+//! - A query word may match a text word with a bounded number of typos, being a missing, an extra
+//!   or a wrong character, or two transposed characters. Thus "raed" matches `read`.
+//!
+//! To score many candidates against the same query, use the [`Index`]: a list of candidates which
+//! are split into words once, and scanned linearly on each search, skipping the ones lacking some
+//! query characters.
+
+use crate::prelude::*;
+
+
+
+// ===============
+// === Options ===
+// ===============
+
+/// Parameters of the typo-tolerant scoring.
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    /// The maximum number of typos in a single query word.
+    pub max_edits:              usize,
+    /// Shorter query words must match without typos, as otherwise they would match almost any
+    /// word.
+    pub min_typo_word_length:   usize,
+    /// Score of a query word being a prefix of the text word.
+    pub prefix_weight:          f32,
+    /// Score of a query word being a subsequence of the text word.
+    pub subsequence_weight:     f32,
+    /// Score of a query word matching the text word prefix with a single typo. Matches with more
+    /// typos have this score divided by the number of typos.
+    pub typo_weight:            f32,
+    /// Additional score multiplied by the ratio of matched characters of the text word.
+    pub coverage_weight:        f32,
+    /// Bonus for matching the first word of the text.
+    pub first_word_bonus:       f32,
+    /// Bonus for each pair of query words matching the text words in the same order.
+    pub order_bonus:            f32,
+    /// Penalty for each text word not matched by any query word.
+    pub unmatched_word_penalty: f32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_edits:              1,
+            min_typo_word_length:   3,
+            prefix_weight:          1.0,
+            subsequence_weight:     0.6,
+            typo_weight:            0.5,
+            coverage_weight:        0.2,
+            first_word_bonus:       0.1,
+            order_bonus:            0.1,
+            unmatched_word_penalty: 0.05,
+        }
+    }
+}
+
+impl Options {
+    fn allowed_edits(&self, query_word: &QueryWord) -> usize {
+        if query_word.chars.len() >= self.min_typo_word_length {
+            self.max_edits
+        } else {
+            0
+        }
+    }
+}
+
+
+
+// =============
+// === Match ===
+// =============
+
+/// How a single query word matched a text word.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MatchKind {
+    /// The query word is a prefix of the text word.
+    Prefix,
+    /// The query word is a subsequence of the text word.
+    Subsequence,
+    /// The query word is a prefix of the text word after fixing the given number of typos.
+    Typo {
+        /// The number of edits needed.
+        edits: usize,
+    },
+}
+
+/// The result of typo-tolerant scoring.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Match {
+    /// The score of the match; the greater, the better.
+    pub score:   f32,
+    /// The total number of typos in the query.
+    pub edits:   usize,
+    /// Sorted indices of `text`'s chars which were matched by the query.
+    pub indices: Vec<usize>,
+}
+
+/// A match of the single query word.
+#[derive(Clone, Debug)]
+struct WordMatch {
+    kind:    MatchKind,
+    score:   f32,
+    indices: Vec<usize>,
+}
+
+
+
+// =============
+// === Words ===
+// =============
+
+/// Bitmask of the characters occurring in a text, used for quick rejection of candidates.
+fn char_mask(chars: impl IntoIterator<Item = char>) -> u64 {
+    chars.into_iter().fold(0, |mask, ch| {
+        let bit = match ch {
+            'a'..='z' => ch as u32 - 'a' as u32,
+            '0'..='9' => ch as u32 - '0' as u32 + 26,
+            _ => 63,
+        };
+        mask | 1 << bit
+    })
+}
+
+/// A single word of the candidate text, lowercased.
+#[derive(Clone, Debug)]
+struct Word {
+    chars: Vec<char>,
+    /// The index of the word's first char in the text.
+    start: usize,
+}
+
+/// A candidate text preprocessed for scoring.
+#[derive(Clone, Debug)]
+struct Candidate {
+    words: Vec<Word>,
+    mask:  u64,
+}
+
+impl Candidate {
+    /// Words are separated by non-alphanumeric characters and by camel-case boundaries. At most
+    /// 64 words are considered.
+    fn new(text: &str) -> Self {
+        let mut words: Vec<Word> = default();
+        let mut previous: Option<char> = None;
+        for (index, ch) in text.chars().enumerate() {
+            if ch.is_alphanumeric() {
+                let camel_case_boundary = previous.map_or(false, char::is_lowercase)
+                    && ch.is_uppercase()
+                    || previous.map_or(false, char::is_numeric) != ch.is_numeric();
+                let continues_word = previous.map_or(false, char::is_alphanumeric);
+                if !continues_word || camel_case_boundary {
+                    words.push(Word { chars: default(), start: index });
+                }
+                if let Some(word) = words.last_mut() {
+                    word.chars.extend(ch.to_lowercase());
+                }
+            }
+            previous = Some(ch);
+        }
+        words.truncate(64);
+        let mask = char_mask(words.iter().flat_map(|word| word.chars.iter().copied()));
+        Candidate { words, mask }
+    }
+}
+
+/// A single word of the query, lowercased.
+#[derive(Clone, Debug)]
+struct QueryWord {
+    chars: Vec<char>,
+    mask:  u64,
+}
+
+/// A query preprocessed for scoring.
+#[derive(Clone, Debug)]
+struct Query {
+    words: Vec<QueryWord>,
+}
+
+impl Query {
+    fn new(query: &str) -> Self {
+        let words = query.split_whitespace().map(|word| {
+            let chars = word.chars().flat_map(char::to_lowercase).collect_vec();
+            let mask = char_mask(chars.iter().copied());
+            QueryWord { chars, mask }
+        });
+        Query { words: words.collect() }
+    }
+
+    /// Quick check whether the candidate may match the query. If it returns `false`, the
+    /// candidate certainly does not match.
+    ///
+    /// Each typo introduces at most one character not occurring in the candidate.
+    fn may_match(&self, candidate: &Candidate, options: &Options) -> bool {
+        self.words.iter().all(|word| {
+            let missing = (word.mask & !candidate.mask).count_ones() as usize;
+            missing <= options.allowed_edits(word)
+        })
+    }
+}
+
+
+
+// ===============
+// === Scoring ===
+// ===============
+
+/// The minimum number of edits (optimal string alignment distance) to turn `query` into some
+/// prefix of `word`, together with that prefix's length. Returns `None` if more than `max_edits`
+/// edits are needed.
+fn prefix_edit_distance(query: &[char], word: &[char], max_edits: usize) -> Option<(usize, usize)> {
+    // `rows[i][j]` is the distance between `query[..i]` and `word[..j]`.
+    let mut rows = vec![(0..=word.len()).collect_vec()];
+    for i in 1..=query.len() {
+        let mut row = vec![i; word.len() + 1];
+        for j in 1..=word.len() {
+            let substitution = (query[i - 1] != word[j - 1]) as usize;
+            row[j] =
+                (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + substitution);
+            let transposed = i > 1 && j > 1 && query[i - 1] == word[j - 2];
+            if transposed && query[i - 2] == word[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        // A transposition may refer two rows back, so both rows must exceed the limit.
+        let exceeds = |row: &[usize]| row.iter().all(|distance| *distance > max_edits);
+        if exceeds(&row) && exceeds(&rows[i - 1]) {
+            return None;
+        }
+        rows.push(row);
+    }
+    let last = rows.last()?;
+    // On ties prefer the prefix of the query's length.
+    let distance_to_prefix = |prefix: &usize| (last[*prefix], prefix.abs_diff(query.len()));
+    let best_prefix = (0..=word.len()).min_by_key(distance_to_prefix)?;
+    (last[best_prefix] <= max_edits).then_some((last[best_prefix], best_prefix))
+}
+
+/// Indices of the first occurrence of `query` as a subsequence of `word`.
+fn subsequence_indices(query: &[char], word: &[char]) -> Option<Vec<usize>> {
+    let mut indices = Vec::with_capacity(query.len());
+    let mut word_chars = word.iter().enumerate();
+    for query_char in query {
+        let (index, _) = word_chars.find(|(_, word_char)| *word_char == query_char)?;
+        indices.push(index);
+    }
+    Some(indices)
+}
+
+fn match_word(query: &QueryWord, word: &Word, options: &Options) -> Option<WordMatch> {
+    let query_len = query.chars.len();
+    let coverage = |matched: usize| matched as f32 / word.chars.len().max(1) as f32;
+    let to_text_indices = |indices: Vec<usize>| indices.into_iter().map(|i| i + word.start);
+    if word.chars.starts_with(&query.chars) {
+        let score = options.prefix_weight + options.coverage_weight * coverage(query_len);
+        let indices = (word.start..word.start + query_len).collect();
+        Some(WordMatch { kind: MatchKind::Prefix, score, indices })
+    } else if let Some(indices) = subsequence_indices(&query.chars, &word.chars) {
+        let score = options.subsequence_weight + options.coverage_weight * coverage(query_len);
+        let indices = to_text_indices(indices).collect();
+        Some(WordMatch { kind: MatchKind::Subsequence, score, indices })
+    } else {
+        let max_edits = options.allowed_edits(query);
+        if max_edits == 0 {
+            return None;
+        }
+        let (edits, prefix) = prefix_edit_distance(&query.chars, &word.chars, max_edits)?;
+        let score = options.typo_weight / edits as f32 + options.coverage_weight * coverage(prefix);
+        let indices = (word.start..word.start + prefix).collect();
+        Some(WordMatch { kind: MatchKind::Typo { edits }, score, indices })
+    }
+}
+
+/// Find the best assignment of query words to distinct candidate words.
+///
+/// The assignment maximizing the sum of the word scores and the first word bonus is found with
+/// the Hungarian algorithm in `O(n²m)` time for `n` query words and `m <= 64` candidate words, so
+/// even a long query of single letters is cheap to score. The order bonus does not decompose into
+/// per-word scores, so it is only added to the score of the found assignment.
+#[derive(Debug)]
+struct Assignment<'a> {
+    options:     &'a Options,
+    /// `matches[i][j]` is the match of i-th query word with j-th candidate word.
+    matches:     Vec<Vec<Option<WordMatch>>>,
+    words_count: usize,
+}
+
+impl<'a> Assignment<'a> {
+    /// The score of assigning the query word to the candidate word, if they match.
+    fn gain(&self, query_word: usize, word: usize) -> Option<f32> {
+        let word_match = self.matches[query_word][word].as_ref()?;
+        let first_word_bonus = if word == 0 { self.options.first_word_bonus } else { 0.0 };
+        Some(word_match.score + first_word_bonus)
+    }
+
+    /// The total score of the assignment, where `words[i]` is the word matched by i-th query word.
+    fn score(&self, words: &[usize]) -> f32 {
+        let gains =
+            words.iter().enumerate().filter_map(|(query_word, word)| self.gain(query_word, *word));
+        let words_score: f32 = gains.sum();
+        let in_order = words.iter().tuple_windows().filter(|(a, b)| a < b).count();
+        let unmatched = self.words_count - words.len();
+        words_score + self.options.order_bonus * in_order as f32
+            - self.options.unmatched_word_penalty * unmatched as f32
+    }
+
+    /// The best score with the assignment, or `None` if some query word cannot be assigned a
+    /// distinct matching word.
+    fn find_best(&self) -> Option<(f32, Vec<usize>)> {
+        let rows = self.matches.len();
+        let columns = self.words_count;
+        let max_gain = (0..rows)
+            .cartesian_product(0..columns)
+            .filter_map(|(row, column)| self.gain(row, column))
+            .fold(0.0_f64, |max, gain| max.max((gain as f64).abs()));
+        // Higher than the cost of any assignment using only matching pairs.
+        let mismatch_cost = 2.0 * rows as f64 * max_gain + 1.0;
+        let cost = |row: usize, column: usize| {
+            self.gain(row, column).map_or(mismatch_cost, |gain| -(gain as f64))
+        };
+        // The potentials and the assignment use 1-based rows and columns; `row_of[column] == 0`
+        // means the column is not assigned.
+        let mut row_potential = vec![0.0; rows + 1];
+        let mut column_potential = vec![0.0; columns + 1];
+        let mut row_of = vec![0; columns + 1];
+        let mut previous_column = vec![0; columns + 1];
+        for row in 1..=rows {
+            row_of[0] = row;
+            let mut column = 0;
+            let mut min_slack = vec![f64::INFINITY; columns + 1];
+            let mut visited = vec![false; columns + 1];
+            while row_of[column] != 0 {
+                visited[column] = true;
+                let current_row = row_of[column];
+                let mut delta = f64::INFINITY;
+                let mut next_column = 0;
+                for candidate in (1..=columns).filter(|candidate| !visited[*candidate]) {
+                    let slack = cost(current_row - 1, candidate - 1)
+                        - row_potential[current_row]
+                        - column_potential[candidate];
+                    if slack < min_slack[candidate] {
+                        min_slack[candidate] = slack;
+                        previous_column[candidate] = column;
+                    }
+                    if min_slack[candidate] < delta {
+                        delta = min_slack[candidate];
+                        next_column = candidate;
+                    }
+                }
+                for candidate in 0..=columns {
+                    if visited[candidate] {
+                        row_potential[row_of[candidate]] += delta;
+                        column_potential[candidate] -= delta;
+                    } else {
+                        min_slack[candidate] -= delta;
+                    }
+                }
+                column = next_column;
+            }
+            while column != 0 {
+                let previous = previous_column[column];
+                row_of[column] = row_of[previous];
+                column = previous;
+            }
+        }
+        let mut words = vec![0; rows];
+        for (column, row) in row_of.iter().enumerate().skip(1) {
+            if *row != 0 {
+                words[row - 1] = column - 1;
+            }
+        }
+        let all_match = words.iter().enumerate().all(|(row, word)| self.gain(row, *word).is_some());
+        all_match.then(|| (self.score(&words), words))
+    }
+}
+
+fn score_candidate(candidate: &Candidate, query: &Query, options: &Options) -> Option<Match> {
+    if query.words.is_empty() {
+        return Some(default());
+    }
+    if query.words.len() > candidate.words.len() || !query.may_match(candidate, options) {
+        return None;
+    }
+    let matches = query.words.iter().map(|query_word| {
+        candidate.words.iter().map(|word| match_word(query_word, word, options)).collect_vec()
+    });
+    let words_count = candidate.words.len();
+    let mut assignment = Assignment { options, matches: matches.collect(), words_count };
+    let (score, words) = assignment.find_best()?;
+    let mut edits = 0;
+    let mut indices = vec![];
+    for (query_word, word) in words.into_iter().enumerate() {
+        if let Some(word_match) = assignment.matches[query_word][word].take() {
+            if let MatchKind::Typo { edits: word_edits } = word_match.kind {
+                edits += word_edits;
+            }
+            indices.extend(word_match.indices);
+        }
+    }
+    indices.sort_unstable();
+    Some(Match { score, edits, indices })
+}
+
+/// Score how the `text` matches the `query`, tolerating typos and words reordering. Returns `None`
+/// if `text` does not match `query`. Empty `query` gives 0.0 score.
+///
+/// When scoring many texts against the same query, prefer using the [`Index`], which splits the
+/// texts into words only once.
+pub fn find_best_match(text: impl Str, query: impl Str, options: &Options) -> Option<Match> {
+    let candidate = Candidate::new(text.as_ref());
+    score_candidate(&candidate, &Query::new(query.as_ref()), options)
+}
+
+
+
+// =============
+// === Index ===
+// =============
+
+/// A list of texts preprocessed for typo-tolerant scoring against many queries. Searching scans
+/// all the texts, cheaply rejecting the ones which lack some characters of the query.
+#[derive(Clone, Debug, Default)]
+pub struct Index {
+    candidates: Vec<Candidate>,
+}
+
+impl Index {
+    /// Preprocess the given texts. The texts are identified by their position.
+    pub fn new<T: Str>(texts: impl IntoIterator<Item = T>) -> Self {
+        let candidates = texts.into_iter().map(|text| Candidate::new(text.as_ref())).collect();
+        Index { candidates }
+    }
+
+    /// Add the text to the list, returning its identifier.
+    pub fn push(&mut self, text: impl Str) -> usize {
+        self.candidates.push(Candidate::new(text.as_ref()));
+        self.candidates.len() - 1
+    }
+
+    /// The number of texts.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Check if there are no texts.
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Score the text with given identifier. Returns `None` if the text does not match the query
+    /// or there is no such text.
+    pub fn score(&self, id: usize, query: impl Str, options: &Options) -> Option<Match> {
+        let candidate = self.candidates.get(id)?;
+        score_candidate(candidate, &Query::new(query.as_ref()), options)
+    }
+
+    /// Find all texts matching the query, sorted by descending score. The texts of equal score
+    /// are ordered by their identifiers.
+    pub fn search(&self, query: impl Str, options: &Options) -> Vec<(usize, Match)> {
+        let query = Query::new(query.as_ref());
+        let candidates = self.candidates.iter().enumerate();
+        let matches = candidates.filter_map(|(id, candidate)| {
+            score_candidate(candidate, &query, options).map(|found| (id, found))
+        });
+        let mut matches = matches.collect_vec();
+        matches.sort_by(|(id1, match1), (id2, match2)| {
+            match2.score.total_cmp(&match1.score).then(id1.cmp(id2))
+        });
+        matches
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find(text: &str, query: &str) -> Option<Match> {
+        find_best_match(text, query, &Options::default())
+    }
+
+    #[test]
+    fn splitting_words() {
+        let candidate = Candidate::new("Data.read_csv fileName2");
+        let words = candidate.words.iter().map(|w| (w.chars.iter().collect::<String>(), w.start));
+        let expected =
+            [("data", 0), ("read", 5), ("csv", 10), ("file", 14), ("name", 18), ("2", 22)];
+        let expected = expected.iter().map(|(word, start)| (word.to_string(), *start));
+        assert_eq!(words.collect_vec(), expected.collect_vec());
+    }
+
+    #[test]
+    fn edit_distance() {
+        let distance = |query: &str, word: &str, max_edits| {
+            let query = query.chars().collect_vec();
+            let word = word.chars().collect_vec();
+            prefix_edit_distance(&query, &word, max_edits)
+        };
+        assert_eq!(distance("read", "read", 1), Some((0, 4)));
+        assert_eq!(distance("raed", "read", 1), Some((1, 4)));
+        assert_eq!(distance("rad", "read_csv", 1), Some((1, 4)));
+        assert_eq!(distance("reaad", "read", 1), Some((1, 4)));
+        assert_eq!(distance("rxad", "read", 1), Some((1, 4)));
+        assert_eq!(distance("arde", "read", 1), None);
+        assert_eq!(distance("arde", "read", 2), Some((2, 2)));
+    }
+
+    #[test]
+    fn matching_with_typos() {
+        let found = find("read", "raed").unwrap();
+        assert_eq!(found.edits, 1);
+        assert_eq!(found.indices, vec![0, 1, 2, 3]);
+        assert!(find("read", "rxad").is_some());
+        assert!(find("read", "redd").is_some());
+        assert!(find("read", "xyz").is_none());
+        // Short words must match exactly.
+        assert!(find("read", "rx").is_none());
+        assert!(find("read", "ra").is_some());
+    }
+
+    #[test]
+    fn matching_reordered_words() {
+        let found = find("read_csv", "csv read").unwrap();
+        assert_eq!(found.edits, 0);
+        assert_eq!(found.indices, (0..8).filter(|i| *i != 4).collect_vec());
+        let in_order = find("read_csv", "read csv").unwrap();
+        assert!(in_order.score > found.score);
+        assert!(find("read_csv", "csv raed").is_some());
+        // Each query word must match a different text word.
+        assert!(find("read_csv", "read rea").is_none());
+    }
+
+    #[test]
+    fn scoring_long_query_of_short_words() {
+        // Every query word matches every text word, so checking all assignments would take
+        // 64!/24! steps.
+        let text = ["a"; 64].join("_");
+        let query = ["a"; 40].join(" ");
+        let started = std::time::Instant::now();
+        let found = find(&text, &query).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(found.indices.len(), 40);
+        assert_eq!(found.edits, 0);
+    }
+
+    #[test]
+    fn ranking_matches() {
+        let options = Options::default();
+        let index = Index::new(["write_csv", "read_csv", "read", "rate_advised", "reader"]);
+        let ids = |query: &str| index.search(query, &options).into_iter().map(|(id, _)| id);
+        assert_eq!(ids("read").collect_vec(), vec![2, 1, 4]);
+        assert_eq!(ids("raed").collect_vec(), vec![2, 1, 4]);
+        assert_eq!(ids("csv").collect_vec(), vec![0, 1]);
+        assert_eq!(ids("").count(), 5);
+        assert!(index.score(2, "raed", &options).is_some());
+        assert!(index.score(7, "raed", &options).is_none());
+    }
+}
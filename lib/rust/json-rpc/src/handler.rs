@@ -10,6 +10,7 @@ use crate::error::HandlingError;
 use crate::error::RpcError;
 use crate::messages;
 use crate::messages::Id;
+use crate::server::Methods;
 use crate::transport::Transport;
 use crate::transport::TransportEvent;
 
@@ -17,10 +18,12 @@ use futures::channel::mpsc::unbounded;
 use futures::channel::mpsc::UnboundedSender;
 use futures::channel::oneshot;
 use futures::future;
+use futures::future::LocalBoxFuture;
 use futures::FutureExt;
 use futures::Stream;
 use futures::StreamExt;
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::future::Future;
 
 
@@ -111,6 +114,12 @@ pub enum Event<N> {
 /// from this container.
 pub type OngoingCalls = HashMap<Id, oneshot::Sender<ReplyMessage>>;
 
+/// A future computing and sending a reply for a request received from the peer.
+pub type PendingReply = LocalBoxFuture<'static, ()>;
+
+/// A future computing a response to a request received from the peer.
+pub type PendingResponse = LocalBoxFuture<'static, messages::ResponseMessage<serde_json::Value>>;
+
 
 
 // ===============
@@ -125,6 +134,11 @@ pub type OngoingCalls = HashMap<Id, oneshot::Sender<ReplyMessage>>;
 ///
 /// Notifications and internal messages are emitted using the `events` stream.
 ///
+/// Requests received from the peer are dispatched to the methods registered in `Methods` and
+/// answered once the method's future completes. Such methods may themselves make requests to
+/// the peer, so both sides of the connection can act as a server. Until any method is
+/// registered, the requests are handled as notifications.
+///
 /// `Notification` is a type for notifications. It should implement
 /// `DeserializeOwned` and deserialize from JSON maps with `method` and `params`
 /// fields.
@@ -145,6 +159,12 @@ pub struct HandlerData<Notification> {
     id_generator    : IdGenerator,
     /// Transports text messages between this handler and the peer.
     transport       : Box<dyn Transport>,
+    /// Methods that can be called by the peer.
+    methods         : Methods,
+    /// Handle to send replies to be completed by the runner.
+    pending_replies : Option<UnboundedSender<PendingReply>>,
+    /// Method of the notification sent when the reply to a request is no longer awaited.
+    cancel_method   : Option<String>,
 }
 
 
@@ -164,6 +184,11 @@ impl<Notification> {
         self.ongoing_calls.remove(&id)
     }
 
+    /// Checks if the request awaits a reply.
+    pub fn is_ongoing_request(&self, id:Id) -> bool {
+        self.ongoing_calls.contains_key(&id)
+    }
+
     /// Removes all the ongoing requests. This will be recognized by the `Future`s
     /// as losing connection error.
     pub fn clear_ongoing_requests(&mut self) {
@@ -206,6 +231,46 @@ impl<Notification> {
     pub fn set_timeout(&mut self, timeout:Duration) {
         self.timeout = timeout;
     }
+
+    /// Method of the notification sent to the peer when the future awaiting a reply is dropped
+    /// (or times out) before the reply is received. If `None` (the default), no notification is
+    /// sent, as not all peers support cancelling requests.
+    pub fn cancel_method(&self) -> Option<String> {
+        self.cancel_method.clone()
+    }
+
+    /// Set the method of the notification sent to the peer when the future awaiting a reply is
+    /// dropped, like [`crate::constants::CANCEL_METHOD`]. Should be set only if the peer supports
+    /// cancelling requests. See `cancel_method`.
+    pub fn set_cancel_method(&mut self, method:Option<String>) {
+        self.cancel_method = method;
+    }
+
+    /// The registry of methods that can be called by the peer.
+    pub fn methods(&self) -> Methods {
+        self.methods.clone_ref()
+    }
+
+    /// Replace the registry of methods that can be called by the peer. Requests already being
+    /// processed are not affected.
+    pub fn set_methods(&mut self, methods:Methods) {
+        self.methods = methods;
+    }
+
+    /// Creates a new stream of replies to be completed. The replies pushed to the previous stream
+    /// will not be sent if it is dropped.
+    fn pending_reply_stream(&mut self) -> impl Stream<Item = PendingReply> {
+        let (transmitter,receiver) = unbounded();
+        self.pending_replies = Some(transmitter);
+        receiver
+    }
+
+    /// Passes the reply future to the runner. Without a runner the reply is dropped.
+    fn push_pending_reply(&self, reply:PendingReply) {
+        if let Some(transmitter) = self.pending_replies.as_ref() {
+            channel::emit(transmitter,reply)
+        }
+    }
 }
 } // shared!
 
@@ -238,6 +303,9 @@ impl<Notification> Handler<Notification> {
             id_generator:    IdGenerator::new(),
             transport:       Box::new(transport),
             outgoing_events: None,
+            methods:         default(),
+            pending_replies: None,
+            cancel_method:   None,
         };
         Handler { rc: Rc::new(RefCell::new(data)) }
     }
@@ -284,26 +352,146 @@ impl<Notification> Handler<Notification> {
         &self,
         id: Id,
         message_json: &str,
+    ) -> impl Future<Output = Result<Returned>> {
+        let sent = Rc::new(Cell::new(false));
+        let ret = self.expect_reply(id, sent.clone_ref());
+        if self.send_text_message(message_json).is_ok() {
+            sent.set(true);
+        } else {
+            // If message cannot be send, future ret must be cancelled.
+            self.remove_ongoing_request(id);
+        }
+        ret
+    }
+
+    /// Registers a request as ongoing and returns a `Future` that shall yield its decoded reply.
+    /// The request itself must be sent by the caller.
+    ///
+    /// Dropping the `Future` before the reply is received removes the ongoing request and, if the
+    /// request was `sent`, notifies the peer with the `cancel_method` notification.
+    fn expect_reply<Returned: DeserializeOwned>(
+        &self,
+        id: Id,
+        sent: Rc<Cell<bool>>,
     ) -> impl Future<Output = Result<Returned>> {
         let (sender, receiver) = oneshot::channel::<ReplyMessage>();
         let ret = receiver.map(|result_or_cancel| {
             let result = result_or_cancel?;
             decode_result(result)
         });
-
         self.insert_ongoing_request(id, sender);
-        if self.send_text_message(message_json).is_err() {
-            // If message cannot be send, future ret must be cancelled.
-            self.remove_ongoing_request(id);
-        }
+        let cancel_guard = CancelOnDrop { id, handler: self.downgrade(), sent };
 
         let millis = self.timeout().as_millis();
-        future::select(ret, sleep(self.timeout()).boxed_local()).map(move |either| match either {
-            future::Either::Left((x, _)) => x,
-            future::Either::Right((_, _)) => Err(RpcError::TimeoutError { millis }),
+        future::select(ret, sleep(self.timeout()).boxed_local()).map(move |either| {
+            // A timed-out request is cancelled once the guard is dropped.
+            drop(cancel_guard);
+            match either {
+                future::Either::Left((x, _)) => x,
+                future::Either::Right((_, _)) => Err(RpcError::TimeoutError { millis }),
+            }
         })
     }
 
+    /// Creates a new batch of requests, which will be sent to the peer in a single message.
+    pub fn open_batch(&self) -> Batch<Notification> {
+        Batch { handler: self.clone(), requests: default(), sent: default() }
+    }
+
+    /// Notifies the peer that the reply to the request is no longer awaited.
+    fn send_cancel_notification(&self, id: Id) {
+        if let Some(method) = self.cancel_method() {
+            let params = serde_json::json!({ "id": id });
+            if let Err(err) = self.send_notification(&method, params) {
+                warn!("Failed to cancel the request {id}: {err}");
+            }
+        }
+    }
+
+    /// Register a method that can be called by the peer. See [`Methods::register`].
+    pub fn register_method<Params, Returned, F, Fut>(&self, name: impl Into<String>, method: F)
+    where
+        Params: DeserializeOwned,
+        Returned: Serialize,
+        F: Fn(Params) -> Fut + 'static,
+        Fut: Future<Output = Result<Returned>> + 'static, {
+        self.methods().register(name, method)
+    }
+
+    /// Sends a notification to the peer. Notifications are not replied to.
+    pub fn send_notification<Params: Serialize>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> FallibleResult {
+        let call = messages::MethodCall { method: method.into(), params };
+        let message = messages::Message::new(messages::Notification(call));
+        let serialized_message = serde_json::to_string(&message)?;
+        self.send_text_message(&serialized_message)
+    }
+
+    /// Sends a reply to the request with the given id.
+    pub fn send_reply(&self, id: Id, result: ReplyMessage) -> FallibleResult {
+        let message = messages::Message::new(messages::Response { id, result });
+        self.send_json(&message)
+    }
+
+    /// Sends a message serialized to JSON to the peer.
+    fn send_json(&self, message: &impl Serialize) -> FallibleResult {
+        let serialized_message = serde_json::to_string(message)?;
+        self.send_text_message(&serialized_message)
+    }
+
+    /// Dispatches the `Request` to the registered method. Returns a `Future` that shall yield
+    /// the response.
+    fn respond(
+        &self,
+        message: messages::Request<messages::MethodCall<serde_json::Value>>,
+    ) -> PendingResponse {
+        let id = message.id;
+        let messages::MethodCall { method, params } = message.call;
+        let reply = self.methods().call(&method, params);
+        reply
+            .map(move |result| messages::Message::new(messages::Response { id, result }))
+            .boxed_local()
+    }
+
+    /// Passes the `Future` yielding the response (or batch of responses) to the runner, which
+    /// will send it to the peer once ready.
+    fn send_response_when_ready<T: Serialize + 'static>(
+        &self,
+        response: impl Future<Output = T> + 'static,
+    ) {
+        let weak_handler = self.downgrade();
+        let send_response = response.map(move |response| {
+            if let Some(handler) = weak_handler.upgrade() {
+                if let Err(err) = handler.send_json(&response) {
+                    warn!("Failed to send a reply to the peer's request: {err}");
+                }
+            }
+        });
+        self.push_pending_reply(send_response.boxed_local());
+    }
+
+    /// Deal with `Request` message from the peer.
+    ///
+    /// The call is dispatched to the registered method, and its reply will be sent by the
+    /// `runner` once ready. Calls to unknown methods are replied to with an error.
+    pub fn process_request(
+        &self,
+        message: messages::Request<messages::MethodCall<serde_json::Value>>,
+    ) {
+        let response = self.respond(message);
+        self.send_response_when_ready(response);
+    }
+
+    /// Check whether the requests of the peer are answered, that is, whether any method that can
+    /// be called by the peer is registered. Otherwise, the messages decoded as requests are
+    /// handled as notifications. See [`messages::IncomingMessage`] to learn more.
+    pub fn answers_requests(&self) -> bool {
+        !self.methods().is_empty()
+    }
+
     /// Deal with `Response` message from the peer.
     ///
     /// It shall be either matched with an open request or yield an error.
@@ -337,18 +525,57 @@ impl<Notification> Handler<Notification> {
 
     /// Deal with incoming text message from the peer.
     ///
-    /// The message must conform either to the `Response` or to the
-    /// `Notification` JSON-serialized format. Otherwise, an error is raised.
+    /// The message must conform either to the `Response`, to the `Request` or
+    /// to the `Notification` JSON-serialized format. Otherwise, an error is
+    /// raised. Requests are handled as notifications unless the handler
+    /// [answers requests](Self::answers_requests).
     pub fn process_incoming_message(&self, message: String)
     where Notification: DeserializeOwned {
+        if let Some(batch) = messages::decode_incoming_batch(&message) {
+            self.process_incoming_batch(batch);
+            return;
+        }
         match messages::decode_incoming_message(&message) {
             Ok(messages::IncomingMessage::Response(response)) => self.process_response(response),
+            Ok(messages::IncomingMessage::Request(request)) if self.answers_requests() =>
+                self.process_request(request),
+            Ok(messages::IncomingMessage::Request(request)) =>
+                self.process_notification(request.into_notification()),
             Ok(messages::IncomingMessage::Notification(notification)) =>
                 self.process_notification(notification),
             Err(err) => self.error_occurred(HandlingError::InvalidMessage(err)),
         }
     }
 
+    /// Deal with incoming batch of messages from the peer.
+    ///
+    /// Each message is processed as by `process_incoming_message`, but the
+    /// responses to the requests are sent back in a single batch.
+    pub fn process_incoming_batch(
+        &self,
+        batch: Vec<serde_json::Result<messages::IncomingMessage>>,
+    ) where
+        Notification: DeserializeOwned,
+    {
+        let mut responses = Vec::new();
+        for message in batch {
+            match message {
+                Ok(messages::IncomingMessage::Response(response)) =>
+                    self.process_response(response),
+                Ok(messages::IncomingMessage::Request(request)) if self.answers_requests() =>
+                    responses.push(self.respond(request)),
+                Ok(messages::IncomingMessage::Request(request)) =>
+                    self.process_notification(request.into_notification()),
+                Ok(messages::IncomingMessage::Notification(notification)) =>
+                    self.process_notification(notification),
+                Err(err) => self.error_occurred(HandlingError::InvalidMessage(err)),
+            }
+        }
+        if !responses.is_empty() {
+            self.send_response_when_ready(future::join_all(responses));
+        }
+    }
+
     /// With with a handling error. Uses `on_error` callback to notify the
     /// owner.
     pub fn error_occurred(&self, error: HandlingError) {
@@ -381,13 +608,19 @@ impl<Notification> Handler<Notification> {
     /// finish, when the `Transport`'s event stream finishes, e.g. due to
     /// dropping the `Transport` itself.
     ///
+    /// The future also completes replies to the requests received from the
+    /// peer. The replies are computed concurrently, so that the methods may
+    /// wait for responses to their own requests.
+    ///
     /// It is expected that upon setting up the `Handler`, this future shall be
     /// passed to the main executor.
     pub fn runner(&mut self) -> impl Future<Output = ()>
     where Notification: DeserializeOwned + 'static {
         let event_receiver = self.transport_event_stream();
+        let reply_receiver = self.pending_reply_stream();
+        let replies = reply_receiver.for_each_concurrent(None, |reply| reply);
         let weak_data = Rc::downgrade(&self.rc);
-        event_receiver.for_each(move |event: TransportEvent| {
+        let events = event_receiver.for_each(move |event: TransportEvent| {
             let data_opt = weak_data.clone().upgrade();
             let handler_opt = data_opt.map(|rc| Handler { rc });
             if let Some(handler) = handler_opt {
@@ -396,6 +629,116 @@ impl<Notification> Handler<Notification> {
                 // If the data is inaccessible, it is ok to just drop the event here.
             }
             futures::future::ready(())
-        })
+        });
+        future::select(events.boxed_local(), replies.boxed_local()).map(|_| ())
+    }
+}
+
+
+
+// ====================
+// === Cancellation ===
+// ====================
+
+/// A guard removing the request from the ongoing calls when the `Future` awaiting its reply is
+/// dropped. If the request was already sent, the peer is notified that the reply is no longer
+/// awaited.
+struct CancelOnDrop<Notification> {
+    id:      Id,
+    handler: WeakHandler<Notification>,
+    sent:    Rc<Cell<bool>>,
+}
+
+impl<Notification> Drop for CancelOnDrop<Notification> {
+    fn drop(&mut self) {
+        if let Some(handler) = self.handler.upgrade() {
+            let was_ongoing = handler.remove_ongoing_request(self.id).is_some();
+            if was_ongoing && self.sent.get() {
+                handler.send_cancel_notification(self.id);
+            }
+        }
+    }
+}
+
+
+
+// =============
+// === Batch ===
+// =============
+
+/// A batch of requests to be sent to the peer in a single message.
+///
+/// Each opened request yields its own `Future`, just like `Handler::open_request`. The requests
+/// are sent once `send` is called; if the batch is dropped without sending, the `Future`s fail
+/// with the `RpcError::LostConnection` error.
+#[derive(Debug)]
+pub struct Batch<Notification> {
+    handler:  Handler<Notification>,
+    requests: Vec<(Id, serde_json::Value)>,
+    sent:     Rc<Cell<bool>>,
+}
+
+impl<Notification> Batch<Notification> {
+    /// Adds a request to the batch and returns a `Future` that shall yield a reply message. It is
+    /// automatically decoded into the expected type.
+    pub fn open_request<In: api::RemoteMethodCall>(
+        &mut self,
+        input: In,
+    ) -> impl Future<Output = Result<In::Returned>> {
+        let id = self.handler.generate_new_id();
+        let message = api::into_request_message(input, id);
+        self.requests.push((id, serde_json::to_value(&message).unwrap()));
+        self.handler.expect_reply(id, self.sent.clone_ref())
+    }
+
+    /// Adds a request to the batch. See `Handler::open_request_with_json`.
+    pub fn open_request_with_json<Returned: DeserializeOwned>(
+        &mut self,
+        method_name: &str,
+        input: &serde_json::Value,
+    ) -> impl Future<Output = Result<Returned>> {
+        let id = self.handler.generate_new_id();
+        let message = messages::Message::new_request(id, method_name, input);
+        self.requests.push((id, serde_json::to_value(&message).unwrap()));
+        self.handler.expect_reply(id, self.sent.clone_ref())
+    }
+
+    /// Number of requests in the batch.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Checks if no request was added to the batch.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Sends all the requests in a single message. The requests whose `Future`s were already
+    /// dropped are skipped.
+    pub fn send(mut self) -> FallibleResult {
+        let requests = mem::take(&mut self.requests);
+        let (ids, payloads): (Vec<_>, Vec<_>) =
+            requests.into_iter().filter(|(id, _)| self.handler.is_ongoing_request(*id)).unzip();
+        if payloads.is_empty() {
+            return Ok(());
+        }
+        let result = self.handler.send_json(&payloads);
+        if result.is_ok() {
+            self.sent.set(true);
+        } else {
+            // If message cannot be send, the futures must be cancelled.
+            for id in ids {
+                self.handler.remove_ongoing_request(id);
+            }
+        }
+        result
+    }
+}
+
+impl<Notification> Drop for Batch<Notification> {
+    fn drop(&mut self) {
+        for (id, _) in mem::take(&mut self.requests) {
+            self.handler.remove_ongoing_request(id);
+        }
     }
 }
@@ -1,5 +1,7 @@
 //! This is a library aimed to facilitate implementing JSON-RPC protocol
 //! clients. The main type is `Handler` that a client should build upon.
+//! The `Handler` can also answer requests made by the peer, dispatching them
+//! to the methods registered in `Methods`.
 
 // === Features ===
 #![feature(trait_alias)]
@@ -28,6 +30,7 @@ pub mod handler;
 pub mod log;
 pub mod macros;
 pub mod messages;
+pub mod server;
 pub mod test_util;
 pub mod transport;
 
@@ -38,8 +41,10 @@ pub use enso_profiler;
 pub use enso_profiler_data;
 pub use enso_web as ensogl;
 pub use error::RpcError;
+pub use handler::Batch;
 pub use handler::Event;
 pub use handler::Handler;
+pub use server::Methods;
 pub use transport::Transport;
 pub use transport::TransportEvent;
 
@@ -51,4 +56,8 @@ pub mod constants {
 
     /// The default timeout for all responses.
     pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The method of the notification cancelling a request, following the Language Server
+    /// Protocol convention. Not sent unless enabled with `Handler::set_cancel_method`.
+    pub const CANCEL_METHOD: &str = "$/cancelRequest";
 }
@@ -22,6 +22,11 @@
 ///     fn expect_call_me_please
 ///     (&mut self, my_number_is:String,result:json_rpc::api::Result<()>) { /* impl */ }
 /// ```
+///
+/// The generated `server` module allows serving any `API` implementation to the peer: the
+/// `server::register_methods` function registers all the methods in a `json_rpc::Methods`
+/// registry, and `server::connect_in_process` creates a `Client` connected to an in-process
+/// `server::Server`, e.g. a fake of the real server for integration tests.
 #[macro_export]
 macro_rules! make_rpc_methods {
     (
@@ -83,6 +88,12 @@ macro_rules! make_rpc_methods {
             pub fn set_timeout(&mut self, timeout:std::time::Duration) {
                 self.handler.borrow().set_timeout(timeout);
             }
+
+            /// Set the method of the notification sent to the peer when a request is no longer
+            /// awaited. Should be set only if the peer supports cancelling requests.
+            pub fn set_cancel_method(&mut self, method:Option<String>) {
+                self.handler.borrow().set_cancel_method(method);
+            }
         }
 
         impl API for Client {
@@ -139,6 +150,76 @@ macro_rules! make_rpc_methods {
 
 
 
+        // ==============
+        // === Server ===
+        // ==============
+
+        /// Serving an `API` implementation to the peer.
+        pub mod server {
+            use super::*;
+
+            $(
+                /// Structure receiving method arguments.
+                #[derive(Deserialize)]
+                #[serde(rename_all="camelCase")]
+                struct $method_input {
+                    $($param_name : $param_ty),*
+                }
+            )*
+
+            /// Register all the `API` methods in the registry. The calls are forwarded to the
+            /// `implementation`.
+            pub fn register_methods(methods:&json_rpc::Methods, implementation:Rc<dyn API>) {
+                $({
+                    let implementation = implementation.clone_ref();
+                    methods.register($rpc_name, move |_input:$method_input| {
+                        implementation.$method($(&_input.$param_name),*)
+                    });
+                })*
+            }
+
+            /// An in-process server answering requests with an `API` implementation.
+            #[derive(Debug)]
+            pub struct Server {
+                /// JSON-RPC protocol handler.
+                pub handler : Handler<serde_json::Value>,
+            }
+
+            impl Server {
+                /// Create a new server that will use given transport.
+                pub fn new
+                (transport:impl json_rpc::Transport + 'static, implementation:Rc<dyn API>)
+                -> Self {
+                    let handler = Handler::new(transport);
+                    register_methods(&handler.methods(),implementation);
+                    Self { handler }
+                }
+
+                /// Returns a future that performs any background, asynchronous work needed
+                /// for this Server to correctly work. Should be continually run while the
+                /// `Server` is used. Will end once `Server` is dropped.
+                pub fn runner(&mut self) -> impl Future<Output = ()> {
+                    self.handler.runner()
+                }
+
+                /// Send a notification to the connected client.
+                pub fn notify
+                (&self, method:&str, params:impl Serialize) -> json_rpc::prelude::FallibleResult {
+                    self.handler.send_notification(method,params)
+                }
+            }
+
+            /// Create a `Client` connected with an in-process `Server` serving the
+            /// `implementation`. The runners of both must be spawned for them to communicate.
+            pub fn connect_in_process(implementation:Rc<dyn API>) -> (Client,Server) {
+                let (client_transport,server_transport) =
+                    json_rpc::transport::memory::pair();
+                (Client::new(client_transport),Server::new(server_transport,implementation))
+            }
+        }
+
+
+
         // ==================
         // === MockClient ===
         // ==================
@@ -125,6 +125,14 @@ impl<M> Request<M> {
     }
 }
 
+impl Request<MethodCall<serde_json::Value>> {
+    /// Reinterpret the request as a notification. The `id` is kept among the notification fields.
+    pub fn into_notification(self) -> Notification<serde_json::Value> {
+        let value = serde_json::to_value(self).expect("A JSON request is always serializable.");
+        Notification(value)
+    }
+}
+
 /// A notification request.
 ///
 /// `Call` must be a type, that upon JSON serialization provides `method` and
@@ -189,13 +197,23 @@ pub struct Error<Payload = serde_json::Value> {
     pub data:    Option<Payload>,
 }
 
-/// A message that can come from Server to Client — either a response or
+/// A message that can come from the peer — either a response, a request or a
 /// notification.
+///
+/// Variants are tried in order, so the catch-all `Notification` must be last.
+///
+/// Note that every message carrying both `id` and `method` is decoded as a `Request`. Before
+/// the peer's requests could be answered, such messages were decoded as notifications. To keep
+/// this behavior, [`crate::Handler`] answers them only if any server-side method is registered in
+/// its [`crate::server::Methods`]; otherwise they are handled as notifications, see
+/// [`Request::into_notification`].
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum IncomingMessage {
     /// A response to a call made by client.
     Response(Response<serde_json::Value>),
+    /// A request call (initiated by the peer) that expects a response.
+    Request(Request<MethodCall<serde_json::Value>>),
     /// A notification call (initiated by the server).
     Notification(Notification<serde_json::Value>),
 }
@@ -203,7 +221,7 @@ pub enum IncomingMessage {
 /// Partially decodes incoming message.
 ///
 /// This checks if has `jsonrpc` version string, and whether it is a
-/// response or a notification.
+/// response, a request or a notification.
 pub fn decode_incoming_message(message: &str) -> serde_json::Result<IncomingMessage> {
     use serde_json::from_str;
     use serde_json::from_value;
@@ -212,6 +230,19 @@ pub fn decode_incoming_message(message: &str) -> serde_json::Result<IncomingMess
     from_value::<IncomingMessage>(message.payload)
 }
 
+/// Partially decodes incoming batch message, i.e. a JSON array of messages.
+///
+/// Returns `None` if the message is not a batch. Otherwise, each element is
+/// decoded as by `decode_incoming_message`.
+pub fn decode_incoming_batch(message: &str) -> Option<Vec<serde_json::Result<IncomingMessage>>> {
+    use serde_json::from_str;
+    use serde_json::from_value;
+    use serde_json::Value;
+    let batch = from_str::<Vec<Value>>(message).ok()?;
+    let decode = |item| from_value::<Message<Value>>(item).and_then(|m| from_value(m.payload));
+    Some(batch.into_iter().map(decode).collect())
+}
+
 /// Message from server to client.
 ///
 /// `In` is any serializable (or already serialized) representation of the
@@ -363,4 +394,48 @@ mod tests {
             _ => panic!("Invalid decoding result of {text}: {decoding_result:?}"),
         }
     }
+
+    #[test]
+    fn decode_incoming_request_and_notification_text() {
+        let text = r#"{"jsonrpc":"2.0","id":3,"method":"ping","params":{"number":1}}"#;
+        match decode_incoming_message(text) {
+            Ok(IncomingMessage::Request(request)) => {
+                assert_eq!(request.id, Id(3));
+                assert_eq!(request.method, "ping");
+                assert_eq!(request.params, serde_json::json!({"number":1}));
+            }
+            result => panic!("Invalid decoding result of {text}: {result:?}"),
+        }
+
+        let text = r#"{"jsonrpc":"2.0","method":"ping","params":{"number":1}}"#;
+        let decoding_result = decode_incoming_message(text);
+        assert!(matches!(decoding_result, Ok(IncomingMessage::Notification(_))));
+    }
+
+    #[test]
+    fn request_into_notification() {
+        let text = r#"{"jsonrpc":"2.0","id":3,"method":"ping","params":{"number":1}}"#;
+        let Ok(IncomingMessage::Request(request)) = decode_incoming_message(text) else {
+            panic!("Expected a request.")
+        };
+        let expected = serde_json::json!({"id":3,"method":"ping","params":{"number":1}});
+        assert_eq!(request.into_notification(), Notification(expected));
+    }
+
+    #[test]
+    fn decode_incoming_batch_text() {
+        let text = r#"[
+            {"jsonrpc":"2.0","id":1,"result":null},
+            {"jsonrpc":"2.0","method":"ping","params":{}},
+            {"id":2,"result":null}
+        ]"#;
+        let batch = decode_incoming_batch(text).expect("Expected a batch.");
+        assert_eq!(batch.len(), 3);
+        assert!(matches!(batch[0], Ok(IncomingMessage::Response(Response { id: Id(1), .. }))));
+        assert!(matches!(batch[1], Ok(IncomingMessage::Notification(_))));
+        assert!(batch[2].is_err(), "Message without version should be rejected.");
+
+        let text = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+        assert!(decode_incoming_batch(text).is_none());
+    }
 }
@@ -0,0 +1,194 @@
+//! Server-side part of the protocol: requests received from the peer are dispatched to the method
+//! implementations registered in [`Methods`], and their results are encoded as replies.
+
+use crate::prelude::*;
+
+use crate::api::Result;
+use crate::error::RpcError;
+use crate::handler::ReplyMessage;
+use crate::messages;
+
+use futures::future;
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+
+
+
+// ===================
+// === Error Codes ===
+// ===================
+
+/// Error codes reserved by the JSON-RPC 2.0 specification, used when replying to requests that
+/// could not be dispatched.
+pub mod code {
+    /// The method does not exist or is not available.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// Invalid method parameters.
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// Internal JSON-RPC error.
+    pub const INTERNAL_ERROR: i64 = -32603;
+}
+
+
+
+// ================
+// === Encoding ===
+// ================
+
+/// Encode the result of a local method call as a reply to be sent to the peer.
+///
+/// [`RpcError::RemoteError`] is passed to the peer as is; any other error is reported as an
+/// internal error.
+pub fn encode_result<Returned: Serialize>(result: Result<Returned>) -> ReplyMessage {
+    match result.and_then(|returned| Ok(serde_json::to_value(returned)?)) {
+        Ok(returned) => messages::Result::new_success(returned),
+        Err(RpcError::RemoteError(error)) => messages::Result::Error { error },
+        Err(error) => messages::Result::new_error_simple(code::INTERNAL_ERROR, error.to_string()),
+    }
+}
+
+
+
+// ===============
+// === Methods ===
+// ===============
+
+/// A type-erased method implementation, taking JSON params and yielding the reply.
+pub type Method = dyn Fn(serde_json::Value) -> LocalBoxFuture<'static, ReplyMessage>;
+
+/// A registry of methods that can be called by the peer, identified by their names.
+///
+/// The registry is shared: all clones refer to the same set of methods, so methods may be
+/// registered also after the registry was passed to a [`crate::Handler`].
+#[derive(Clone, CloneRef, Default)]
+pub struct Methods {
+    methods: Rc<RefCell<HashMap<String, Rc<Method>>>>,
+}
+
+impl Methods {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Register a method implementation under the given name, replacing any previous one.
+    ///
+    /// The request params are deserialized into `Params` (a params-mismatch is replied to with
+    /// [`code::INVALID_PARAMS`] error) and the returned value is serialized as the call result.
+    pub fn register<Params, Returned, F, Fut>(&self, name: impl Into<String>, method: F)
+    where
+        Params: DeserializeOwned,
+        Returned: Serialize,
+        F: Fn(Params) -> Fut + 'static,
+        Fut: Future<Output = Result<Returned>> + 'static, {
+        let method = move |params: serde_json::Value| match serde_json::from_value(params) {
+            Ok(params) => method(params).map(encode_result).boxed_local(),
+            Err(error) => {
+                let message = format!("Invalid params: {error}.");
+                let reply = messages::Result::new_error_simple(code::INVALID_PARAMS, message);
+                future::ready(reply).boxed_local()
+            }
+        };
+        self.methods.borrow_mut().insert(name.into(), Rc::new(method));
+    }
+
+    /// Remove the method with the given name. Returns `false` if there was no such method.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.methods.borrow_mut().remove(name).is_some()
+    }
+
+    /// Check if a method with the given name is registered.
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.methods.borrow().contains_key(name)
+    }
+
+    /// Check if no method is registered.
+    pub fn is_empty(&self) -> bool {
+        self.methods.borrow().is_empty()
+    }
+
+    /// Call the method with given JSON params. Calling a method that is not registered yields a
+    /// [`code::METHOD_NOT_FOUND`] error.
+    pub fn call(
+        &self,
+        name: &str,
+        params: serde_json::Value,
+    ) -> LocalBoxFuture<'static, ReplyMessage> {
+        // The method is cloned out, so it may access the registry itself.
+        let method = self.methods.borrow().get(name).cloned();
+        match method {
+            Some(method) => method(params),
+            None => {
+                let message = format!("Method not found: {name}.");
+                let reply = messages::Result::new_error_simple(code::METHOD_NOT_FOUND, message);
+                future::ready(reply).boxed_local()
+            }
+        }
+    }
+}
+
+impl Debug for Methods {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.methods.borrow().keys().sorted().cloned().collect_vec();
+        f.debug_tuple("Methods").field(&names).finish()
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct PowParams {
+        i: i64,
+    }
+
+    fn call(methods: &Methods, name: &str, params: serde_json::Value) -> ReplyMessage {
+        methods.call(name, params).now_or_never().expect("Method should reply immediately.")
+    }
+
+    fn expect_error_code(reply: ReplyMessage, expected: i64) {
+        match reply {
+            messages::Result::Error { error } => assert_eq!(error.code, expected),
+            messages::Result::Success(_) => panic!("Expected an error reply."),
+        }
+    }
+
+    #[test]
+    fn dispatching_calls() {
+        let methods = Methods::new();
+        methods.register("pow", |params: PowParams| future::ready(Ok(params.i * params.i)));
+        assert!(methods.is_registered("pow"));
+
+        let reply = call(&methods, "pow", serde_json::json!({"i":4}));
+        assert_eq!(reply, messages::Result::new_success(serde_json::json!(16)));
+        expect_error_code(call(&methods, "pow", serde_json::json!({"j":4})), code::INVALID_PARAMS);
+        expect_error_code(call(&methods, "sqrt", serde_json::json!({})), code::METHOD_NOT_FOUND);
+
+        assert!(methods.unregister("pow"));
+        assert!(!methods.unregister("pow"));
+        expect_error_code(
+            call(&methods, "pow", serde_json::json!({"i":4})),
+            code::METHOD_NOT_FOUND,
+        );
+    }
+
+    #[test]
+    fn encoding_errors() {
+        let remote = RpcError::new_remote_error(7, "Custom error");
+        expect_error_code(encode_result::<()>(Err(remote)), 7);
+        let lost = RpcError::LostConnection;
+        expect_error_code(encode_result::<()>(Err(lost)), code::INTERNAL_ERROR);
+    }
+}
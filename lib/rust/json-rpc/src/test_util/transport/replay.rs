@@ -0,0 +1,210 @@
+//! Module provides a transport serving back a session recorded by the
+//! [`crate::transport::RecordingTransport`].
+//!
+//! It is meant to be used in deterministic tests: the client under test is expected to send the
+//! same messages as during the recording, and receives the recorded replies in return.
+
+use crate::prelude::*;
+
+use crate::constants::CANCEL_METHOD;
+use crate::transport::recording::Direction;
+use crate::transport::recording::Entry;
+use crate::transport::recording::Payload;
+use crate::transport::recording::Recording;
+use crate::transport::Transport;
+use crate::transport::TransportEvent;
+
+use failure::Error;
+use futures::channel::mpsc::UnboundedSender;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::Path;
+
+
+
+// ===============
+// === Helpers ===
+// ===============
+
+/// Remove the request ids from the JSON-RPC message (or each message of a batch), returning them
+/// in order. The ids of the requests being cancelled are removed as well.
+fn take_ids(message: &mut Value) -> Vec<Option<Value>> {
+    let take_id = |message: &mut Value| {
+        let object = message.as_object_mut()?;
+        if object.get("method").and_then(Value::as_str) == Some(CANCEL_METHOD) {
+            object.get_mut("params").and_then(Value::as_object_mut).map(|p| p.remove("id"));
+        }
+        object.remove("id")
+    };
+    match message {
+        Value::Array(messages) => messages.iter_mut().map(take_id).collect(),
+        message => vec![take_id(message)],
+    }
+}
+
+/// Check if the JSON-RPC message (or any message of a batch) is a request expecting a reply.
+fn requests(message: &Value) -> Vec<bool> {
+    let is_request = |message: &Value| message.get("method").is_some();
+    match message {
+        Value::Array(messages) => messages.iter().map(is_request).collect(),
+        message => vec![is_request(message)],
+    }
+}
+
+
+
+// =======================
+// === ReplayTransport ===
+// =======================
+
+#[derive(Debug, Default)]
+struct Model {
+    entries:           VecDeque<Entry>,
+    event_transmitter: Option<UnboundedSender<TransportEvent>>,
+    buffered_events:   Vec<TransportEvent>,
+    /// Maps the ids of the recorded requests to the ids of the requests actually sent.
+    ids:               HashMap<String, Value>,
+}
+
+impl Model {
+    fn emit(&mut self, event: TransportEvent) {
+        match self.event_transmitter.as_ref() {
+            Some(transmitter) => channel::emit(transmitter, event),
+            None => self.buffered_events.push(event),
+        }
+    }
+
+    /// Deliver all the received entries up to the next sent one.
+    fn deliver_received(&mut self) {
+        while self.entries.front().map_or(false, |e| e.direction == Direction::Received) {
+            let entry = self.entries.pop_front().expect("Entry checked to be present.");
+            let event = match entry.payload {
+                Payload::Text(text) => TransportEvent::TextMessage(self.map_reply_ids(text)),
+                Payload::Binary(data) => TransportEvent::BinaryMessage(data),
+                Payload::Closed => TransportEvent::Closed,
+            };
+            self.emit(event);
+        }
+    }
+
+    /// Replace the ids of recorded replies with the ids of the requests actually sent.
+    fn map_reply_ids(&self, text: String) -> String {
+        let Ok(mut message) = serde_json::from_str::<Value>(&text) else { return text };
+        let map_id = |message: &mut Value| {
+            if message.get("method").is_none() {
+                if let Some(id) = message.get_mut("id") {
+                    if let Some(actual) = self.ids.get(&id.to_string()) {
+                        *id = actual.clone();
+                    }
+                }
+            }
+        };
+        match &mut message {
+            Value::Array(messages) => messages.iter_mut().for_each(map_id),
+            message => map_id(message),
+        }
+        message.to_string()
+    }
+
+    fn next_sent(&mut self, sent: &dyn Debug) -> Payload {
+        match self.entries.pop_front() {
+            Some(Entry { direction: Direction::Sent, payload, .. }) => payload,
+            Some(entry) => panic!("Sent {sent:?} while expecting to receive {:?}.", entry.payload),
+            None => panic!("Sent {sent:?} after the recorded session ended."),
+        }
+    }
+
+    fn send_text(&mut self, message: &str) {
+        let expected = match self.next_sent(&message) {
+            Payload::Text(expected) => expected,
+            expected => panic!("Sent text {message:?} while expecting {expected:?}."),
+        };
+        let parsed = serde_json::from_str::<Value>(message);
+        let parsed_expected = serde_json::from_str::<Value>(&expected);
+        match (parsed, parsed_expected) {
+            (Ok(mut actual), Ok(mut recorded)) => {
+                let is_request = requests(&recorded);
+                let actual_ids = take_ids(&mut actual);
+                let recorded_ids = take_ids(&mut recorded);
+                assert_eq!(actual, recorded, "Sent message differs from the recorded one.");
+                let pairs = recorded_ids.into_iter().zip(actual_ids).zip(is_request);
+                for ((recorded_id, actual_id), is_request) in pairs {
+                    if let (Some(recorded_id), Some(actual_id), true) =
+                        (recorded_id, actual_id, is_request)
+                    {
+                        self.ids.insert(recorded_id.to_string(), actual_id);
+                    }
+                }
+            }
+            _ => assert_eq!(message, expected, "Sent message differs from the recorded one."),
+        }
+        self.deliver_received();
+    }
+
+    fn send_binary(&mut self, message: &[u8]) {
+        match self.next_sent(&message) {
+            Payload::Binary(expected) =>
+                assert_eq!(message, expected, "Sent data differs from the recorded one."),
+            expected => panic!("Sent data {message:?} while expecting {expected:?}."),
+        }
+        self.deliver_received();
+    }
+}
+
+/// A transport replaying the recorded session.
+///
+/// Every message sent through this transport must be equivalent to the next message sent in the
+/// recorded session, otherwise it panics. Equivalent JSON messages may differ in formatting and
+/// request ids; the ids in the replayed replies are adjusted accordingly. After each sent message,
+/// all the messages received next in the recorded session are delivered.
+#[derive(Clone, CloneRef, Debug)]
+pub struct ReplayTransport {
+    model: Rc<RefCell<Model>>,
+}
+
+impl ReplayTransport {
+    /// Create a transport replaying the given recording. Messages received before sending
+    /// anything are delivered once the event transmitter is set.
+    pub fn new(recording: Recording) -> Self {
+        let mut model = Model { entries: recording.entries.into(), ..default() };
+        model.deliver_received();
+        Self { model: Rc::new(RefCell::new(model)) }
+    }
+
+    /// Create a transport replaying the recording saved in the given file.
+    pub fn load(path: impl AsRef<Path>) -> FallibleResult<Self> {
+        Ok(Self::new(Recording::load(path)?))
+    }
+
+    /// Number of recorded entries not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.model.borrow().entries.len()
+    }
+
+    /// Panics if the recorded session was not replayed entirely.
+    pub fn expect_finished(&self) {
+        if let Some(entry) = self.model.borrow().entries.front() {
+            panic!("The recorded session did not finish, expected to send {:?}.", entry.payload);
+        }
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send_text(&mut self, message: &str) -> Result<(), Error> {
+        self.model.borrow_mut().send_text(message);
+        Ok(())
+    }
+
+    fn send_binary(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.model.borrow_mut().send_binary(message);
+        Ok(())
+    }
+
+    fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>) {
+        let mut model = self.model.borrow_mut();
+        for event in mem::take(&mut model.buffered_events) {
+            channel::emit(&transmitter, event);
+        }
+        model.event_transmitter = Some(transmitter);
+    }
+}
@@ -8,6 +8,19 @@ use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::mpsc::UnboundedSender;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod memory;
+pub mod reconnecting;
+pub mod recording;
+
+pub use memory::MemoryTransport;
+pub use reconnecting::ReconnectingTransport;
+pub use recording::RecordingTransport;
+
+
 
 /// A transport that facilitate JSON-RPC protocol.
 ///
@@ -0,0 +1,120 @@
+//! Module provides a pair of connected in-process transports.
+//!
+//! They connect two `Handler`s (e.g. a client and an in-process server) without any actual
+//! networking, while still going through the whole message serialization.
+
+use crate::prelude::*;
+
+use crate::transport::Transport;
+use crate::transport::TransportEvent;
+
+use failure::Error;
+use futures::channel::mpsc::UnboundedSender;
+
+
+
+// =================
+// === SendError ===
+// =================
+
+/// Errors emitted by the `MemoryTransport`.
+#[derive(Clone, Copy, Debug, Fail)]
+pub enum SendError {
+    /// Cannot send message after the connection was closed or the peer transport was dropped.
+    #[fail(display = "Cannot send message, the connection has been closed.")]
+    TransportClosed,
+}
+
+
+
+// ================
+// === Endpoint ===
+// ================
+
+/// One side of the in-memory connection, receiving the events sent by the other side.
+///
+/// The events received before the event transmitter is set are buffered.
+#[derive(Debug, Default)]
+struct Endpoint {
+    event_transmitter: Option<UnboundedSender<TransportEvent>>,
+    buffered_events:   Vec<TransportEvent>,
+}
+
+impl Endpoint {
+    fn receive(&mut self, event: TransportEvent) {
+        match self.event_transmitter.as_ref() {
+            Some(transmitter) => channel::emit(transmitter, event),
+            None => self.buffered_events.push(event),
+        }
+    }
+}
+
+
+
+// =======================
+// === MemoryTransport ===
+// =======================
+
+/// A transport delivering the messages directly to the connected peer transport.
+///
+/// Sending fails once the connection is closed or the peer transport is dropped.
+#[derive(Clone, CloneRef, Debug)]
+pub struct MemoryTransport {
+    local:  Rc<RefCell<Endpoint>>,
+    remote: Weak<RefCell<Endpoint>>,
+    closed: Rc<Cell<bool>>,
+}
+
+/// Create two transports connected with each other.
+pub fn pair() -> (MemoryTransport, MemoryTransport) {
+    let first = Rc::new(RefCell::new(Endpoint::default()));
+    let second = Rc::new(RefCell::new(Endpoint::default()));
+    let closed = Rc::new(Cell::new(false));
+    let remote = Rc::downgrade(&second);
+    let first_transport =
+        MemoryTransport { local: first.clone_ref(), remote, closed: closed.clone_ref() };
+    let remote = Rc::downgrade(&first);
+    let second_transport = MemoryTransport { local: second, remote, closed };
+    (first_transport, second_transport)
+}
+
+impl MemoryTransport {
+    /// Close the connection. Both sides receive the `Closed` event, and no more messages can be
+    /// sent.
+    pub fn close(&self) {
+        if !self.closed.replace(true) {
+            self.local.borrow_mut().receive(TransportEvent::Closed);
+            if let Some(remote) = self.remote.upgrade() {
+                remote.borrow_mut().receive(TransportEvent::Closed);
+            }
+        }
+    }
+
+    fn send(&self, event: TransportEvent) -> Result<(), Error> {
+        match self.remote.upgrade() {
+            Some(remote) if !self.closed.get() => {
+                remote.borrow_mut().receive(event);
+                Ok(())
+            }
+            _ => Err(SendError::TransportClosed.into()),
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send_text(&mut self, message: &str) -> Result<(), Error> {
+        self.send(TransportEvent::TextMessage(message.into()))
+    }
+
+    fn send_binary(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.send(TransportEvent::BinaryMessage(message.into()))
+    }
+
+    fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>) {
+        let mut local = self.local.borrow_mut();
+        for event in mem::take(&mut local.buffered_events) {
+            channel::emit(&transmitter, event);
+        }
+        local.event_transmitter = Some(transmitter);
+    }
+}
@@ -0,0 +1,698 @@
+//! A `Transport` wrapper that re-establishes the lost connection.
+//!
+//! When the underlying transport gets closed, the `ReconnectingTransport` tries to open a new one
+//! with an exponential backoff, instead of reporting the `Closed` event. Meanwhile, all the sent
+//! messages are queued. After reconnecting, the session initialization requests are re-issued,
+//! the idempotent requests which did not get a reply are sent again, and then the queue is
+//! flushed. The requests which cannot be safely repeated get an error reply with
+//! [`CONNECTION_LOST_ERROR_CODE`].
+
+use crate::prelude::*;
+
+use crate::ensogl::sleep;
+use crate::ensogl::Duration;
+use crate::messages;
+use crate::messages::Id;
+use crate::transport::Transport;
+use crate::transport::TransportEvent;
+
+use failure::Error;
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::future::Future;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// Error code of the replies generated for requests which were interrupted by the connection loss
+/// and could not be repeated. Belongs to the range reserved for implementation-defined errors.
+pub const CONNECTION_LOST_ERROR_CODE: i64 = -32000;
+
+
+
+// =================
+// === SendError ===
+// =================
+
+/// Errors emitted by the `ReconnectingTransport`.
+#[derive(Clone, Copy, Debug, Fail)]
+pub enum SendError {
+    /// Cannot send message after giving up reconnecting or closing the transport.
+    #[fail(display = "Cannot send message, the connection has been closed.")]
+    TransportClosed,
+}
+
+
+
+// ==============
+// === Policy ===
+// ==============
+
+/// Describes how the connection is re-established and which requests are repeated.
+#[derive(Clone, Debug)]
+pub struct Policy {
+    /// Delay before the first reconnection attempt.
+    pub initial_delay:        Duration,
+    /// Upper bound of the delay between attempts.
+    pub max_delay:            Duration,
+    /// Factor by which the delay grows after each failed attempt.
+    pub multiplier:           f64,
+    /// Number of failed attempts after which the transport gives up and reports being closed.
+    /// If `None`, the transport never gives up.
+    pub max_attempts:         Option<usize>,
+    /// Methods which can be safely called again when the connection was lost before the reply
+    /// has been received.
+    pub idempotent_methods:   HashSet<String>,
+    /// Methods initializing the session. The last request of each is re-issued after
+    /// reconnecting, before any other message is sent.
+    pub session_init_methods: HashSet<String>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            initial_delay:        Duration::from_millis(500),
+            max_delay:            Duration::from_secs(30),
+            multiplier:           2.0,
+            max_attempts:         None,
+            idempotent_methods:   default(),
+            session_init_methods: default(),
+        }
+    }
+}
+
+impl Policy {
+    /// The delay before the given reconnection attempt, counted from 0.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let factor = self.multiplier.powi(i32::try_from(attempt).unwrap_or(i32::MAX));
+        let delay = self.initial_delay.as_secs_f64() * factor;
+        // `f64::min` ignores NaN, which appears for zero initial delay and an infinite factor.
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+}
+
+
+
+// =============
+// === State ===
+// =============
+
+/// State of the connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// The connection is open. Messages are sent right away.
+    Connected,
+    /// The connection was lost and the given attempt (counted from 1) to re-establish it is made.
+    Reconnecting {
+        /// The attempt number.
+        attempt: usize,
+    },
+    /// The connection was re-established, and the session initialization requests await replies.
+    Initializing,
+    /// The connection is closed for good, either explicitly or after giving up reconnecting.
+    Closed,
+}
+
+
+
+// =============
+// === Model ===
+// =============
+
+/// A function opening a new underlying transport.
+pub type Connector = dyn Fn() -> LocalBoxFuture<'static, FallibleResult<Box<dyn Transport>>>;
+
+/// A message waiting in the outgoing queue.
+#[derive(Clone, Debug)]
+enum Outgoing {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// A partially decoded outgoing request.
+type OutgoingRequest =
+    messages::Message<messages::Request<messages::MethodCall<serde_json::Value>>>;
+
+/// A partially decoded outgoing notification cancelling a request.
+type OutgoingCancel = messages::Message<messages::MethodCall<CancelParams>>;
+
+/// Parameters of the notification cancelling a request.
+#[derive(Debug, Deserialize)]
+struct CancelParams {
+    id: Id,
+}
+
+/// If the message is a notification cancelling a request, returns the request id.
+fn cancelled_request(text: &str) -> Option<Id> {
+    let cancel = serde_json::from_str::<OutgoingCancel>(text).ok()?;
+    (cancel.method == crate::constants::CANCEL_METHOD).then_some(cancel.params.id)
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct Model {
+    policy:            Policy,
+    #[derivative(Debug = "ignore")]
+    connector:         Rc<Connector>,
+    transport:         Option<Box<dyn Transport>>,
+    transport_events:  Option<UnboundedReceiver<TransportEvent>>,
+    state:             State,
+    event_transmitter: Option<UnboundedSender<TransportEvent>>,
+    state_transmitter: Option<UnboundedSender<State>>,
+    queue:             VecDeque<Outgoing>,
+    /// Sent idempotent requests awaiting a reply, to be repeated after reconnecting.
+    replayable:        BTreeMap<Id, String>,
+    /// Sent requests awaiting a reply which cannot be repeated.
+    unreplayable:      BTreeSet<Id>,
+    /// The last request of each session initialization method, by method name.
+    session_init:      BTreeMap<String, serde_json::Value>,
+    /// Re-issued session initialization requests awaiting a reply. Their replies are not passed
+    /// to the owner of the transport.
+    pending_init:      HashSet<Id>,
+    /// Source of ids for the re-issued requests. Negative, not to collide with the owner's ids.
+    last_internal_id:  i64,
+}
+
+impl Model {
+    fn set_state(&mut self, state: State) {
+        self.state = state;
+        if let Some(transmitter) = self.state_transmitter.as_ref() {
+            channel::emit(transmitter, state);
+        }
+    }
+
+    fn emit_event(&self, event: TransportEvent) {
+        if let Some(transmitter) = self.event_transmitter.as_ref() {
+            channel::emit(transmitter, event);
+        }
+    }
+
+    /// Connect the new underlying transport.
+    fn connected(&mut self, mut transport: Box<dyn Transport>) {
+        let (transmitter, receiver) = unbounded();
+        transport.set_event_transmitter(transmitter);
+        self.transport = Some(transport);
+        self.transport_events = Some(receiver);
+        let init_requests = mem::take(&mut self.session_init);
+        for (method, params) in &init_requests {
+            self.last_internal_id -= 1;
+            let id = Id(self.last_internal_id);
+            let message = messages::Message::new_request(id, method, params);
+            let text = serde_json::to_string(&message).expect("Serializing JSON must not fail.");
+            self.pending_init.insert(id);
+            // If sending fails, the request is re-issued after reconnecting again.
+            self.send_now(&Outgoing::Text(text));
+        }
+        self.session_init = init_requests;
+        if self.pending_init.is_empty() {
+            self.initialized();
+        } else {
+            self.set_state(State::Initializing);
+        }
+    }
+
+    /// Repeat the idempotent requests and flush the queue once the session is ready.
+    fn initialized(&mut self) {
+        self.set_state(State::Connected);
+        let replayed = self.replayable.values().cloned().map(Outgoing::Text).collect_vec();
+        for message in &replayed {
+            // If sending fails, the request is still tracked and will be repeated again.
+            self.send_now(message);
+        }
+        for message in mem::take(&mut self.queue) {
+            self.send_or_queue(message);
+        }
+    }
+
+    /// Drop the underlying transport and reply with errors to the requests that cannot be
+    /// repeated.
+    fn connection_lost(&mut self) {
+        self.transport = None;
+        self.transport_events = None;
+        self.pending_init.clear();
+        for id in mem::take(&mut self.unreplayable) {
+            let message = "The connection was lost before receiving a reply.".to_owned();
+            let reply: messages::ResponseMessage<serde_json::Value> =
+                messages::Message::new_error(id, CONNECTION_LOST_ERROR_CODE, message, None);
+            let text = serde_json::to_string(&reply).expect("Serializing JSON must not fail.");
+            self.emit_event(TransportEvent::TextMessage(text));
+        }
+    }
+
+    /// Close the transport for good, reporting it to the owner.
+    fn close(&mut self) {
+        if self.state != State::Closed {
+            self.transport = None;
+            self.transport_events = None;
+            self.queue.clear();
+            self.replayable.clear();
+            self.unreplayable.clear();
+            self.set_state(State::Closed);
+            self.emit_event(TransportEvent::Closed);
+        }
+    }
+
+    fn send(&mut self, message: Outgoing) -> Result<(), Error> {
+        match self.state {
+            State::Closed => Err(SendError::TransportClosed.into()),
+            State::Connected => {
+                self.send_or_queue(message);
+                Ok(())
+            }
+            State::Reconnecting { .. } | State::Initializing => {
+                self.queue.push_back(message);
+                Ok(())
+            }
+        }
+    }
+
+    /// Send the owner's message. If it fails, the message is queued until reconnecting, as the
+    /// transport will report being closed.
+    fn send_or_queue(&mut self, message: Outgoing) {
+        if !self.send_now(&message) {
+            self.queue.push_back(message);
+        }
+    }
+
+    /// Send the message through the underlying transport. Only the successfully sent requests are
+    /// remembered, so the failed ones are neither reported as lost nor repeated. Returns `false` if
+    /// sending failed.
+    fn send_now(&mut self, message: &Outgoing) -> bool {
+        let result = match (&mut self.transport, message) {
+            (Some(transport), Outgoing::Text(text)) => transport.send_text(text),
+            (Some(transport), Outgoing::Binary(data)) => transport.send_binary(data),
+            (None, _) => Err(SendError::TransportClosed.into()),
+        };
+        let sent = result.is_ok();
+        if let (true, Outgoing::Text(text)) = (sent, message) {
+            self.track_request(text);
+        }
+        sent
+    }
+
+    fn track_request(&mut self, text: &str) {
+        if let Ok(batch) = serde_json::from_str::<Vec<OutgoingRequest>>(text) {
+            // Batches are never repeated.
+            self.unreplayable.extend(batch.iter().map(|request| request.id));
+        } else if let Some(id) = cancelled_request(text) {
+            self.forget_request(id);
+        } else if let Ok(request) = serde_json::from_str::<OutgoingRequest>(text) {
+            let id = request.id;
+            let method = &request.method;
+            if id.0 >= 0 {
+                if self.policy.session_init_methods.contains(method) {
+                    self.session_init.insert(method.clone(), request.params.clone());
+                }
+                if self.policy.idempotent_methods.contains(method) {
+                    self.replayable.insert(id, text.to_owned());
+                } else {
+                    self.unreplayable.insert(id);
+                }
+            }
+        }
+    }
+
+    /// Stop tracking the request, as it got a reply or was cancelled.
+    fn forget_request(&mut self, id: Id) {
+        self.replayable.remove(&id);
+        self.unreplayable.remove(&id);
+    }
+
+    /// Process the event of the underlying transport. Returns `false` if it was closed.
+    fn process_event(&mut self, event: TransportEvent) -> bool {
+        match event {
+            TransportEvent::TextMessage(text) => {
+                if let Some(batch) = messages::decode_incoming_batch(&text) {
+                    for message in batch {
+                        if let Ok(messages::IncomingMessage::Response(response)) = message {
+                            self.forget_request(response.id);
+                        }
+                    }
+                    self.emit_event(TransportEvent::TextMessage(text));
+                    return true;
+                }
+                let response = match messages::decode_incoming_message(&text) {
+                    Ok(messages::IncomingMessage::Response(response)) => Some(response),
+                    _ => None,
+                };
+                if let Some(response) = &response {
+                    self.forget_request(response.id);
+                }
+                if let Some(response) = response.filter(|r| self.pending_init.remove(&r.id)) {
+                    if let messages::Result::Error { error } = response.result {
+                        warn!("Failed to re-initialize the session: {}", error.message);
+                    }
+                    if self.pending_init.is_empty() {
+                        self.initialized();
+                    }
+                } else {
+                    self.emit_event(TransportEvent::TextMessage(text));
+                }
+                true
+            }
+            TransportEvent::BinaryMessage(data) => {
+                self.emit_event(TransportEvent::BinaryMessage(data));
+                true
+            }
+            TransportEvent::Opened => true,
+            TransportEvent::Closed => false,
+        }
+    }
+}
+
+
+
+// ============================
+// === ReconnectingTransport ===
+// ============================
+
+/// A transport re-establishing the lost connection, see the module documentation.
+///
+/// The `runner` future must be spawned for the transport to work.
+#[derive(Clone, CloneRef, Debug)]
+pub struct ReconnectingTransport {
+    model: Rc<RefCell<Model>>,
+}
+
+impl ReconnectingTransport {
+    /// Wrap the given open transport. The `connect` function is used to open a new one whenever
+    /// the connection is lost.
+    pub fn new<T, F, Fut>(transport: T, policy: Policy, connect: F) -> Self
+    where
+        T: Transport + 'static,
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = FallibleResult<T>> + 'static, {
+        let connector = move || {
+            let connecting = connect();
+            async move {
+                let transport: Box<dyn Transport> = Box::new(connecting.await?);
+                Ok(transport)
+            }
+            .boxed_local()
+        };
+        let model = Model {
+            policy,
+            connector: Rc::new(connector),
+            transport: None,
+            transport_events: None,
+            state: State::Connected,
+            event_transmitter: None,
+            state_transmitter: None,
+            queue: default(),
+            replayable: default(),
+            unreplayable: default(),
+            session_init: default(),
+            pending_init: default(),
+            last_internal_id: 0,
+        };
+        let model = Rc::new(RefCell::new(model));
+        model.borrow_mut().connected(Box::new(transport));
+        Self { model }
+    }
+
+    /// The current state of the connection.
+    pub fn state(&self) -> State {
+        self.model.borrow().state
+    }
+
+    /// Creates a new stream with the connection state changes.
+    ///
+    /// If such stream was already existing, it will be finished.
+    pub fn state_changes(&self) -> UnboundedReceiver<State> {
+        let (transmitter, receiver) = unbounded();
+        self.model.borrow_mut().state_transmitter = Some(transmitter);
+        receiver
+    }
+
+    /// Close the connection for good. No reconnection attempts will be made.
+    pub fn close(&self) {
+        self.model.borrow_mut().close()
+    }
+
+    /// Returns a future that forwards the events of the underlying transport and re-establishes
+    /// the connection when it is lost. Will end once the transport is closed or dropped.
+    pub fn runner(&self) -> impl Future<Output = ()> {
+        let weak = Rc::downgrade(&self.model);
+        async move {
+            loop {
+                let events = weak.upgrade().and_then(|m| m.borrow_mut().transport_events.take());
+                let Some(mut events) = events else { break };
+                while let Some(event) = events.next().await {
+                    let Some(model) = weak.upgrade() else { return };
+                    let is_open = model.borrow_mut().process_event(event);
+                    if !is_open {
+                        break;
+                    }
+                }
+                let Some(model) = weak.upgrade() else { return };
+                if model.borrow().state == State::Closed {
+                    break;
+                }
+                model.borrow_mut().connection_lost();
+                drop(model);
+                if !Self::reconnect(weak.clone()).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Try opening a new transport until succeeded. Returns `false` if given up.
+    async fn reconnect(weak: Weak<RefCell<Model>>) -> bool {
+        for attempt in 1.. {
+            let Some(strong) = weak.upgrade() else { return false };
+            let (delay, connector) = {
+                let mut model = strong.borrow_mut();
+                let given_up = model.policy.max_attempts.map_or(false, |max| attempt > max);
+                if given_up {
+                    warn!("Giving up reconnecting after {} attempts.", attempt - 1);
+                    model.close();
+                }
+                if model.state == State::Closed {
+                    return false;
+                }
+                model.set_state(State::Reconnecting { attempt });
+                (model.policy.delay(attempt - 1), model.connector.clone_ref())
+            };
+            drop(strong);
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+            let connecting = connector();
+            let result = connecting.await;
+            let Some(strong) = weak.upgrade() else { return false };
+            let mut model = strong.borrow_mut();
+            match result {
+                Ok(_) if model.state == State::Closed => return false,
+                Ok(transport) => {
+                    info!("Reconnected after {attempt} attempts.");
+                    model.connected(transport);
+                    return true;
+                }
+                Err(error) => warn!("Reconnection attempt {attempt} failed: {error}"),
+            }
+        }
+        false
+    }
+}
+
+impl Transport for ReconnectingTransport {
+    fn send_text(&mut self, message: &str) -> Result<(), Error> {
+        self.model.borrow_mut().send(Outgoing::Text(message.into()))
+    }
+
+    fn send_binary(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.model.borrow_mut().send(Outgoing::Binary(message.into()))
+    }
+
+    fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>) {
+        self.model.borrow_mut().event_transmitter = Some(transmitter);
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::transport::mock::MockTransport;
+
+    use futures::executor::LocalPool;
+    use futures::task::LocalSpawnExt;
+    use serde_json::json;
+
+    /// Transports to be returned by the subsequent reconnection attempts. If there is none left,
+    /// the attempt fails.
+    type Reconnections = Rc<RefCell<VecDeque<MockTransport>>>;
+
+    struct Fixture {
+        first:         MockTransport,
+        reconnections: Reconnections,
+        transport:     ReconnectingTransport,
+        events:        UnboundedReceiver<TransportEvent>,
+        states:        UnboundedReceiver<State>,
+        pool:          LocalPool,
+    }
+
+    impl Fixture {
+        fn new(policy: Policy) -> Self {
+            let first = MockTransport::new();
+            let reconnections = Reconnections::default();
+            let connect = f!([reconnections]() {
+                let transport = reconnections.borrow_mut().pop_front();
+                let result = transport.ok_or_else(|| failure::err_msg("Connection refused."));
+                futures::future::ready(result)
+            });
+            let mut transport = ReconnectingTransport::new(first.clone(), policy, connect);
+            let events = transport.establish_event_stream();
+            let states = transport.state_changes();
+            let pool = LocalPool::new();
+            pool.spawner().spawn_local(transport.runner()).unwrap();
+            Self { first, reconnections, transport, events, states, pool }
+        }
+
+        fn send(&mut self, message: serde_json::Value) {
+            self.transport.send_text(&message.to_string()).unwrap();
+        }
+
+        fn expect_reply(&mut self, expected: serde_json::Value) {
+            match self.events.expect_next() {
+                TransportEvent::TextMessage(text) => {
+                    let reply = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+                    assert_eq!(reply, expected);
+                }
+                event => panic!("Expected a text message, got {event:?}."),
+            }
+        }
+    }
+
+    fn request(id: i64, method: &str) -> serde_json::Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": {"id": id}})
+    }
+
+    fn reply(id: i64) -> serde_json::Value {
+        json!({"jsonrpc": "2.0", "id": id, "result": null})
+    }
+
+    fn test_policy() -> Policy {
+        Policy {
+            initial_delay: Duration::ZERO,
+            max_attempts: Some(2),
+            idempotent_methods: ["read".to_owned()].into(),
+            session_init_methods: ["init".to_owned()].into(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn backoff_delays() {
+        let policy = Policy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            ..default()
+        };
+        let delays = (0..6).map(|attempt| policy.delay(attempt).as_millis()).collect_vec();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(policy.delay(usize::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn replaying_requests_after_reconnecting() {
+        let mut fixture = Fixture::new(test_policy());
+        let mut second = MockTransport::new();
+        fixture.reconnections.borrow_mut().push_back(second.clone());
+
+        fixture.send(request(0, "init"));
+        fixture.first.mock_peer_json_message(reply(0));
+        fixture.pool.run_until_stalled();
+        fixture.expect_reply(reply(0));
+        fixture.send(request(1, "read"));
+        fixture.send(request(2, "write"));
+        assert_eq!(fixture.first.with_mut_data(|data| data.sent_text_msgs.len()), 3);
+
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+        let lost = json!({"jsonrpc": "2.0", "id": 2, "error": {
+            "code": CONNECTION_LOST_ERROR_CODE,
+            "message": "The connection was lost before receiving a reply.",
+            "data": null
+        }});
+        fixture.expect_reply(lost);
+        fixture.events.expect_pending();
+        assert_eq!(fixture.states.expect_next(), State::Reconnecting { attempt: 1 });
+        assert_eq!(fixture.states.expect_next(), State::Initializing);
+
+        // The session is re-initialized before any other message is sent.
+        let init = second.expect_json_message::<serde_json::Value>();
+        assert_eq!(
+            init,
+            json!({"jsonrpc": "2.0", "id": -1, "method": "init", "params": {"id": 0}})
+        );
+        fixture.send(request(3, "write"));
+        assert!(second.with_mut_data(|data| data.sent_text_msgs.is_empty()));
+
+        second.mock_peer_json_message(reply(-1));
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.transport.state(), State::Connected);
+        assert_eq!(second.expect_json_message::<serde_json::Value>(), request(1, "read"));
+        assert_eq!(second.expect_json_message::<serde_json::Value>(), request(3, "write"));
+        fixture.events.expect_pending();
+
+        second.mock_peer_json_message(reply(1));
+        fixture.pool.run_until_stalled();
+        fixture.expect_reply(reply(1));
+    }
+
+    #[test]
+    fn sending_during_connection_drop() {
+        let mut fixture = Fixture::new(test_policy());
+        let mut second = MockTransport::new();
+        fixture.reconnections.borrow_mut().push_back(second.clone());
+
+        // The underlying transport is already closed, but the runner has not noticed it yet.
+        fixture.first.mock_connection_closed();
+        fixture.send(request(1, "read"));
+        fixture.send(request(2, "write"));
+        fixture.pool.run_until_stalled();
+        assert_eq!(fixture.states.expect_next(), State::Reconnecting { attempt: 1 });
+        assert_eq!(fixture.states.expect_next(), State::Connected);
+
+        // The failed requests are neither reported as lost nor sent twice.
+        fixture.events.expect_pending();
+        assert_eq!(second.expect_json_message::<serde_json::Value>(), request(1, "read"));
+        assert_eq!(second.expect_json_message::<serde_json::Value>(), request(2, "write"));
+        assert!(second.with_mut_data(|data| data.sent_text_msgs.is_empty()));
+
+        second.mock_peer_json_message(reply(2));
+        fixture.pool.run_until_stalled();
+        fixture.expect_reply(reply(2));
+    }
+
+    #[test]
+    fn giving_up_reconnecting() {
+        let mut fixture = Fixture::new(test_policy());
+        fixture.first.mock_connection_closed();
+        fixture.pool.run_until_stalled();
+
+        assert_eq!(fixture.states.expect_next(), State::Reconnecting { attempt: 1 });
+        assert_eq!(fixture.states.expect_next(), State::Reconnecting { attempt: 2 });
+        assert_eq!(fixture.states.expect_next(), State::Closed);
+        assert!(matches!(fixture.events.expect_next(), TransportEvent::Closed));
+        assert!(fixture.transport.send_text("{}").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! A `Transport` decorator recording all the traffic with timestamps.
+//!
+//! The saved recordings can be served back in tests by the
+//! [`crate::test_util::transport::replay::ReplayTransport`].
+
+use crate::prelude::*;
+
+use crate::ensogl::time_from_start;
+use crate::transport::Transport;
+use crate::transport::TransportEvent;
+
+use failure::Error;
+use futures::channel::mpsc::unbounded;
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::channel::mpsc::UnboundedSender;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+use std::task::Context;
+use std::task::Poll;
+
+
+
+// =================
+// === Recording ===
+// =================
+
+/// Direction in which a recorded message was transported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    /// The message was sent to the peer.
+    Sent,
+    /// The message was received from the peer.
+    Received,
+}
+
+/// Recorded transport event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum Payload {
+    /// A text message.
+    Text(String),
+    /// A binary message.
+    Binary(Vec<u8>),
+    /// The connection has been closed by the peer.
+    Closed,
+}
+
+/// A single entry of the recording.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Time in milliseconds, counted from the recording start.
+    pub time:      f64,
+    /// Whether the message was sent or received.
+    pub direction: Direction,
+    /// The message contents.
+    pub payload:   Payload,
+}
+
+/// A recorded session: all the messages in the order of transporting them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// The recorded entries.
+    pub entries: Vec<Entry>,
+}
+
+impl Recording {
+    /// Serialize the recording to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Serializing recording must not fail.")
+    }
+
+    /// Deserialize the recording from JSON.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Save the recording to the file as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> FallibleResult {
+        Ok(std::fs::write(path, self.to_json())?)
+    }
+
+    /// Load the recording from the JSON file.
+    pub fn load(path: impl AsRef<Path>) -> FallibleResult<Self> {
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+
+
+// ==========================
+// === RecordingTransport ===
+// ==========================
+
+#[derive(Debug)]
+struct Model {
+    transport:         Box<dyn Transport>,
+    transport_events:  UnboundedReceiver<TransportEvent>,
+    event_transmitter: Option<UnboundedSender<TransportEvent>>,
+    recording:         Recording,
+    start_time:        f64,
+}
+
+impl Model {
+    fn record(&mut self, direction: Direction, payload: Payload) {
+        let time = time_from_start() - self.start_time;
+        self.recording.entries.push(Entry { time, direction, payload });
+    }
+
+    fn receive(&mut self, event: TransportEvent) {
+        match &event {
+            TransportEvent::TextMessage(text) =>
+                self.record(Direction::Received, Payload::Text(text.clone())),
+            TransportEvent::BinaryMessage(data) =>
+                self.record(Direction::Received, Payload::Binary(data.clone())),
+            TransportEvent::Closed => self.record(Direction::Received, Payload::Closed),
+            TransportEvent::Opened => {}
+        }
+        if let Some(transmitter) = self.event_transmitter.as_ref() {
+            channel::emit(transmitter, event);
+        }
+    }
+
+    /// Record and pass all the events which have already arrived from the wrapped transport, so
+    /// they are not recorded after the messages sent later.
+    fn receive_arrived(&mut self) {
+        while let Ok(Some(event)) = self.transport_events.try_next() {
+            self.receive(event);
+        }
+    }
+
+    /// Record and pass the next event of the wrapped transport, if it has arrived. Returns
+    /// `Ready(false)` if the wrapped transport was dropped.
+    fn poll_receive(&mut self, cx: &mut Context) -> Poll<bool> {
+        self.transport_events.poll_next_unpin(cx).map(|event| match event {
+            Some(event) => {
+                self.receive(event);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+/// A transport recording all the messages sent and received through the wrapped transport.
+///
+/// The `runner` future must be spawned for the received messages to be passed to the owner. The
+/// received messages are also recorded before sending any message, so the recording keeps the
+/// order in which the messages were transported, even if the runner has not been polled yet.
+#[derive(Clone, CloneRef, Debug)]
+pub struct RecordingTransport {
+    model: Rc<RefCell<Model>>,
+}
+
+impl RecordingTransport {
+    /// Wrap the given transport. The recording starts immediately.
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        let mut transport: Box<dyn Transport> = Box::new(transport);
+        let (transmitter, receiver) = unbounded();
+        transport.set_event_transmitter(transmitter);
+        let model = Model {
+            transport,
+            transport_events: receiver,
+            event_transmitter: None,
+            recording: default(),
+            start_time: time_from_start(),
+        };
+        Self { model: Rc::new(RefCell::new(model)) }
+    }
+
+    /// The messages recorded so far, including the already received ones.
+    pub fn recording(&self) -> Recording {
+        let mut model = self.model.borrow_mut();
+        model.receive_arrived();
+        model.recording.clone()
+    }
+
+    /// Returns a future that records and passes the messages received by the wrapped transport.
+    /// Will end once the wrapped transport or this transport is dropped.
+    pub fn runner(&self) -> impl Future<Output = ()> {
+        let weak = Rc::downgrade(&self.model);
+        futures::future::poll_fn(move |cx| loop {
+            let Some(model) = weak.upgrade() else { return Poll::Ready(()) };
+            let received = model.borrow_mut().poll_receive(cx);
+            match received {
+                Poll::Ready(true) => {}
+                Poll::Ready(false) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        })
+    }
+
+    fn send(&self, payload: Payload) -> Result<(), Error> {
+        let mut model = self.model.borrow_mut();
+        model.receive_arrived();
+        let result = match &payload {
+            Payload::Text(text) => model.transport.send_text(text),
+            Payload::Binary(data) => model.transport.send_binary(data),
+            Payload::Closed => Ok(()),
+        };
+        if result.is_ok() {
+            model.record(Direction::Sent, payload);
+        }
+        result
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn send_text(&mut self, message: &str) -> Result<(), Error> {
+        self.send(Payload::Text(message.into()))
+    }
+
+    fn send_binary(&mut self, message: &[u8]) -> Result<(), Error> {
+        self.send(Payload::Binary(message.into()))
+    }
+
+    fn set_event_transmitter(&mut self, transmitter: UnboundedSender<TransportEvent>) {
+        self.model.borrow_mut().event_transmitter = Some(transmitter);
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::transport::mock::MockTransport;
+
+    use futures::executor::LocalPool;
+    use futures::task::LocalSpawnExt;
+
+    #[test]
+    fn recording_traffic() {
+        let mut inner = MockTransport::new();
+        let mut transport = RecordingTransport::new(inner.clone());
+        let mut events = transport.establish_event_stream();
+        let mut pool = LocalPool::new();
+        pool.spawner().spawn_local(transport.runner()).unwrap();
+
+        transport.send_text("ping").unwrap();
+        inner.mock_peer_text_message("pong");
+        transport.send_binary(&[1, 2, 3]).unwrap();
+        inner.mock_connection_closed();
+        assert!(transport.send_text("ping").is_err());
+        pool.run_until_stalled();
+
+        assert!(
+            matches!(events.expect_next(), TransportEvent::TextMessage(text) if text == "pong")
+        );
+        assert!(matches!(events.expect_next(), TransportEvent::Closed));
+        let recording = transport.recording();
+        let entries = recording.entries.iter().map(|e| (e.direction, e.payload.clone()));
+        let expected = vec![
+            (Direction::Sent, Payload::Text("ping".into())),
+            (Direction::Received, Payload::Text("pong".into())),
+            (Direction::Sent, Payload::Binary(vec![1, 2, 3])),
+            (Direction::Received, Payload::Closed),
+        ];
+        assert_eq!(entries.collect_vec(), expected);
+        assert_eq!(Recording::from_json(&recording.to_json()).unwrap(), recording);
+    }
+}
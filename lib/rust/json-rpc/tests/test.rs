@@ -304,3 +304,253 @@ fn test_handling_invalid_notification() {
         panic!("expected InvalidNotification error");
     }
 }
+
+
+
+// ===========================
+// === Server-Side Methods ===
+// ===========================
+
+fn register_pow(handler: &Handler<MockNotification>) {
+    handler.register_method(MockRequest::NAME, |input: MockRequest| {
+        futures::future::ready(Ok(MockResponse { result: input.i * input.i }))
+    });
+}
+
+#[test]
+fn test_answering_request() {
+    let mut fixture = Fixture::new();
+    register_pow(&fixture.client.handler);
+    let request = Message::new_request(Id(5), MockRequest::NAME, MockRequest { i: 3 });
+    fixture.transport.mock_peer_json_message(request);
+    fixture.pool.run_until_stalled();
+
+    let reply = fixture.transport.expect_json_message::<MockResponseMessage>();
+    assert_eq!(reply, Message::new_success(Id(5), MockResponse { result: 9 }));
+    fixture.client.expect_no_notification_yet();
+}
+
+#[test]
+fn test_answering_request_to_unknown_method() {
+    let mut fixture = Fixture::new();
+    register_pow(&fixture.client.handler);
+    let request = Message::new_request(Id(1), "sqrt", MockRequest { i: 3 });
+    fixture.transport.mock_peer_json_message(request);
+    fixture.pool.run_until_stalled();
+
+    let reply = fixture.transport.expect_json_message::<MockResponseMessage>();
+    assert_eq!(reply.id, Id(1));
+    match reply.payload.result {
+        messages::Result::Error { error } =>
+            assert_eq!(error.code, json_rpc::server::code::METHOD_NOT_FOUND),
+        messages::Result::Success(_) => panic!("Expected an error reply."),
+    }
+}
+
+#[test]
+fn test_requests_as_notifications_without_methods() {
+    // A client that does not register any methods handles the messages with both `id` and
+    // `method` as notifications.
+    let mut fixture = Fixture::new();
+    let message = r#"{"jsonrpc":"2.0","id":1,"method":"Meow","params":{"text":"meow!"}}"#;
+    fixture.transport.mock_peer_text_message(message);
+    fixture.pool.run_until_stalled();
+    assert_eq!(fixture.client.expect_notification(), MockNotification::Meow {
+        text: "meow!".into(),
+    });
+    assert!(fixture.transport.with_mut_data(|data| data.sent_text_msgs.is_empty()));
+
+    let batch = format!("[{message}]");
+    fixture.transport.mock_peer_text_message(batch);
+    fixture.pool.run_until_stalled();
+    assert_eq!(fixture.client.expect_notification(), MockNotification::Meow {
+        text: "meow!".into(),
+    });
+    assert!(fixture.transport.with_mut_data(|data| data.sent_text_msgs.is_empty()));
+}
+
+#[test]
+fn test_bidirectional_calls() {
+    let (client_transport, server_transport) = transport::memory::pair();
+    let mut client = Client::new(client_transport);
+    let mut server = Handler::<MockNotification>::new(server_transport);
+    register_pow(&client.handler);
+
+    // The server answers "sumOfSquares" by asking the client to compute the squares.
+    let server_handle = server.clone();
+    server.register_method("sumOfSquares", move |numbers: Vec<i64>| {
+        let squares = numbers.into_iter().map(|i| server_handle.open_request(MockRequest { i }));
+        futures::future::try_join_all(squares)
+            .map(|squares| squares.map(|squares| squares.iter().map(|s| s.result).sum::<i64>()))
+    });
+
+    let mut pool = futures::executor::LocalPool::new();
+    pool.spawner().spawn_local(client.events_processor()).unwrap();
+    pool.spawner().spawn_local(server.runner()).unwrap();
+
+    let mut fut = Box::pin(
+        client.handler.open_request_with_json::<i64>("sumOfSquares", &serde_json::json!([1, 2, 3])),
+    );
+    fut.expect_pending();
+    pool.run_until_stalled();
+    assert_eq!(fut.expect_ok(), 1 + 4 + 9);
+
+    server.send_notification("Meow", serde_json::json!({"text": "meow!"})).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(client.expect_notification(), MockNotification::Meow { text: "meow!".into() });
+}
+
+
+
+// ==============================
+// === Batches & Cancellation ===
+// ==============================
+
+#[test]
+fn test_batch_call() {
+    let mut fixture = Fixture::new();
+    let mut batch = fixture.client.handler.open_batch();
+    let mut first = Box::pin(batch.open_request(MockRequest { i: 2 }));
+    let mut second = Box::pin(batch.open_request(MockRequest { i: 3 }));
+    assert_eq!(batch.len(), 2);
+    batch.send().unwrap();
+
+    let requests = fixture.transport.expect_json_message::<Vec<MockRequestMessage>>();
+    assert_eq!(requests.iter().map(|r| (r.id, r.i)).collect_vec(), vec![(Id(0), 2), (Id(1), 3)]);
+    let replies = requests.into_iter().rev().map(pow_impl).collect_vec();
+    fixture.transport.mock_peer_json_message(replies);
+    fixture.pool.run_until_stalled();
+
+    assert_eq!(first.expect_ok().result, 4);
+    assert_eq!(second.expect_ok().result, 9);
+}
+
+#[test]
+fn test_dropped_batch() {
+    let fixture = Fixture::new();
+    let mut batch = fixture.client.handler.open_batch();
+    let mut fut = Box::pin(batch.open_request(MockRequest { i: 2 }));
+    drop(batch);
+    assert!(matches!(fut.expect_err(), RpcError::LostConnection));
+}
+
+#[test]
+fn test_cancel_on_drop() {
+    let mut fixture = Fixture::new();
+    let method = json_rpc::constants::CANCEL_METHOD;
+    fixture.client.handler.set_cancel_method(Some(method.into()));
+    let fut = fixture.client.pow(8);
+    let request = fixture.transport.expect_json_message::<MockRequestMessage>();
+    drop(fut);
+
+    let cancel = fixture.transport.expect_json_message::<serde_json::Value>();
+    let expected = serde_json::json!({"jsonrpc": "2.0", "method": method, "params": {"id": 0}});
+    assert_eq!(cancel, expected);
+
+    // The late reply is no longer expected.
+    fixture.transport.mock_peer_json_message(pow_impl(request));
+    fixture.pool.run_until_stalled();
+    let error = fixture.client.expect_handling_error();
+    assert!(matches!(error, HandlingError::UnexpectedResponse(_)));
+}
+
+#[test]
+fn test_no_cancel_by_default() {
+    let mut fixture = Fixture::new();
+    let fut = fixture.client.pow(8);
+    fixture.transport.expect_json_message::<MockRequestMessage>();
+    drop(fut);
+    assert!(fixture.transport.with_mut_data(|data| data.sent_text_msgs.is_empty()));
+}
+
+#[test]
+fn test_answering_batch_request() {
+    let mut fixture = Fixture::new();
+    register_pow(&fixture.client.handler);
+    let requests = vec![
+        Message::new_request(Id(1), MockRequest::NAME, MockRequest { i: 2 }),
+        Message::new_request(Id(2), MockRequest::NAME, MockRequest { i: 3 }),
+    ];
+    fixture.transport.mock_peer_json_message(requests);
+    fixture.pool.run_until_stalled();
+
+    let replies = fixture.transport.expect_json_message::<Vec<MockResponseMessage>>();
+    let expected = vec![
+        Message::new_success(Id(1), MockResponse { result: 4 }),
+        Message::new_success(Id(2), MockResponse { result: 9 }),
+    ];
+    assert_eq!(replies, expected);
+}
+
+
+
+// ==========================
+// === Recording & Replay ===
+// ==========================
+
+fn recorded_pow_session(id: i64, i: i64) -> transport::recording::Recording {
+    use transport::recording::*;
+    let request =
+        serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "pow", "params": {"i": i}});
+    let reply = serde_json::json!({"jsonrpc": "2.0", "id": id, "result": {"result": i * i}});
+    let entry = |time, direction, message: serde_json::Value| Entry {
+        time,
+        direction,
+        payload: Payload::Text(message.to_string()),
+    };
+    let entries =
+        vec![entry(0.0, Direction::Sent, request), entry(1.0, Direction::Received, reply)];
+    Recording { entries }
+}
+
+#[test]
+fn test_recording_and_replaying_session() {
+    let (client_transport, server_transport) = transport::memory::pair();
+    let recording_transport = transport::RecordingTransport::new(client_transport);
+    let mut client = Client::new(recording_transport.clone_ref());
+    let server = Handler::<MockNotification>::new(server_transport);
+    register_pow(&server);
+    let mut pool = futures::executor::LocalPool::new();
+    pool.spawner().spawn_local(recording_transport.runner()).unwrap();
+    pool.spawner().spawn_local(client.events_processor()).unwrap();
+    pool.spawner().spawn_local(server.runner()).unwrap();
+
+    let mut fut = Box::pin(client.pow(3));
+    pool.run_until_stalled();
+    assert_eq!(fut.expect_ok(), 9);
+    server.send_notification("Meow", serde_json::json!({"text": "meow!"})).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(client.expect_notification(), MockNotification::Meow { text: "meow!".into() });
+    let recording = recording_transport.recording();
+    assert_eq!(recording.entries.len(), 3);
+
+    let replay = test_util::transport::replay::ReplayTransport::new(recording);
+    let mut client = Client::new(replay.clone_ref());
+    let mut pool = futures::executor::LocalPool::new();
+    pool.spawner().spawn_local(client.events_processor()).unwrap();
+    let mut fut = Box::pin(client.pow(3));
+    pool.run_until_stalled();
+    assert_eq!(fut.expect_ok(), 9);
+    assert_eq!(client.expect_notification(), MockNotification::Meow { text: "meow!".into() });
+    replay.expect_finished();
+}
+
+#[test]
+fn test_replaying_session_with_different_ids() {
+    let replay = test_util::transport::replay::ReplayTransport::new(recorded_pow_session(7, 2));
+    let mut client = Client::new(replay.clone_ref());
+    let mut pool = futures::executor::LocalPool::new();
+    pool.spawner().spawn_local(client.events_processor()).unwrap();
+    let mut fut = Box::pin(client.pow(2));
+    pool.run_until_stalled();
+    assert_eq!(fut.expect_ok(), 4);
+    replay.expect_finished();
+}
+
+#[test]
+#[should_panic]
+fn test_replaying_session_with_different_request() {
+    let replay = test_util::transport::replay::ReplayTransport::new(recorded_pow_session(0, 2));
+    let mut client = Client::new(replay);
+    let _fut = client.pow(3);
+}
@@ -23,12 +23,6 @@ use quote::quote;
 
 
 
-/// The log levels defined in the Console Web API.
-/// See: https://console.spec.whatwg.org/#loglevel-severity
-const WEB_LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug"];
-
-
-
 // ==================================
 // === Compile-time configuration ===
 // ==================================
@@ -38,6 +32,11 @@ const LEVEL_CONFIGURATION_ENV_VARS: LevelConfiguration<&str> = LevelConfiguratio
     max_uncollapsed: "ENSO_MAX_UNCOLLAPSED_LOG_LEVEL",
 };
 
+/// The maximum compiled-in log level used if `ENSO_MAX_LOG_LEVEL` is not set. It allows displaying
+/// debug logs of shipped builds with the runtime filter, while the most verbose levels are compiled
+/// out.
+const DEFAULT_MAX_ENABLED_LEVEL: &str = "debug";
+
 
 
 // =====================
@@ -86,7 +85,10 @@ macro_rules! map_fns {
 // === Interface ===
 // =================
 
-/// Implement a logging API for the spcecified log levels.
+/// Implement a logging API for the specified log levels, given from the most to the least severe.
+/// The levels less severe than the one set in the `ENSO_MAX_LOG_LEVEL` environment variable (the
+/// `debug` level by default, if defined) are compiled out. The runtime filter can only narrow the
+/// compiled-in levels down.
 #[proc_macro]
 pub fn define_log_levels(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
     use syn::parse::Parser;
@@ -99,8 +101,15 @@ pub fn define_log_levels(ts: proc_macro::TokenStream) -> proc_macro::TokenStream
             panic!("{error}. Found: {name:?}, expected one of: {names:?}.")
         })
     };
-    let level_configuration = LEVEL_CONFIGURATION_ENV_VARS
-        .map(|var| std::env::var(var).ok().map(position_in_args).unwrap_or_default());
+    let configured = LEVEL_CONFIGURATION_ENV_VARS.map(|var| {
+        let value = std::env::var(var).ok().filter(|value| !value.is_empty());
+        value.map(position_in_args)
+    });
+    let default_max_enabled = names.iter().position(|name| name == DEFAULT_MAX_ENABLED_LEVEL);
+    let level_configuration = LevelConfiguration {
+        max_enabled:     configured.max_enabled.or(default_max_enabled).unwrap_or_default(),
+        max_uncollapsed: configured.max_uncollapsed.unwrap_or_default(),
+    };
     logging_api(names, level_configuration)
 }
 
@@ -120,18 +129,11 @@ fn logging_api(
     level_names: impl IntoIterator<Item = String>,
     config: LevelConfiguration<usize>,
 ) -> proc_macro::TokenStream {
-    let global_logger_ident = ident("GlobalLogger");
-    let global_logger_path = ident_to_path(global_logger_ident.clone());
-    let levels = levels(level_names, &config, global_logger_path);
-    let api: Api = [
-        span_trait(),
-        logger_trait(&levels),
-        span_api(&levels),
-        event_api(&levels),
-        global_logger(global_logger_ident, &levels),
-    ]
-    .into_iter()
-    .collect();
+    let levels = levels(level_names, &config);
+    let api: Api =
+        [level_enum(&levels, &config), span_trait(), span_api(&levels), event_api(&levels)]
+            .into_iter()
+            .collect();
     api.into_library().into()
 }
 
@@ -139,33 +141,30 @@ fn logging_api(
 // === Information used to construct level-specific interfaces ===
 
 struct Level {
-    // Intrinsic properties of a level:
-    name:                  String,
-    enabled:               bool,
-    uncollapsed:           bool,
-    // Identifiers for API cross-references:
-    trait_methods:         LoggerMethods<syn::Ident>,
-    global_logger_methods: LoggerMethods<syn::Path>,
+    name:    String,
+    variant: syn::Ident,
+    enabled: bool,
 }
 
 impl Level {
-    fn new(name: String, enabled: bool, uncollapsed: bool, global_logger_path: &syn::Path) -> Self {
-        let trait_methods = trait_methods(&name);
-        let global_logger_methods =
-            trait_methods.clone().map(|x| qualified(global_logger_path.clone(), x));
-        Level { global_logger_methods, trait_methods, enabled, uncollapsed, name }
+    fn new(name: String, enabled: bool) -> Self {
+        let variant = ident(name.to_pascal_case());
+        Level { name, variant, enabled }
+    }
+
+    /// The path to the variant of the `Level` enum, to be used in the macros.
+    fn path(&self) -> proc_macro2::TokenStream {
+        let variant = &self.variant;
+        quote! { $crate::Level::#variant }
     }
 }
 
 fn levels(
     names: impl IntoIterator<Item = String>,
     config: &LevelConfiguration<usize>,
-    global_logger_path: syn::Path,
 ) -> Vec<Level> {
     let enabled = |i| config.max_enabled >= i;
-    let uncollapsed = |i| config.max_uncollapsed >= i;
-    let level = |(i, name)| Level::new(name, enabled(i), uncollapsed(i), &global_logger_path);
-    names.into_iter().enumerate().map(level).collect()
+    names.into_iter().enumerate().map(|(i, name)| Level::new(name, enabled(i))).collect()
 }
 
 
@@ -231,6 +230,62 @@ impl Export {
     fn prelude(ident: syn::Ident) -> Self {
         Self { ident, prelude: true }
     }
+
+    fn public(ident: syn::Ident) -> Self {
+        Self { ident, prelude: false }
+    }
+}
+
+
+
+// ==================
+// === Level enum ===
+// ==================
+
+fn level_enum(levels: &[Level], config: &LevelConfiguration<usize>) -> Api {
+    let enum_name = ident("Level");
+    let max_enabled_name = ident("MAX_ENABLED_LEVEL");
+    let max_uncollapsed_name = ident("MAX_UNCOLLAPSED_LEVEL");
+    let variants: Vec<_> = levels.iter().map(|level| &level.variant).collect();
+    let names: Vec<_> = levels.iter().map(|level| &level.name).collect();
+    let tags: Vec<_> = levels.iter().map(|level| level.name.to_screaming_snake_case()).collect();
+    let max_enabled = &levels[config.max_enabled].variant;
+    let max_uncollapsed = &levels[config.max_uncollapsed].variant;
+    let implementation = quote! {
+        /// The severity of a log message. The levels are ordered from the most to the least
+        /// severe one.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[allow(missing_docs)]
+        pub enum #enum_name {
+            #(#variants),*
+        }
+        impl #enum_name {
+            /// All the levels, from the most to the least severe one.
+            pub const ALL: &'static [Self] = &[#(Self::#variants),*];
+            /// The lowercase name of the level, as used in the filter directives.
+            pub fn name(self) -> &'static str {
+                match self { #(Self::#variants => #names),* }
+            }
+            /// The uppercase tag of the level, as used in the log messages.
+            pub fn tag(self) -> &'static str {
+                match self { #(Self::#variants => #tags),* }
+            }
+        }
+        /// The least severe level compiled in, set with the `ENSO_MAX_LOG_LEVEL` environment
+        /// variable at compile time. Less severe messages are never emitted, regardless of the
+        /// runtime filter.
+        pub const #max_enabled_name: #enum_name = #enum_name::#max_enabled;
+        /// The least severe level of spans displayed initially-open in hierarchical views, such as
+        /// the Web Console. Set with the `ENSO_MAX_UNCOLLAPSED_LOG_LEVEL` environment variable at
+        /// compile time.
+        pub const #max_uncollapsed_name: #enum_name = #enum_name::#max_uncollapsed;
+    };
+    let exports = vec![
+        Export::public(enum_name),
+        Export::public(max_enabled_name),
+        Export::public(max_uncollapsed_name),
+    ];
+    Api { implementation, exports }
 }
 
 
@@ -281,66 +336,28 @@ fn span_trait() -> Api {
 
 
 
-// ====================
-// === Logger trait ===
-// ====================
+// ==============
+// === Fields ===
+// ==============
 
-fn logger_trait(levels: impl IntoIterator<Item = &Level>) -> Api {
-    let mut methods = proc_macro2::TokenStream::new();
-    for level in levels {
-        level.trait_methods.signatures().for_each(|x| methods.extend(x))
-    }
-    (quote! {
-        /// A type that serves as a destination for logging.
-        pub trait Logger {
-            #methods
-        }
-    })
-    .into()
+/// The macro rule patterns of the log messages, with and without structured fields. The fields are
+/// given before the message, separated by a semicolon, like `info!(id = node_id; "Node added.")`.
+fn message_patterns() -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let with_fields = quote! { ($($key:ident = $value:expr),+ ; $($args:tt)*) };
+    let without_fields = quote! { ($($args:tt)*) };
+    (with_fields, without_fields)
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-struct LoggerMethods<T = proc_macro2::TokenStream> {
-    emit_fn:  T,
-    enter_fn: T,
-    exit_fn:  T,
-}
-map_fns!(LoggerMethods, [emit_fn, enter_fn, exit_fn]);
-
-impl LoggerMethods<syn::Ident> {
-    fn signatures(&self) -> LoggerMethods {
-        let LoggerMethods { emit_fn, enter_fn, exit_fn } = self;
-        LoggerMethods {
-            emit_fn:  quote! { #[allow(missing_docs)] fn #emit_fn(span: &str); },
-            enter_fn: quote! { #[allow(missing_docs)] fn #enter_fn(span: &str); },
-            exit_fn:  quote! { #[allow(missing_docs)] fn #exit_fn(); },
-        }
-    }
-
-    fn with_bodies(
-        &self,
-        bodies: &LoggerMethods<proc_macro2::TokenStream>,
-    ) -> LoggerMethods<proc_macro2::TokenStream> {
-        let LoggerMethods { emit_fn, enter_fn, exit_fn } = self;
-        let LoggerMethods { emit_fn: emit_body, enter_fn: enter_body, exit_fn: exit_body } = bodies;
-        LoggerMethods {
-            emit_fn:  quote! { #[inline] fn #emit_fn(span: &str) { #emit_body } },
-            enter_fn: quote! { #[inline] fn #enter_fn(span: &str) { #enter_body } },
-            exit_fn:  quote! { #[inline] fn #exit_fn() { #exit_body } },
-        }
+/// Evaluate the field values, if any, so they are not reported as unused when the level is
+/// compiled out.
+fn unused_fields(with_fields: bool) -> proc_macro2::TokenStream {
+    if with_fields {
+        quote! { $(let _unused_at_this_log_level = &$value;)* }
+    } else {
+        proc_macro2::TokenStream::new()
     }
 }
 
-fn trait_methods(level: &str) -> LoggerMethods<syn::Ident> {
-    (LoggerMethods {
-        emit_fn:  format!("emit_{level}"),
-        enter_fn: format!("enter_{level}"),
-        exit_fn:  format!("exit_{level}"),
-    })
-    .as_ref()
-    .map(ident)
-}
-
 
 
 // =================
@@ -348,38 +365,49 @@ fn trait_methods(level: &str) -> LoggerMethods<syn::Ident> {
 // =================
 
 fn event_api(levels: impl IntoIterator<Item = &Level>) -> Api {
-    levels
-        .into_iter()
-        .map(|level| event_api_for_level(&level.name, &level.global_logger_methods, level.enabled))
-        .collect()
+    levels.into_iter().map(event_api_for_level).collect()
 }
 
-fn event_api_for_level(level: &str, methods: &LoggerMethods<syn::Path>, enabled: bool) -> Api {
-    let event_macro = ident(level);
-    let emit_fn = &methods.emit_fn;
-    let level_tag = level.to_screaming_snake_case();
-    let body = if enabled {
-        quote! {
-            use $crate::internal::Logger;
-            $crate::internal::#emit_fn(
-                &format!(
-                    "[{}] {}:{} {}",
-                    #level_tag,
-                    file!(),
-                    line!(),
-                    format_args!($($args)*)
-                ));
-        }
-    } else {
-        quote! {
-            let _unused_at_this_log_level = format_args!($($args)*);
+fn event_api_for_level(level: &Level) -> Api {
+    let event_macro = ident(&level.name);
+    let level_path = level.path();
+    let body = |with_fields: bool| {
+        if level.enabled {
+            let fields = if with_fields {
+                quote! { &[$($crate::record::field(stringify!($key), &$value)),*] }
+            } else {
+                quote! { &[] }
+            };
+            quote! {
+                if $crate::filter::is_enabled(#level_path, module_path!()) {
+                    $crate::sink::emit(&$crate::record::Record {
+                        level:       #level_path,
+                        module_path: module_path!(),
+                        file:        file!(),
+                        line:        line!(),
+                        message:     format_args!($($args)*),
+                        fields:      #fields,
+                    });
+                }
+            }
+        } else {
+            let unused_fields = unused_fields(with_fields);
+            quote! {
+                let _unused_at_this_log_level = format_args!($($args)*);
+                #unused_fields
+            }
         }
     };
+    let (with_fields, without_fields) = message_patterns();
+    let body_with_fields = body(true);
+    let body_without_fields = body(false);
     let implementation = quote! {
-        /// Emit a log message, if the log-level is enabled.
+        /// Emit a log message, if the log-level is enabled. Structured fields can be given before
+        /// the message, separated by a semicolon, like `info!(id = node_id; "Node added.")`.
         #[macro_export]
         macro_rules! #event_macro {
-            ($($args:tt)*) => {{ #body }};
+            #with_fields => {{ #body_with_fields }};
+            #without_fields => {{ #body_without_fields }};
         }
     };
     Api { implementation, ..Default::default() }
@@ -392,50 +420,70 @@ fn event_api_for_level(level: &str, methods: &LoggerMethods<syn::Path>, enabled:
 // ================
 
 fn span_api(levels: impl IntoIterator<Item = &Level>) -> Api {
-    levels
-        .into_iter()
-        .map(|level| span_api_for_level(&level.name, &level.global_logger_methods, level.enabled))
-        .collect()
+    levels.into_iter().map(span_api_for_level).collect()
 }
 
-fn span_api_for_level(level: &str, methods: &LoggerMethods<syn::Path>, enabled: bool) -> Api {
-    let object_name = ident(level.to_pascal_case());
-    let macro_name = ident(format!("{level}_span"));
-    let object_contents = enabled.then_some(quote! { pub String }).unwrap_or_default();
-    let enter_fn = &methods.enter_fn;
-    let exit_fn = &methods.exit_fn;
-    let enter_impl = enabled
+fn span_api_for_level(level: &Level) -> Api {
+    let object_name = ident(level.name.to_pascal_case());
+    let macro_name = ident(format!("{}_span", level.name));
+    let level_path = level.path();
+    let object_contents = level
+        .enabled
+        .then_some(quote! { pub Option<crate::record::SpanRecord> })
+        .unwrap_or_default();
+    let enter_impl = level
+        .enabled
         .then_some(quote! {
-            #enter_fn(&self.0);
+            if let Some(record) = &self.0 {
+                crate::sink::enter(record);
+            }
         })
         .unwrap_or_default();
-    let exit_impl = enabled
+    let exit_impl = level
+        .enabled
         .then_some(quote! {
-            #exit_fn();
+            if let Some(record) = &self.0 {
+                crate::sink::exit(record);
+            }
         })
         .unwrap_or_default();
-    let level_tag = level.to_screaming_snake_case();
-    let creation_body = if enabled {
-        quote! {
-            $crate::internal::#object_name(
-                format!(
-                    "[{}] {}:{} {}",
-                    #level_tag,
-                    file!(),
-                    line!(),
-                    format_args!($($args)*)
-                )
-            )
-        }
-    } else {
-        quote! {
-            let _unused_at_this_log_level = format_args!($($args)*);
-            $crate::internal::#object_name()
+    let creation_body = |with_fields: bool| {
+        if level.enabled {
+            let fields = if with_fields {
+                quote! { vec![$((stringify!($key), $value.to_string())),*] }
+            } else {
+                quote! { Vec::new() }
+            };
+            quote! {
+                let record = if $crate::filter::is_enabled(#level_path, module_path!()) {
+                    Some($crate::record::SpanRecord {
+                        level:       #level_path,
+                        module_path: module_path!(),
+                        file:        file!(),
+                        line:        line!(),
+                        message:     format!($($args)*),
+                        fields:      #fields,
+                    })
+                } else {
+                    None
+                };
+                $crate::internal::#object_name(record)
+            }
+        } else {
+            let unused_fields = unused_fields(with_fields);
+            quote! {
+                let _unused_at_this_log_level = format_args!($($args)*);
+                #unused_fields
+                $crate::internal::#object_name()
+            }
         }
     };
+    let (with_fields, without_fields) = message_patterns();
+    let creation_body_with_fields = creation_body(true);
+    let creation_body_without_fields = creation_body(false);
     let implementation = quote! {
         /// Refers to a region in the source code that may have associated logging.
-        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[derive(Clone, Debug)]
         pub struct #object_name(#object_contents);
         impl LogSpan for #object_name {
             #[inline(always)]
@@ -448,11 +496,12 @@ fn span_api_for_level(level: &str, methods: &LoggerMethods<syn::Path>, enabled:
             }
         }
         /// Create an object that identifies a location in the source code for logging purposes.
+        /// Structured fields can be given before the message, separated by a semicolon, like
+        /// `debug_span!(id = node_id; "Updating node.")`.
         #[macro_export]
         macro_rules! #macro_name {
-            ($($args:tt)*) => {{
-                #creation_body
-            }}
+            #with_fields => {{ #creation_body_with_fields }};
+            #without_fields => {{ #creation_body_without_fields }};
         }
     };
     implementation.into()
@@ -460,130 +509,10 @@ fn span_api_for_level(level: &str, methods: &LoggerMethods<syn::Path>, enabled:
 
 
 
-// =========================
-// === Global Logger API ===
-// =========================
-
-fn global_logger(logger: syn::Ident, levels: impl IntoIterator<Item = &Level>) -> Api {
-    let console_logger = ident("NativeConsole");
-    let web_logger = ident("WebConsole");
-    let mut web_logger_methods = proc_macro2::TokenStream::new();
-    let mut console_logger_methods = proc_macro2::TokenStream::new();
-    let mut web_level = WEB_LOG_LEVELS.iter().copied().fuse();
-    let mut web = web_level.next().unwrap();
-    for level in levels {
-        level
-            .trait_methods
-            .with_bodies(&level.enabled.then(console_logger_impl).unwrap_or_default())
-            .for_each(|x| console_logger_methods.extend(x));
-        level
-            .trait_methods
-            .with_bodies(&level.enabled.then_some(web_logger_impl(web, level)).unwrap_or_default())
-            .for_each(|x| web_logger_methods.extend(x));
-        web = web_level.next().unwrap_or(web);
-    }
-    (quote! {
-        #[cfg(target_arch = "wasm32")]
-        /// The currently-enabled global logger.
-        pub type #logger = web::#web_logger;
-        #[cfg(not(target_arch = "wasm32"))]
-        /// The currently-enabled global logger.
-        pub type #logger = native::#console_logger;
-        /// Logging support for wasm environments.
-        #[cfg(target_arch = "wasm32")]
-        pub mod web {
-            use super::*;
-            /// A [`Logger`] that emits messages to the Console Web API.
-            pub struct #web_logger;
-            impl Logger for #web_logger {
-                #web_logger_methods
-            }
-        }
-        /// Logging support for non-wasm environments.
-        #[cfg(not(target_arch = "wasm32"))]
-        pub mod native {
-            use super::*;
-            /// A [`Logger`] that emits messages to the native console.
-            pub struct #console_logger;
-            thread_local! {
-                static CONSOLE_INDENT: core::cell::Cell<usize> = core::cell::Cell::new(0);
-            }
-            impl Logger for #console_logger {
-                #console_logger_methods
-            }
-        }
-    })
-    .into()
-}
-
-
-
-// =============================
-// === Native console logger ===
-// =============================
-
-fn console_logger_impl() -> LoggerMethods {
-    LoggerMethods {
-        emit_fn:  quote! {
-            let indent = CONSOLE_INDENT.get();
-            println!("{:indent$}{}", "", span, indent=indent*4);
-        },
-        enter_fn: quote! {
-            let indent = CONSOLE_INDENT.get();
-            println!("{:indent$}{}", "", span, indent=indent*4);
-            CONSOLE_INDENT.set(indent + 1);
-        },
-        exit_fn:  quote! {
-            let indent = CONSOLE_INDENT.get();
-            CONSOLE_INDENT.set(indent - 1);
-        },
-    }
-}
-
-
-
-// ======================
-// === Web API logger ===
-// ======================
-
-fn web_logger_impl(web_level: &str, level: &Level) -> LoggerMethods {
-    let event_fn = ident(format!("{web_level}_1"));
-    let group_fn = ident(if level.uncollapsed { "group_1" } else { "group_collapsed_1" });
-    LoggerMethods {
-        emit_fn:  quote! {
-            web_sys::console::#event_fn(&span.into());
-        },
-        enter_fn: quote! {
-            web_sys::console::#group_fn(&span.into());
-        },
-        exit_fn:  quote! {
-            web_sys::console::group_end();
-        },
-    }
-}
-
-
-
 // ============================
 // === Syn-building helpers ===
 // ============================
 
-fn qualified(mut path: syn::Path, name: syn::Ident) -> syn::Path {
-    path.segments.push(path_segment(name));
-    path
-}
-
-fn path_segment(ident: syn::Ident) -> syn::PathSegment {
-    syn::PathSegment { ident, arguments: Default::default() }
-}
-
 fn ident(name: impl AsRef<str>) -> syn::Ident {
     syn::Ident::new(name.as_ref(), proc_macro2::Span::call_site())
 }
-
-fn ident_to_path(segment: syn::Ident) -> syn::Path {
-    syn::Path {
-        leading_colon: Default::default(),
-        segments:      std::iter::once(path_segment(segment)).collect(),
-    }
-}
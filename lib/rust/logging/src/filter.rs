@@ -0,0 +1,254 @@
+//! Runtime filtering of the log messages by their level and module path.
+//!
+//! The filter is configured with comma-separated directives, in the same format as the popular
+//! `RUST_LOG` environment variable:
+//! - `level`, like `info`, sets the level of the modules not matching any other directive. If not
+//!   provided, the [`DEFAULT_LEVEL`] is used.
+//! - `module=level`, like `graph_editor=debug`, sets the level of the given module and all of its
+//!   submodules. The most specific directive matching the module is used.
+//! - `module` enables all the levels of the given module.
+//!
+//! The `off` level disables all the messages. The module paths are matched by whole segments, and
+//! the crate name can be shortened by omitting its leading underscore-separated words. For example,
+//! `frp` matches the `enso_frp` crate, and `graph_editor::component` matches the
+//! `ide_view_graph_editor::component::node` module.
+
+use crate::Level;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The level of the modules not matching any filter directive, if not set explicitly.
+pub const DEFAULT_LEVEL: Level = Level::Warn;
+
+
+
+// ==============
+// === Filter ===
+// ==============
+
+/// A runtime filter of the log messages. See the module docs to learn more.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    /// The level of the modules not matching any directive. `None` disables them.
+    default:    Option<Level>,
+    directives: Vec<Directive>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Directive {
+    module: String,
+    level:  Option<Level>,
+}
+
+impl Filter {
+    /// A filter enabling the messages of the given level and more severe ones in all modules.
+    pub const fn new(level: Level) -> Self {
+        Self { default: Some(level), directives: Vec::new() }
+    }
+
+    /// The least severe level enabled for the given module, or `None` if all the messages of the
+    /// module are disabled.
+    pub fn level(&self, module_path: &str) -> Option<Level> {
+        let matching = self.directives.iter().filter(|d| module_matches(&d.module, module_path));
+        let most_specific = matching.max_by_key(|directive| directive.module.len());
+        most_specific.map_or(self.default, |directive| directive.level)
+    }
+
+    /// Check whether the messages of the given level are enabled in the given module.
+    pub fn is_enabled(&self, level: Level, module_path: &str) -> bool {
+        self.level(module_path).map_or(false, |max_level| level <= max_level)
+    }
+
+    /// The least severe level enabled in any module.
+    pub fn max_level(&self) -> Option<Level> {
+        let levels = self.directives.iter().map(|directive| directive.level);
+        levels.chain(std::iter::once(self.default)).max().flatten()
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Filter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let error = || ParseError { directive: directive.to_owned() };
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    let level = parse_level(level.trim()).ok_or_else(error)?;
+                    if !is_module_path(module) {
+                        return Err(error());
+                    }
+                    filter.directives.push(Directive { module: module.to_owned(), level });
+                }
+                None =>
+                    if let Some(level) = parse_level(directive) {
+                        filter.default = level;
+                    } else if is_module_path(directive) {
+                        let level = Level::ALL.last().copied();
+                        filter.directives.push(Directive { module: directive.to_owned(), level });
+                    } else {
+                        return Err(error());
+                    },
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// Parse the level name, case-insensitively. The `off` level is returned as `Some(None)`.
+fn parse_level(name: &str) -> Option<Option<Level>> {
+    if name.eq_ignore_ascii_case("off") {
+        Some(None)
+    } else {
+        Level::ALL.iter().find(|level| name.eq_ignore_ascii_case(level.name())).map(|l| Some(*l))
+    }
+}
+
+fn is_module_path(path: &str) -> bool {
+    let is_segment = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_');
+    path.split("::").all(is_segment)
+}
+
+/// Check whether the `pattern` matches the module, or any of its parents. See the module docs to
+/// learn more.
+fn module_matches(pattern: &str, module_path: &str) -> bool {
+    let (crate_name, rest) = module_path.split_once("::").unwrap_or((module_path, ""));
+    let shortened = crate_name.match_indices('_').map(|(index, _)| &crate_name[index + 1..]);
+    let mut crate_names = std::iter::once(crate_name).chain(shortened);
+    crate_names.any(|crate_name| match pattern.strip_prefix(crate_name) {
+        Some("") => true,
+        Some(pattern_rest) => match pattern_rest.strip_prefix("::") {
+            Some(pattern_rest) => is_path_prefix(pattern_rest, rest),
+            None => false,
+        },
+        None => false,
+    })
+}
+
+/// Check whether the `prefix` is equal to the first segments of the `path`.
+fn is_path_prefix(prefix: &str, path: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(path_rest) => path_rest.is_empty() || path_rest.starts_with("::"),
+        None => false,
+    }
+}
+
+
+// === ParseError ===
+
+/// An error of parsing the [`Filter`] directives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The invalid directive.
+    pub directive: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<_> = Level::ALL.iter().map(|level| level.name()).collect();
+        let levels = levels.join(", ");
+        write!(f, "Invalid log filter directive: `{}`. ", self.directive)?;
+        write!(
+            f,
+            "Expected `level`, `module=level` or `module`, where level is one of: {levels}, off."
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+
+
+// =====================
+// === Global filter ===
+// =====================
+
+static FILTER: RwLock<Filter> = RwLock::new(Filter::new(DEFAULT_LEVEL));
+/// The number of levels enabled in any module, used to quickly reject the disabled messages.
+static ENABLED_LEVEL_COUNT: AtomicUsize = AtomicUsize::new(DEFAULT_LEVEL as usize + 1);
+/// Whether the filter has any per-module directives. If not, the filter does not need to be read.
+static HAS_DIRECTIVES: AtomicBool = AtomicBool::new(false);
+
+/// Set the filter of the log messages emitted by all threads.
+pub fn set_filter(filter: Filter) {
+    let enabled_level_count = filter.max_level().map_or(0, |level| level as usize + 1);
+    let has_directives = !filter.directives.is_empty();
+    *FILTER.write().unwrap_or_else(PoisonError::into_inner) = filter;
+    ENABLED_LEVEL_COUNT.store(enabled_level_count, Ordering::Relaxed);
+    HAS_DIRECTIVES.store(has_directives, Ordering::Relaxed);
+}
+
+/// Check whether the messages of the given level are enabled in the given module by the filter set
+/// with [`set_filter`]. Used by the logging macros.
+#[inline]
+pub fn is_enabled(level: Level, module_path: &str) -> bool {
+    let is_level_enabled = (level as usize) < ENABLED_LEVEL_COUNT.load(Ordering::Relaxed);
+    is_level_enabled
+        && (!HAS_DIRECTIVES.load(Ordering::Relaxed)
+            || FILTER.read().unwrap_or_else(PoisonError::into_inner).is_enabled(level, module_path))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let filter: Filter = "graph_editor=debug, frp=WARN,info,text::buffer".parse().unwrap();
+        assert_eq!(filter.default, Some(Level::Info));
+        let levels =
+            filter.directives.iter().map(|d| (d.module.as_str(), d.level)).collect::<Vec<_>>();
+        assert_eq!(levels, [
+            ("graph_editor", Some(Level::Debug)),
+            ("frp", Some(Level::Warn)),
+            ("text::buffer", Some(Level::Trace))
+        ]);
+        assert_eq!(filter.max_level(), Some(Level::Trace));
+        assert_eq!("".parse::<Filter>(), Ok(Filter::default()));
+        assert_eq!("off".parse::<Filter>().unwrap().max_level(), None);
+        let error = "frp=loud".parse::<Filter>().unwrap_err();
+        assert_eq!(error.directive, "frp=loud");
+        assert!("frp:nodes".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn matching_modules() {
+        let filter: Filter =
+            "frp=off,frp::nodes=debug,graph_editor::component=trace".parse().unwrap();
+        assert_eq!(filter.level("enso_frp"), None);
+        assert_eq!(filter.level("enso_frp::network"), None);
+        assert_eq!(filter.level("enso_frp::nodes::tests"), Some(Level::Debug));
+        assert_eq!(filter.level("enso_frp::nodes_extra"), None);
+        assert_eq!(filter.level("ide_view_graph_editor::component::node"), Some(Level::Trace));
+        assert_eq!(filter.level("ide_view_graph_editor"), Some(DEFAULT_LEVEL));
+        assert_eq!(filter.level("enso_frp_ext"), Some(DEFAULT_LEVEL));
+        assert!(filter.is_enabled(Level::Error, "enso_text"));
+        assert!(!filter.is_enabled(Level::Info, "enso_text"));
+    }
+}
@@ -1,4 +1,18 @@
 //! High-performance logging library.
+//!
+//! The log messages are emitted with the level macros, like [`warn!`] or [`debug_span!`]. Each
+//! message can carry structured key/value fields, given before the message and separated with a
+//! semicolon:
+//!
+//! ```text
+//! warn!(node = node_id, port = port_id; "Cannot connect the ports.");
+//! ```
+//!
+//! The messages of the levels less severe than [`MAX_ENABLED_LEVEL`] are compiled out. All the
+//! other messages are filtered at runtime by the [`Filter`] set with [`set_filter`], which can be
+//! parsed from env-style directives, like `graph_editor=debug,frp=warn`. The messages passing the
+//! filter are passed to all the [`Sink`]s registered in the current thread, see the [`sink`] module
+//! to learn more.
 
 // === Features ===
 #![feature(local_key_cell_methods)]
@@ -16,6 +30,17 @@
 #![warn(trivial_numeric_casts)]
 #![warn(unused_import_braces)]
 
+pub mod filter;
+pub mod record;
+pub mod sink;
+
+pub use filter::set_filter;
+pub use filter::Filter;
+pub use record::Record;
+pub use sink::add_sink;
+pub use sink::remove_sink;
+pub use sink::Sink;
+
 
 
 enso_logging_macros::define_log_levels![Error, Warn, Info, Debug, Trace];
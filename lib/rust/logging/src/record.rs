@@ -0,0 +1,198 @@
+//! Log records, describing the log messages passed to the [`crate::Sink`]s.
+
+use crate::Level;
+
+use std::fmt;
+use std::fmt::Display;
+
+
+
+// =============
+// === Field ===
+// =============
+
+/// A structured key/value field of a log message.
+pub type Field<'a> = (&'static str, &'a dyn Display);
+
+/// Create a [`Field`]. Used by the logging macros.
+pub fn field<'a>(key: &'static str, value: &'a dyn Display) -> Field<'a> {
+    (key, value)
+}
+
+
+
+// ==============
+// === Record ===
+// ==============
+
+/// A single log message, together with its location in the source code.
+#[derive(Clone, Copy)]
+pub struct Record<'a> {
+    /// The level of the message.
+    pub level:       Level,
+    /// The path of the module the message was emitted from.
+    pub module_path: &'static str,
+    /// The source file the message was emitted from.
+    pub file:        &'static str,
+    /// The line of the source file the message was emitted from.
+    pub line:        u32,
+    /// The message.
+    pub message:     fmt::Arguments<'a>,
+    /// The structured fields of the message.
+    pub fields:      &'a [Field<'a>],
+}
+
+/// Formats the record the same way as the console does, like `[WARN] src/lib.rs:10 Message.`,
+/// followed by the fields in the `key=value` format.
+impl Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}:{} {}", self.level.tag(), self.file, self.line, self.message)?;
+        for (key, value) in self.fields {
+            write!(f, " {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<_> =
+            self.fields.iter().map(|(key, value)| (key, value.to_string())).collect();
+        f.debug_struct("Record")
+            .field("level", &self.level)
+            .field("module_path", &self.module_path)
+            .field("file", &self.file)
+            .field("line", &self.line)
+            .field("message", &self.message)
+            .field("fields", &fields)
+            .finish()
+    }
+}
+
+impl Record<'_> {
+    /// Format the record as a single-line JSON object, like
+    /// `{"level":"warn","module":"crate::module","file":"src/lib.rs","line":10,"message":"Message."
+    /// , "fields":{"key":"value"}}`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push('{');
+        self.push_json_properties(&mut out);
+        out.push('}');
+        out
+    }
+
+    /// Push the properties of the JSON object returned by [`Self::to_json`], without the braces.
+    pub(crate) fn push_json_properties(&self, out: &mut String) {
+        out.push_str(r#""level":"#);
+        push_json_string(out, self.level.name());
+        out.push_str(r#","module":"#);
+        push_json_string(out, self.module_path);
+        out.push_str(r#","file":"#);
+        push_json_string(out, self.file);
+        out.push_str(&format!(r#","line":{}"#, self.line));
+        out.push_str(r#","message":"#);
+        push_json_string(out, &self.message.to_string());
+        out.push_str(r#","fields":{"#);
+        for (index, (key, value)) in self.fields.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            push_json_string(out, key);
+            out.push(':');
+            push_json_string(out, &value.to_string());
+        }
+        out.push('}');
+    }
+}
+
+/// Quote and escape the string to be used in JSON.
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for char in s.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+
+
+// ==================
+// === SpanRecord ===
+// ==================
+
+/// The description of a log span, created when the span is enabled. In contrast to [`Record`], it
+/// owns the message and the fields, as the span may be entered long after it was created.
+#[derive(Clone, Debug)]
+pub struct SpanRecord {
+    /// The level of the span.
+    pub level:       Level,
+    /// The path of the module the span was created in.
+    pub module_path: &'static str,
+    /// The source file the span was created in.
+    pub file:        &'static str,
+    /// The line of the source file the span was created in.
+    pub line:        u32,
+    /// The message describing the span.
+    pub message:     String,
+    /// The structured fields of the span, with the values already formatted.
+    pub fields:      Vec<(&'static str, String)>,
+}
+
+impl SpanRecord {
+    /// Run the function with the [`Record`] describing this span.
+    pub fn with_record<T>(&self, f: impl FnOnce(&Record) -> T) -> T {
+        let fields: Vec<Field> =
+            self.fields.iter().map(|(key, value)| field(*key, value)).collect();
+        f(&Record {
+            level:       self.level,
+            module_path: self.module_path,
+            file:        self.file,
+            line:        self.line,
+            message:     format_args!("{}", self.message),
+            fields:      &fields,
+        })
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting() {
+        let id = 5;
+        let name = "a \"quoted\"\nname";
+        let fields = [field("id", &id), field("name", &name)];
+        let check = |record: &Record| {
+            let expected = "[WARN] src/nodes.rs:12 Node 5 dropped. id=5 name=a \"quoted\"\nname";
+            assert_eq!(record.to_string(), expected);
+            let expected = concat!(
+                r#"{"level":"warn","module":"enso_frp::nodes","file":"src/nodes.rs","line":12,"#,
+                r#""message":"Node 5 dropped.","fields":{"id":"5","name":"a \"quoted\"\nname"}}"#
+            );
+            assert_eq!(record.to_json(), expected);
+        };
+        check(&Record {
+            level:       Level::Warn,
+            module_path: "enso_frp::nodes",
+            file:        "src/nodes.rs",
+            line:        12,
+            message:     format_args!("Node {} dropped.", id),
+            fields:      &fields,
+        });
+    }
+}
@@ -0,0 +1,366 @@
+//! Destinations of the log messages.
+//!
+//! Every message passing the [`crate::Filter`] is passed to all the [`Sink`]s registered in the
+//! current thread. Initially, only the [`ConsoleSink`] is registered. The following sinks are
+//! provided:
+//! - [`ConsoleSink`], printing the messages to the Web Console, or to the standard output in
+//!   non-wasm environments.
+//! - [`JsonLinesSink`], writing every message as a single-line JSON object.
+//! - [`RingBufferSink`], keeping the most recent messages in memory, so they can be attached to a
+//!   crash report:
+//!
+//! ```text
+//! let recent_logs = RingBufferSink::new(1000);
+//! enso_logging::add_sink(recent_logs.clone());
+//! ...
+//! report.attach("logs", recent_logs.dump());
+//! ```
+
+use crate::record::Record;
+use crate::record::SpanRecord;
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::rc::Rc;
+
+
+
+// ============
+// === Sink ===
+// ============
+
+/// A destination of the log messages. The `depth` passed to the methods is the number of the spans
+/// entered when the message was emitted, and can be used to indent the messages.
+pub trait Sink {
+    /// Handle a log event.
+    fn event(&self, record: &Record, depth: usize);
+
+    /// Handle entering a span. By default, it is handled the same way as an event.
+    fn enter(&self, record: &Record, depth: usize) {
+        self.event(record, depth)
+    }
+
+    /// Handle exiting a span. The `depth` is the same as the one the span was entered with.
+    fn exit(&self, _record: &Record, _depth: usize) {}
+}
+
+
+// === Shared sinks ===
+
+impl<S: Sink + ?Sized> Sink for Rc<S> {
+    fn event(&self, record: &Record, depth: usize) {
+        (**self).event(record, depth)
+    }
+
+    fn enter(&self, record: &Record, depth: usize) {
+        (**self).enter(record, depth)
+    }
+
+    fn exit(&self, record: &Record, depth: usize) {
+        (**self).exit(record, depth)
+    }
+}
+
+
+
+// ================
+// === Registry ===
+// ================
+
+/// Identifier of a registered sink, used to remove it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SinkId(usize);
+
+/// The identifier of the [`ConsoleSink`] registered by default.
+pub const CONSOLE_SINK: SinkId = SinkId(0);
+
+type Sinks = Rc<Vec<(SinkId, Rc<dyn Sink>)>>;
+
+thread_local! {
+    /// The sinks are replaced rather than modified, so the sinks can register other sinks or emit
+    /// messages while handling a message.
+    static SINKS: RefCell<Sinks> = RefCell::new(default_sinks());
+    static NEXT_SINK_ID: Cell<usize> = Cell::new(CONSOLE_SINK.0 + 1);
+    static SPAN_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+fn default_sinks() -> Sinks {
+    let console: Rc<dyn Sink> = Rc::new(ConsoleSink);
+    Rc::new(vec![(CONSOLE_SINK, console)])
+}
+
+/// Register a sink in the current thread.
+pub fn add_sink(sink: impl Sink + 'static) -> SinkId {
+    let id = SinkId(NEXT_SINK_ID.replace(NEXT_SINK_ID.get() + 1));
+    let sink: Rc<dyn Sink> = Rc::new(sink);
+    SINKS.with_borrow_mut(|sinks| {
+        let mut new_sinks = sinks.to_vec();
+        new_sinks.push((id, sink));
+        *sinks = Rc::new(new_sinks);
+    });
+    id
+}
+
+/// Unregister a sink from the current thread. The default [`ConsoleSink`] can be removed by passing
+/// the [`CONSOLE_SINK`] id.
+pub fn remove_sink(id: SinkId) {
+    SINKS.with_borrow_mut(|sinks| {
+        let new_sinks = sinks.iter().filter(|(sink_id, _)| *sink_id != id).cloned().collect();
+        *sinks = Rc::new(new_sinks);
+    });
+}
+
+fn sinks() -> Sinks {
+    SINKS.with_borrow(|sinks| sinks.clone())
+}
+
+/// Pass the event to all the registered sinks. Used by the logging macros.
+pub fn emit(record: &Record) {
+    let depth = SPAN_DEPTH.get();
+    for (_, sink) in sinks().iter() {
+        sink.event(record, depth);
+    }
+}
+
+/// Pass the span entry to all the registered sinks. Used by the logging macros.
+pub fn enter(span: &SpanRecord) {
+    let depth = SPAN_DEPTH.get();
+    span.with_record(|record| {
+        for (_, sink) in sinks().iter() {
+            sink.enter(record, depth);
+        }
+    });
+    SPAN_DEPTH.set(depth + 1);
+}
+
+/// Pass the span exit to all the registered sinks. Used by the logging macros.
+pub fn exit(span: &SpanRecord) {
+    let depth = SPAN_DEPTH.get().saturating_sub(1);
+    SPAN_DEPTH.set(depth);
+    span.with_record(|record| {
+        for (_, sink) in sinks().iter() {
+            sink.exit(record, depth);
+        }
+    });
+}
+
+
+
+// ===================
+// === ConsoleSink ===
+// ===================
+
+/// A [`Sink`] printing the messages to the Web Console, or to the standard output in non-wasm
+/// environments. The spans are displayed as groups in the Web Console, collapsed if their level is
+/// less severe than [`crate::MAX_UNCOLLAPSED_LEVEL`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConsoleSink;
+
+#[cfg(target_arch = "wasm32")]
+impl Sink for ConsoleSink {
+    fn event(&self, record: &Record, _depth: usize) {
+        use crate::Level;
+        let message = wasm_bindgen::JsValue::from(record.to_string());
+        match record.level {
+            Level::Error => web_sys::console::error_1(&message),
+            Level::Warn => web_sys::console::warn_1(&message),
+            Level::Info => web_sys::console::info_1(&message),
+            Level::Debug | Level::Trace => web_sys::console::debug_1(&message),
+        }
+    }
+
+    fn enter(&self, record: &Record, _depth: usize) {
+        let message = wasm_bindgen::JsValue::from(record.to_string());
+        if record.level <= crate::MAX_UNCOLLAPSED_LEVEL {
+            web_sys::console::group_1(&message);
+        } else {
+            web_sys::console::group_collapsed_1(&message);
+        }
+    }
+
+    fn exit(&self, _record: &Record, _depth: usize) {
+        web_sys::console::group_end();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Sink for ConsoleSink {
+    fn event(&self, record: &Record, depth: usize) {
+        println!("{:indent$}{record}", "", indent = depth * 4);
+    }
+}
+
+
+
+// =====================
+// === JsonLinesSink ===
+// =====================
+
+/// A [`Sink`] writing every event and span entry as a single-line JSON object, in the format
+/// described in [`Record::to_json`], extended with the `kind` (`event` or `span`) and `depth`
+/// properties. The write errors are ignored.
+#[derive(Debug, Default)]
+pub struct JsonLinesSink<W> {
+    writer: RefCell<W>,
+}
+
+impl<W: io::Write> JsonLinesSink<W> {
+    /// Constructor.
+    pub fn new(writer: W) -> Self {
+        Self { writer: RefCell::new(writer) }
+    }
+
+    /// Consume the sink, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    fn write(&self, record: &Record, kind: &str, depth: usize) {
+        let mut line = String::from('{');
+        record.push_json_properties(&mut line);
+        line.push_str(&format!(r#","kind":"{kind}","depth":{depth}}}"#));
+        line.push('\n');
+        let _ = self.writer.borrow_mut().write_all(line.as_bytes());
+    }
+}
+
+impl<W: io::Write> Sink for JsonLinesSink<W> {
+    fn event(&self, record: &Record, depth: usize) {
+        self.write(record, "event", depth);
+    }
+
+    fn enter(&self, record: &Record, depth: usize) {
+        self.write(record, "span", depth);
+    }
+}
+
+
+
+// ======================
+// === RingBufferSink ===
+// ======================
+
+/// A [`Sink`] keeping the most recent messages in memory, formatted the same way as the console
+/// does. Cloning the sink creates a new reference to the same buffer, so a clone can be registered
+/// while the original is used to read the messages.
+#[derive(Clone, Debug)]
+pub struct RingBufferSink {
+    data: Rc<RingBufferData>,
+}
+
+#[derive(Debug)]
+struct RingBufferData {
+    capacity: usize,
+    lines:    RefCell<VecDeque<String>>,
+}
+
+impl RingBufferSink {
+    /// Constructor. Only the `capacity` most recent messages are kept.
+    pub fn new(capacity: usize) -> Self {
+        let lines = RefCell::new(VecDeque::with_capacity(capacity));
+        Self { data: Rc::new(RingBufferData { capacity, lines }) }
+    }
+
+    /// The kept messages, from the oldest to the most recent one.
+    pub fn lines(&self) -> Vec<String> {
+        self.data.lines.borrow().iter().cloned().collect()
+    }
+
+    /// All the kept messages, one per line.
+    pub fn dump(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    /// Remove all the kept messages.
+    pub fn clear(&self) {
+        self.data.lines.borrow_mut().clear();
+    }
+}
+
+impl Sink for RingBufferSink {
+    fn event(&self, record: &Record, depth: usize) {
+        if self.data.capacity > 0 {
+            let mut lines = self.data.lines.borrow_mut();
+            if lines.len() == self.data.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(format!("{:indent$}{record}", "", indent = depth * 4));
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::filter;
+    use crate::prelude::*;
+    use crate::Filter;
+
+    #[test]
+    fn dispatching() {
+        remove_sink(CONSOLE_SINK);
+        let recent = RingBufferSink::new(3);
+        let recent_id = add_sink(recent.clone());
+        let json = Rc::new(JsonLinesSink::new(Vec::new()));
+        let json_id = add_sink(json.clone());
+        filter::set_filter("warn,logging::sink=debug".parse().unwrap());
+        crate::trace!("Disabled.");
+        crate::debug_span!(node = 1; "Updating.").in_scope(|| {
+            crate::warn!(port = "in", count = 2; "Port {} disconnected.", 3);
+        });
+        filter::set_filter(Filter::default());
+        crate::info!("Disabled.");
+        let lines = recent.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("[DEBUG] "));
+        assert!(lines[0].ends_with(" Updating. node=1"));
+        assert!(lines[1].starts_with("    [WARN] "));
+        assert!(lines[1].ends_with(" Port 3 disconnected. port=in count=2"));
+        remove_sink(recent_id);
+        crate::error!("Not recorded.");
+        assert_eq!(recent.lines().len(), 2);
+        remove_sink(json_id);
+        let json = Rc::try_unwrap(json).unwrap().into_inner();
+        let json = String::from_utf8(json).unwrap();
+        let json_lines: Vec<_> = json.lines().collect();
+        assert_eq!(json_lines.len(), 3);
+        let module = r#"{"level":"debug","module":"enso_logging::sink::tests","#;
+        assert!(json_lines[0].starts_with(module));
+        assert!(json_lines[0].ends_with(r#""fields":{"node":"1"},"kind":"span","depth":0}"#));
+        assert!(json_lines[1].ends_with(r#""kind":"event","depth":1}"#));
+        assert!(json_lines[2].contains(r#""level":"error""#));
+    }
+
+    #[test]
+    fn ring_buffer_capacity() {
+        let sink = RingBufferSink::new(2);
+        for index in 0..3 {
+            sink.event(
+                &Record {
+                    level:       crate::Level::Info,
+                    module_path: module_path!(),
+                    file:        file!(),
+                    line:        line!(),
+                    message:     format_args!("Message {index}."),
+                    fields:      &[],
+                },
+                0,
+            );
+        }
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Message 1."));
+        assert!(lines[1].ends_with("Message 2."));
+        sink.clear();
+        assert!(sink.dump().is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Check parser invariants on randomly generated inputs.
+
+// === Features ===
+#![feature(exact_size_is_empty)]
+#![feature(let_chains)]
+#![feature(if_let_guard)]
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+
+use enso_parser_debug::fuzz;
+
+
+
+// ============
+// === Fuzz ===
+// ============
+
+const USAGE: &str = "Usage: fuzz [--seed <N>] [--iterations <N>] [--mode tokens|programs]";
+
+/// Generate inputs and check the parser's invariants on them. Each failure is printed with the
+/// seed that reproduces it, and the process exits with status 1 if there were any failures.
+pub fn main() {
+    let mut seed = 0;
+    let mut iterations = 1000;
+    let mut modes = vec![fuzz::Mode::Tokens, fuzz::Mode::Programs];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(USAGE));
+        match arg.as_str() {
+            "--seed" => seed = value().parse().unwrap_or_else(|e| fail(e)),
+            "--iterations" => iterations = value().parse().unwrap_or_else(|e| fail(e)),
+            "--mode" =>
+                modes = match value().as_str() {
+                    "tokens" => vec![fuzz::Mode::Tokens],
+                    "programs" => vec![fuzz::Mode::Programs],
+                    _ => fail(USAGE),
+                },
+            _ => fail(USAGE),
+        }
+    }
+    // Panics are reported as failures; don't print them as they are caught.
+    std::panic::set_hook(Box::new(|_| {}));
+    let mut failed = false;
+    for mode in modes {
+        let failures = fuzz::run(mode, seed, iterations);
+        println!("{mode:?}: {} of {iterations} cases failed.", failures.len());
+        for failure in &failures {
+            println!("seed {}: {}", failure.seed, failure.problem);
+            println!("    input: {:?}", failure.input);
+        }
+        failed |= !failures.is_empty();
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(2)
+}
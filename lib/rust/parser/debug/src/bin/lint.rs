@@ -0,0 +1,91 @@
+//! Check Enso source files with the linter from the command line, see [`enso_parser::lint`].
+
+// === Features ===
+#![feature(exact_size_is_empty)]
+#![feature(let_chains)]
+#![feature(if_let_guard)]
+// === Standard Linter Configuration ===
+#![deny(non_ascii_idents)]
+#![warn(unsafe_code)]
+#![allow(clippy::bool_to_int_with_if)]
+#![allow(clippy::let_and_return)]
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+
+
+
+// ============
+// === Lint ===
+// ============
+
+const USAGE: &str = "Usage: lint [--json] [--config <FILE>] [--rules] <FILE>...";
+
+/// Check the given Enso source files. The process exits with status 1 if any finding is an error.
+pub fn main() {
+    let mut json = false;
+    let mut config = None;
+    let mut paths = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--config" => config = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--rules" => {
+                for rule in enso_parser::lint::Linter::new().rules() {
+                    println!("{}: {}", rule.name(), rule.description());
+                }
+                return;
+            }
+            _ if arg.starts_with("--") => fail(USAGE),
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        fail(USAGE);
+    }
+    let config = match config {
+        Some(path) => {
+            let json =
+                std::fs::read_to_string(&path).unwrap_or_else(|e| fail(format!("{path}: {e}")));
+            enso_parser::lint::Config::parse(&json).unwrap_or_else(|e| fail(format!("{path}: {e}")))
+        }
+        None => Default::default(),
+    };
+    let linter = enso_parser::lint::Linter::with_config(&config).unwrap_or_else(|e| fail(e));
+    let parser = enso_parser::Parser::new();
+    let mut has_errors = false;
+    let mut reports = vec![];
+    for path in paths {
+        let input = std::fs::read_to_string(&path).unwrap_or_else(|e| fail(format!("{path}: {e}")));
+        let (code, _metadata) = enso_parser::metadata::extract(&input);
+        let tree = parser.run(code);
+        let report = linter.run(&tree, code);
+        has_errors |= report.has_errors();
+        if json {
+            reports.push(serde_json::json!({ "path": path, "findings": report }));
+        } else {
+            print!("{}", report.to_human(&path, code));
+        }
+    }
+    if json {
+        println!("{}", serde_json::Value::Array(reports));
+    }
+    if has_errors {
+        std::process::exit(1);
+    }
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(2)
+}
@@ -0,0 +1,451 @@
+//! Randomized testing of the parser.
+//!
+//! Inputs are generated either as random sequences of token-like fragments, which are mostly
+//! invalid Enso code, or as random programs generated from a simplified grammar of the language.
+//! For every input, [`check`] verifies the invariants that must hold for any input:
+//! - The lexer and parser do not panic.
+//! - The lexer does not report an internal error, and the tree contains no internal-error nodes.
+//! - The code of the tokens, and the code of the tree, is exactly the input.
+//! - The tree can be serialized, and deserialized into a tree with the same structure.
+//!
+//! Each case is generated from its own seed, so that a failure can be reproduced by running the
+//! single case again. Failing inputs are reduced with [`minimize`] before they are reported.
+
+use enso_parser::syntax::tree;
+use enso_parser::Parser;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::panic;
+
+
+
+// ==================
+// === Invariants ===
+// ==================
+
+/// Check that the parser satisfies its invariants for the given input. Returns a description of
+/// the first violated invariant.
+pub fn check(parser: &Parser, input: &str) -> Result<(), String> {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| check_invariants(parser, input)));
+    result.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(format!("Panic: {message}"))
+    })
+}
+
+fn check_invariants(parser: &Parser, input: &str) -> Result<(), String> {
+    let tokens = enso_parser::lexer::run(input);
+    if let Some(error) = &tokens.internal_error {
+        return Err(format!("Lexer internal error: {error}"));
+    }
+    let token_code: String = tokens
+        .value
+        .iter()
+        .map(|token| format!("{}{}", token.left_offset.code, token.code))
+        .collect();
+    if token_code != input {
+        return Err(format!("The code of the tokens differs from the input: {token_code:?}"));
+    }
+    let tree = parser.run(input);
+    let code = tree.code();
+    if code != input {
+        return Err(format!("The code of the tree differs from the input: {code:?}"));
+    }
+    let internal_error = tree.collect_vec_ref().into_iter().find_map(|tree| match &*tree.variant {
+        tree::Variant::Invalid(invalid) if invalid.error.message.starts_with("Internal error") =>
+            Some(invalid.error.message.to_string()),
+        _ => None,
+    });
+    if let Some(error) = internal_error {
+        return Err(format!("Parser internal error: {error}"));
+    }
+    let serialized = enso_parser::serialization::serialize_tree(&tree)
+        .map_err(|e| format!("Serialization failed: {e}"))?;
+    let deserialized = enso_parser::serialization::deserialize_tree(&serialized)
+        .map_err(|e| format!("Deserialization failed: {e}"))?;
+    if shape(&deserialized) != shape(&tree) {
+        return Err("The deserialized tree differs from the original tree.".into());
+    }
+    Ok(())
+}
+
+/// The variant and length of each node of the tree, in pre-order. Deserialization does not restore
+/// the code of the tree, so the code is not compared.
+fn shape(tree: &enso_parser::syntax::Tree) -> Vec<(tree::VariantMarker, usize)> {
+    let nodes = tree.collect_vec_ref().into_iter();
+    nodes.map(|tree| (tree.variant.marker(), tree.span.code_length.utf8_bytes())).collect()
+}
+
+
+
+// ==============
+// === Inputs ===
+// ==============
+
+/// Fragments of Enso code from which random token sequences are built.
+const FRAGMENTS: &[&str] = &[
+    "a",
+    "foo",
+    "Bar",
+    "_",
+    "x1",
+    "self",
+    "Nothing",
+    "0",
+    "12",
+    "3.5",
+    "0x1F",
+    "1_000",
+    "+",
+    "-",
+    "*",
+    "/",
+    "=",
+    "==",
+    "!=",
+    "->",
+    "<-",
+    "<|",
+    "|>",
+    ".",
+    "..",
+    ",",
+    ":",
+    "~",
+    "@",
+    "&&",
+    "||",
+    "\\",
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    "'",
+    "\"",
+    "'''",
+    "\"\"\"",
+    "`",
+    "\\n",
+    "\\u{41}",
+    "\\q",
+    "#",
+    "##",
+    " ",
+    "  ",
+    "    ",
+    "\t",
+    "\n",
+    "\n",
+    "\r\n",
+    "\n    ",
+    "\n  ",
+    "if",
+    "then",
+    "else",
+    "case",
+    "of",
+    "type",
+    "import",
+    "from",
+    "all",
+    "export",
+    "as",
+    "hiding",
+    "polyglot",
+    "java",
+    "foreign",
+    "js",
+    "private",
+    "@Builtin_Type",
+    "λ",
+    "é",
+    "😀",
+];
+
+/// Return a random sequence of `len` fragments of Enso code.
+pub fn random_tokens(rng: &mut impl Rng, len: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..len {
+        // Occasionally, insert an arbitrary character.
+        if rng.gen_ratio(1, 50) {
+            out.push(rng.gen::<char>());
+        } else {
+            out.push_str(FRAGMENTS.choose(rng).unwrap());
+        }
+    }
+    out
+}
+
+/// Generates random programs from a simplified grammar of Enso.
+#[derive(Debug)]
+pub struct ProgramGenerator<'r, R> {
+    rng:       &'r mut R,
+    max_depth: usize,
+}
+
+const INDENT: &str = "    ";
+const NAMES: &[&str] = &["a", "b", "x", "value", "self", "my_list", "count"];
+const TYPES: &[&str] = &["Integer", "Text", "Vector", "Maybe", "My_Type"];
+const OPERATORS: &[&str] = &["+", "-", "*", "/", "==", "<", ">=", "&&", "||", "<|", "|>", "%"];
+
+impl<'r, R: Rng> ProgramGenerator<'r, R> {
+    /// Constructor. `max_depth` limits the nesting of expressions.
+    pub fn new(rng: &'r mut R, max_depth: usize) -> Self {
+        Self { rng, max_depth }
+    }
+
+    /// Generate a module.
+    pub fn module(&mut self) -> String {
+        let mut out = String::new();
+        for _ in 0..self.rng.gen_range(0..3) {
+            out.push_str(self.import());
+            out.push('\n');
+        }
+        for _ in 0..self.rng.gen_range(1..6) {
+            if self.rng.gen_ratio(1, 4) {
+                out.push('\n');
+            }
+            match self.rng.gen_range(0..6) {
+                0 => out.push_str(&self.type_definition()),
+                1 => out.push_str("## A documented definition.\n"),
+                2 => out.push_str("# A comment.\n"),
+                _ => {}
+            }
+            out.push_str(&self.function(0));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn import(&mut self) -> &'static str {
+        *[
+            "import Standard.Base",
+            "from Standard.Base import all",
+            "from Standard.Table import Table, Column",
+            "import Standard.Base.Data.Vector as Vec",
+            "polyglot java import java.lang.Long",
+        ]
+        .choose(self.rng)
+        .unwrap()
+    }
+
+    fn name(&mut self) -> &'static str {
+        NAMES.choose(self.rng).unwrap()
+    }
+
+    fn type_name(&mut self) -> &'static str {
+        TYPES.choose(self.rng).unwrap()
+    }
+
+    fn type_definition(&mut self) -> String {
+        let mut out = format!("type {}", self.type_name());
+        if self.rng.gen() {
+            out.push_str(" a");
+        }
+        out.push('\n');
+        for _ in 0..self.rng.gen_range(1..4) {
+            out.push_str(INDENT);
+            if self.rng.gen() {
+                out.push_str(&format!("{} {}", self.type_name(), self.name()));
+                if self.rng.gen() {
+                    out.push_str(&format!(" ({}:{})", self.name(), self.type_name()));
+                }
+            } else {
+                out.push_str(&self.function(1));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Generate a function definition, indented by the given number of levels.
+    fn function(&mut self, indent: usize) -> String {
+        let mut out = self.name().to_owned();
+        for _ in 0..self.rng.gen_range(0..3) {
+            match self.rng.gen_range(0..4) {
+                0 => out.push_str(&format!(" ({}:{})", self.name(), self.type_name())),
+                1 => out.push_str(&format!(" ({}=0)", self.name())),
+                2 => out.push_str(&format!(" ~{}", self.name())),
+                _ => out.push_str(&format!(" {}", self.name())),
+            }
+        }
+        out.push_str(" =");
+        if self.rng.gen() {
+            out.push(' ');
+            out.push_str(&self.expression(0, indent));
+        } else {
+            out.push_str(&self.block(indent + 1));
+        }
+        out
+    }
+
+    /// Generate the lines of a block indented by the given number of levels, each preceded by a
+    /// newline.
+    fn block(&mut self, indent: usize) -> String {
+        let mut out = String::new();
+        let lines = self.rng.gen_range(1..4);
+        for i in 0..lines {
+            out.push('\n');
+            out.push_str(&INDENT.repeat(indent));
+            let is_last = i + 1 == lines;
+            if !is_last && self.rng.gen() {
+                out.push_str(&format!("{} = ", self.name()));
+            }
+            out.push_str(&self.expression(0, indent));
+        }
+        out
+    }
+
+    /// Generate an expression at the given nesting depth, on a line indented by `indent` levels.
+    fn expression(&mut self, depth: usize, indent: usize) -> String {
+        if depth >= self.max_depth {
+            return self.atom();
+        }
+        let depth = depth + 1;
+        match self.rng.gen_range(0..14) {
+            0 | 1 => self.atom(),
+            2 => format!("{} {}", self.expression(depth, indent), self.atom()),
+            3 => {
+                let opr = OPERATORS.choose(self.rng).unwrap();
+                let lhs = self.expression(depth, indent);
+                let rhs = self.expression(depth, indent);
+                format!("{lhs} {opr} {rhs}")
+            }
+            4 => format!("{}{}{}", self.atom(), OPERATORS.choose(self.rng).unwrap(), self.atom()),
+            5 => format!("({})", self.expression(depth, indent)),
+            6 => format!("{}.{}", self.atom(), self.name()),
+            7 => {
+                let items: Vec<_> = (0..self.rng.gen_range(0..4)).map(|_| self.atom()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            8 => format!("{}-> {}", self.name(), self.expression(depth, indent)),
+            9 => {
+                let condition = self.expression(depth, indent);
+                let then = self.expression(depth, indent);
+                let otherwise = self.expression(depth, indent);
+                format!("if {condition} then {then} else {otherwise}")
+            }
+            10 => {
+                let mut out = format!("case {} of", self.atom());
+                for _ in 0..self.rng.gen_range(1..4) {
+                    out.push('\n');
+                    out.push_str(&INDENT.repeat(indent + 1));
+                    let pattern = match self.rng.gen_range(0..3) {
+                        0 => "_".to_owned(),
+                        1 => format!("{} {}", self.type_name(), self.name()),
+                        _ => self.atom(),
+                    };
+                    out.push_str(&format!("{pattern} -> {}", self.atom()));
+                }
+                out
+            }
+            11 => format!("-{}", self.atom()),
+            12 => self.text(depth, indent),
+            _ => format!("{} ({}={})", self.name(), self.name(), self.atom()),
+        }
+    }
+
+    fn atom(&mut self) -> String {
+        match self.rng.gen_range(0..5) {
+            0 => self.rng.gen_range(0..1000).to_string(),
+            1 => format!("{}.{}", self.rng.gen_range(0..100), self.rng.gen_range(0..100)),
+            2 => self.type_name().to_owned(),
+            3 => "_".to_owned(),
+            _ => self.name().to_owned(),
+        }
+    }
+
+    fn text(&mut self, depth: usize, indent: usize) -> String {
+        match self.rng.gen_range(0..4) {
+            0 => "\"raw text\"".to_owned(),
+            1 => format!("'text with `{}` splice'", self.expression(depth, indent)),
+            2 => "'escapes \\n \\t \\u{1F600}'".to_owned(),
+            _ => {
+                let line_indent = INDENT.repeat(indent + 1);
+                format!("'''\n{line_indent}Text block\n{line_indent}  with two lines")
+            }
+        }
+    }
+}
+
+
+
+// ==============
+// === Runner ===
+// ==============
+
+/// The kind of inputs to generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Random sequences of token-like fragments.
+    Tokens,
+    /// Random programs generated from a grammar.
+    Programs,
+}
+
+/// An input for which an invariant does not hold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// The seed of the case that produced the input.
+    pub seed:    u64,
+    /// The input, reduced to a minimal input violating an invariant.
+    pub input:   String,
+    /// Description of the violated invariant.
+    pub problem: String,
+}
+
+/// Generate the input of the case with the given seed.
+pub fn generate(mode: Mode, seed: u64) -> String {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    match mode {
+        Mode::Tokens => {
+            let len = rng.gen_range(0..40);
+            random_tokens(&mut rng, len)
+        }
+        Mode::Programs => ProgramGenerator::new(&mut rng, 4).module(),
+    }
+}
+
+/// Check the cases with seeds `first_seed .. first_seed + cases`, returning the failures.
+pub fn run(mode: Mode, first_seed: u64, cases: u64) -> Vec<Failure> {
+    let parser = Parser::new();
+    let mut failures = vec![];
+    for seed in first_seed..first_seed + cases {
+        let input = generate(mode, seed);
+        if check(&parser, &input).is_err() {
+            let input = minimize(&input, |input| check(&parser, input).is_err());
+            let problem = check(&parser, &input).unwrap_err();
+            failures.push(Failure { seed, input, problem });
+        }
+    }
+    failures
+}
+
+/// Reduce an input for which `fails` returns true, by removing parts of it as long as the result
+/// still fails.
+pub fn minimize(input: &str, fails: impl Fn(&str) -> bool) -> String {
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut chunk = chars.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < chars.len() {
+            let end = (start + chunk).min(chars.len());
+            let candidate: String = chars[..start].iter().chain(&chars[end..]).collect();
+            if fails(&candidate) {
+                chars.drain(start..end);
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+    chars.into_iter().collect()
+}
@@ -28,6 +28,13 @@ use lexpr::Value;
 use std::collections::HashSet;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod fuzz;
+
+
 
 // =====================
 // === S-expressions ===
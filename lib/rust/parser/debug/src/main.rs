@@ -1,4 +1,7 @@
 //! Show debug-representation of AST of input sources.
+//!
+//! With the `--format` argument, print the input in canonical format instead. With the
+//! `--query <QUERY>` argument, print the items matching the query (see [`enso_parser::query`]).
 
 // === Features ===
 #![feature(exact_size_is_empty)]
@@ -32,7 +35,17 @@ fn main() {
     use std::io::Read;
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input).unwrap();
-    check_file("<stdin>", input.as_str());
+    match std::env::args().nth(1).as_deref() {
+        Some("--format") => format_file("<stdin>", input.as_str()),
+        Some("--query") => match std::env::args().nth(2) {
+            Some(query) => query_file("<stdin>", input.as_str(), &query),
+            None => {
+                eprintln!("Usage: --query <QUERY>");
+                std::process::exit(2);
+            }
+        },
+        _ => check_file("<stdin>", input.as_str()),
+    }
 }
 
 fn check_file(path: &str, mut code: &str) {
@@ -46,3 +59,41 @@ fn check_file(path: &str, mut code: &str) {
     let s_expr = enso_parser_debug::to_s_expr(&ast, code);
     println!("{s_expr}");
 }
+
+/// Print the input in canonical format. The metadata section, if present, is copied unchanged.
+fn format_file(path: &str, input: &str) {
+    let (code, metadata) = enso_parser::metadata::extract(input);
+    let parser = enso_parser::Parser::new();
+    let formatted = match enso_parser::formatter::format(&parser, code) {
+        Ok(formatted) => formatted,
+        Err(error) => {
+            eprintln!("{path}: {error}");
+            std::process::exit(1);
+        }
+    };
+    match metadata {
+        Some(_) => print!("{}{}", formatted.trim_end(), &input[code.len()..]),
+        None => print!("{formatted}"),
+    }
+}
+
+/// Print the items matching the query, with their captures.
+fn query_file(path: &str, input: &str, query: &str) {
+    let query = match enso_parser::query::Query::new(query) {
+        Ok(query) => query,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    let (code, _metadata) = enso_parser::metadata::extract(input);
+    let ast = enso_parser::Parser::new().run(code);
+    for m in query.matches(&ast) {
+        let span = m.span;
+        println!("{path}:{}..{}: pattern {}: {:?}", span.start, span.end, m.pattern, &code[span]);
+        for capture in m.captures {
+            let span = capture.span;
+            println!("    @{} {}..{}: {:?}", capture.name, span.start, span.end, &code[span]);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Check parser invariants on randomly generated inputs.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(non_ascii_idents)]
+#![deny(unconditional_recursion)]
+#![warn(unsafe_code)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+
+use enso_parser_debug::fuzz;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+const CASES: u64 = 200;
+
+#[test]
+fn random_tokens() {
+    let failures = fuzz::run(fuzz::Mode::Tokens, 0, CASES);
+    assert!(failures.is_empty(), "{failures:#?}");
+}
+
+#[test]
+fn random_programs() {
+    let failures = fuzz::run(fuzz::Mode::Programs, 0, CASES);
+    assert!(failures.is_empty(), "{failures:#?}");
+}
+
+#[test]
+fn generation_is_deterministic() {
+    for mode in [fuzz::Mode::Tokens, fuzz::Mode::Programs] {
+        for seed in 0..10 {
+            assert_eq!(fuzz::generate(mode, seed), fuzz::generate(mode, seed));
+        }
+    }
+}
+
+#[test]
+fn minimize() {
+    let minimized = fuzz::minimize("foo = bar (x + y) baz", |input| input.contains('+'));
+    assert_eq!(minimized, "+");
+    let minimized = fuzz::minimize("a ( b ) c", |input| input.contains('(') && input.contains(')'));
+    assert_eq!(minimized, "()");
+}
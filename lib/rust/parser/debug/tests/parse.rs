@@ -544,6 +544,25 @@ fn code_block_bad_indents2() {
     test(&code.join("\n"), expected);
 }
 
+#[test]
+fn code_block_bad_indents_recovery() {
+    // In error-recovery mode, the block is resynchronized to the indentation of `bar`, so `baz`
+    // is its child block.
+    let code = ["main =", "  foo", " bar", "  baz"];
+    let expected = block![
+        (Function (Ident main) #() "=" (BodyBlock #(
+         (Ident foo)
+         (ArgumentBlockApplication (Ident bar) #((Ident baz))))))
+    ];
+    test_with_recovery(&code.join("\n"), expected);
+    let code = ["main =", "  foo", " bar", "baz"];
+    let expected = block![
+        (Function (Ident main) #() "=" (BodyBlock #((Ident foo) (Ident bar))))
+        (Ident baz)
+    ];
+    test_with_recovery(&code.join("\n"), expected);
+}
+
 #[test]
 fn code_block_with_following_statement() {
     let code = ["main =", "    foo", "bar"];
@@ -1433,6 +1452,15 @@ fn test(code: &str, expect: lexpr::Value) {
     deserialized.unwrap();
 }
 
+/// Check that the given code parses to the AST represented by the given S-expression when parsed
+/// in error-recovery mode, in which the diagnostics are collected. See [`test`] to learn more.
+fn test_with_recovery(code: &str, expect: lexpr::Value) {
+    let (ast, _) = enso_parser::Parser::new().run_with_diagnostics(code);
+    let ast_s_expr = to_s_expr(&ast, code);
+    assert_eq!(ast_s_expr.to_string(), expect.to_string(), "{:?}", &ast);
+    assert_eq!(ast.code(), code, "{:?}", &ast);
+}
+
 /// Checks that an input contains an `Invalid` node somewhere.
 fn test_invalid(code: &str) {
     let ast = enso_parser::Parser::new().run(code);
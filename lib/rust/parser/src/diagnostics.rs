@@ -0,0 +1,433 @@
+//! Machine-readable list of the syntax errors found in a parsed module.
+//!
+//! The parser never fails: errors are represented in the [`syntax::Tree`] as [`Invalid`] nodes
+//! wrapping the offending subtrees, or as tokens whose content is not valid (for example an unknown
+//! escape sequence in a text literal). This module walks a tree and reports each error as a
+//! [`Diagnostic`] with a stable [`ErrorCode`], the byte range of the offending code, a message, and
+//! suggested fixes when a fix can be determined syntactically.
+//!
+//! [`Invalid`]: syntax::tree::Variant::Invalid
+
+use crate::prelude::*;
+
+use crate::macros;
+use crate::syntax;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::Variant;
+
+
+
+// ===================
+// === Diagnostics ===
+// ===================
+
+/// The diagnostics reported for a module, ordered by their position in the source code.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deref, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Return whether any diagnostic of [`Severity::Error`] was reported.
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Add a diagnostic.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    /// Add the diagnostics that are not already present, keeping the diagnostics ordered by their
+    /// position in the source code.
+    pub fn merge(&mut self, other: Diagnostics) {
+        for diagnostic in other {
+            let is_duplicate = self.items.iter().any(|existing| {
+                existing.code == diagnostic.code && existing.span == diagnostic.span
+            });
+            if !is_duplicate {
+                self.items.push(diagnostic);
+            }
+        }
+        self.items.sort_by_key(|diagnostic| diagnostic.span.start);
+    }
+
+    /// Return the diagnostics as a JSON array.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Diagnostics are always serializable.")
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+
+
+// ==================
+// === Diagnostic ===
+// ==================
+
+/// A problem found in the source code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Identifies the kind of problem.
+    pub code:        ErrorCode,
+    /// How serious the problem is.
+    pub severity:    Severity,
+    /// The byte range of the code the problem applies to.
+    pub span:        Range<usize>,
+    /// Human-readable description of the problem.
+    pub message:     String,
+    /// Edits that would fix the problem, if any could be determined.
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    /// Constructor. Creates an error with no suggestions.
+    pub fn error(code: ErrorCode, span: Range<usize>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self { code, severity: Severity::Error, span, message, suggestions: default() }
+    }
+
+    /// Constructor. Creates a warning with no suggestions.
+    pub fn warning(code: ErrorCode, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(code, span, message) }
+    }
+
+    /// Add a suggested fix.
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+}
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(missing_docs)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A fix-it: replacement of a range of the source code with new text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// Description of the fix, e.g. "insert `then`".
+    pub message:     String,
+    /// The byte range to be replaced. It is empty for insertions.
+    pub span:        Range<usize>,
+    /// The replacement text.
+    pub replacement: String,
+}
+
+impl Suggestion {
+    /// Constructor for a suggestion to insert text at the given position.
+    pub fn insert(position: usize, text: impl Into<String>) -> Self {
+        let replacement = text.into();
+        let message = format!("insert `{}`", replacement.trim());
+        Self { message, span: position..position, replacement }
+    }
+
+    /// Constructor for a suggestion to remove the code in the given range.
+    pub fn remove(span: Range<usize>, code: &str) -> Self {
+        let message = format!("remove `{code}`");
+        Self { message, span, replacement: default() }
+    }
+}
+
+
+
+// =================
+// === ErrorCode ===
+// =================
+
+/// Stable identifier of a kind of [`Diagnostic`]. It is serialized in kebab-case, e.g.
+/// `"unmatched-delimiter"`. The parser attaches the code to each [`Invalid`](Variant::Invalid)
+/// node when it is created, see [`tree::Error`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// The parser encountered a bug in its implementation.
+    InternalError,
+    /// An opening or closing delimiter without its counterpart, e.g. `(a b`.
+    UnmatchedDelimiter,
+    /// A single-line text literal missing its closing quote.
+    UnclosedText,
+    /// An interpolation in a text literal missing its closing `` ` ``.
+    UnclosedSplice,
+    /// An escape sequence that does not denote any character, e.g. `'\q'`.
+    InvalidEscape,
+    /// The beginning of a macro was found, but its required segments were not, e.g. `if a` with
+    /// no `then`.
+    IncompleteMacro,
+    /// Excess tokens found in a segment of a macro invocation.
+    UnexpectedMacroTokens,
+    /// Operators placed next to each other, e.g. `a + * b`.
+    MultipleOperators,
+    /// An operator used with missing operands, or in an invalid position.
+    InvalidOperatorUse,
+    /// Spacing that changes the meaning of an operator, e.g. `f -x` written as `f-x y`.
+    OperatorSpacing,
+    /// An identifier or qualified name was expected.
+    ExpectedIdentifier,
+    /// An expression was expected.
+    ExpectedExpression,
+    /// An invalid line in a `case` expression.
+    InvalidCase,
+    /// An invalid comma-delimited sequence.
+    MalformedSequence,
+    /// A token that cannot appear in the given context.
+    UnexpectedToken,
+    /// A line indented less than the block it belongs to, but more than the enclosing block.
+    InconsistentIndentation,
+    /// Any other syntax error.
+    Syntax,
+}
+
+
+
+// =================
+// === Collector ===
+// =================
+
+/// Collect the diagnostics for the given tree, which must be the result of parsing `code`. The
+/// macro definitions are used to suggest the missing segments of incomplete macro invocations.
+pub fn collect(
+    tree: &syntax::Tree,
+    code: &str,
+    macros: &macros::resolver::MacroMap,
+) -> Diagnostics {
+    let mut collector = Collector { code, macros, diagnostics: default() };
+    tree::visit_item_spans(tree, |item, span| collector.check_item(item, span));
+    collector.diagnostics
+}
+
+struct Collector<'c> {
+    code:        &'c str,
+    macros:      &'c macros::resolver::MacroMap,
+    diagnostics: Diagnostics,
+}
+
+impl<'c> Collector<'c> {
+    fn check_item(&mut self, item: item::Ref, span: Range<usize>) {
+        match item {
+            item::Ref::Tree(tree) => self.check_tree(tree, span),
+            item::Ref::Token(token) =>
+                if let token::Variant::TextEscape(token::variant::TextEscape { value: None }) =
+                    token.data
+                {
+                    let message = format!("Invalid escape sequence: {}", token.code);
+                    let error = Diagnostic::error(ErrorCode::InvalidEscape, span, message);
+                    self.diagnostics.push(error);
+                },
+        }
+    }
+
+    fn check_tree(&mut self, tree: &syntax::Tree, span: Range<usize>) {
+        match &*tree.variant {
+            // A nested error on the same code is more specific; report only that one.
+            Variant::Invalid(tree::Invalid { ast, .. })
+                if matches!(&*ast.variant, Variant::Invalid(_))
+                    && ast.span.left_offset.is_empty() => {}
+            Variant::Invalid(tree::Invalid { error, ast }) => {
+                let message = error.message.clone();
+                let diagnostic = Diagnostic::error(error.code, span.clone(), message);
+                let diagnostic = self.suggest(diagnostic, ast, span);
+                self.diagnostics.push(diagnostic);
+            }
+            Variant::OprApp(tree::OprApp { opr: Err(error), .. }) => {
+                let operators = error.operators.iter().map(|opr| format!("`{}`", opr.code));
+                let operators = operators.collect::<Vec<_>>().join(", ");
+                let message = format!("Consecutive operators: {operators}.");
+                let error = Diagnostic::error(ErrorCode::MultipleOperators, span, message);
+                self.diagnostics.push(error);
+            }
+            Variant::TextLiteral(tree::TextLiteral { open: Some(open), close: None, .. })
+                if matches!(open.code.repr.as_ref(), "\"" | "'") =>
+            {
+                let message = "Unclosed text literal.";
+                let error = Diagnostic::error(ErrorCode::UnclosedText, span.clone(), message);
+                let error = error.with_suggestion(Suggestion::insert(span.end, &*open.code.repr));
+                self.diagnostics.push(error);
+            }
+            _ => {}
+        }
+    }
+
+    /// Attach suggested fixes to a diagnostic reported for an [`Invalid`](Variant::Invalid) node.
+    fn suggest(
+        &self,
+        diagnostic: Diagnostic,
+        ast: &syntax::Tree,
+        span: Range<usize>,
+    ) -> Diagnostic {
+        match (diagnostic.code, &*ast.variant) {
+            (ErrorCode::UnmatchedDelimiter, Variant::Group(group)) => match group {
+                tree::Group { open: Some(open), close: None, .. } => {
+                    let close = match open.code.repr.as_ref() {
+                        "[" => "]",
+                        "{" => "}",
+                        _ => ")",
+                    };
+                    diagnostic.with_suggestion(Suggestion::insert(self.line_end(span.end), close))
+                }
+                tree::Group { open: None, close: Some(close), .. } =>
+                    diagnostic.with_suggestion(Suggestion::remove(span, &close.code.repr)),
+                _ => diagnostic,
+            },
+            (ErrorCode::IncompleteMacro, _) => {
+                let header = &self.code[span.clone()];
+                let line_end = self.line_end(span.end);
+                let expected = self.expected_segments(header);
+                let mut diagnostic = diagnostic;
+                if !expected.is_empty() {
+                    let names = expected.iter().map(|next| format!("`{next}`"));
+                    let names = names.collect::<Vec<_>>().join(" or ");
+                    diagnostic.message = format!("Missing {names} after `{header}`.");
+                }
+                for next in expected {
+                    let suggestion = Suggestion::insert(line_end, format!(" {next}"));
+                    diagnostic = diagnostic.with_suggestion(suggestion);
+                }
+                diagnostic
+            }
+            (ErrorCode::OperatorSpacing, _) =>
+                diagnostic.with_suggestion(Suggestion::insert(span.start, " ")),
+            _ => diagnostic,
+        }
+    }
+
+    /// The headers of the segments that can follow the first segment of the macros introduced by
+    /// the given header.
+    fn expected_segments(&self, header: &str) -> Vec<&'static str> {
+        let entries = [&self.macros.statement, &self.macros.expression];
+        let entries = entries.into_iter().filter_map(|map| map.get(header));
+        let mut next: Vec<_> = entries
+            .flat_map(|entries| entries.iter())
+            .filter_map(|entry| entry.required_segments.head().map(|segment| segment.header))
+            .collect();
+        next.sort_unstable();
+        next.dedup();
+        next
+    }
+
+    /// Return the position of the end of the line containing the given position.
+    fn line_end(&self, position: usize) -> usize {
+        let rest = &self.code[position..];
+        position + rest.find(['\r', '\n']).unwrap_or(rest.len())
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(code: &str) -> Vec<(ErrorCode, Range<usize>, Vec<(Range<usize>, String)>)> {
+        let (_, diagnostics) = crate::Parser::new().run_with_diagnostics(code);
+        let suggestions = |d: &Diagnostic| {
+            d.suggestions.iter().map(|s| (s.span.clone(), s.replacement.clone())).collect()
+        };
+        diagnostics.iter().map(|d| (d.code, d.span.clone(), suggestions(d))).collect()
+    }
+
+    #[test]
+    fn valid_code() {
+        assert_eq!(diagnostics("main =\n    x = 'text \\n'\n    x + 1\n"), vec![]);
+    }
+
+    #[test]
+    fn unmatched_delimiters() {
+        assert_eq!(diagnostics("f (a b"), vec![(ErrorCode::UnmatchedDelimiter, 2..3, vec![(
+            6..6,
+            ")".into()
+        )])]);
+        assert_eq!(diagnostics("f a)"), vec![(ErrorCode::UnmatchedDelimiter, 3..4, vec![(
+            3..4,
+            "".into()
+        )])]);
+    }
+
+    #[test]
+    fn incomplete_macro() {
+        assert_eq!(diagnostics("x = if a b\ny"), vec![(ErrorCode::IncompleteMacro, 4..6, vec![(
+            10..10,
+            " then".into()
+        )])]);
+    }
+
+    #[test]
+    fn incomplete_macro_with_alternative_segments() {
+        use crate::macros::pattern::everything;
+        use crate::macros::SegmentDefinition;
+        let mut parser = crate::Parser::new();
+        for next in ["times", "until"] {
+            let segments = vec![
+                SegmentDefinition::new("repeat", everything()),
+                SegmentDefinition::new(next, everything()),
+            ];
+            let definition = macros::Definition::multi_segment_app(segments).unwrap();
+            parser.macros.register(macros::resolver::Context::Expression, definition);
+        }
+        let (_, diagnostics) = parser.run_with_diagnostics("repeat x");
+        let [diagnostic] = &diagnostics[..] else { panic!("{diagnostics:?}") };
+        assert_eq!(diagnostic.code, ErrorCode::IncompleteMacro);
+        assert_eq!(diagnostic.message, "Missing `times` or `until` after `repeat`.");
+        let replacements: Vec<_> =
+            diagnostic.suggestions.iter().map(|s| s.replacement.as_str()).collect();
+        assert_eq!(replacements, vec![" times", " until"]);
+    }
+
+    #[test]
+    fn text_errors() {
+        assert_eq!(diagnostics("x = 'abc\\q'"), vec![(ErrorCode::InvalidEscape, 8..10, vec![])]);
+        assert_eq!(diagnostics("x = \"abc\ny"), vec![(ErrorCode::UnclosedText, 4..8, vec![(
+            8..8,
+            "\"".into()
+        )])]);
+    }
+
+    #[test]
+    fn lexer_errors() {
+        let splice = diagnostics("x = 'a `b\ny = 1");
+        assert!(splice.contains(&(ErrorCode::UnclosedSplice, 9..9, vec![(9..9, "`".into())])));
+        assert!(splice.iter().all(|(_, span, _)| span.start < 10), "{splice:?}");
+        assert_eq!(diagnostics("main =\n    a\n  b\nc"), vec![(
+            ErrorCode::InconsistentIndentation,
+            13..15,
+            vec![]
+        )]);
+    }
+
+    #[test]
+    fn serialization() {
+        let (_, diagnostics) = crate::Parser::new().run_with_diagnostics("f a)");
+        let json = diagnostics.to_json();
+        assert!(json.contains("\"code\":\"unmatched-delimiter\""), "{json}");
+        assert!(json.contains("\"severity\":\"error\""), "{json}");
+        let deserialized: Diagnostics = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, diagnostics);
+    }
+}
@@ -0,0 +1,431 @@
+//! Canonical formatting of Enso source code.
+//!
+//! The formatter re-emits the tokens of a [`syntax::Tree`], replacing the whitespace between them
+//! with canonical whitespace:
+//! - Blocks are indented by four spaces per nesting level.
+//! - Within a line, any non-empty whitespace between tokens is a single space. Whitespace is never
+//!   added where there was none, because in Enso spacing affects operator precedence.
+//! - Trailing whitespace is removed, line endings are normalized to `\n`, and the file ends with a
+//!   single newline.
+//! - Runs of empty lines are limited to two at the top level of the module and one within blocks;
+//!   empty lines at the beginning and end of the file are removed.
+//!
+//! Comments are kept, with their trailing whitespace removed. The content of text literals and
+//! documentation comments is kept verbatim; the lines of multi-line literals are shifted together
+//! with the line they start on, so that their content is unchanged.
+//!
+//! Formatting is idempotent. As a safety net, [`format`] parses its output and checks that its
+//! structure is the same as the structure of the input.
+
+use crate::prelude::*;
+
+use crate::lexer;
+use crate::source::*;
+use crate::syntax;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::ItemVisitable;
+use crate::Parser;
+use crate::Result;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The number of spaces per block nesting level.
+pub const INDENTATION: usize = 4;
+/// The maximum number of consecutive empty lines at the top level of a module.
+const MAX_EMPTY_LINES_TOP_LEVEL: usize = 2;
+/// The maximum number of consecutive empty lines within a block.
+const MAX_EMPTY_LINES_IN_BLOCK: usize = 1;
+
+
+
+// ==============
+// === Format ===
+// ==============
+
+/// Format the code. Returns an error if the formatted code would not parse to a tree with the same
+/// structure as the input; this indicates a bug in the formatter.
+pub fn format(parser: &Parser, code: &str) -> Result<String> {
+    let tree = parser.run(code);
+    let formatted = format_tree(&tree, code);
+    let reparsed = parser.run(&formatted);
+    if shape(&tree) == shape(&reparsed) {
+        Ok(formatted)
+    } else {
+        Err("Formatting would change the structure of the code.".into())
+    }
+}
+
+/// Re-emit the tree in canonical style. The tree must be the result of parsing `code`.
+pub fn format_tree(tree: &syntax::Tree, code: &str) -> String {
+    let raw_regions = raw_regions(code);
+    let pieces = pieces(tree);
+    let root_indentation = code
+        .chars()
+        .map_while(lexer::space_char_visible_size)
+        .fold(default(), Add::add)
+        .width_in_spaces;
+    let indentation = vec![root_indentation];
+    let raw_regions = &raw_regions;
+    let mut printer = Printer { code, raw_regions, indentation, at_line_start: true, ..default() };
+    for piece in pieces {
+        printer.print(piece);
+    }
+    printer.finish()
+}
+
+
+
+// ==============
+// === Pieces ===
+// ==============
+
+/// A token of the input, with the whitespace preceding it.
+#[derive(Clone, Debug)]
+struct Piece {
+    whitespace: Range<usize>,
+    code:       Range<usize>,
+    kind:       PieceKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PieceKind {
+    /// A line break.
+    Break,
+    /// A comment. Comments are represented as newline tokens.
+    Comment,
+    /// Any other token.
+    Other,
+}
+
+impl PieceKind {
+    fn new(variant: &token::Variant, code: &str) -> Self {
+        match variant {
+            token::Variant::Newline(_) if code.starts_with('#') => PieceKind::Comment,
+            token::Variant::Newline(_) | token::Variant::TextNewline(_) => PieceKind::Break,
+            _ => PieceKind::Other,
+        }
+    }
+}
+
+/// Return the tokens of a tree, with their positions in the source code.
+fn pieces(tree: &syntax::Tree) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut whitespace_start = 0;
+    tree::visit_item_spans(tree, |item, code| {
+        let item::Ref::Token(token) = item else { return };
+        let kind = PieceKind::new(&token.data, &token.code.repr);
+        // Empty newline tokens mark the beginning and end of the input; the whitespace before them
+        // belongs to the following token, or is trailing whitespace.
+        if kind == PieceKind::Break && code.is_empty() {
+            return;
+        }
+        let whitespace = whitespace_start..code.start;
+        whitespace_start = code.end;
+        pieces.push(Piece { whitespace, code, kind });
+    });
+    pieces
+}
+
+/// Return the byte ranges of the text literals and documentation comments in the code. The ranges
+/// begin after the opening token.
+fn raw_regions(code: &str) -> Vec<Range<usize>> {
+    let mut regions = vec![];
+    let mut offset = 0;
+    let mut depth = 0usize;
+    let mut start = 0;
+    for token in lexer::run(code).value {
+        offset += token.left_offset.code.repr.len();
+        let end = offset + token.code.repr.len();
+        match token.variant {
+            token::Variant::TextStart(_) => {
+                if depth == 0 {
+                    start = end;
+                }
+                depth += 1;
+            }
+            token::Variant::TextEnd(_) if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    regions.push(start..end);
+                }
+            }
+            _ => (),
+        }
+        offset = end;
+    }
+    if depth > 0 {
+        regions.push(start..code.len());
+    }
+    regions
+}
+
+
+
+// ===============
+// === Printer ===
+// ===============
+
+#[derive(Debug, Default)]
+struct Printer<'a> {
+    code:            &'a str,
+    raw_regions:     &'a [Range<usize>],
+    out:             String,
+    /// The indentation of each enclosing block in the input, like the lexer's block stack. The
+    /// first element is the indentation of the root block.
+    indentation:     Vec<usize>,
+    /// Whether no token has been printed on the current line yet.
+    at_line_start:   bool,
+    /// The number of consecutive empty lines printed.
+    empty_lines:     usize,
+    /// The change in indentation of the current line.
+    line_shift:      isize,
+    /// The change in indentation applied to the lines of the current raw region.
+    raw_shift:       isize,
+    /// Whether the last printed piece was a line break within a raw region.
+    raw_line_start:  bool,
+    /// Index of the first raw region that may contain pieces that haven't been printed yet.
+    next_raw_region: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn print(&mut self, piece: Piece) {
+        let source = self.code;
+        let whitespace = &source[piece.whitespace.clone()];
+        let code = &source[piece.code.clone()];
+        if self.is_raw(&piece.code) {
+            if self.raw_line_start && piece.kind != PieceKind::Break {
+                self.out.push_str(&shift(whitespace, self.raw_shift));
+            } else {
+                self.out.push_str(whitespace);
+            }
+            self.out.push_str(code);
+            self.raw_line_start = piece.kind == PieceKind::Break && !code.is_empty();
+            return;
+        }
+        match piece.kind {
+            PieceKind::Break => {
+                if self.at_line_start {
+                    self.empty_lines += 1;
+                    let max_empty_lines = match self.indentation.len() {
+                        1 => MAX_EMPTY_LINES_TOP_LEVEL,
+                        _ => MAX_EMPTY_LINES_IN_BLOCK,
+                    };
+                    if self.out.is_empty() || self.empty_lines > max_empty_lines {
+                        return;
+                    }
+                }
+                self.out.push('\n');
+                self.at_line_start = true;
+            }
+            PieceKind::Comment | PieceKind::Other => {
+                if self.at_line_start {
+                    let width = VisibleOffset::from(whitespace).width_in_spaces;
+                    let depth = self.enter_line(width);
+                    let new_width = depth * INDENTATION;
+                    self.line_shift = new_width as isize - width as isize;
+                    self.out.push_str(&" ".repeat(new_width));
+                    self.at_line_start = false;
+                    self.empty_lines = 0;
+                } else if !whitespace.is_empty() {
+                    self.out.push(' ');
+                }
+                match piece.kind {
+                    PieceKind::Comment => self.out.push_str(code.trim_end()),
+                    _ => self.out.push_str(code),
+                }
+                self.raw_shift = self.line_shift;
+                self.raw_line_start = false;
+            }
+        }
+    }
+
+    /// Update the block stack for a line with the given indentation, following the rules the lexer
+    /// uses to start and end blocks. Returns the nesting depth of the line.
+    fn enter_line(&mut self, width: usize) -> usize {
+        let current = self.indentation.last().copied().unwrap_or_default();
+        if width > current {
+            self.indentation.push(width);
+        }
+        while let [.., previous, current] = self.indentation[..]
+            && width < current && width <= previous {
+            self.indentation.pop();
+        }
+        self.indentation.len() - 1
+    }
+
+    /// Return whether the code is within a raw region (which doesn't include the opening token).
+    fn is_raw(&mut self, code: &Range<usize>) -> bool {
+        while let Some(region) = self.raw_regions.get(self.next_raw_region)
+            && region.end < code.start {
+            self.next_raw_region += 1;
+        }
+        self.raw_regions
+            .get(self.next_raw_region)
+            .map_or(false, |region| region.start <= code.start && code.end <= region.end)
+    }
+
+    fn finish(mut self) -> String {
+        let len = self.out.trim_end().len();
+        self.out.truncate(len);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+}
+
+/// Change the width of the whitespace by the given number of columns.
+fn shift(whitespace: &str, columns: isize) -> Cow<str> {
+    if columns == 0 {
+        return whitespace.into();
+    }
+    let width = VisibleOffset::from(whitespace).width_in_spaces as isize + columns;
+    " ".repeat(width.max(0) as usize).into()
+}
+
+
+
+// =============
+// === Shape ===
+// =============
+
+/// An element of the shape of a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ShapeElement {
+    Enter,
+    Exit,
+    Tree(tree::VariantMarker),
+    Token(String),
+}
+
+/// Return a representation of the structure and content of the tree that does not depend on the
+/// whitespace, line breaks, or empty lines in the code.
+fn shape(tree: &syntax::Tree) -> Vec<ShapeElement> {
+    let mut collector = ShapeCollector::default();
+    tree.visit_item(&mut collector);
+    collector.shape
+}
+
+#[derive(Debug, Default)]
+struct ShapeCollector {
+    shape: Vec<ShapeElement>,
+}
+
+impl tree::Visitor for ShapeCollector {
+    fn before_visiting_children(&mut self) {
+        self.shape.push(ShapeElement::Enter);
+    }
+
+    fn after_visiting_children(&mut self) {
+        // Elements that contain only whitespace, such as empty lines, are omitted.
+        if self.shape.last() == Some(&ShapeElement::Enter) {
+            self.shape.pop();
+        } else {
+            self.shape.push(ShapeElement::Exit);
+        }
+    }
+}
+
+impl<'s: 'a, 'a> tree::ItemVisitor<'s, 'a> for ShapeCollector {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        match item {
+            item::Ref::Tree(tree) => self.shape.push(ShapeElement::Tree(tree.variant.marker())),
+            item::Ref::Token(token) => {
+                let code: &str = &token.code.repr;
+                let code = match PieceKind::new(&token.data, code) {
+                    PieceKind::Break => return true,
+                    PieceKind::Comment => code.trim_end(),
+                    PieceKind::Other => code,
+                };
+                self.shape.push(ShapeElement::Token(code.into()));
+            }
+        }
+        true
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_format(input: &str, expected: &str) {
+        let parser = Parser::new();
+        let formatted = format(&parser, input).unwrap();
+        assert_eq!(formatted, expected);
+        let reformatted = format(&parser, &formatted).unwrap();
+        assert_eq!(reformatted, formatted, "Formatting is not idempotent.");
+    }
+
+    #[test]
+    fn indentation() {
+        test_format(
+            "main =\n  x = 1\n  y =\n        x\n  y\n",
+            "main =\n    x = 1\n    y =\n        x\n    y\n",
+        );
+        test_format("  a\n  b\n    c\n", "a\nb\n    c\n");
+        test_format("  a\nb\n  c\n", "a\nb\nc\n");
+        test_format("main =\n\tx\n", "main =\n    x\n");
+    }
+
+    #[test]
+    fn spacing() {
+        test_format("x   =  a  +   b", "x = a + b\n");
+        test_format("x = a+b  c", "x = a+b c\n");
+        test_format("f   (a)  b   ", "f (a) b\n");
+    }
+
+    #[test]
+    fn empty_lines() {
+        test_format("\n\na\n\n\n\n\nb\n\n\n", "a\n\n\nb\n");
+        test_format("main =\n    a\n\n\n\n    b\n", "main =\n    a\n\n    b\n");
+        test_format("a\r\nb\r\n", "a\nb\n");
+    }
+
+    #[test]
+    fn comments() {
+        test_format("a  # comment   \n# own line\nb", "a # comment\n# own line\nb\n");
+        test_format("main =\n  # comment\n  x", "main =\n    # comment\n    x\n");
+    }
+
+    #[test]
+    fn documentation() {
+        test_format(
+            "## Doc  with  spaces.\n   More  doc.\nfoo  =  1",
+            "## Doc  with  spaces.\n   More  doc.\nfoo = 1\n",
+        );
+        test_format(
+            "type T\n  ## Doc.\n     More.\n  foo = 1",
+            "type T\n    ## Doc.\n       More.\n    foo = 1\n",
+        );
+    }
+
+    #[test]
+    fn text_literals() {
+        test_format("x  =  'a  b'", "x = 'a  b'\n");
+        test_format("x  =  'a  `b  +  c`  d'", "x = 'a  `b  +  c`  d'\n");
+        test_format(
+            "main =\n  x = '''\n      text\n        more\n  x",
+            "main =\n    x = '''\n        text\n          more\n    x\n",
+        );
+        test_format("x = \"unclosed  text\ny  =  1", "x = \"unclosed  text\ny = 1\n");
+    }
+
+    #[test]
+    fn invalid_code() {
+        test_format("f  (a  b", "f (a b\n");
+        test_format("x = if  a  b", "x = if a b\n");
+        test_format("", "");
+    }
+}
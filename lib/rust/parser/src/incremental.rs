@@ -0,0 +1,230 @@
+//! Incremental reparsing. When a source file is edited, only the lines of the root block that are
+//! affected by the edit are lexed and resolved again; the syntax trees of all the other lines are
+//! reused from the previous parse result.
+//!
+//! # Unit of reuse
+//! Both the lexer and the macro resolver start from a clean state at the beginning of every line of
+//! the root block: block structure is determined by indentation only, and macro resolution scopes
+//! never extend past the end of a line. A root-block line (together with any indented child lines,
+//! and with the lines of documentation or annotations that are attached to it) is therefore the
+//! smallest fragment of a module that can be parsed independently of its neighbours.
+//!
+//! An edit can change how its neighbours are interpreted: a line that becomes indented is attached
+//! to the preceding line, and a line that becomes a documentation comment or an annotation is
+//! attached to the following line. For this reason, the reparsed region always includes one
+//! unchanged line on each side of the edited lines.
+
+use crate::prelude::*;
+
+use crate::lexer;
+use crate::macros;
+use crate::source::*;
+use crate::syntax;
+use crate::syntax::tree::block;
+
+
+
+// ============
+// === Edit ===
+// ============
+
+/// A text edit: replacement of a range of the old source code with new text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Edit<'a> {
+    /// Byte range of the old source code that is replaced.
+    pub range: Range<usize>,
+    /// The text inserted in place of the range.
+    pub text:  &'a str,
+}
+
+impl<'a> Edit<'a> {
+    /// Constructor.
+    pub fn new(range: Range<usize>, text: &'a str) -> Self {
+        Self { range, text }
+    }
+
+    /// Apply the edit to the given code.
+    pub fn apply(&self, code: &str) -> String {
+        let mut out = String::with_capacity(code.len() + self.text.len() - self.range.len());
+        out.push_str(&code[..self.range.start]);
+        out.push_str(self.text);
+        out.push_str(&code[self.range.end..]);
+        out
+    }
+
+    /// Return the position in the new code corresponding to the given position in the old code,
+    /// which must not be inside of the replaced range.
+    fn shift(&self, old_position: usize) -> usize {
+        old_position + self.text.len() - self.range.len()
+    }
+}
+
+
+
+// ===============
+// === Reparse ===
+// ===============
+
+/// Parse `code`, which is the result of applying `edit` to the source code of `tree`. Only the
+/// lines of the root block affected by the edit are lexed and resolved; the other lines of `tree`
+/// are moved to the result.
+///
+/// The `tree` must be the result of parsing the entire old source code; if the edit cannot be
+/// reparsed incrementally, `fallback` is used to parse the whole new source code.
+pub fn reparse<'s>(
+    macros: &macros::resolver::MacroMap,
+    tree: syntax::Tree<'s>,
+    edit: &Edit,
+    code: &'s str,
+    fallback: impl FnOnce(&'s str) -> syntax::Tree<'s>,
+) -> syntax::Tree<'s> {
+    let syntax::Tree { span, variant } = tree;
+    let statements = match variant {
+        box syntax::tree::Variant::BodyBlock(syntax::tree::BodyBlock { statements }) => statements,
+        _ => return fallback(code),
+    };
+    let root_offset = span.left_offset;
+    let starts = line_starts(&root_offset, &statements);
+    let old_len = starts.last().copied().unwrap_or_default();
+    let edit_is_consistent = edit.range.start <= edit.range.end
+        && edit.range.end <= old_len
+        && edit.shift(old_len) == code.len();
+    if !edit_is_consistent || statements.is_empty() {
+        return fallback(code);
+    }
+    let line_containing = |position: usize| {
+        let next = starts[..statements.len()].partition_point(|&start| start <= position);
+        next.saturating_sub(1)
+    };
+    let first_line = line_containing(edit.range.start).saturating_sub(1);
+    let last_line = (line_containing(edit.range.end) + 1).min(statements.len() - 1);
+    let old_start = if first_line == 0 { 0 } else { starts[first_line] };
+    let old_end = starts[last_line + 1];
+    if old_start > edit.range.start || old_end < edit.range.end {
+        return fallback(code);
+    }
+    let region = &code[old_start..edit.shift(old_end)];
+    let tokens = match first_line {
+        0 => lexer::run(region),
+        _ => lexer::run_fragment(region, root_offset.visible),
+    };
+    if tokens.internal_error.is_some() {
+        return fallback(code);
+    }
+    let resolver = macros::resolver::Resolver::new_statement();
+    let region_tree = resolver.run(macros, tokens.value);
+    let syntax::Tree { span: region_span, variant: region_variant } = region_tree;
+    let mut region_lines = match region_variant {
+        box syntax::tree::Variant::BodyBlock(syntax::tree::BodyBlock { statements }) => statements,
+        _ => return fallback(code),
+    };
+    let left_offset = if first_line == 0 {
+        region_span.left_offset
+    } else {
+        // The fragment starts with the newline token of its first line. The resolver started the
+        // fragment with an empty line and moved the left offset of that newline token to the root
+        // of the fragment's tree; undo both.
+        if region_lines.len() < 2 || region_lines[0].expression.is_some() {
+            return fallback(code);
+        }
+        region_lines.remove(0);
+        region_lines[0].newline.left_offset = region_span.left_offset;
+        root_offset
+    };
+    let mut statements = statements;
+    statements.splice(first_line..=last_line, region_lines);
+    let mut tree = syntax::Tree::body_block(statements);
+    tree.span.left_offset = left_offset;
+    tree
+}
+
+/// Return the byte offset of the beginning of each line, followed by the offset of the end of the
+/// last line.
+fn line_starts(root_offset: &Offset, lines: &[block::Line]) -> Vec<usize> {
+    let mut position = root_offset.code.repr.len();
+    let mut starts = Vec::with_capacity(lines.len() + 1);
+    for line in lines {
+        starts.push(position);
+        position += line_len(line);
+    }
+    starts.push(position);
+    starts
+}
+
+/// The length of the code of a line, in bytes, including its newline token and all whitespace.
+fn line_len(line: &block::Line) -> usize {
+    let newline = &line.newline;
+    let newline_len = newline.left_offset.code.repr.len() + newline.code.repr.len();
+    let expression_len = line.expression.as_ref().map_or(0, |expression| {
+        expression.span.left_offset.code.repr.len() + expression.span.code_length.utf8_bytes()
+    });
+    newline_len + expression_len
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that reparsing the result of the edit produces the same tree as a full parse.
+    fn test_edit(code: &str, range: Range<usize>, text: &str) {
+        let parser = crate::Parser::new();
+        let edit = Edit::new(range, text);
+        let new_code = edit.apply(code);
+        let tree = parser.run(code);
+        let reparsed = parser.reparse(tree, &edit, &new_code);
+        let expected = parser.run(&new_code);
+        assert_eq!(reparsed.code(), new_code);
+        assert_eq!(reparsed, expected, "Incremental reparse of {new_code:?} differs.");
+    }
+
+    const MODULE: &str = "import Standard.Base\n\nmain =\n    x = 1\n    y = x + 2\n    y\n\n## Doc.\nfoo a b = a + b\nbar = 3\n";
+
+    #[test]
+    fn edit_inside_line() {
+        test_edit(MODULE, 37..38, "23");
+        test_edit(MODULE, 68..71, "baz");
+        test_edit(MODULE, 90..91, "(4");
+    }
+
+    #[test]
+    fn edit_first_line() {
+        test_edit(MODULE, 7..15, "Base");
+        test_edit(MODULE, 0..0, "  ");
+        test_edit("  a\n  b\nc", 4..5, "d");
+    }
+
+    #[test]
+    fn edit_changing_block_structure() {
+        // Indent a root-level line, attaching it to the previous line's block.
+        test_edit(MODULE, 84..84, "    ");
+        // Remove the indentation of the last line of a block.
+        test_edit(MODULE, 53..57, "");
+        // Turn a line into an annotation of the following line.
+        test_edit(MODULE, 84..84, "@annotation\n");
+        // Indent a line inconsistently with its block.
+        test_edit(MODULE, 39..41, "");
+        test_edit("main =\n    a\n    b\n    c\nd\n", 13..15, "");
+    }
+
+    #[test]
+    fn edit_text_literals() {
+        test_edit(MODULE, 90..91, "\"unterminated");
+        test_edit(MODULE, 90..91, "'''\n    multiline");
+        test_edit("a = 'text'\nb = 1\nc = 2\n", 5..5, "`");
+        test_edit("a = 'text'\nb = 'x`y`'\n", 5..5, "`");
+    }
+
+    #[test]
+    fn edit_insert_and_remove_lines() {
+        test_edit(MODULE, 52..52, "\n    z = 0");
+        test_edit(MODULE, 20..60, "");
+        test_edit(MODULE, MODULE.len()..MODULE.len(), "baz = 4\n");
+        test_edit(MODULE, 0..MODULE.len(), "");
+    }
+}
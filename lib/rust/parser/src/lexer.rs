@@ -1,6 +1,22 @@
 //! Implementation of lexer, a utility transforming source code into stream of tokens. Read the docs
 //! of the main module of this crate to learn more about the parsing process.
 //!
+//! # Error recovery
+//! The lexer accepts any input. In error-recovery mode (see [`run_with_recovery`]), it additionally
+//! reports the errors it encounters as [`Diagnostic`]s, and limits their effect on the rest of the
+//! file:
+//! - An interpolation (`` ` ``) in a text literal that is not closed before the end of its line is
+//!   terminated at the line break. A single-line text literal containing it ends at the line break;
+//!   in a multi-line text literal, the following lines are text content, up to the next line with
+//!   lower-or-equal indentation than the literal. Without error recovery, the lexer would interpret
+//!   the following lines as code, until the next `` ` `` character resumes the unfinished literal.
+//! - Single-line text literals missing their closing quote are reported.
+//! - A line indented less than the block it belongs to, but more than its parent block, is
+//!   reported, and the block indentation is resynchronized to the line: the following lines with
+//!   lower-or-equal indentation continue the block as usual, while the more indented ones form a
+//!   child block of the line. Without error recovery, the following lines are lexed relative to the
+//!   original block indentation.
+//!
 //! TODO: Implement token validators - validating if the consumed token was OK and reporting human
 //!       readable errors.
 
@@ -8,6 +24,11 @@ use crate::prelude::*;
 use crate::source::*;
 use crate::syntax::*;
 
+use crate::diagnostics::Diagnostic;
+use crate::diagnostics::Diagnostics;
+use crate::diagnostics::ErrorCode;
+use crate::diagnostics::Suggestion;
+
 use std::str;
 
 
@@ -101,6 +122,8 @@ pub struct LexerState {
     pub block_indent_stack: Vec<VisibleOffset>,
     pub internal_error: Option<String>,
     pub stack: Vec<State>,
+    /// Errors recovered from. [`None`] if error recovery is disabled.
+    pub diagnostics: Option<Diagnostics>,
 }
 
 /// Suspended states.
@@ -233,6 +256,25 @@ impl<'s> Lexer<'s> {
             out
         })
     }
+
+    /// Enable error recovery. See the module docs to learn more.
+    pub fn with_error_recovery(mut self) -> Self {
+        self.diagnostics = Some(default());
+        self
+    }
+
+    /// Return whether error recovery is enabled.
+    #[inline(always)]
+    pub fn error_recovery(&self) -> bool {
+        self.diagnostics.is_some()
+    }
+
+    /// Report an error, if error recovery is enabled.
+    fn report(&mut self, diagnostic: impl FnOnce() -> Diagnostic) {
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.push(diagnostic());
+        }
+    }
 }
 
 
@@ -922,10 +964,15 @@ impl<'s> Lexer<'s> {
         } else {
             // One quote followed by non-quote character: Inline quote.
             let open_quote_end = self.mark();
+            let start = open_quote_start.0.unchecked_raw();
             let token = self.make_token(open_quote_start, open_quote_end,
                 token::Variant::text_start());
             self.output.push(token);
-            self.inline_quote(quote_char, text_type);
+            if self.inline_quote(quote_char, text_type) == TextEndedAt::Unclosed {
+                let span = start..self.current_offset.unchecked_raw();
+                let message = "Unclosed text literal.";
+                self.report(|| Diagnostic::error(ErrorCode::UnclosedText, span, message));
+            }
         }
         self.spaces_after_lexeme();
     }
@@ -952,9 +999,9 @@ impl<'s> Lexer<'s> {
         });
     }
 
-    fn inline_quote(&mut self, quote_char: char, text_type: TextType) {
+    fn inline_quote(&mut self, quote_char: char, text_type: TextType) -> TextEndedAt {
         let is_interpolated = text_type.is_interpolated();
-        self.text_content(quote_char.into(), is_interpolated, State::InlineText);
+        self.text_content(quote_char.into(), is_interpolated, State::InlineText)
     }
 
     fn end_splice(&mut self, state: State) {
@@ -965,13 +1012,43 @@ impl<'s> Lexer<'s> {
             self.make_token(splice_quote_start, splice_quote_end, token::Variant::close_symbol());
         self.output.push(token);
         match state {
-            State::InlineText => self.inline_quote('\'', TextType::Interpolated),
+            State::InlineText => {
+                self.inline_quote('\'', TextType::Interpolated);
+            }
             State::MultilineText { .. } => {
                 self.text_content(None, true, state);
             }
         }
     }
 
+    /// Terminate the interpolations that were not closed before the end of the line, and the
+    /// single-line text literals containing them. If an interpolation is within a multi-line text
+    /// literal, the lexer resumes reading the literal's content at the line break. Returns the
+    /// `first` token of the line break, unless it was consumed as text content.
+    fn close_unterminated_splices(&mut self, mut first: Option<Token<'s>>) -> Option<Token<'s>> {
+        while let Some(state) = self.stack.pop() {
+            let position =
+                self.current_offset.unchecked_raw() - self.last_spaces_offset.unchecked_raw();
+            let message = "Unclosed interpolation: expected ` before the end of the line.";
+            self.report(|| {
+                Diagnostic::error(ErrorCode::UnclosedSplice, position..position, message)
+                    .with_suggestion(Suggestion::insert(position, "`"))
+            });
+            match state {
+                State::InlineText => self.output.push(Token::from(token::text_end("", ""))),
+                State::MultilineText { .. } => {
+                    // The rest of the line belongs to the broken interpolation.
+                    if let Some(comment) = first.take() {
+                        self.output.push(comment.with_variant(token::Variant::text_section()));
+                    }
+                    self.text_content(None, true, state);
+                    break;
+                }
+            }
+        }
+        first
+    }
+
     fn text_content(
         &mut self,
         closing_char: Option<char>,
@@ -1099,7 +1176,8 @@ impl<'s> Lexer<'s> {
         if !(token.code.is_empty() && token.left_offset.code.is_empty()) {
             self.output.push(token);
         }
-        let end_token = if self.current_char == closing_char {
+        let closed = self.current_char == closing_char;
+        let end_token = if closed {
             self.take_next();
             let close_quote_end = self.mark();
             self.make_token(text_end, close_quote_end, token::Variant::text_end())
@@ -1107,7 +1185,11 @@ impl<'s> Lexer<'s> {
             Token::from(token::text_end("", ""))
         };
         self.output.push(end_token);
-        TextEndedAt::End
+        if closed {
+            TextEndedAt::End
+        } else {
+            TextEndedAt::Unclosed
+        }
     }
 
     fn text_escape(
@@ -1205,6 +1287,8 @@ impl<'s> Lexer<'s> {
 enum TextEndedAt {
     Splice,
     End,
+    /// The end of the line or input was reached before the closing quote.
+    Unclosed,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -1279,7 +1363,11 @@ impl<'s> Lexer<'s> {
         self.newlines_starting_with(None);
     }
 
-    fn newlines_starting_with(&mut self, first: Option<Token<'s>>) {
+    fn newlines_starting_with(&mut self, mut first: Option<Token<'s>>) {
+        let at_line_break = first.is_some() || self.current_char.map_or(false, is_newline_char);
+        if self.error_recovery() && at_line_break && !self.stack.is_empty() {
+            first = self.close_unterminated_splices(first);
+        }
         let mut newlines = self.token_storage.take();
         newlines.extend(first);
         while let Some(token) = self.line_break() {
@@ -1309,7 +1397,17 @@ impl<'s> Lexer<'s> {
             if block_indent > previous_indent {
                 // The new line indent is smaller than current block but bigger than the
                 // previous one. We are treating the line as belonging to the
-                // block. The warning should be reported by parser.
+                // block. In error-recovery mode, the block indentation is resynchronized to the
+                // line, so the following lines are lexed relative to it.
+                if self.error_recovery() {
+                    self.current_block_indent = block_indent;
+                }
+                let end = self.current_offset.unchecked_raw();
+                let span = end - self.last_spaces_offset.unchecked_raw()..end;
+                let message = "Inconsistent indentation: the line is indented less than its block.";
+                self.report(|| {
+                    Diagnostic::warning(ErrorCode::InconsistentIndentation, span, message)
+                });
                 break;
             }
             self.end_block();
@@ -1342,9 +1440,23 @@ const PARSERS: &[for<'r> fn(&'r mut Lexer<'_>)] = &[
 impl<'s> Lexer<'s> {
     /// Run the lexer. Return non-hierarchical list of tokens (the token groups will be represented
     /// as start and end tokens).
-    pub fn run(mut self) -> ParseResult<Vec<Token<'s>>> {
+    pub fn run(self) -> ParseResult<Vec<Token<'s>>> {
+        self.run_with_root_indent(None).0
+    }
+
+    /// Run the lexer in error-recovery mode. Return the tokens, and the errors recovered from.
+    pub fn run_with_recovery(self) -> (ParseResult<Vec<Token<'s>>>, Diagnostics) {
+        self.with_error_recovery().run_with_root_indent(None)
+    }
+
+    /// Run the lexer, treating the given indentation as the root block level. If no indentation is
+    /// provided, the root level is determined by the leading whitespace of the input.
+    fn run_with_root_indent(
+        mut self,
+        root_indent: Option<VisibleOffset>,
+    ) -> (ParseResult<Vec<Token<'s>>>, Diagnostics) {
         self.spaces_after_lexeme();
-        self.current_block_indent = self.last_spaces_visible_offset;
+        self.current_block_indent = root_indent.unwrap_or(self.last_spaces_visible_offset);
         let mut any_parser_matched = true;
         while any_parser_matched {
             any_parser_matched = false;
@@ -1355,6 +1467,9 @@ impl<'s> Lexer<'s> {
                 }
             }
         }
+        if self.error_recovery() && !self.stack.is_empty() {
+            self.close_unterminated_splices(None);
+        }
         while self.end_block().is_some() {
             let block_end = self.marker_token(token::Variant::block_end());
             self.submit_token(block_end);
@@ -1372,9 +1487,10 @@ impl<'s> Lexer<'s> {
             let message = format!("Lexer did not consume all input. State: {self:?}");
             internal_error.get_or_insert(message);
         }
+        let diagnostics = self.diagnostics.take().unwrap_or_default();
         let value = self.output;
         trace!("Tokens:\n{:#?}", value);
-        ParseResult { value, internal_error }
+        (ParseResult { value, internal_error }, diagnostics)
     }
 }
 
@@ -1384,6 +1500,20 @@ pub fn run(input: &'_ str) -> ParseResult<Vec<Token<'_>>> {
     Lexer::new(input).run()
 }
 
+/// Run the lexer on a fragment of a larger source file. The fragment must start at the beginning of
+/// a line of the root block (i.e. with the whitespace preceding its newline token), and the
+/// provided indentation is used as the root block level instead of the fragment's own leading
+/// whitespace.
+pub fn run_fragment(input: &'_ str, root_indent: VisibleOffset) -> ParseResult<Vec<Token<'_>>> {
+    Lexer::new(input).run_with_root_indent(Some(root_indent)).0
+}
+
+/// Run the lexer in error-recovery mode. Return the tokens, and the errors recovered from. See the
+/// module docs to learn more.
+pub fn run_with_recovery(input: &'_ str) -> (ParseResult<Vec<Token<'_>>>, Diagnostics) {
+    Lexer::new(input).run_with_recovery()
+}
+
 
 
 // =============
@@ -1500,6 +1630,82 @@ mod tests {
         ])]);
     }
 
+    /// Lex the code in error-recovery mode; return the tokens and the codes and spans of the
+    /// reported diagnostics.
+    fn lex_with_recovery(code: &str) -> (Vec<Token>, Vec<(ErrorCode, Range<usize>)>) {
+        let (tokens, diagnostics) = run_with_recovery(code);
+        let diagnostics = diagnostics.iter().map(|d| (d.code, d.span.clone())).collect();
+        (tokens.unwrap(), diagnostics)
+    }
+
+    #[test]
+    fn test_recovery_unclosed_splice() {
+        let code = "x = 'a `b\ny = `c`";
+        let (tokens, diagnostics) = lex_with_recovery(code);
+        assert_eq!(diagnostics, vec![(ErrorCode::UnclosedSplice, 9..9)]);
+        // The second line is lexed as if the first line were correct.
+        let line_start = tokens.iter().position(|t| t.variant == token::Variant::newline());
+        let second_line = &tokens[line_start.unwrap() + 1..];
+        assert_eq!(second_line, &run("y = `c`").unwrap()[..]);
+        // Without error recovery, the backtick on the second line resumes the unclosed literal.
+        assert_ne!(run(code).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_recovery_unclosed_splice_in_multiline_text() {
+        let code = "x = '''\n    a `b\n    c\ny = 1";
+        let (tokens, diagnostics) = lex_with_recovery(code);
+        assert_eq!(diagnostics, vec![(ErrorCode::UnclosedSplice, 16..16)]);
+        let is_section = |t: &Token| matches!(t.variant, token::Variant::TextSection(_));
+        assert!(tokens.iter().any(|t| is_section(t) && t.code.repr == "c"));
+        assert_eq!(&tokens[tokens.len() - 3..], &run("y = 1").unwrap()[..]);
+    }
+
+    #[test]
+    fn test_recovery_unclosed_text() {
+        let (_, diagnostics) = lex_with_recovery("x = \"abc\ny");
+        assert_eq!(diagnostics, vec![(ErrorCode::UnclosedText, 4..8)]);
+    }
+
+    #[test]
+    fn test_recovery_inconsistent_indentation() {
+        let code = "main =\n    a\n  b\nc";
+        let (tokens, diagnostics) = lex_with_recovery(code);
+        assert_eq!(diagnostics, vec![(ErrorCode::InconsistentIndentation, 13..15)]);
+        assert_eq!(tokens, run(code).unwrap());
+    }
+
+    #[test]
+    fn test_recovery_resynchronized_indentation() {
+        let code = "main =\n    a\n  b\n    c\n  d\ne";
+        let (tokens, diagnostics) = lex_with_recovery(code);
+        assert_eq!(diagnostics, vec![(ErrorCode::InconsistentIndentation, 13..15)]);
+        let structure = |tokens: &[Token]| -> Vec<String> {
+            let structure = tokens.iter().filter_map(|token| match token.variant {
+                token::Variant::BlockStart(_) => Some("{".into()),
+                token::Variant::BlockEnd(_) => Some("}".into()),
+                token::Variant::Ident(_) => Some(token.code.repr.to_string()),
+                _ => None,
+            });
+            structure.collect()
+        };
+        // The block is resynchronized to the indentation of `b`, so `c` is its child, and `d`
+        // continues the block.
+        let expected = ["main", "{", "a", "b", "{", "c", "}", "d", "}", "e"];
+        assert_eq!(structure(&tokens), expected);
+        // Without error recovery, `c` continues the block of `a`.
+        let expected = ["main", "{", "a", "b", "c", "d", "}", "e"];
+        assert_eq!(structure(&run(code).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_recovery_valid_code() {
+        let code = "main =\n    x = 'a `b` \\n'\n    y = '''\n        c `d`\n    x + y\n";
+        let (tokens, diagnostics) = lex_with_recovery(code);
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(tokens, run(code).unwrap());
+    }
+
     #[test]
     fn test_case_utf_8_idents() {
         test_lexer_many(lexer_case_idents(&[
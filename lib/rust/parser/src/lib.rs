@@ -8,8 +8,10 @@
 //! # Building macro registry.
 //! Macros in Enso are a very powerful mechanism and are used to transform group of tokens into
 //! almost any statement. First, macros need to be discovered and registered. Currently, there is no
-//! real macro discovery process, as there is no support for user-defined macros. Instead, there is
-//! a set of hardcoded macros defined in the compiler.
+//! real macro discovery process. Instead, there is a set of hardcoded macros defined in the
+//! compiler, see [`macros::built_in`]. Additional macros can be registered in the parser's
+//! [`macros::resolver::MacroMap`], either by providing a [`macros::Definition`] at runtime, or by
+//! loading a macro description file, see [`macros::declarative`].
 //!
 //! Each macro defines one or more segments. Every segment starts with a predefined token and can
 //! contain any number of other tokens. For example, the macro `if ... then ... else ...` contains
@@ -111,9 +113,14 @@ use crate::prelude::*;
 // === Export ===
 // ==============
 
+pub mod diagnostics;
+pub mod formatter;
+pub mod incremental;
 pub mod lexer;
+pub mod lint;
 pub mod macros;
 pub mod metadata;
+pub mod query;
 pub mod serialization;
 pub mod source;
 pub mod syntax;
@@ -163,6 +170,49 @@ pub mod prelude {
 
 
 
+// =============
+// === Error ===
+// =============
+
+/// Result of an operation on source code that may fail because of its input, such as formatting
+/// code or parsing a query.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error of an operation on source code, with a human-readable description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// The description of the error.
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        message.to_owned().into()
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        error.to_string().into()
+    }
+}
+
+
+
 // ==============
 // === Parser ===
 // ==============
@@ -183,15 +233,47 @@ impl Parser {
 
     /// Main entry point.
     pub fn run<'s>(&self, code: &'s str) -> syntax::Tree<'s> {
-        let tokens = lexer::run(code);
+        self.resolve(lexer::run(code))
+    }
+
+    fn resolve<'s>(&self, tokens: ParseResult<Vec<syntax::Token<'s>>>) -> syntax::Tree<'s> {
         let resolver = macros::resolver::Resolver::new_statement();
         let result = tokens.map(|tokens| resolver.run(&self.macros, tokens));
         let value = result.value;
         if let Some(error) = result.internal_error {
-            return value.with_error(format!("Internal error: {error}"));
+            let error = format!("Internal error: {error}");
+            return value.with_error_code(diagnostics::ErrorCode::InternalError, error);
         }
         value
     }
+
+    /// Parse the code, and return the resulting tree together with the list of syntax errors it
+    /// contains. See the [`diagnostics`] module to learn more.
+    ///
+    /// The code is lexed in error-recovery mode, which limits the effect of some errors on the rest
+    /// of the module; see the [`lexer`] module to learn more.
+    pub fn run_with_diagnostics<'s>(
+        &self,
+        code: &'s str,
+    ) -> (syntax::Tree<'s>, diagnostics::Diagnostics) {
+        let (tokens, lexer_diagnostics) = lexer::run_with_recovery(code);
+        let tree = self.resolve(tokens);
+        let mut diagnostics = diagnostics::collect(&tree, code, &self.macros);
+        diagnostics.merge(lexer_diagnostics);
+        (tree, diagnostics)
+    }
+
+    /// Incremental entry point. Parse `code`, which is the result of applying `edit` to the source
+    /// code previously parsed into `tree`. Only the lines of the root block that are affected by
+    /// the edit are lexed and resolved again; see the [`incremental`] module to learn more.
+    pub fn reparse<'s>(
+        &self,
+        tree: syntax::Tree<'s>,
+        edit: &incremental::Edit,
+        code: &'s str,
+    ) -> syntax::Tree<'s> {
+        incremental::reparse(&self.macros, tree, edit, code, |code| self.run(code))
+    }
 }
 
 impl Default for Parser {
@@ -0,0 +1,449 @@
+//! Style and consistency checks of Enso source code that do not require the compiler.
+//!
+//! A [`Linter`] runs a set of [`Rule`]s over a parsed module. Each rule inspects the module's
+//! syntax tree through a [`Module`], which gives access to all the trees and tokens of the module
+//! together with their positions in the source code, and reports [`Finding`]s. The built-in rules
+//! are defined in [`rules`]; additional rules can be added with [`Linter::register`].
+//!
+//! # Configuration
+//! Rules are configured per rule name with a JSON [`Config`], for example:
+//!
+//! ```text
+//! {
+//!   "rules": {
+//!     "unused-binding": { "severity": "error" },
+//!     "shadowed-name": { "enabled": false },
+//!     "todo-in-doc": { "options": { "markers": ["TODO", "FIXME"] } }
+//!   }
+//! }
+//! ```
+//!
+//! Rules that are not mentioned in the configuration are enabled, with the warning severity.
+//!
+//! # Output
+//! A [`Report`] can be printed in a human-readable format, with one line per finding, or serialized
+//! to JSON.
+
+use crate::prelude::*;
+
+use crate::diagnostics::Severity;
+use crate::diagnostics::Suggestion;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::Tree;
+use crate::Result;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::ptr;
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod rules;
+
+
+
+// ============
+// === Rule ===
+// ============
+
+/// A check of a module.
+pub trait Rule: Debug {
+    /// The identifier of the rule, in kebab-case, e.g. `"unused-binding"`. It is used in the
+    /// configuration and in the output.
+    fn name(&self) -> &'static str;
+
+    /// A short description of what the rule checks.
+    fn description(&self) -> &'static str;
+
+    /// Apply rule-specific options from the configuration. The options are `null` if the
+    /// configuration does not specify any. By default, a rule has no options.
+    fn configure(&mut self, options: &serde_json::Value) -> Result<()> {
+        match options {
+            serde_json::Value::Null => Ok(()),
+            _ => Err(format!("Rule `{}` has no options.", self.name()).into()),
+        }
+    }
+
+    /// Check the module, reporting any problems found.
+    fn check(&self, module: &Module, reporter: &mut Reporter);
+}
+
+
+
+// ==============
+// === Module ===
+// ==============
+
+/// A parsed module being checked. Provides the positions of the trees and tokens of the module.
+#[derive(Debug)]
+pub struct Module<'s, 'a> {
+    /// The source code of the module.
+    pub code: &'a str,
+    /// The syntax tree of the module.
+    pub tree: &'a Tree<'s>,
+    trees:    Vec<&'a Tree<'s>>,
+    tokens:   Vec<(token::Ref<'s, 'a>, Range<usize>)>,
+    spans:    HashMap<*const Tree<'s>, Range<usize>>,
+}
+
+impl<'s: 'a, 'a> Module<'s, 'a> {
+    /// Constructor. The tree must be the result of parsing `code`.
+    pub fn new(tree: &'a Tree<'s>, code: &'a str) -> Self {
+        let mut trees = vec![];
+        let mut tokens = vec![];
+        let mut spans = HashMap::new();
+        tree::visit_item_spans(tree, |item, span| match item {
+            item::Ref::Tree(tree) => {
+                trees.push(tree);
+                spans.insert(ptr::addr_of!(*tree), span);
+            }
+            item::Ref::Token(token) => tokens.push((token, span)),
+        });
+        Self { code, tree, trees, tokens, spans }
+    }
+
+    /// All the trees of the module, in pre-order.
+    pub fn trees(&self) -> impl Iterator<Item = &'a Tree<'s>> + '_ {
+        self.trees.iter().copied()
+    }
+
+    /// All the tokens of the module, in the order of the source code, with their byte ranges.
+    pub fn tokens(&self) -> &[(token::Ref<'s, 'a>, Range<usize>)] {
+        &self.tokens
+    }
+
+    /// The byte range of the code of a tree of this module, excluding the leading whitespace.
+    pub fn span(&self, tree: &Tree<'s>) -> Range<usize> {
+        self.spans.get(&ptr::addr_of!(*tree)).cloned().unwrap_or_default()
+    }
+
+    /// Return whether the tree is the root of the module.
+    pub fn is_root(&self, tree: &Tree<'s>) -> bool {
+        ptr::eq(tree, self.tree)
+    }
+}
+
+/// Return the trees that are direct children of the given tree.
+pub fn children<'s: 'a, 'a>(tree: &'a Tree<'s>) -> Vec<&'a Tree<'s>> {
+    let children = tree::children_of(&*tree.variant).into_iter();
+    let trees = children.filter_map(|item| match item {
+        item::Ref::Tree(tree) => Some(tree),
+        item::Ref::Token(_) => None,
+    });
+    trees.collect()
+}
+
+
+
+// ===============
+// === Finding ===
+// ===============
+
+/// A problem reported by a [`Rule`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// The name of the rule that reported the problem.
+    pub rule:        String,
+    /// How serious the problem is, as configured for the rule.
+    pub severity:    Severity,
+    /// The byte range of the code the problem applies to.
+    pub span:        Range<usize>,
+    /// Human-readable description of the problem.
+    pub message:     String,
+    /// Edits that would fix the problem, if any could be determined.
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Collects the findings of a rule.
+#[derive(Debug)]
+pub struct Reporter<'r> {
+    rule:     &'static str,
+    severity: Severity,
+    findings: &'r mut Vec<Finding>,
+}
+
+impl<'r> Reporter<'r> {
+    /// Report a problem. Suggested fixes can be added to the returned finding.
+    pub fn report(&mut self, span: Range<usize>, message: impl Into<String>) -> &mut Finding {
+        let rule = self.rule.to_owned();
+        let message = message.into();
+        let severity = self.severity;
+        self.findings.push(Finding { rule, severity, span, message, suggestions: default() });
+        self.findings.last_mut().unwrap()
+    }
+}
+
+
+
+// ==============
+// === Report ===
+// ==============
+
+/// The findings of all rules for a module, ordered by their position in the source code.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deref, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Report {
+    findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Return whether any finding of [`Severity::Error`] was reported.
+    pub fn has_errors(&self) -> bool {
+        self.findings.iter().any(|finding| finding.severity == Severity::Error)
+    }
+
+    /// Return the findings as a JSON array.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Findings are always serializable.")
+    }
+
+    /// Format the findings for display, one line per finding, followed by a line per suggested fix.
+    /// Positions are given as 1-based line and column numbers in `code`, which must be the source
+    /// code of the module the report was created for.
+    pub fn to_human(&self, path: &str, code: &str) -> String {
+        let mut out = String::new();
+        for finding in &self.findings {
+            let before = &code[..finding.span.start];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+            let severity = match finding.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            let rule = &finding.rule;
+            let message = &finding.message;
+            out.push_str(&format!("{path}:{line}:{column}: {severity}[{rule}]: {message}\n"));
+            for suggestion in &finding.suggestions {
+                out.push_str(&format!("    help: {}\n", suggestion.message));
+            }
+        }
+        out
+    }
+}
+
+impl IntoIterator for Report {
+    type Item = Finding;
+    type IntoIter = std::vec::IntoIter<Finding>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.findings.into_iter()
+    }
+}
+
+
+
+// ==============
+// === Config ===
+// ==============
+
+/// Configuration of a [`Linter`]. See the module docs for the format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Configuration of the rules, by rule name.
+    pub rules: BTreeMap<String, RuleConfig>,
+}
+
+impl Config {
+    /// Parse a configuration from JSON.
+    pub fn parse(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Configuration of a single [`Rule`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConfig {
+    /// Whether the rule is run.
+    pub enabled:  bool,
+    /// The severity of the findings of the rule; if not set, the rule's findings are warnings.
+    pub severity: Option<Severity>,
+    /// Rule-specific options, see [`Rule::configure`].
+    pub options:  serde_json::Value,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self { enabled: true, severity: None, options: default() }
+    }
+}
+
+
+
+// ==============
+// === Linter ===
+// ==============
+
+/// A set of configured rules.
+#[derive(Debug)]
+pub struct Linter {
+    rules: Vec<ConfiguredRule>,
+}
+
+#[derive(Debug)]
+struct ConfiguredRule {
+    rule:     Box<dyn Rule>,
+    enabled:  bool,
+    severity: Severity,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        let mut linter = Self { rules: default() };
+        for rule in rules::built_in() {
+            linter.register(rule);
+        }
+        linter
+    }
+}
+
+impl Linter {
+    /// Constructor. The linter runs all the built-in rules, with their default configuration.
+    pub fn new() -> Self {
+        default()
+    }
+
+    /// Constructor. The linter runs the built-in rules, configured by `config`.
+    pub fn with_config(config: &Config) -> Result<Self> {
+        let mut linter = Self::new();
+        linter.configure(config)?;
+        Ok(linter)
+    }
+
+    /// Add a rule. It replaces any rule with the same name.
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
+        self.rules.retain(|configured| configured.rule.name() != rule.name());
+        self.rules.push(ConfiguredRule { rule, enabled: true, severity: Severity::Warning });
+    }
+
+    /// Apply the configuration to the registered rules. Fails if the configuration refers to a rule
+    /// that is not registered, or if the options of a rule are invalid.
+    pub fn configure(&mut self, config: &Config) -> Result<()> {
+        for (name, rule_config) in &config.rules {
+            let configured =
+                self.rules.iter_mut().find(|configured| configured.rule.name() == name);
+            let configured = configured.ok_or_else(|| format!("Unknown lint rule `{name}`."))?;
+            configured.rule.configure(&rule_config.options)?;
+            configured.enabled = rule_config.enabled;
+            configured.severity = rule_config.severity.unwrap_or(Severity::Warning);
+        }
+        Ok(())
+    }
+
+    /// The enabled rules.
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().filter(|configured| configured.enabled).map(|c| c.rule.as_ref())
+    }
+
+    /// Run the enabled rules over the tree, which must be the result of parsing `code`.
+    pub fn run(&self, tree: &Tree, code: &str) -> Report {
+        let module = Module::new(tree, code);
+        let mut findings = vec![];
+        for configured in self.rules.iter().filter(|configured| configured.enabled) {
+            let rule = configured.rule.name();
+            let severity = configured.severity;
+            let mut reporter = Reporter { rule, severity, findings: &mut findings };
+            configured.rule.check(&module, &mut reporter);
+        }
+        findings.sort_by_key(|finding| finding.span.start);
+        Report { findings }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn lint(linter: &Linter, code: &str) -> Report {
+        let tree = Parser::new().run(code);
+        linter.run(&tree, code)
+    }
+
+    const CODE: &str = "main =\n    x = 1\n    y = 2\n    y\n";
+
+    #[test]
+    fn configuration() {
+        let report = lint(&Linter::new(), CODE);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].rule, "unused-binding");
+        assert_eq!(report[0].severity, Severity::Warning);
+        assert!(!report.has_errors());
+
+        let config = Config::parse(r#"{"rules": {"unused-binding": {"severity": "error"}}}"#);
+        let report = lint(&Linter::with_config(&config.unwrap()).unwrap(), CODE);
+        assert_eq!(report[0].severity, Severity::Error);
+        assert!(report.has_errors());
+
+        let config = Config::parse(r#"{"rules": {"unused-binding": {"enabled": false}}}"#);
+        let linter = Linter::with_config(&config.unwrap()).unwrap();
+        assert!(linter.rules().all(|rule| rule.name() != "unused-binding"));
+        assert_eq!(lint(&linter, CODE), Report::default());
+    }
+
+    #[test]
+    fn invalid_configuration() {
+        let unknown = Config::parse(r#"{"rules": {"no-such-rule": {}}}"#).unwrap();
+        assert!(Linter::with_config(&unknown).is_err());
+        let options = r#"{"rules": {"unused-binding": {"options": {"a": 1}}}}"#;
+        assert!(Linter::with_config(&Config::parse(options).unwrap()).is_err());
+        assert!(Config::parse(r#"{"rules": {"unused-binding": {"severity": "fatal"}}}"#).is_err());
+    }
+
+    /// A rule reporting every number literal.
+    #[derive(Debug)]
+    struct NoNumbers;
+
+    impl Rule for NoNumbers {
+        fn name(&self) -> &'static str {
+            "no-numbers"
+        }
+
+        fn description(&self) -> &'static str {
+            "Number literals are not allowed."
+        }
+
+        fn check(&self, module: &Module, reporter: &mut Reporter) {
+            for tree in module.trees() {
+                if let tree::Variant::Number(_) = &*tree.variant {
+                    reporter.report(module.span(tree), "Number literal.");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn custom_rule() {
+        let mut linter = Linter::new();
+        linter.register(Box::new(NoNumbers));
+        let report = lint(&linter, CODE);
+        let rules: Vec<_> = report.iter().map(|finding| finding.rule.as_str()).collect();
+        assert_eq!(rules, vec!["unused-binding", "no-numbers", "no-numbers"]);
+        assert_eq!(&CODE[report[1].span.clone()], "1");
+    }
+
+    #[test]
+    fn output() {
+        let report = lint(&Linter::new(), CODE);
+        let human = report.to_human("main.enso", CODE);
+        assert_eq!(
+            human,
+            "main.enso:2:5: warning[unused-binding]: `x` is assigned but never used.\n"
+        );
+        let json = report.to_json();
+        assert!(json.contains("\"rule\":\"unused-binding\""), "{json}");
+        let deserialized: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, report);
+    }
+}
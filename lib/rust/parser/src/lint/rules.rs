@@ -0,0 +1,432 @@
+//! The built-in lint rules.
+
+use crate::prelude::*;
+
+use crate::diagnostics::Suggestion;
+use crate::lint::children;
+use crate::lint::Module;
+use crate::lint::Reporter;
+use crate::lint::Rule;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::ItemVisitable;
+use crate::syntax::tree::Variant;
+use crate::syntax::Tree;
+use crate::Result;
+
+
+
+/// All the built-in rules, with their default options.
+pub fn built_in() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(UnusedBinding),
+        Box::new(ShadowedName),
+        Box::new(OperatorSpacing),
+        Box::new(EmptyCaseBranch),
+        Box::new(TodoInDoc::default()),
+    ]
+}
+
+/// If the pattern binds a single variable, return its name.
+fn binding_name<'a>(pattern: &'a Tree) -> Option<&'a str> {
+    match &*pattern.variant {
+        Variant::Ident(tree::Ident { token }) if !token.is_type => Some(&*token.code.repr),
+        _ => None,
+    }
+}
+
+
+
+// =====================
+// === UnusedBinding ===
+// =====================
+
+/// Reports local variables that are not used by any later statement of their block.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnusedBinding;
+
+impl Rule for UnusedBinding {
+    fn name(&self) -> &'static str {
+        "unused-binding"
+    }
+
+    fn description(&self) -> &'static str {
+        "A local variable is assigned, but never used."
+    }
+
+    fn check(&self, module: &Module, reporter: &mut Reporter) {
+        for tree in module.trees() {
+            // The bindings of the root block are methods of the module.
+            let Variant::BodyBlock(block) = &*tree.variant else { continue };
+            if module.is_root(tree) {
+                continue;
+            }
+            let statements = block.statements.iter().filter_map(|line| line.expression.as_ref());
+            let statements: Vec<_> = statements.collect();
+            for (i, statement) in statements.iter().enumerate() {
+                let Variant::Assignment(assignment) = &*statement.variant else { continue };
+                let Some(name) = binding_name(&assignment.pattern) else { continue };
+                if !statements[i + 1..].iter().any(|statement| mentions(statement, name)) {
+                    let message = format!("`{name}` is assigned but never used.");
+                    reporter.report(module.span(&assignment.pattern), message);
+                }
+            }
+        }
+    }
+}
+
+/// Return whether the tree contains a use of the variable. A variable that is only assigned is not
+/// used.
+fn mentions(tree: &Tree, name: &str) -> bool {
+    let mut finder = MentionFinder { name, found: false };
+    tree.visit_item(&mut finder);
+    finder.found
+}
+
+struct MentionFinder<'n> {
+    name:  &'n str,
+    found: bool,
+}
+
+impl<'n> tree::Visitor for MentionFinder<'n> {}
+impl<'s: 'a, 'a, 'n> tree::ItemVisitor<'s, 'a> for MentionFinder<'n> {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        match item {
+            item::Ref::Tree(tree) => match &*tree.variant {
+                Variant::Assignment(assignment) if binding_name(&assignment.pattern).is_some() => {
+                    assignment.expr.visit_item(self);
+                    false
+                }
+                _ => !self.found,
+            },
+            item::Ref::Token(token) => {
+                let is_ident = matches!(token.data, token::Variant::Ident(_));
+                self.found |= is_ident && token.code.repr == self.name;
+                false
+            }
+        }
+    }
+}
+
+
+
+// ====================
+// === ShadowedName ===
+// ====================
+
+/// Reports local variables and function arguments with the same name as a variable that is in
+/// scope where they are defined.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShadowedName;
+
+impl Rule for ShadowedName {
+    fn name(&self) -> &'static str {
+        "shadowed-name"
+    }
+
+    fn description(&self) -> &'static str {
+        "A local variable or argument hides another variable with the same name."
+    }
+
+    fn check(&self, module: &Module, reporter: &mut Reporter) {
+        let mut scopes = vec![];
+        check_scopes(module, module.tree, &mut scopes, reporter);
+    }
+}
+
+/// Check the bindings of the tree; `scopes` contains the names bound in each enclosing scope.
+fn check_scopes<'s>(
+    module: &Module<'s, '_>,
+    tree: &Tree<'s>,
+    scopes: &mut Vec<Vec<String>>,
+    reporter: &mut Reporter,
+) {
+    match &*tree.variant {
+        Variant::Function(function) => {
+            scopes.push(default());
+            for argument in &function.args {
+                bind(module, &argument.pattern, scopes, reporter);
+            }
+            if let Some(body) = &function.body {
+                check_scopes(module, body, scopes, reporter);
+            }
+            scopes.pop();
+        }
+        Variant::Assignment(assignment) => {
+            check_scopes(module, &assignment.expr, scopes, reporter);
+            bind(module, &assignment.pattern, scopes, reporter);
+        }
+        // The bindings of the root block are methods of the module.
+        Variant::BodyBlock(_) if !module.is_root(tree) => {
+            scopes.push(default());
+            for child in children(tree) {
+                check_scopes(module, child, scopes, reporter);
+            }
+            scopes.pop();
+        }
+        _ =>
+            for child in children(tree) {
+                check_scopes(module, child, scopes, reporter);
+            },
+    }
+}
+
+/// Add the variable bound by the pattern to the innermost scope, reporting it if it is already in
+/// scope.
+fn bind<'s>(
+    module: &Module<'s, '_>,
+    pattern: &Tree<'s>,
+    scopes: &mut [Vec<String>],
+    reporter: &mut Reporter,
+) {
+    if let Some(name) = binding_name(pattern) {
+        if scopes.iter().any(|scope| scope.iter().any(|bound| bound == name)) {
+            let message = format!("`{name}` shadows a variable with the same name.");
+            reporter.report(module.span(pattern), message);
+        }
+        if let Some(scope) = scopes.last_mut() {
+            scope.push(name.to_owned());
+        }
+    }
+}
+
+
+
+// =======================
+// === OperatorSpacing ===
+// =======================
+
+/// Reports binary operators with whitespace on only one side. In Enso, spacing affects operator
+/// precedence: `a+ b * c` means `(a + b) * c`, which is easily misread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OperatorSpacing;
+
+impl Rule for OperatorSpacing {
+    fn name(&self) -> &'static str {
+        "operator-spacing"
+    }
+
+    fn description(&self) -> &'static str {
+        "A binary operator has whitespace on one side only, which affects its precedence."
+    }
+
+    fn check(&self, module: &Module, reporter: &mut Reporter) {
+        for tree in module.trees() {
+            let Variant::OprApp(tree::OprApp { lhs: Some(lhs), opr: Ok(opr), rhs: Some(rhs) }) =
+                &*tree.variant else { continue };
+            if opr.code.repr == "," {
+                continue;
+            }
+            let spaced_before = !opr.left_offset.is_empty();
+            let spaced_after = !rhs.span.left_offset.is_empty();
+            if spaced_before == spaced_after {
+                continue;
+            }
+            let start = module.span(lhs).end + opr.left_offset.code.repr.len();
+            let end = start + opr.code.repr.len();
+            let message = format!("Operator `{}` has whitespace on one side only.", opr.code);
+            let suggestion = if spaced_before {
+                Suggestion::insert(end, " ")
+            } else {
+                Suggestion::insert(start, " ")
+            };
+            reporter.report(start..end, message).suggestions.push(suggestion);
+        }
+    }
+}
+
+
+
+// =======================
+// === EmptyCaseBranch ===
+// =======================
+
+/// Reports `case` expressions without branches, and branches without an expression.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EmptyCaseBranch;
+
+impl Rule for EmptyCaseBranch {
+    fn name(&self) -> &'static str {
+        "empty-case-branch"
+    }
+
+    fn description(&self) -> &'static str {
+        "A `case` expression has no branches, or a branch has no expression."
+    }
+
+    fn check(&self, module: &Module, reporter: &mut Reporter) {
+        for tree in module.trees() {
+            let Variant::CaseOf(case_of) = &*tree.variant else { continue };
+            let cases = case_of.cases.iter().filter_map(|line| line.case.as_ref());
+            // Lines containing only documentation are not branches.
+            let branches = cases.filter(|case| case.pattern.is_some() || case.arrow.is_some());
+            let branches: Vec<_> = branches.collect();
+            for branch in &branches {
+                if branch.expression.is_none() {
+                    let span = branch.pattern.as_ref().map(|pattern| module.span(pattern));
+                    let span = span.unwrap_or_else(|| module.span(tree));
+                    reporter.report(span, "The branch has no expression.");
+                }
+            }
+            if branches.is_empty() {
+                reporter.report(module.span(tree), "The `case` expression has no branches.");
+            }
+        }
+    }
+}
+
+
+
+// =================
+// === TodoInDoc ===
+// =================
+
+/// Reports markers of unfinished work, such as `TODO`, in documentation comments. Options:
+/// `{ "markers": [<string>, ...] }`.
+#[derive(Clone, Debug)]
+pub struct TodoInDoc {
+    /// The words that are reported.
+    pub markers: Vec<String>,
+}
+
+impl Default for TodoInDoc {
+    fn default() -> Self {
+        Self { markers: vec!["TODO".into()] }
+    }
+}
+
+#[derive(Deserialize)]
+struct TodoInDocOptions {
+    markers: Vec<String>,
+}
+
+impl Rule for TodoInDoc {
+    fn name(&self) -> &'static str {
+        "todo-in-doc"
+    }
+
+    fn description(&self) -> &'static str {
+        "A documentation comment contains a marker of unfinished work, such as `TODO`."
+    }
+
+    fn configure(&mut self, options: &serde_json::Value) -> Result<()> {
+        if !options.is_null() {
+            let options: TodoInDocOptions = serde_json::from_value(options.clone())?;
+            self.markers = options.markers;
+        }
+        Ok(())
+    }
+
+    fn check(&self, module: &Module, reporter: &mut Reporter) {
+        let mut in_documentation = false;
+        for (token, span) in module.tokens() {
+            match token.data {
+                token::Variant::TextStart(_) =>
+                    in_documentation = token.code.repr.starts_with("##"),
+                token::Variant::TextSection(_) if in_documentation =>
+                    for (offset, marker) in self.find_markers(&token.code.repr) {
+                        let start = span.start + offset;
+                        let message = format!("Documentation contains `{marker}`.");
+                        reporter.report(start..start + marker.len(), message);
+                    },
+                token::Variant::TextEscape(_) | token::Variant::TextNewline(_) => {}
+                _ => in_documentation = false,
+            }
+        }
+    }
+}
+
+impl TodoInDoc {
+    /// Return the byte offsets of the markers occurring as whole words in the text.
+    fn find_markers<'t>(&'t self, text: &str) -> Vec<(usize, &'t str)> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut found = vec![];
+        for marker in self.markers.iter().filter(|marker| !marker.is_empty()) {
+            for (offset, _) in text.match_indices(marker.as_str()) {
+                let before = text[..offset].chars().next_back();
+                let after = text[offset + marker.len()..].chars().next();
+                if !before.map_or(false, is_word_char) && !after.map_or(false, is_word_char) {
+                    found.push((offset, marker.as_str()));
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::Linter;
+    use crate::Parser;
+
+    /// Run a single rule, returning the code of each finding's span.
+    fn check(rule: impl Rule + 'static, code: &str) -> Vec<String> {
+        let tree = Parser::new().run(code);
+        let mut linter = Linter::new();
+        let name = rule.name();
+        linter.register(Box::new(rule));
+        let report = linter.run(&tree, code);
+        let findings = report.iter().filter(|finding| finding.rule == name);
+        findings.map(|finding| code[finding.span.clone()].to_string()).collect()
+    }
+
+    #[test]
+    fn unused_binding() {
+        let code = "main =\n    x = 1\n    y = x + 1\n    z = 2\n    y\n";
+        assert_eq!(check(UnusedBinding, code), vec!["z"]);
+        let code = "main =\n    x = 1\n    x = 2\n    x\n";
+        assert_eq!(check(UnusedBinding, code), Vec::<String>::new());
+        let code = "main =\n    x = 1\n    f =\n        x + 1\n    f\n";
+        assert_eq!(check(UnusedBinding, code), Vec::<String>::new());
+        assert_eq!(check(UnusedBinding, "x = 1\ny = 2\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn shadowed_name() {
+        let code = "foo a =\n    b = a\n    a = b\n    a\n";
+        assert_eq!(check(ShadowedName, code), vec!["a"]);
+        let code = "foo a =\n    b = a\n    c =\n        b = 1\n        b\n    c\n";
+        assert_eq!(check(ShadowedName, code), vec!["b"]);
+        let code = "foo a =\n    b = a\n    b\nbar a =\n    b = a\n    b\n";
+        assert_eq!(check(ShadowedName, code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn operator_spacing() {
+        assert_eq!(check(OperatorSpacing, "x = a+ b * c"), vec!["+"]);
+        assert_eq!(check(OperatorSpacing, "x = a + b*c\ny = a.b"), Vec::<String>::new());
+        let tree = Parser::new().run("x = a+ b");
+        let report = Linter::new().run(&tree, "x = a+ b");
+        let suggestion = &report[0].suggestions[0];
+        assert_eq!((suggestion.span.clone(), suggestion.replacement.as_str()), (5..5, " "));
+    }
+
+    #[test]
+    fn empty_case_branch() {
+        let code = "foo x = case x of\n    1 -> a\n    2 ->\n";
+        assert_eq!(check(EmptyCaseBranch, code), vec!["2"]);
+        let code = "foo x = case x of\n    1 -> a\n    _ -> b\n";
+        assert_eq!(check(EmptyCaseBranch, code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn todo_in_doc() {
+        let code =
+            "## Computes the result.\n   TODO: Handle errors. TODOS\nfoo = 1\n# TODO: not a doc\n";
+        assert_eq!(check(TodoInDoc::default(), code), vec!["TODO"]);
+        let mut rule = TodoInDoc::default();
+        rule.configure(&serde_json::json!({ "markers": ["FIXME", "TODO"] })).unwrap();
+        let code = "## FIXME later.\nfoo = 1\n";
+        assert_eq!(check(rule, code), vec!["FIXME"]);
+        assert!(TodoInDoc::default().configure(&serde_json::json!({ "marker": [] })).is_err());
+    }
+}
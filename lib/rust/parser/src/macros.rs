@@ -15,6 +15,7 @@ use enso_data_structures::im_list;
 // ==============
 
 pub mod built_in;
+pub mod declarative;
 pub mod expand;
 pub mod pattern;
 pub mod resolver;
@@ -44,6 +45,23 @@ pub struct Definition<'a> {
     pub body:     Rc<DefinitionBody>,
 }
 
+impl<'a> Definition<'a> {
+    /// Constructor. Returns [`None`] if no segments are provided.
+    pub fn new(
+        segments: Vec<SegmentDefinition<'a>>,
+        body: impl for<'s> Fn(pattern::MatchedSegments<'s>) -> syntax::Tree<'s> + 'static,
+    ) -> Option<Self> {
+        let segments = im_list::NonEmpty::try_from(segments).ok()?;
+        Some(Self { segments, body: Rc::new(body) })
+    }
+
+    /// Constructor of a macro that is represented in the syntax tree as a
+    /// [`syntax::tree::MultiSegmentApp`]. Returns [`None`] if no segments are provided.
+    pub fn multi_segment_app(segments: Vec<SegmentDefinition<'a>>) -> Option<Self> {
+        Self::new(segments, matched_segments_into_multi_segment_app)
+    }
+}
+
 /// A function that transforms matched macro tokens into [`syntax::Tree`].
 pub type DefinitionBody = dyn for<'s> Fn(pattern::MatchedSegments<'s>) -> syntax::Tree<'s>;
 
@@ -3,6 +3,7 @@
 use crate::macros::pattern::*;
 use crate::macros::*;
 
+use crate::diagnostics::ErrorCode;
 use crate::syntax::operator;
 
 
@@ -122,7 +123,8 @@ fn import_body(segments: NonEmptyVec<MatchedSegment>) -> syntax::Tree {
     }
     let import = syntax::Tree::import(polyglot, from, import.unwrap(), all, as_, hiding);
     if incomplete_import {
-        return import.with_error("Expected name or `all` keyword following `import` keyword.");
+        let error = "Expected name or `all` keyword following `import` keyword.";
+        return import.with_error_code(ErrorCode::ExpectedIdentifier, error);
     }
     import
 }
@@ -201,7 +203,8 @@ fn export_body(segments: NonEmptyVec<MatchedSegment>) -> syntax::Tree {
     }
     let export = syntax::Tree::export(from, export.unwrap(), all, as_, hiding);
     if incomplete_export {
-        return export.with_error("Expected name or `all` keyword following `export` keyword.");
+        let error = "Expected name or `all` keyword following `export` keyword.";
+        return export.with_error_code(ErrorCode::ExpectedIdentifier, error);
     }
     export
 }
@@ -279,7 +282,10 @@ fn type_def_body(matched_segments: NonEmptyVec<MatchedSegment>) -> syntax::Tree
             code,
             variant: syntax::token::Variant::Ident(ident),
         })) => syntax::Token(left_offset, code, ident),
-        _ => return Tree::ident(header).with_error("Expected identifier after `type` keyword."),
+        _ => {
+            let error = "Expected identifier after `type` keyword.";
+            return Tree::ident(header).with_error_code(ErrorCode::ExpectedIdentifier, error);
+        }
     };
     let mut precedence = operator::Precedence::new();
     let params = precedence
@@ -421,7 +427,7 @@ fn case_body(segments: NonEmptyVec<MatchedSegment>) -> syntax::Tree {
     let (case_lines, any_invalid) = case_builder.finish();
     let tree = Tree::case_of(case_, expression, of_, case_lines);
     if any_invalid {
-        return tree.with_error("Invalid case expression.");
+        return tree.with_error_code(ErrorCode::InvalidCase, "Invalid case expression.");
     }
     tree
 }
@@ -614,7 +620,8 @@ fn sequence_tree<'s>(
         tree = Tree::opr_app(tree, Ok(operator), body.map(&mut f)).into();
     }
     if invalid {
-        tree = tree.map(|tree| tree.with_error("Malformed comma-delimited sequence."));
+        let error = "Malformed comma-delimited sequence.";
+        tree = tree.map(|tree| tree.with_error_code(ErrorCode::MalformedSequence, error));
     }
     tree
 }
@@ -668,24 +675,34 @@ fn foreign_body(segments: NonEmptyVec<MatchedSegment>) -> syntax::Tree {
             Some(rhs) => syntax::Tree::app(keyword.into(), rhs),
             None => keyword.into(),
         })
-        .with_error(error),
+        .with_error_code(error.0, error.1),
     }
 }
 
 fn try_foreign_body<'s>(
     keyword: syntax::token::Ident<'s>,
     tokens: impl IntoIterator<Item = syntax::Item<'s>>,
-) -> Result<syntax::Tree, &'static str> {
+) -> Result<syntax::Tree, (ErrorCode, &'static str)> {
     let mut tokens = tokens.into_iter();
+    let expected_language = (
+        ErrorCode::ExpectedIdentifier,
+        "Expected an identifier specifying foreign method's language.",
+    );
     let language = tokens
         .next()
         .and_then(try_into_token)
         .and_then(try_token_into_ident)
-        .ok_or("Expected an identifier specifying foreign method's language.")?;
-    let expected_name = "Expected an identifier specifying foreign function's name.";
+        .ok_or(expected_language)?;
+    let expected_name = (
+        ErrorCode::ExpectedIdentifier,
+        "Expected an identifier specifying foreign function's name.",
+    );
     let function =
         operator::resolve_operator_precedence_if_non_empty(tokens).ok_or(expected_name)?;
-    let expected_function = "Expected a function definition after foreign declaration.";
+    let expected_function = (
+        ErrorCode::ExpectedExpression,
+        "Expected a function definition after foreign declaration.",
+    );
     let box syntax::tree::Variant::OprApp(
             syntax::tree::OprApp { lhs: Some(lhs), opr: Ok(equals), rhs: Some(body) }) = function.variant else {
         return Err(expected_function)
@@ -694,7 +711,8 @@ fn try_foreign_body<'s>(
         return Err(expected_function);
     };
     if !matches!(body.variant, box syntax::tree::Variant::TextLiteral(_)) {
-        return Err("Expected a text literal as body of `foreign` declaration.");
+        let error = "Expected a text literal as body of `foreign` declaration.";
+        return Err((ErrorCode::ExpectedExpression, error));
     }
     let (name, args) = crate::collect_arguments(lhs);
     let mut name = try_tree_into_ident(name).ok_or(expected_name)?;
@@ -750,7 +768,7 @@ fn expect_ident(tree: syntax::Tree) -> syntax::Tree {
     if matches!(&*tree.variant, syntax::tree::Variant::Ident(_)) {
         tree
     } else {
-        tree.with_error("Expected identifier.")
+        tree.with_error_code(ErrorCode::ExpectedIdentifier, "Expected identifier.")
     }
 }
 
@@ -758,11 +776,11 @@ fn expect_qualified(tree: syntax::Tree) -> syntax::Tree {
     if crate::is_qualified_name(&tree) {
         tree
     } else {
-        tree.with_error("Expected qualified name.")
+        tree.with_error_code(ErrorCode::ExpectedIdentifier, "Expected qualified name.")
     }
 }
 
 fn expected_nonempty<'s>() -> syntax::Tree<'s> {
     let empty = syntax::Tree::ident(syntax::token::ident("", "", false, 0, false, false, false));
-    empty.with_error("Expected tokens.")
+    empty.with_error_code(ErrorCode::ExpectedExpression, "Expected tokens.")
 }
@@ -0,0 +1,287 @@
+//! Declarative macro definitions. Macros can be described in a JSON file, so that new syntax can be
+//! tried out without modifying the parser. For example, the following file defines an expression
+//! macro `when ... otherwise ...`:
+//!
+//! ```text
+//! [
+//!   {
+//!     "context": "expression",
+//!     "segments": [
+//!       { "header": "when", "pattern": "everything" },
+//!       { "header": "otherwise", "pattern": "everything" }
+//!     ]
+//!   }
+//! ]
+//! ```
+//!
+//! Patterns are described as follows:
+//! - `"everything"`, `"nothing"`, `"identifier"`: [`pattern::everything`], [`pattern::nothing`],
+//!   [`pattern::identifier`].
+//! - `{ "many": <pattern> }`: [`pattern::many`].
+//! - `{ "seq": [<pattern>, ...] }`, `{ "or": [<pattern>, ...] }`: [`pattern::seq`], [`pattern::or`]
+//!   of all the patterns in the list.
+//! - `{ "expected": { "message": <string>, "pattern": <pattern> } }`: [`pattern::expected`].
+//! - `{ "named": { "label": <string>, "pattern": <pattern> } }`: [`pattern::named`].
+//!
+//! Macros defined this way are represented in the syntax tree as
+//! [`syntax::tree::MultiSegmentApp`]s. To define a macro constructing any other tree, register a
+//! [`Definition`] with a custom body using [`MacroMap::register`].
+
+use crate::prelude::*;
+
+use crate::macros::pattern;
+use crate::macros::resolver::Context;
+use crate::macros::resolver::MacroMap;
+use crate::macros::Definition;
+use crate::macros::Pattern;
+use crate::macros::SegmentDefinition;
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+
+
+// ===================
+// === Description ===
+// ===================
+
+/// Result of loading macro descriptions.
+pub type Result<T = ()> = std::result::Result<T, String>;
+
+/// Description of a macro.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MacroDescription {
+    /// The context in which the macro can be used.
+    pub context:  Context,
+    /// The segments of the macro. There must be at least one segment.
+    pub segments: Vec<SegmentDescription>,
+}
+
+/// Description of a macro segment.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SegmentDescription {
+    /// The token starting the segment.
+    pub header:  String,
+    /// The pattern the tokens of the segment must match.
+    pub pattern: PatternDescription,
+}
+
+/// Description of a [`Pattern`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[allow(missing_docs)]
+pub enum PatternDescription {
+    Everything,
+    Nothing,
+    Identifier,
+    Many(Box<PatternDescription>),
+    Seq(Vec<PatternDescription>),
+    Or(Vec<PatternDescription>),
+    Expected { message: String, pattern: Box<PatternDescription> },
+    Named { label: String, pattern: Box<PatternDescription> },
+}
+
+impl PatternDescription {
+    /// Build the described pattern.
+    pub fn to_pattern(&self) -> Result<Pattern> {
+        Ok(match self {
+            Self::Everything => pattern::everything(),
+            Self::Nothing => pattern::nothing(),
+            Self::Identifier => pattern::identifier(),
+            Self::Many(item) => pattern::many(item.to_pattern()?),
+            Self::Seq(items) => {
+                let mut items = items.iter().map(|item| item.to_pattern()).rev();
+                let last = items.next().unwrap_or_else(|| Ok(pattern::nothing()))?;
+                items.try_fold(last, |rest, item| -> Result<_> { Ok(pattern::seq(item?, rest)) })?
+            }
+            Self::Or(items) => {
+                let mut items = items.iter().map(|item| item.to_pattern()).rev();
+                let last = items.next().ok_or("An `or` pattern must have alternatives.")??;
+                items.try_fold(last, |rest, item| -> Result<_> { Ok(pattern::or(item?, rest)) })?
+            }
+            Self::Expected { message, pattern } =>
+                pattern::expected(message, pattern.to_pattern()?),
+            Self::Named { label, pattern } => pattern::named(label, pattern.to_pattern()?),
+        })
+    }
+}
+
+impl MacroDescription {
+    /// Build the described macro definition.
+    ///
+    /// Macro definitions used by the parser live as long as the program, so the segment headers are
+    /// interned, see [`intern_header`].
+    pub fn to_definition(&self) -> Result<Definition<'static>> {
+        let segments = self.segments.iter().map(|segment| -> Result<_> {
+            if segment.header.is_empty() {
+                return Err("A macro segment header must not be empty.".into());
+            }
+            let header = intern_header(&segment.header);
+            Ok(SegmentDefinition::new(header, segment.pattern.to_pattern()?))
+        });
+        let segments = segments.collect::<Result<Vec<_>>>()?;
+        Definition::multi_segment_app(segments).ok_or_else(|| "A macro must have segments.".into())
+    }
+}
+
+/// Return the `'static` copy of the segment header. Every distinct header is allocated only once,
+/// so reloading the macro descriptions does not leak memory.
+fn intern_header(header: &str) -> &'static str {
+    static HEADERS: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut headers = HEADERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match headers.get(header) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(header.into());
+            headers.insert(interned);
+            interned
+        }
+    }
+}
+
+
+
+// ============
+// === Load ===
+// ============
+
+/// Parse macro descriptions from JSON.
+pub fn parse(json: &str) -> Result<Vec<MacroDescription>> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/// Register the macros described in JSON. If any of the descriptions is invalid, no macros are
+/// registered.
+pub fn register(macros: &mut MacroMap, json: &str) -> Result {
+    let descriptions = parse(json)?;
+    let definitions = descriptions.iter().map(|description| {
+        description.to_definition().map(|definition| (description.context, definition))
+    });
+    let definitions = definitions.collect::<Result<Vec<_>>>()?;
+    for (context, definition) in definitions {
+        macros.register(context, definition);
+    }
+    Ok(())
+}
+
+/// Register the macros described in the given JSON file.
+pub fn load(macros: &mut MacroMap, path: impl AsRef<Path>) -> Result {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    register(macros, &json).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax;
+    use crate::syntax::tree;
+    use crate::Parser;
+
+    /// Return the segment headers of the first [`tree::MultiSegmentApp`] in the tree.
+    fn segment_headers(tree: &syntax::Tree) -> Option<Vec<String>> {
+        let headers = RefCell::new(None);
+        tree.map(|tree| {
+            if let tree::Variant::MultiSegmentApp(app) = &*tree.variant {
+                let app_headers =
+                    app.segments.iter().map(|segment| segment.header.code.repr.to_string());
+                headers.borrow_mut().get_or_insert_with(|| app_headers.collect());
+            }
+        });
+        headers.into_inner()
+    }
+
+    const WHEN_OTHERWISE: &str = r#"[
+        {
+            "context": "expression",
+            "segments": [
+                { "header": "when", "pattern": "everything" },
+                { "header": "otherwise", "pattern": "everything" }
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn load_declarative_macro() {
+        let mut parser = Parser::new();
+        register(&mut parser.macros, WHEN_OTHERWISE).unwrap();
+        let tree = parser.run("x = when a b otherwise c");
+        assert_eq!(segment_headers(&tree), Some(vec!["when".into(), "otherwise".into()]));
+        let tree = Parser::new().run("x = when a b otherwise c");
+        assert_eq!(segment_headers(&tree), None);
+    }
+
+    #[test]
+    fn reloading_reuses_headers() {
+        let header = |macros: &MacroMap| *macros.expression.get_key_value("when").unwrap().0;
+        let mut first = MacroMap::default();
+        register(&mut first, WHEN_OTHERWISE).unwrap();
+        let mut second = MacroMap::default();
+        register(&mut second, WHEN_OTHERWISE).unwrap();
+        assert!(std::ptr::eq(header(&first), header(&second)));
+    }
+
+    #[test]
+    fn parse_patterns() {
+        let json = r#"[{
+            "context": "statement",
+            "segments": [{
+                "header": "define",
+                "pattern": { "seq": [
+                    { "expected": { "message": "Expected a name.", "pattern": "identifier" } },
+                    { "many": { "or": ["identifier", "nothing"] } },
+                    { "named": { "label": "body", "pattern": "everything" } }
+                ] }
+            }]
+        }]"#;
+        let descriptions = parse(json).unwrap();
+        assert_eq!(descriptions.len(), 1);
+        assert_eq!(descriptions[0].context, Context::Statement);
+        descriptions[0].to_definition().unwrap();
+    }
+
+    #[test]
+    fn invalid_descriptions() {
+        let mut macros = MacroMap::default();
+        assert!(register(&mut macros, "[{\"context\": \"expression\", \"segments\": []}]").is_err());
+        let empty_or = r#"[{"context": "expression", "segments": [
+            { "header": "when", "pattern": { "or": [] } }
+        ]}]"#;
+        assert!(register(&mut macros, empty_or).is_err());
+        assert!(register(&mut macros, "{").is_err());
+        assert!(macros.expression.is_empty());
+    }
+
+    #[test]
+    fn register_definition_with_body() {
+        let mut parser = Parser::new();
+        let segments = vec![SegmentDefinition::new("unless", pattern::everything())];
+        let definition = Definition::new(segments, |segments| {
+            let segments = segments.mapped(|segment| {
+                let header = segment.header;
+                let tokens = segment.result.tokens();
+                let body = syntax::operator::resolve_operator_precedence_if_non_empty(tokens);
+                tree::MultiSegmentAppSegment { header, body }
+            });
+            syntax::Tree::multi_segment_app(segments).with_error("Custom macro.")
+        });
+        parser.macros.register(Context::Expression, definition.unwrap());
+        let tree = parser.run("x = unless a b");
+        assert_eq!(tree.code(), "x = unless a b");
+        assert_eq!(segment_headers(&tree), Some(vec!["unless".into()]));
+        let errors = RefCell::new(vec![]);
+        tree.map(|tree| {
+            if let tree::Variant::Invalid(invalid) = &*tree.variant {
+                errors.borrow_mut().push(invalid.error.message.to_string());
+            }
+        });
+        assert_eq!(errors.into_inner(), vec!["Custom macro.".to_string()]);
+    }
+}
@@ -24,6 +24,7 @@
 
 use crate::prelude::*;
 
+use crate::diagnostics::ErrorCode;
 use crate::macros;
 use crate::macros::pattern;
 use crate::syntax;
@@ -50,6 +51,18 @@ pub struct MacroMap {
 }
 
 impl MacroMap {
+    /// Register a macro definition, making it available in the given context. A macro registered
+    /// in the [`Context::Expression`] context can also be used in statement context.
+    ///
+    /// A macro registered later takes precedence over an earlier macro with the same segments in
+    /// the same context, including the built-in macros.
+    pub fn register(&mut self, context: Context, definition: macros::Definition<'static>) {
+        match context {
+            Context::Expression => self.expression.register(definition),
+            Context::Statement => self.statement.register(definition),
+        }
+    }
+
     /// Return the macro matching the given token in the given context, if any.
     fn get(&self, key: &str, context: Context) -> Option<&NonEmptyVec<SegmentEntry<'static>>> {
         let statement_result = || self.statement.get(key);
@@ -58,9 +71,13 @@ impl MacroMap {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Context {
+/// The context in which a macro can be used.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Context {
+    /// Anywhere in an expression.
     Expression,
+    /// Only from the first token of a line.
     Statement,
 }
 
@@ -478,7 +495,9 @@ impl<'s> Resolver<'s> {
                     if let Some(excess) =
                         syntax::operator::resolve_operator_precedence_if_non_empty(excess)
                     {
-                        let excess = excess.with_error("Unexpected tokens in macro invocation.");
+                        let error = "Unexpected tokens in macro invocation.";
+                        let excess =
+                            excess.with_error_code(ErrorCode::UnexpectedMacroTokens, error);
                         tokens.push(excess.into());
                     }
                     let body = syntax::operator::resolve_operator_precedence_if_non_empty(tokens);
@@ -499,7 +518,9 @@ impl<'s> Resolver<'s> {
                 items.push_back(syntax::Item::Token(header));
                 items.append(&mut segment);
             }
-            let header0 = syntax::tree::to_ast(header0).with_error("Invalid macro invocation.");
+            let error = "Invalid macro invocation.";
+            let header0 =
+                syntax::tree::to_ast(header0).with_error_code(ErrorCode::IncompleteMacro, error);
             (header0, items)
         }
     }
@@ -53,45 +53,24 @@ fn check_file(path: &str, mut code: &str) {
     if let Some((_meta, code_)) = enso_parser::metadata::parse(code) {
         code = code_;
     }
-    let ast = enso_parser::Parser::new().run(code);
-    let errors = RefCell::new(vec![]);
-    ast.map(|tree| {
-        if let enso_parser::syntax::tree::Variant::Invalid(err) = &*tree.variant {
-            let error = format!("{}: {}", err.error.message, tree.code());
-            errors.borrow_mut().push((error, tree.span.clone()));
-        } else if let enso_parser::syntax::tree::Variant::TextLiteral(text) = &*tree.variant {
-            for element in &text.elements {
-                if let enso_parser::syntax::tree::TextElement::Escape { token } = element {
-                    if token.variant.value.is_none() {
-                        let escape = token.code.to_string();
-                        let error = format!("Invalid escape sequence: {escape}");
-                        errors.borrow_mut().push((error, tree.span.clone()));
-                    }
-                }
+    let (ast, diagnostics) = enso_parser::Parser::new().run_with_diagnostics(code);
+    for diagnostic in &diagnostics {
+        let mut line = 1;
+        let mut char = 0;
+        for (i, c) in code.char_indices() {
+            if i >= diagnostic.span.start {
+                break;
             }
-        }
-    });
-    for (error, span) in &*errors.borrow() {
-        let whitespace = &span.left_offset.code.repr;
-        if matches!(whitespace, Cow::Borrowed(_)) {
-            let start = whitespace.as_ptr() as usize + whitespace.len() - code.as_ptr() as usize;
-            let mut line = 1;
-            let mut char = 0;
-            for (i, c) in code.char_indices() {
-                if i >= start {
-                    break;
-                }
-                if c == '\n' {
-                    line += 1;
-                    char = 0;
-                } else {
-                    char += 1;
-                }
+            if c == '\n' {
+                line += 1;
+                char = 0;
+            } else {
+                char += 1;
             }
-            eprintln!("{path}:{line}:{char}: {}", &error);
-        } else {
-            eprintln!("{path}:?:?: {}", &error);
-        };
+        }
+        let error = &diagnostic.message;
+        let source = &code[diagnostic.span.clone()];
+        eprintln!("{path}:{line}:{char}: {error}: {source}");
     }
     for (parsed, original) in ast.code().lines().zip(code.lines()) {
         assert_eq!(parsed, original, "Bug: dropped tokens, while parsing: {path}");
@@ -2,8 +2,21 @@
 //!
 //! This data is currently represented as two lines containing one JSON value each, placed at the
 //! end of a file after a line containing exactly the text "#### METADATA ####".
+//!
+//! The first line is the ID map, which associates the spans of nodes with their UUIDs. When the
+//! code is edited, the spans of the nodes following the edit change; [`Metadata::rebase`] updates
+//! the ID map accordingly, and [`write`] produces the full file with the updated metadata section.
+//! The other lines of the metadata section are preserved unchanged.
+
+use crate::incremental::Edit;
+use crate::syntax;
+use crate::syntax::tree;
+use crate::Error;
+use crate::Parser;
+use crate::Result;
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -18,16 +31,120 @@ const MARKER: &str = "\n\n\n#### METADATA ####\n";
 // ================
 
 /// Attaches stable IDs to AST nodes, and associates properties with them.
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Metadata {
     id_map: BTreeMap<Location, Uuid>,
+    /// The lines of the metadata section following the ID map, including the line break that
+    /// precedes them.
+    rest:   String,
 }
 
 impl Metadata {
     /// Return the UUID associated with the node identified by offset/length, if any is found.
     pub fn get_uuid(&self, index: usize, size: usize) -> Option<Uuid> {
-        let loc = Location { index: Number { value: index }, size: Number { value: size } };
-        Some(*self.id_map.get(&loc)?)
+        Some(*self.id_map.get(&Location::new(index, size))?)
+    }
+
+    /// Associate the UUID with the node identified by offset/length, replacing any UUID that was
+    /// associated with it before.
+    pub fn set_uuid(&mut self, index: usize, size: usize, id: Uuid) {
+        self.id_map.insert(Location::new(index, size), id);
+    }
+
+    /// Return the entries of the ID map, as ((offset, length), UUID) pairs ordered by position.
+    pub fn id_map(&self) -> impl Iterator<Item = ((usize, usize), Uuid)> + '_ {
+        self.id_map.iter().map(|(location, id)| ((location.index.value, location.size.value), *id))
+    }
+}
+
+
+// === Rebasing ===
+
+impl Metadata {
+    /// Update the ID map after the code it was created for has been edited. `tree` must be the
+    /// result of parsing the edited code.
+    ///
+    /// The nodes preceding the edit keep their spans, and the nodes following it are moved by the
+    /// difference in length of the edited code. A node containing the edit is resized by the
+    /// difference, so that it keeps its ID if it still exists after the edit. An entry is only kept
+    /// if the node it identifies exists in the new tree; if several entries end up identifying the
+    /// same node, entries of nodes unaffected by the edit take precedence.
+    pub fn rebase(&self, edit: &Edit, tree: &syntax::Tree) -> Self {
+        let mut nodes = BTreeSet::new();
+        tree::visit_item_spans(tree, |_, span| {
+            nodes.insert((span.start, span.len()));
+        });
+        let removed = edit.range.len();
+        let inserted = edit.text.len();
+        let mut id_map = BTreeMap::new();
+        let mut resized = vec![];
+        for (location, id) in &self.id_map {
+            let (index, size) = (location.index.value, location.size.value);
+            let end = index + size;
+            let moved = if end <= edit.range.start {
+                Some(index)
+            } else if index >= edit.range.end {
+                Some(index + inserted - removed)
+            } else {
+                None
+            };
+            match moved {
+                Some(index) if nodes.contains(&(index, size)) => {
+                    id_map.entry(Location::new(index, size)).or_insert(*id);
+                }
+                _ if index <= edit.range.start && end >= edit.range.end => {
+                    resized.push(((index, size + inserted - removed), *id));
+                }
+                _ => {}
+            }
+        }
+        for ((index, size), id) in resized {
+            if nodes.contains(&(index, size)) {
+                id_map.entry(Location::new(index, size)).or_insert(id);
+            }
+        }
+        Self { id_map, rest: self.rest.clone() }
+    }
+}
+
+
+// === Writing ===
+
+impl Metadata {
+    /// Return the metadata section, including the marker line that separates it from the code.
+    pub fn to_section(&self) -> String {
+        let id_map: Vec<_> = self.id_map.iter().collect();
+        let id_map = serde_json::to_string(&id_map).expect("The ID map is always serializable.");
+        format!("{MARKER}{id_map}{}", self.rest)
+    }
+}
+
+/// Return the contents of a file consisting of the code, followed by the metadata section.
+pub fn write(code: &str, metadata: &Metadata) -> String {
+    let mut out = code.to_owned();
+    out.push_str(&metadata.to_section());
+    out
+}
+
+/// Apply an edit to the code of a file, updating the ID map of its metadata section to match the
+/// edited code. The range of the edit is relative to the beginning of the file, and must not
+/// extend into the metadata section.
+pub fn apply_edit(parser: &Parser, input: &str, edit: &Edit) -> Result<String> {
+    let (code, metadata) = extract(input);
+    if edit.range.start > edit.range.end || edit.range.end > code.len() {
+        return Err("The edit is not within the code of the file.".into());
+    }
+    if !code.is_char_boundary(edit.range.start) || !code.is_char_boundary(edit.range.end) {
+        return Err("The edit does not start and end at character boundaries.".into());
+    }
+    let new_code = edit.apply(code);
+    match metadata {
+        Some(metadata) => {
+            let metadata: Metadata = metadata.parse::<MetadataFormat>()?.into();
+            let tree = parser.run(&new_code);
+            Ok(write(&new_code, &metadata.rebase(edit, &tree)))
+        }
+        None => Ok(new_code),
     }
 }
 
@@ -38,12 +155,13 @@ impl Metadata {
 #[derive(Debug)]
 struct MetadataFormat {
     id_map: Vec<(Location, Uuid)>,
+    rest:   String,
 }
 
 impl From<MetadataFormat> for Metadata {
     fn from(metadata: MetadataFormat) -> Self {
         let id_map = metadata.id_map.into_iter().collect();
-        Self { id_map }
+        Self { id_map, rest: metadata.rest }
     }
 }
 
@@ -57,7 +175,7 @@ pub fn extract(input: &str) -> (&str, Option<&str>) {
 
 /// Given source code, if a metadata section is found: Attempt to parse it; return the result, and
 /// the non-metadata portion of the input.
-pub fn parse(input: &str) -> Option<(Result, &str)> {
+pub fn parse(input: &str) -> Option<(Result<Metadata>, &str)> {
     let (code, metadata) = input.rsplit_once(MARKER)?;
     Some((metadata.parse().map(|data: MetadataFormat| data.into()), code))
 }
@@ -74,16 +192,16 @@ pub fn parse_metadata(input: &str) -> Option<Vec<((usize, usize), Uuid)>> {
     )
 }
 
-/// Result of parsing metadata.
-pub type Result<T = Metadata> = std::result::Result<T, String>;
-
 impl FromStr for MetadataFormat {
-    type Err = String;
+    type Err = Error;
     fn from_str(s: &str) -> Result<MetadataFormat> {
-        let mut lines = s.lines();
-        let line0 = lines.next().ok_or("Expected a value.")?;
-        let id_map = serde_json::from_str(line0).map_err(|e| e.to_string())?;
-        Ok(MetadataFormat { id_map })
+        let line0_end = s.find(['\r', '\n']).unwrap_or(s.len());
+        let (line0, rest) = s.split_at(line0_end);
+        if line0.trim().is_empty() {
+            return Err("Expected a value.".into());
+        }
+        let id_map = serde_json::from_str(line0)?;
+        Ok(MetadataFormat { id_map, rest: rest.to_owned() })
     }
 }
 
@@ -104,6 +222,12 @@ struct Number {
     value: usize,
 }
 
+impl Location {
+    fn new(index: usize, size: usize) -> Self {
+        Self { index: Number { value: index }, size: Number { value: size } }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -118,4 +242,66 @@ mod test {
     fn empty_metadata() {
         MetadataFormat::from_str("[]").expect("Empty sequence is valid.");
     }
+
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn rebase(
+        code: &str,
+        ids: &[((usize, usize), Uuid)],
+        edit: Edit,
+    ) -> Vec<((usize, usize), Uuid)> {
+        let mut metadata = Metadata::default();
+        for &((index, size), id) in ids {
+            metadata.set_uuid(index, size, id);
+        }
+        let new_code = edit.apply(code);
+        let tree = Parser::new().run(&new_code);
+        metadata.rebase(&edit, &tree).id_map().collect()
+    }
+
+    #[test]
+    fn rebase_id_map() {
+        let code = "main = foo bar";
+        let ids = [((0, 4), id(1)), ((7, 7), id(2)), ((7, 3), id(3)), ((11, 3), id(4))];
+        // Replacing a node keeps its ID, and resizes the nodes containing it.
+        let rebased = rebase(code, &ids, Edit::new(7..10, "baz_qux"));
+        assert_eq!(rebased, vec![
+            ((0, 4), id(1)),
+            ((7, 7), id(3)),
+            ((7, 11), id(2)),
+            ((15, 3), id(4))
+        ]);
+        // The IDs of removed nodes are dropped; a node that was not affected by the edit keeps its
+        // ID, even if a resized node now has the same span.
+        let rebased = rebase(code, &ids, Edit::new(7..11, ""));
+        assert_eq!(rebased, vec![((0, 4), id(1)), ((7, 3), id(4))]);
+        // Extending a node keeps its ID.
+        let rebased = rebase(code, &ids, Edit::new(14..14, "x"));
+        assert_eq!(rebased, vec![
+            ((0, 4), id(1)),
+            ((7, 3), id(3)),
+            ((7, 8), id(2)),
+            ((11, 4), id(4))
+        ]);
+    }
+
+    #[test]
+    fn write_metadata() {
+        let ide_metadata = "\n{\"ide\":{}}\n";
+        let uuid = "00000000-0000-0000-0000-000000000003";
+        let id_map = format!(r#"[[{{"index":{{"value":7}},"size":{{"value":3}}}},"{uuid}"]]"#);
+        let input = format!("main = foo bar{MARKER}{id_map}{ide_metadata}");
+        let (result, code) = parse(&input).unwrap();
+        let metadata = result.unwrap();
+        assert_eq!(metadata.get_uuid(7, 3), Some(id(3)));
+        assert_eq!(write(code, &metadata), input);
+
+        let edited = apply_edit(&Parser::new(), &input, &Edit::new(0..4, "run")).unwrap();
+        let id_map = format!(r#"[[{{"index":{{"value":6}},"size":{{"value":3}}}},"{uuid}"]]"#);
+        assert_eq!(edited, format!("run = foo bar{MARKER}{id_map}{ide_metadata}"));
+        assert_eq!(apply_edit(&Parser::new(), "x = 1", &Edit::new(4..5, "2")).unwrap(), "x = 2");
+        assert!(apply_edit(&Parser::new(), &input, &Edit::new(0..20, "")).is_err());
+    }
 }
@@ -0,0 +1,535 @@
+//! Structural queries over syntax trees. A query is a list of patterns written as S-expressions, in
+//! the style of tree-sitter queries. For example, the following query matches method calls named
+//! `read`, capturing the object and the method name:
+//!
+//! ```text
+//! (OprApp lhs: (_) @obj opr: "." rhs: (Ident "read") @method)
+//! ```
+//!
+//! The syntax of patterns is:
+//! - `(Kind ...)`: a [`Tree`] of the given [`tree::Variant`]. The kind `_` matches a tree of any
+//!   variant. The kind may be followed by any number of:
+//!   - `field: <pattern>`: the field of the variant contains an item matching the pattern.
+//!   - `!field`: the field of the variant is empty.
+//!   - `<pattern>`: the children of the tree, in order, contain items matching the patterns. Other
+//!     children may occur between the matched ones.
+//! - `_`: any tree or token.
+//! - `"text"`: a tree or token whose code, excluding the leading whitespace, is the given text. The
+//!   escapes `\"`, `\\`, `\n` and `\t` are supported.
+//!
+//! Any pattern can be followed by `@name`, which captures the matched item. Comments start with `;`
+//! and extend to the end of the line.
+//!
+//! The items of a field are the trees and tokens it contains directly; for example, the items of
+//! the `statements` field of a [`tree::BodyBlock`] are the newline tokens and expressions of its
+//! lines. The children of a tree are the items of all of its fields.
+
+use crate::prelude::*;
+
+use crate::source::*;
+use crate::syntax::item;
+use crate::syntax::tree;
+use crate::syntax::Tree;
+use crate::Result;
+
+use std::collections::HashMap;
+use std::ptr;
+
+
+
+// ===============
+// === Pattern ===
+// ===============
+
+/// A pattern matching a syntax tree item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    /// The condition the item must satisfy.
+    pub node:    Node,
+    /// The name under which the matched item is captured.
+    pub capture: Option<String>,
+}
+
+/// The condition of a [`Pattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    /// Matches any tree or token.
+    Any,
+    /// Matches a tree or token with the given code, excluding the leading whitespace.
+    Code(String),
+    /// Matches a tree.
+    Tree {
+        /// The variant of the tree; if not set, a tree of any variant matches.
+        kind:     Option<tree::VariantMarker>,
+        /// Each of the named fields must contain an item matching the pattern.
+        fields:   Vec<(&'static str, Pattern)>,
+        /// Each of the named fields must be empty.
+        absent:   Vec<&'static str>,
+        /// The children of the tree must contain items matching these patterns, in order.
+        children: Vec<Pattern>,
+    },
+}
+
+
+
+// =============
+// === Query ===
+// =============
+
+/// A compiled query. See the module docs to learn about the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    /// The top-level patterns of the query.
+    pub patterns: Vec<Pattern>,
+}
+
+/// An item matching a top-level pattern of a [`Query`].
+#[derive(Clone, Debug)]
+pub struct Match<'s, 'a> {
+    /// The index of the matching pattern in [`Query::patterns`].
+    pub pattern:  usize,
+    /// The matched item.
+    pub item:     item::Ref<'s, 'a>,
+    /// Byte range of the matched item in the source code, excluding its leading whitespace.
+    pub span:     Range<usize>,
+    /// The items captured by the pattern, in the order of the patterns that captured them.
+    pub captures: Vec<Capture<'s, 'a>>,
+}
+
+/// An item captured by a pattern.
+#[derive(Clone, Debug)]
+pub struct Capture<'s, 'a> {
+    /// The name of the capture.
+    pub name: String,
+    /// The captured item.
+    pub item: item::Ref<'s, 'a>,
+    /// Byte range of the captured item in the source code, excluding its leading whitespace.
+    pub span: Range<usize>,
+}
+
+impl Query {
+    /// Parse a query.
+    pub fn new(source: &str) -> Result<Self> {
+        let mut parser = QueryParser { source, position: 0 };
+        let mut patterns = vec![];
+        while !parser.at_end() {
+            patterns.push(parser.pattern()?);
+        }
+        Ok(Self { patterns })
+    }
+
+    /// Find the items of the tree that match any of the patterns. The tree must be the result of
+    /// parsing a whole source file, so that the spans are positions in the file. Items are tested
+    /// in pre-order; an item matching several patterns is reported once per pattern.
+    pub fn matches<'s: 'a, 'a>(&self, tree: &'a Tree<'s>) -> Vec<Match<'s, 'a>> {
+        let index = ItemIndex::new(tree);
+        let mut matches = vec![];
+        for &item in &index.items {
+            for (i, pattern) in self.patterns.iter().enumerate() {
+                let mut matcher = Matcher { spans: &index, captures: vec![] };
+                if matcher.matches(pattern, item) {
+                    let span = index.span(item);
+                    let captures = matcher.captures;
+                    matches.push(Match { pattern: i, item, span, captures });
+                }
+            }
+        }
+        matches
+    }
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+/// Recursive-descent parser of the query syntax.
+#[derive(Debug)]
+struct QueryParser<'q> {
+    source:   &'q str,
+    position: usize,
+}
+
+impl<'q> QueryParser<'q> {
+    fn rest(&self) -> &'q str {
+        &self.source[self.position..]
+    }
+
+    fn error<T>(&self, message: impl Display) -> Result<T> {
+        Err(format!("{message} at offset {} of the query.", self.position).into())
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if !trimmed.starts_with(';') {
+                break;
+            }
+            self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_trivia();
+        self.rest().is_empty()
+    }
+
+    /// Consume the given character, if it is next in the input.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_trivia();
+        let found = self.rest().starts_with(c);
+        if found {
+            self.position += c.len_utf8();
+        }
+        found
+    }
+
+    fn name(&mut self) -> Result<&'q str> {
+        self.skip_trivia();
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return self.error("Expected a name");
+        }
+        self.position += len;
+        Ok(&rest[..len])
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, c @ ('"' | '\\'))) => out.push(c),
+                    _ => {
+                        self.position += i;
+                        return self.error("Invalid escape sequence");
+                    }
+                },
+                c => out.push(c),
+            }
+        }
+        self.error("Unclosed string")
+    }
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        let node = if self.eat('(') {
+            self.tree()?
+        } else if self.eat('"') {
+            Node::Code(self.string()?)
+        } else if self.rest().starts_with('_') {
+            match self.name()? {
+                "_" => Node::Any,
+                name => return self.error(format_args!("Unexpected name `{name}`")),
+            }
+        } else if self.rest().is_empty() {
+            return self.error("Unexpected end of query");
+        } else {
+            return self.error("Expected a pattern");
+        };
+        let capture = if self.eat('@') { Some(self.name()?.to_owned()) } else { None };
+        Ok(Pattern { node, capture })
+    }
+
+    /// Parse the contents of a tree pattern, after the opening parenthesis.
+    fn tree(&mut self) -> Result<Node> {
+        let kind = match self.name()? {
+            "_" => None,
+            name => match kind_by_name(name) {
+                Some(kind) => Some(kind),
+                None => return self.error(format_args!("Unknown tree kind `{name}`")),
+            },
+        };
+        let mut fields = vec![];
+        let mut absent = vec![];
+        let mut children = vec![];
+        while !self.eat(')') {
+            if self.eat('!') {
+                absent.push(self.field(kind)?);
+                continue;
+            }
+            let start = self.position;
+            let is_field = self.name().is_ok() && self.eat(':');
+            self.position = start;
+            if is_field {
+                let field = self.field(kind)?;
+                self.eat(':');
+                fields.push((field, self.pattern()?));
+            } else {
+                children.push(self.pattern()?);
+            }
+        }
+        Ok(Node::Tree { kind, fields, absent, children })
+    }
+
+    /// Parse the name of a field of the given tree kind, or of any kind if it is not set.
+    fn field(&mut self, kind: Option<tree::VariantMarker>) -> Result<&'static str> {
+        let name = self.name()?;
+        let field = match kind {
+            Some(kind) => field_names(kind).iter().find(|field| **field == name).copied(),
+            None => {
+                let mut fields = ALL_KINDS.iter().flat_map(|kind| field_names(*kind));
+                fields.find(|field| **field == name).copied()
+            }
+        };
+        match field {
+            Some(field) => Ok(field),
+            None => self.error(format_args!("Unknown field `{name}`")),
+        }
+    }
+}
+
+
+
+// ================
+// === Matching ===
+// ================
+
+/// All items of a tree in pre-order, with their spans.
+#[derive(Debug, Default)]
+struct ItemIndex<'s, 'a> {
+    items:       Vec<item::Ref<'s, 'a>>,
+    tree_spans:  HashMap<*const Tree<'s>, Range<usize>>,
+    token_spans: HashMap<*const Code<'s>, Range<usize>>,
+}
+
+impl<'s: 'a, 'a> ItemIndex<'s, 'a> {
+    fn new(tree: &'a Tree<'s>) -> Self {
+        let mut index = Self::default();
+        tree::visit_item_spans(tree, |item, span| {
+            index.items.push(item);
+            match item {
+                item::Ref::Tree(tree) => index.tree_spans.insert(ptr::addr_of!(*tree), span),
+                item::Ref::Token(token) =>
+                    index.token_spans.insert(ptr::addr_of!(*token.code), span),
+            };
+        });
+        index
+    }
+
+    fn span(&self, item: item::Ref<'s, 'a>) -> Range<usize> {
+        let span = match item {
+            item::Ref::Tree(tree) => self.tree_spans.get(&ptr::addr_of!(*tree)),
+            item::Ref::Token(token) => self.token_spans.get(&ptr::addr_of!(*token.code)),
+        };
+        span.cloned().unwrap_or_default()
+    }
+}
+
+/// The state of matching a pattern against an item.
+#[derive(Debug)]
+struct Matcher<'i, 's, 'a> {
+    spans:    &'i ItemIndex<'s, 'a>,
+    captures: Vec<Capture<'s, 'a>>,
+}
+
+impl<'i, 's: 'a, 'a> Matcher<'i, 's, 'a> {
+    /// Check whether the item matches the pattern. If it does not, the captures are unchanged.
+    fn matches(&mut self, pattern: &Pattern, item: item::Ref<'s, 'a>) -> bool {
+        let checkpoint = self.captures.len();
+        if let Some(name) = &pattern.capture {
+            let span = self.spans.span(item);
+            self.captures.push(Capture { name: name.clone(), item, span });
+        }
+        let matches = match &pattern.node {
+            Node::Any => true,
+            Node::Code(code) => match item {
+                item::Ref::Tree(tree) => tree.trimmed_code() == *code,
+                item::Ref::Token(token) => token.code.repr == *code,
+            },
+            Node::Tree { kind, fields, absent, children } => match item {
+                item::Ref::Tree(tree) =>
+                    kind.map_or(true, |kind| tree.variant.marker() == kind)
+                        && self.matches_fields(tree, fields, absent)
+                        && self.matches_sequence(children, &tree::children_of(&*tree.variant)),
+                item::Ref::Token(_) => false,
+            },
+        };
+        if !matches {
+            self.captures.truncate(checkpoint);
+        }
+        matches
+    }
+
+    fn matches_fields(
+        &mut self,
+        tree: &'a Tree<'s>,
+        fields: &[(&'static str, Pattern)],
+        absent: &[&'static str],
+    ) -> bool {
+        if fields.is_empty() && absent.is_empty() {
+            return true;
+        }
+        let tree_fields = variant_fields(&tree.variant);
+        let field = |name: &str| tree_fields.iter().find(|(field, _)| *field == name);
+        let absent = absent.iter().all(|name| field(name).map_or(false, |(_, it)| it.is_empty()));
+        absent
+            && fields.iter().all(|(name, pattern)| match field(name) {
+                Some((_, items)) => items.iter().any(|item| self.matches(pattern, *item)),
+                None => false,
+            })
+    }
+
+    /// Check whether the patterns match a subsequence of the items.
+    fn matches_sequence(&mut self, patterns: &[Pattern], items: &[item::Ref<'s, 'a>]) -> bool {
+        let Some((pattern, patterns)) = patterns.split_first() else { return true };
+        for (i, item) in items.iter().enumerate() {
+            let checkpoint = self.captures.len();
+            if self.matches(pattern, *item) {
+                if self.matches_sequence(patterns, &items[i + 1..]) {
+                    return true;
+                }
+                self.captures.truncate(checkpoint);
+            }
+        }
+        false
+    }
+}
+
+
+
+// ==============
+// === Fields ===
+// ==============
+
+/// For each tree variant, generates code giving access to its fields by name:
+/// - `variant_fields` returns the items of each field of a variant.
+/// - `field_names` returns the names of the fields of a variant.
+/// - `kind_by_name` returns the variant with the given name.
+macro_rules! generate_field_access {
+    (
+        $(#$enum_meta:tt)*
+        pub enum $enum:ident<'s> {
+            $(
+                $(#$variant_meta:tt)*
+                $variant:ident $({$($(#$field_meta:tt)* pub $field:ident : $field_ty:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        /// All tree variants.
+        const ALL_KINDS: &[tree::VariantMarker] = &[$(tree::VariantMarker::$variant),*];
+
+        #[allow(unused_variables)]
+        fn variant_fields<'s: 'a, 'a>(
+            variant: &'a tree::Variant<'s>,
+        ) -> Vec<(&'static str, Vec<item::Ref<'s, 'a>>)> {
+            match variant {
+                $(tree::Variant::$variant(node) =>
+                    vec![$($((stringify!($field), tree::children_of(&node.$field))),*)?],)*
+            }
+        }
+
+        fn field_names(kind: tree::VariantMarker) -> &'static [&'static str] {
+            match kind {
+                $(tree::VariantMarker::$variant => &[$($(stringify!($field)),*)?],)*
+            }
+        }
+
+        fn kind_by_name(name: &str) -> Option<tree::VariantMarker> {
+            match name {
+                $(stringify!($variant) => Some(tree::VariantMarker::$variant),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+crate::with_ast_definition!(generate_field_access());
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    /// Run the query on the code, returning the code of the matches and of their captures.
+    fn run(query: &str, code: &str) -> Vec<(String, Vec<(String, String)>)> {
+        let query = Query::new(query).unwrap();
+        let tree = Parser::new().run(code);
+        let matches = query.matches(&tree);
+        let text = |span: Range<usize>| code[span].to_string();
+        let named = |m: Match| m.captures.into_iter().map(|c| (c.name, text(c.span))).collect();
+        matches.into_iter().map(|m| (text(m.span.clone()), named(m))).collect()
+    }
+
+    fn captures(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(name, code)| (name.to_string(), code.to_string())).collect()
+    }
+
+    #[test]
+    fn method_call() {
+        let query = r#"(OprApp lhs: (_) @obj opr: "." rhs: (Ident "read") @method)"#;
+        let code = "main =\n    x = file.read\n    y = file.write\n    z = (a + b).read";
+        let expected = vec![
+            ("file.read".to_string(), captures(&[("obj", "file"), ("method", "read")])),
+            ("(a + b).read".to_string(), captures(&[("obj", "(a + b)"), ("method", "read")])),
+        ];
+        assert_eq!(run(query, code), expected);
+    }
+
+    #[test]
+    fn positional_patterns() {
+        let query = r#"(App (Ident) @func "x" @arg)"#;
+        assert_eq!(run(query, "f x"), vec![(
+            "f x".into(),
+            captures(&[("func", "f"), ("arg", "x")])
+        )]);
+        assert_eq!(run(query, "f y"), vec![]);
+        assert_eq!(run("(App _ _ _)", "f x"), vec![]);
+    }
+
+    #[test]
+    fn absent_fields() {
+        let code = "a = (+ 1)\nb = (1 +)\nc = 1 + 2";
+        assert_eq!(run("(OprApp !lhs) @section", code), vec![(
+            "+ 1".into(),
+            captures(&[("section", "+ 1")])
+        )]);
+        assert_eq!(run("(OprApp !rhs)", code), vec![("1 +".into(), vec![])]);
+    }
+
+    #[test]
+    fn multiple_patterns() {
+        let query = "; Numbers and identifiers.\n(Number) @n\n(Ident) @i";
+        let expected =
+            vec![("x".into(), captures(&[("i", "x")])), ("1".into(), captures(&[("n", "1")]))];
+        assert_eq!(run(query, "x = 1"), expected);
+        let query = Query::new(query).unwrap();
+        assert_eq!(query.patterns.len(), 2);
+    }
+
+    #[test]
+    fn tokens_and_escapes() {
+        assert_eq!(run(r#""+" @op"#, "a + b"), vec![("+".into(), captures(&[("op", "+")]))]);
+        let query = Query::new(r#""a \"quoted\" \\ text""#).unwrap();
+        assert_eq!(query.patterns[0].node, Node::Code("a \"quoted\" \\ text".into()));
+    }
+
+    #[test]
+    fn invalid_queries() {
+        assert!(Query::new("(NoSuchKind)").is_err());
+        assert!(Query::new("(OprApp no_such_field: _)").is_err());
+        assert!(Query::new("(Ident !lhs)").is_err());
+        assert!(Query::new("(OprApp").is_err());
+        assert!(Query::new("\"unclosed").is_err());
+        assert!(Query::new("(Ident) @").is_err());
+        assert!(Query::new(")").is_err());
+        assert!(Query::new("(_ lhs: _)").is_ok());
+    }
+}
@@ -98,7 +98,7 @@ pub(crate) struct Error(String);
 impl From<Error> for crate::syntax::tree::Error {
     fn from(error: Error) -> Self {
         let message = error.0.into();
-        crate::syntax::tree::Error { message }
+        crate::syntax::tree::Error::new(message)
     }
 }
 
@@ -2,6 +2,7 @@
 
 use crate::prelude::*;
 
+use crate::diagnostics::ErrorCode;
 use crate::syntax;
 use crate::syntax::token;
 use crate::syntax::token::Token;
@@ -283,8 +284,10 @@ impl<'s> ExpressionBuilder<'s> {
             let ast = match opr.opr {
                 Arity::Unary(Unary::Simple(opr)) =>
                     Operand::from(rhs_).map(|item| syntax::tree::apply_unary_operator(opr, item)),
-                Arity::Unary(Unary::Invalid { token, error }) => Operand::from(rhs_)
-                    .map(|item| syntax::tree::apply_unary_operator(token, item).with_error(error)),
+                Arity::Unary(Unary::Invalid { token, error }) => Operand::from(rhs_).map(|item| {
+                    let tree = syntax::tree::apply_unary_operator(token, item);
+                    tree.with_error_code(ErrorCode::OperatorSpacing, error)
+                }),
                 Arity::Unary(Unary::Fragment { mut fragment }) => {
                     if let Some(rhs_) = rhs_ {
                         fragment.operand(rhs_);
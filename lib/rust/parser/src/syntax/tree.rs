@@ -4,6 +4,7 @@ use crate::prelude::*;
 use crate::source::*;
 use crate::syntax::*;
 
+use crate::diagnostics::ErrorCode;
 use crate::span_builder;
 
 use enso_parser_syntax_tree_visitor::Visitor;
@@ -397,21 +398,35 @@ with_ast_definition!(generate_ast_definition());
 pub struct Error {
     #[serde(skip_deserializing)]
     pub message: Cow<'static, str>,
+    /// The kind of the error, reported in the [`crate::diagnostics`].
+    #[serde(skip)]
+    #[reflect(skip)]
+    pub code:    ErrorCode,
 }
 
 impl Error {
-    /// Constructor.
+    /// Constructor. The error is of the generic [`ErrorCode::Syntax`] kind.
     pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
+        Self::with_code(ErrorCode::Syntax, message)
+    }
+
+    /// Constructor.
+    pub fn with_code(code: ErrorCode, message: impl Into<Cow<'static, str>>) -> Self {
         let message = message.into();
-        Self { message }
+        Self { message, code }
     }
 }
 
 impl<'s> Tree<'s> {
-    /// Constructor.
+    /// Constructor. The error is of the generic [`ErrorCode::Syntax`] kind.
     pub fn with_error(self, message: impl Into<Cow<'static, str>>) -> Self {
         Tree::invalid(Error::new(message), self)
     }
+
+    /// Constructor.
+    pub fn with_error_code(self, code: ErrorCode, message: impl Into<Cow<'static, str>>) -> Self {
+        Tree::invalid(Error::with_code(code, message), self)
+    }
 }
 
 impl<'s> span::Builder<'s> for Error {
@@ -881,7 +896,8 @@ pub fn apply_operator<'s>(
     }
     if let Ok(opr_) = &opr && opr_.properties.is_special() {
         let tree = Tree::opr_app(lhs, opr, rhs);
-        return tree.with_error("Invalid use of special operator.");
+        let error = "Invalid use of special operator.";
+        return tree.with_error_code(ErrorCode::InvalidOperatorUse, error);
     }
     if let Ok(opr_) = &opr && opr_.properties.is_type_annotation() {
         return match (lhs, rhs) {
@@ -891,14 +907,17 @@ pub fn apply_operator<'s>(
             },
             (lhs, rhs) => {
                 let invalid = Tree::opr_app(lhs, opr, rhs);
-                invalid.with_error("`:` operator must be applied to two operands.")
+                invalid.with_error_code(
+                    ErrorCode::InvalidOperatorUse,
+                    "`:` operator must be applied to two operands.",
+                )
             }
         };
     }
     if let Ok(opr_) = &opr && !opr_.properties.can_form_section() && lhs.is_none() && rhs.is_none() {
         let error = format!("Operator `{opr:?}` must be applied to two operands.");
         let invalid = Tree::opr_app(lhs, opr, rhs);
-        return invalid.with_error(error);
+        return invalid.with_error_code(ErrorCode::InvalidOperatorUse, error);
     }
     if let Ok(opr) = &opr && opr.properties.is_decimal()
         && let Some(lhs) = lhs.as_mut()
@@ -942,7 +961,7 @@ pub fn apply_unary_operator<'s>(opr: token::Operator<'s>, rhs: Option<Tree<'s>>)
     if !opr.properties.can_form_section() && rhs.is_none() {
         let error = format!("Operator `{opr:?}` must be applied to an operand.");
         let invalid = Tree::unary_opr_app(opr, rhs);
-        return invalid.with_error(error);
+        return invalid.with_error_code(ErrorCode::InvalidOperatorUse, error);
     }
     Tree::unary_opr_app(opr, rhs)
 }
@@ -980,9 +999,11 @@ pub fn to_ast(token: Token) -> Tree {
         token::Variant::Wildcard(wildcard) => Tree::wildcard(token.with_variant(wildcard), default()),
         token::Variant::AutoScope(t) => Tree::auto_scope(token.with_variant(t)),
         token::Variant::OpenSymbol(s) =>
-            Tree::group(Some(token.with_variant(s)), default(), default()).with_error("Unmatched delimiter"),
+            Tree::group(Some(token.with_variant(s)), default(), default())
+                .with_error_code(ErrorCode::UnmatchedDelimiter, "Unmatched delimiter"),
         token::Variant::CloseSymbol(s) =>
-            Tree::group(default(), default(), Some(token.with_variant(s))).with_error("Unmatched delimiter"),
+            Tree::group(default(), default(), Some(token.with_variant(s)))
+                .with_error_code(ErrorCode::UnmatchedDelimiter, "Unmatched delimiter"),
         // These should be unreachable: They are handled when assembling items into blocks,
         // before parsing proper.
         token::Variant::Newline(_)
@@ -996,7 +1017,7 @@ pub fn to_ast(token: Token) -> Tree {
             let message = format!("Unexpected token: {token:?}");
             let ident = token::variant::Ident(false, 0, false, false, false);
             let value = Tree::ident(token.with_variant(ident));
-            Tree::with_error(value, message)
+            value.with_error_code(ErrorCode::UnexpectedToken, message)
         }
     }
 }
@@ -1211,6 +1232,7 @@ macro_rules! spanless_leaf_impls {
 spanless_leaf_impls!(u32);
 spanless_leaf_impls!(bool);
 spanless_leaf_impls!(VisibleOffset);
+spanless_leaf_impls!(ErrorCode);
 
 
 // === TreeVisitable special cases ===
@@ -1454,6 +1476,64 @@ impl<'s> Tree<'s> {
 }
 
 
+// === Children ===
+
+/// Return the [`Token`]s and [`Tree`]s contained directly in the value, without their children.
+pub fn children_of<'s: 'a, 'a>(value: &'a impl ItemVisitable<'s, 'a>) -> Vec<item::Ref<'s, 'a>> {
+    struct ChildCollector<'s, 'a> {
+        items: Vec<item::Ref<'s, 'a>>,
+    }
+    impl<'s, 'a> Visitor for ChildCollector<'s, 'a> {}
+    impl<'s: 'a, 'a> ItemVisitor<'s, 'a> for ChildCollector<'s, 'a> {
+        fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+            self.items.push(item);
+            false
+        }
+    }
+    let mut collector = ChildCollector { items: default() };
+    value.visit_item(&mut collector);
+    collector.items
+}
+
+
+// === Item Spans ===
+
+/// Apply the provided function to each [`Token`] and [`Tree`] contained in the value, in pre-order,
+/// together with the byte range of its code, excluding the leading whitespace. The ranges are
+/// relative to the beginning of the value, including its leading whitespace.
+pub fn visit_item_spans<'s: 'a, 'a, F>(value: &'a impl ItemVisitable<'s, 'a>, f: F)
+where F: FnMut(item::Ref<'s, 'a>, Range<usize>) {
+    struct SpanIndexer<F> {
+        /// Byte offset of the end of the last visited token, or of the beginning of the last
+        /// visited tree.
+        offset: usize,
+        f:      F,
+    }
+    impl<F> Visitor for SpanIndexer<F> {}
+    impl<'s: 'a, 'a, F> ItemVisitor<'s, 'a> for SpanIndexer<F>
+    where F: FnMut(item::Ref<'s, 'a>, Range<usize>)
+    {
+        fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+            let (left_offset, length) = match item {
+                item::Ref::Tree(tree) =>
+                    (tree.span.left_offset.code.repr.len(), tree.span.code_length.utf8_bytes()),
+                item::Ref::Token(token) =>
+                    (token.left_offset.code.repr.len(), token.code.repr.len()),
+            };
+            let start = self.offset + left_offset;
+            let span = start..start + length;
+            self.offset = match item {
+                item::Ref::Tree(_) => start,
+                item::Ref::Token(_) => span.end,
+            };
+            (self.f)(item, span);
+            true
+        }
+    }
+    value.visit_item(&mut SpanIndexer { offset: 0, f });
+}
+
+
 
 // =================
 // === Traversal ===
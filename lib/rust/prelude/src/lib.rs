@@ -160,6 +160,7 @@ macro_rules! console_log {
 // === Logging ===
 // ===============
 
+pub use enso_logging as logging;
 pub use enso_logging::debug;
 pub use enso_logging::debug_span;
 pub use enso_logging::error;
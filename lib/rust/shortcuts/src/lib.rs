@@ -67,6 +67,19 @@ lazy_static! {
 /// `DoublePress`/`DoubleClick` event.
 pub const DOUBLE_EVENT_TIME_MS: f32 = 300.0;
 
+/// The default maximum time between the strokes of a key sequence, like "ctrl k, ctrl c". See
+/// [`HashSetRegistryModel::set_chord_timeout`].
+pub const CHORD_TIMEOUT_MS: f32 = 1500.0;
+
+/// The key which cancels a partially entered key sequence.
+const CANCEL_KEY: &str = "escape";
+
+/// Check whether the key is a side key, like "ctrl" or "shift-left".
+fn is_side_key(key: &str) -> bool {
+    let key = key.strip_suffix("-left").or_else(|| key.strip_suffix("-right")).unwrap_or(key);
+    SIDE_KEYS_SET.contains(key)
+}
+
 
 
 // ==================
@@ -88,6 +101,35 @@ pub enum ActionType {
 }
 pub use ActionType::*;
 
+impl ActionType {
+    /// Name of the action type, as used in keymap files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Press => "press",
+            PressAndRepeat => "press-and-repeat",
+            Release => "release",
+            DoublePress => "double-press",
+            DoubleClick => "double-click",
+        }
+    }
+}
+
+impl FromStr for ActionType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Press, PressAndRepeat, Release, DoublePress, DoubleClick]
+            .into_iter()
+            .find(|action_type| action_type.name() == s)
+            .ok_or_else(|| format!("Unknown action type '{s}'."))
+    }
+}
+
+impl Display for ActionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 
 
 // ================
@@ -98,9 +140,13 @@ pub use ActionType::*;
 #[allow(missing_docs)]
 pub trait Registry<T>: Default {
     /// Add a new action mapping. `The expr` needs to be a list of keys separated by space, like
-    /// "ctrl shift a".
+    /// "ctrl shift a", or a sequence of such lists separated by commas, like "ctrl k, ctrl c".
     fn add(&self, action_type: ActionType, expr: impl AsRef<str>, action: impl Into<T>);
 
+    /// Remove an action mapping added with [`Registry::add`]. If the same mapping was added several
+    /// times, only one of its copies is removed.
+    fn remove(&self, action_type: ActionType, expr: impl AsRef<str>, action: &T);
+
     /// Get a list of items registered for the action that just happened. It might include items
     /// registered for `DoublePress` or `DoubleClick` if the actions were performed fast enough.
     fn on_press(&self, input: impl AsRef<str>) -> Vec<T>;
@@ -109,6 +155,13 @@ pub trait Registry<T>: Default {
     /// registered for `DoublePress` or `DoubleClick` if the actions were performed fast enough.
     fn on_release(&self, input: impl AsRef<str>) -> Vec<T>;
 
+    /// The strokes of the partially entered key sequence, like `["ctrl k"]` after pressing
+    /// "ctrl k" when "ctrl k, ctrl c" is registered. It can be used to display a hint that the
+    /// next key of a sequence is expected.
+    fn pending_strokes(&self) -> Vec<String> {
+        default()
+    }
+
     /// Some engines might implement a separate optimization stage. This is intended to force the
     /// optimization at a given point in time. Used mainly in benchmarks.
     fn optimize(&self) {}
@@ -123,15 +176,24 @@ pub trait Registry<T>: Default {
 pub trait HashSetRegistryItem = Clone + Debug + Eq + Hash;
 
 /// Internal model for `HashSetRegistry`.
+///
+/// Key sequences, like "ctrl k, ctrl c", are triggered when their last stroke is pressed. While a
+/// sequence is partially entered, strokes don't trigger single-stroke shortcuts. A stroke which
+/// doesn't continue any sequence, pressing [`CANCEL_KEY`], or waiting longer than the chord
+/// timeout between strokes abandons the sequence.
 #[derive(Debug)]
 pub struct HashSetRegistryModel<T> {
-    current_expr:  String,
-    actions:       HashMap<ActionType, HashMap<String, Vec<T>>>,
-    pressed:       HashSet<String>,
-    press_times:   HashMap<String, f32>,
-    release_times: HashMap<String, f32>,
-    side_keys:     HashMap<String, Vec<String>>,
-    key_aliases:   HashMap<String, String>,
+    current_expr:     String,
+    actions:          HashMap<ActionType, HashMap<String, Vec<T>>>,
+    sequences:        HashMap<Vec<String>, Vec<T>>,
+    pending:          Vec<String>,
+    last_stroke_time: f32,
+    chord_timeout:    f32,
+    pressed:          HashSet<String>,
+    press_times:      HashMap<String, f32>,
+    release_times:    HashMap<String, f32>,
+    side_keys:        HashMap<String, Vec<String>>,
+    key_aliases:      HashMap<String, String>,
 }
 
 impl<T> HashSetRegistryModel<T> {
@@ -139,13 +201,29 @@ impl<T> HashSetRegistryModel<T> {
     pub fn new() -> Self {
         let current_expr = default();
         let actions = default();
+        let sequences = default();
+        let pending = default();
+        let last_stroke_time = default();
+        let chord_timeout = CHORD_TIMEOUT_MS;
         let pressed = default();
         let press_times = default();
         let release_times = default();
         let side_keys = default();
         let key_aliases = key_aliases();
-        Self { current_expr, actions, pressed, press_times, release_times, side_keys, key_aliases }
-            .init()
+        Self {
+            current_expr,
+            actions,
+            sequences,
+            pending,
+            last_stroke_time,
+            chord_timeout,
+            pressed,
+            press_times,
+            release_times,
+            side_keys,
+            key_aliases,
+        }
+        .init()
     }
 
     fn init(mut self) -> Self {
@@ -159,6 +237,27 @@ impl<T> HashSetRegistryModel<T> {
     fn current_expr(&self) -> String {
         self.pressed.iter().sorted().join(" ")
     }
+
+    /// Set the maximum time between the strokes of a key sequence.
+    pub fn set_chord_timeout(&mut self, timeout_ms: f32) {
+        self.chord_timeout = timeout_ms;
+    }
+
+    /// The maximum time between the strokes of a key sequence.
+    pub fn chord_timeout(&self) -> f32 {
+        self.chord_timeout
+    }
+
+    /// The strokes of the partially entered key sequence. See [`Registry::pending_strokes`].
+    pub fn pending_strokes(&self) -> Vec<String> {
+        let time = web::time_from_start() as f32;
+        let expired = time - self.last_stroke_time > self.chord_timeout;
+        if expired {
+            default()
+        } else {
+            self.pending.clone()
+        }
+    }
 }
 
 impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
@@ -166,6 +265,15 @@ impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
     pub fn add(&mut self, action_type: ActionType, input: impl AsRef<str>, action: impl Into<T>) {
         let input = input.as_ref();
         let action = action.into();
+        if input.contains(',') {
+            if action_type != Press {
+                warn!("Key sequence '{input}' is triggered on press, ignoring {action_type:?}.");
+            }
+            for sequence in self.possible_sequences(input) {
+                self.sequences.entry(sequence).or_default().push(action.clone());
+            }
+            return;
+        }
         let exprs = self.possible_exprs(input);
         let map = self.actions.entry(action_type).or_default();
         for expr in exprs {
@@ -173,6 +281,38 @@ impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
         }
     }
 
+    /// Remove a shortcut definition. If the same definition was added several times, only one of
+    /// its copies is removed.
+    pub fn remove(&mut self, action_type: ActionType, input: impl AsRef<str>, action: &T) {
+        let input = input.as_ref();
+        if input.contains(',') {
+            for sequence in self.possible_sequences(input) {
+                if let Some(actions) = self.sequences.get_mut(&sequence) {
+                    if let Some(index) = actions.iter().position(|t| t == action) {
+                        actions.remove(index);
+                    }
+                    if actions.is_empty() {
+                        self.sequences.remove(&sequence);
+                    }
+                }
+            }
+            return;
+        }
+        let exprs = self.possible_exprs(input);
+        if let Some(map) = self.actions.get_mut(&action_type) {
+            for expr in exprs {
+                if let Some(actions) = map.get_mut(&expr) {
+                    if let Some(index) = actions.iter().position(|t| t == action) {
+                        actions.remove(index);
+                    }
+                    if actions.is_empty() {
+                        map.remove(&expr);
+                    }
+                }
+            }
+        }
+    }
+
     #[allow(clippy::collapsible_else_if)]
     fn on_event(&mut self, input: impl AsRef<str>, press: bool) -> Vec<T> {
         let input = input.as_ref().to_lowercase();
@@ -186,7 +326,12 @@ impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
                 self.pressed.remove(&input);
             }
             self.current_expr = self.current_expr();
-            out.extended(self.process_event(Press)).extended(self.process_event(PressAndRepeat))
+            match self.process_sequence(press) {
+                Some(actions) => out.extended(actions),
+                None => out
+                    .extended(self.process_event(Press))
+                    .extended(self.process_event(PressAndRepeat)),
+            }
         } else {
             if press {
                 self.process_event(PressAndRepeat)
@@ -231,6 +376,43 @@ impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
         out
     }
 
+    /// Match the current stroke against the registered key sequences. Returns [`None`] if the
+    /// stroke should be handled as a single-stroke shortcut.
+    fn process_sequence(&mut self, press: bool) -> Option<Vec<T>> {
+        let time = web::time_from_start() as f32;
+        if time - self.last_stroke_time > self.chord_timeout {
+            self.pending.clear();
+        }
+        let only_side_keys = self.pressed.iter().all(|key| is_side_key(key));
+        if !press || only_side_keys || (self.pending.is_empty() && self.sequences.is_empty()) {
+            return None;
+        }
+        if !self.pending.is_empty() && self.pressed.contains(CANCEL_KEY) {
+            self.pending.clear();
+            return Some(default());
+        }
+        let mut strokes = mem::take(&mut self.pending);
+        strokes.push(self.current_expr.clone());
+        if let Some(actions) = self.sequences.get(&strokes) {
+            return Some(actions.clone());
+        }
+        let is_prefix = self
+            .sequences
+            .keys()
+            .any(|sequence| sequence.len() > strokes.len() && sequence.starts_with(&strokes));
+        if is_prefix {
+            self.pending = strokes;
+            self.last_stroke_time = time;
+            Some(default())
+        } else if strokes.len() > 1 {
+            // The stroke doesn't continue the pending sequence. It is consumed, so that breaking
+            // a sequence doesn't trigger an unexpected shortcut.
+            Some(default())
+        } else {
+            None
+        }
+    }
+
     /// Handle the key press.
     pub fn on_press(&mut self, input: impl AsRef<str>) -> Vec<T>
     where T: Debug {
@@ -243,6 +425,12 @@ impl<T: HashSetRegistryItem> HashSetRegistryModel<T> {
         self.on_event(input, false)
     }
 
+    /// Return all possible sequences of expressions for a given key sequence, like "ctrl k, c".
+    fn possible_sequences(&self, input: &str) -> Vec<Vec<String>> {
+        let strokes = input.split(',').map(|stroke| self.possible_exprs(stroke));
+        strokes.multi_cartesian_product().collect()
+    }
+
     /// Return all possible expressions with sorted keys for a given input expression. For example,
     /// for the input expression "cmd a", it will return ["a cmd", "a cmd-left", "a cmd-right"].
     fn possible_exprs(&self, expr: impl AsRef<str>) -> Vec<String> {
@@ -281,6 +469,20 @@ impl<T> Default for HashSetRegistryModel<T> {
     }
 }
 
+/// Normalize a key expression, like "Shift cmd  z", by lowercasing it, resolving key aliases
+/// like "cmd" or "option", and sorting the keys. Expressions describing the same shortcut have the
+/// same normal form. The strokes of key sequences, like "ctrl k, ctrl c", are normalized
+/// separately.
+pub fn normalize_expr(expr: impl AsRef<str>) -> String {
+    let aliases = key_aliases();
+    let normalize_stroke = |stroke: &str| {
+        let keys = stroke.split_whitespace().map(|key| key.to_lowercase());
+        let keys = keys.map(|key| aliases.get(&key).cloned().unwrap_or(key));
+        keys.sorted().dedup().join(" ")
+    };
+    expr.as_ref().split(',').map(normalize_stroke).join(", ")
+}
+
 fn key_aliases() -> HashMap<String, String> {
     let mut map = HashMap::<String, String>::new();
     let cmd_target = match web::platform::current() {
@@ -329,6 +531,16 @@ impl<T> HashSetRegistry<T> {
     pub fn new() -> Self {
         default()
     }
+
+    /// Set the maximum time between the strokes of a key sequence.
+    pub fn set_chord_timeout(&self, timeout_ms: f32) {
+        self.rc.borrow_mut().set_chord_timeout(timeout_ms)
+    }
+
+    /// The maximum time between the strokes of a key sequence.
+    pub fn chord_timeout(&self) -> f32 {
+        self.rc.borrow().chord_timeout()
+    }
 }
 
 impl<T: HashSetRegistryItem> Registry<T> for HashSetRegistry<T> {
@@ -336,6 +548,10 @@ impl<T: HashSetRegistryItem> Registry<T> for HashSetRegistry<T> {
         self.rc.borrow_mut().add(action_type, expr, action)
     }
 
+    fn remove(&self, action_type: ActionType, expr: impl AsRef<str>, action: &T) {
+        self.rc.borrow_mut().remove(action_type, expr, action)
+    }
+
     fn on_press(&self, input: impl AsRef<str>) -> Vec<T> {
         self.rc.borrow_mut().on_press(input)
     }
@@ -343,6 +559,10 @@ impl<T: HashSetRegistryItem> Registry<T> for HashSetRegistry<T> {
     fn on_release(&self, input: impl AsRef<str>) -> Vec<T> {
         self.rc.borrow_mut().on_release(input)
     }
+
+    fn pending_strokes(&self) -> Vec<String> {
+        self.rc.borrow().pending_strokes()
+    }
 }
 
 
@@ -515,6 +735,118 @@ mod tests {
     }
 
 
+    // === Remove ===
+
+    #[test]
+    fn hash_set_registry_remove() {
+        remove::<HashSetRegistry<i32>>();
+    }
+    fn remove<T: Registry<i32>>() -> T {
+        let nothing = Vec::<i32>::new();
+        let registry: T = default();
+        registry.add(Press, "ctrl a", 0);
+        registry.add(Press, "ctrl a", 1);
+        registry.add(Press, "ctrl a", 1);
+        registry.remove(Press, "ctrl a", &1);
+        registry.remove(Release, "ctrl a", &0);
+        assert_eq!(registry.on_press("ctrl-left"), nothing);
+        assert_eq!(registry.on_press("a"), vec![0, 1]);
+        assert_eq!(registry.on_release("a"), nothing);
+        registry.remove(Press, "ctrl a", &0);
+        registry.remove(Press, "ctrl a", &1);
+        assert_eq!(registry.on_press("a"), nothing);
+        registry
+    }
+
+
+    // === Sequences ===
+
+    #[test]
+    fn hash_set_registry_sequence_chords() {
+        let nothing = Vec::<i32>::new();
+        let registry = HashSetRegistry::<i32>::new();
+        // The simulated time is shared by the tests, so the timeout is disabled.
+        registry.set_chord_timeout(f32::INFINITY);
+        registry.add(Press, "ctrl k, ctrl c", 0);
+        registry.add(Press, "ctrl k, x", 1);
+        registry.add(Press, "ctrl c", 2);
+        // A single chord, outside of a sequence.
+        assert_eq!(registry.on_press("ctrl-left"), nothing);
+        assert_eq!(registry.on_press("c"), vec![2]);
+        assert_eq!(registry.on_release("c"), nothing);
+        // A sequence entered while holding ctrl.
+        assert_eq!(registry.on_press("k"), nothing);
+        assert_eq!(registry.pending_strokes(), vec!["ctrl-left k"]);
+        assert_eq!(registry.on_release("k"), nothing);
+        assert_eq!(registry.on_press("c"), vec![0]);
+        assert_eq!(registry.pending_strokes(), Vec::<String>::new());
+        assert_eq!(registry.on_release("c"), nothing);
+        // A sequence with ctrl released between the strokes.
+        assert_eq!(registry.on_press("k"), nothing);
+        assert_eq!(registry.on_release("k"), nothing);
+        assert_eq!(registry.on_release("ctrl-left"), nothing);
+        assert_eq!(registry.on_press("x"), vec![1]);
+        assert_eq!(registry.on_release("x"), nothing);
+    }
+
+    #[test]
+    fn hash_set_registry_sequence_cancel() {
+        let nothing = Vec::<i32>::new();
+        let registry = HashSetRegistry::<i32>::new();
+        registry.add(Press, "ctrl k, c", 0);
+        registry.add(Press, "c", 1);
+        let start_sequence = || {
+            assert_eq!(registry.on_press("ctrl-left"), nothing);
+            assert_eq!(registry.on_press("k"), nothing);
+            assert_eq!(registry.on_release("k"), nothing);
+            assert_eq!(registry.on_release("ctrl-left"), nothing);
+        };
+        // Cancelled with Escape.
+        start_sequence();
+        assert_eq!(registry.on_press("escape"), nothing);
+        assert_eq!(registry.on_release("escape"), nothing);
+        assert_eq!(registry.on_press("c"), vec![1]);
+        assert_eq!(registry.on_release("c"), nothing);
+        // Broken by a stroke which doesn't continue the sequence.
+        start_sequence();
+        assert_eq!(registry.on_press("d"), nothing);
+        assert_eq!(registry.on_release("d"), nothing);
+        assert_eq!(registry.on_press("c"), vec![1]);
+        assert_eq!(registry.on_release("c"), nothing);
+        // Timed out.
+        registry.set_chord_timeout(500.0);
+        start_sequence();
+        web::simulate_sleep(600.0);
+        assert_eq!(registry.pending_strokes(), Vec::<String>::new());
+        assert_eq!(registry.on_press("c"), vec![1]);
+        assert_eq!(registry.on_release("c"), nothing);
+        // Removed.
+        registry.remove(Press, "ctrl k, c", &0);
+        assert_eq!(registry.on_press("ctrl-left"), nothing);
+        assert_eq!(registry.on_press("k"), nothing);
+        assert_eq!(registry.pending_strokes(), Vec::<String>::new());
+    }
+
+
+    // === Keymap Helpers ===
+
+    #[test]
+    fn action_type_names() {
+        for action_type in [Press, PressAndRepeat, Release, DoublePress, DoubleClick] {
+            assert_eq!(action_type.name().parse(), Ok(action_type));
+        }
+        assert!("hold".parse::<ActionType>().is_err());
+    }
+
+    #[test]
+    fn normalized_exprs() {
+        assert_eq!(normalize_expr("Shift  control z"), "ctrl shift z");
+        assert_eq!(normalize_expr("option-left up"), "alt-left arrow-up");
+        assert_eq!(normalize_expr(" a a "), "a");
+        assert_eq!(normalize_expr("Control K,control c"), "ctrl k, c ctrl");
+    }
+
+
     // === Valid States ===
 
     #[test]
@@ -0,0 +1,623 @@
+//! Line-based comparison of texts: diffs and three-way merges.
+//!
+//! The diff is computed with the linear-space variant of the Myers algorithm, finding the shortest
+//! edit script in terms of whole lines. A line includes its terminator, so a last line without a
+//! trailing newline differs from the same line followed by one.
+
+use crate::index::*;
+use crate::prelude::*;
+
+use crate::range::Range;
+use crate::text::Change;
+use crate::text::Rope;
+
+use std::borrow::Cow;
+
+
+
+// =============
+// === Lines ===
+// =============
+
+/// The lines of the rope, each including its terminator.
+fn rope_lines(rope: &Rope) -> Vec<Cow<str>> {
+    rope.lines_raw(..).collect()
+}
+
+fn as_strs<'a>(lines: &'a [Cow<str>]) -> Vec<&'a str> {
+    lines.iter().map(|line| line.as_ref()).collect()
+}
+
+/// Byte offsets of the line starts, followed by the text length.
+fn line_offsets(lines: &[&str]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut offset = 0;
+    offsets.push(offset);
+    for line in lines {
+        offset += line.len();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+
+
+// =============
+// === Myers ===
+// =============
+
+/// Find the longest common subsequence of `old` and `new` with the linear-space variant of the
+/// Myers algorithm. Returns the indices of the matching elements in both sequences, in increasing
+/// order.
+fn common_subsequence<T: Eq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let max_d = max_d(old.len(), new.len());
+    let mut diagonals = (Diagonals::new(max_d), Diagonals::new(max_d));
+    let mut pairs = vec![];
+    collect_matches(old, new, (0, 0), &mut diagonals, &mut pairs);
+    pairs
+}
+
+fn common_prefix<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+/// The upper bound of the number of steps of the search from both ends after which the searches
+/// meet.
+fn max_d(old_len: usize, new_len: usize) -> usize {
+    (old_len + new_len + 1) / 2 + 1
+}
+
+/// Push the matching elements on the shortest edit path between `old` and `new` to `pairs`, with
+/// the indices shifted by `offset`. The path is split at its middle snake, and both halves are
+/// processed recursively, so the memory usage is linear in the length of the sequences.
+fn collect_matches<T: Eq>(
+    old: &[T],
+    new: &[T],
+    offset: (usize, usize),
+    diagonals: &mut (Diagonals, Diagonals),
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let prefix = common_prefix(old, new);
+    pairs.extend((0..prefix).map(|i| (offset.0 + i, offset.1 + i)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let offset = (offset.0 + prefix, offset.1 + prefix);
+    let suffix = common_suffix(old, new);
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+    if !old.is_empty() && !new.is_empty() {
+        let (x, y) = middle_snake(old, new, diagonals);
+        collect_matches(&old[..x], &new[..y], offset, diagonals, pairs);
+        collect_matches(&old[x..], &new[y..], (offset.0 + x, offset.1 + y), diagonals, pairs);
+    }
+    let suffix_start = (offset.0 + old.len(), offset.1 + new.len());
+    pairs.extend((0..suffix).map(|i| (suffix_start.0 + i, suffix_start.1 + i)));
+}
+
+/// Find the start of the middle snake of the shortest edit path between `old` and `new`, by
+/// searching from both ends at once until the searches meet. The sequences must be non-empty and
+/// differ at both ends, so the returned point splits the path into two shorter ones.
+fn middle_snake<T: Eq>(
+    old: &[T],
+    new: &[T],
+    (forward, backward): &mut (Diagonals, Diagonals),
+) -> (usize, usize) {
+    let n = old.len();
+    let m = new.len();
+    let delta = n as isize - m as isize;
+    let is_odd = delta % 2 != 0;
+    forward[1] = 0;
+    backward[1] = 0;
+    for d in 0..=max_d(n, m) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let go_down = k == -d || (k != d && forward[k - 1] < forward[k + 1]);
+            let mut x = if go_down { forward[k + 1] } else { forward[k - 1] + 1 };
+            let y = (x as isize - k) as usize;
+            let snake_start = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&old[x..], &new[y..]);
+            }
+            forward[k] = x;
+            if is_odd && (k - delta).abs() < d && x + backward[delta - k] >= n {
+                return snake_start;
+            }
+        }
+        // The backward search runs on the reversed sequences.
+        for k in (-d..=d).rev().step_by(2) {
+            let go_down = k == -d || (k != d && backward[k - 1] < backward[k + 1]);
+            let mut x = if go_down { backward[k + 1] } else { backward[k - 1] + 1 };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let snake = common_suffix(&old[..n - x], &new[..m - y]);
+                x += snake;
+                y += snake;
+            }
+            backward[k] = x;
+            if !is_odd && (k - delta).abs() <= d && x + forward[delta - k] >= n {
+                return (n - x, m - y);
+            }
+        }
+    }
+    unreachable!("The searches from both ends always meet.")
+}
+
+
+// === Diagonals ===
+
+/// The furthest reaching `x` coordinates on the diagonals `k = x - y` of the edit graph, indexed by
+/// `k`, which can be negative.
+#[derive(Clone, Debug)]
+struct Diagonals {
+    offset: isize,
+    values: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(max_d: usize) -> Self {
+        let offset = max_d as isize + 1;
+        Self { offset, values: vec![0; 2 * max_d + 3] }
+    }
+}
+
+impl Index<isize> for Diagonals {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.values[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for Diagonals {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.values[(k + self.offset) as usize]
+    }
+}
+
+// ============
+// === Hunk ===
+// ============
+
+/// A maximal block of lines differing between the old and the new text.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Hunk {
+    /// The replaced lines of the old text.
+    pub old_lines: Range<Line>,
+    /// The lines of the new text replacing the `old_lines`.
+    pub new_lines: Range<Line>,
+    /// The byte range of `old_lines` in the old text.
+    pub old_range: Range<Byte>,
+    /// The byte range of `new_lines` in the new text.
+    pub new_range: Range<Byte>,
+}
+
+impl Hunk {
+    /// Location of the hunk start in the old text.
+    pub fn old_location(&self) -> Location<Byte> {
+        Location(self.old_lines.start, Byte(0))
+    }
+
+    /// Location of the hunk start in the new text.
+    pub fn new_location(&self) -> Location<Byte> {
+        Location(self.new_lines.start, Byte(0))
+    }
+
+    /// The number of removed lines.
+    pub fn removed_lines(&self) -> usize {
+        self.old_lines.end.value - self.old_lines.start.value
+    }
+
+    /// The number of inserted lines.
+    pub fn inserted_lines(&self) -> usize {
+        self.new_lines.end.value - self.new_lines.start.value
+    }
+}
+
+/// Compute hunks from the line indices of sequences' matching elements.
+fn hunks_of_lines(old: &[&str], new: &[&str]) -> Vec<Hunk> {
+    let old_offsets = line_offsets(old);
+    let new_offsets = line_offsets(new);
+    let matches = common_subsequence(old, new);
+    let ends = iter::once((old.len(), new.len()));
+    let mut hunks = vec![];
+    let (mut old_line, mut new_line) = (0, 0);
+    for (old_match, new_match) in matches.into_iter().chain(ends) {
+        if old_match > old_line || new_match > new_line {
+            hunks.push(Hunk {
+                old_lines: Range::new(Line(old_line), Line(old_match)),
+                new_lines: Range::new(Line(new_line), Line(new_match)),
+                old_range: Range::new(Byte(old_offsets[old_line]), Byte(old_offsets[old_match])),
+                new_range: Range::new(Byte(new_offsets[new_line]), Byte(new_offsets[new_match])),
+            });
+        }
+        old_line = old_match + 1;
+        new_line = new_match + 1;
+    }
+    hunks
+}
+
+
+
+// ============
+// === Diff ===
+// ============
+
+/// The line-based difference between two texts.
+#[derive(Clone, Debug, Default)]
+pub struct Diff {
+    /// The differing blocks, in order of their positions.
+    pub hunks: Vec<Hunk>,
+    old:       Vec<String>,
+    new:       Vec<String>,
+}
+
+impl Diff {
+    /// Compute the difference between the texts.
+    ///
+    /// ```
+    /// # use enso_text::*;
+    /// # use enso_text::diff::Diff;
+    /// let old = Rope::from("a\nb\nc\n");
+    /// let new = Rope::from("a\nB\nc\nd\n");
+    /// let diff = Diff::new(&old, &new);
+    /// assert_eq!(diff.hunks.len(), 2);
+    /// assert_eq!(diff.hunks[0].old_range, Range::new(Byte(2), Byte(4)));
+    /// let mut patched = old.clone();
+    /// for change in diff.changes() {
+    ///     patched.apply_change(change);
+    /// }
+    /// assert_eq!(patched.to_string(), new.to_string());
+    /// ```
+    pub fn new(old: &Rope, new: &Rope) -> Self {
+        let old = rope_lines(old);
+        let new = rope_lines(new);
+        Self::from_lines(&as_strs(&old), &as_strs(&new))
+    }
+
+    fn from_lines(old: &[&str], new: &[&str]) -> Self {
+        let hunks = hunks_of_lines(old, new);
+        let old = old.iter().map(|line| line.to_string()).collect();
+        let new = new.iter().map(|line| line.to_string()).collect();
+        Self { hunks, old, new }
+    }
+
+    /// Check if the texts are equal.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    fn new_text(&self, hunk: &Hunk) -> String {
+        self.new[hunk.new_lines.start.value..hunk.new_lines.end.value].concat()
+    }
+
+    /// The changes transforming the old text into the new one. They are returned in reverse order
+    /// of their positions, so applying them one by one keeps the ranges of the following changes
+    /// valid.
+    pub fn changes(&self) -> Vec<Change<Byte, String>> {
+        let changes = self.hunks.iter().rev();
+        changes.map(|hunk| Change { range: hunk.old_range, text: self.new_text(hunk) }).collect()
+    }
+
+    /// Render the diff in the unified format, with the given number of context lines around each
+    /// hunk. Returns an empty string if the texts are equal.
+    ///
+    /// ```
+    /// # use enso_text::*;
+    /// # use enso_text::diff::Diff;
+    /// let diff = Diff::new(&Rope::from("a\nb\nc\n"), &Rope::from("a\nB\nc\n"));
+    /// assert_eq!(diff.unified(0), "@@ -2 +2 @@\n-b\n+B\n");
+    /// ```
+    pub fn unified(&self, context: usize) -> String {
+        let mut output = String::new();
+        let mut hunks = self.hunks.iter().peekable();
+        while let Some(first) = hunks.next() {
+            // Hunks closer than twice the context are rendered as one.
+            let mut group = vec![first];
+            while let Some(next) = hunks.peek() {
+                let last = group.last().expect("Group is never empty.");
+                if next.old_lines.start.value - last.old_lines.end.value > 2 * context {
+                    break;
+                }
+                group.extend(hunks.next());
+            }
+            self.render_group(&group, context, &mut output);
+        }
+        output
+    }
+
+    fn render_group(&self, group: &[&Hunk], context: usize, output: &mut String) {
+        let first = group[0];
+        let last = group[group.len() - 1];
+        let old_start = first.old_lines.start.value.saturating_sub(context);
+        let old_end = (last.old_lines.end.value + context).min(self.old.len());
+        let new_start = first.new_lines.start.value - (first.old_lines.start.value - old_start);
+        let new_end = last.new_lines.end.value + (old_end - last.old_lines.end.value);
+        let header_range = |start: usize, end: usize| match end - start {
+            1 => format!("{}", start + 1),
+            // An empty range is denoted by the line preceding it.
+            0 => format!("{start},0"),
+            len => format!("{},{len}", start + 1),
+        };
+        let old_header = header_range(old_start, old_end);
+        let new_header = header_range(new_start, new_end);
+        output.push_str(&format!("@@ -{old_header} +{new_header} @@\n"));
+        let mut push_line = |prefix: char, line: &str| {
+            output.push(prefix);
+            output.push_str(line);
+            if !line.ends_with('\n') {
+                output.push_str("\n\\ No newline at end of file\n");
+            }
+        };
+        let mut old_line = old_start;
+        for hunk in group {
+            for line in &self.old[old_line..hunk.old_lines.start.value] {
+                push_line(' ', line);
+            }
+            for line in &self.old[hunk.old_lines.start.value..hunk.old_lines.end.value] {
+                push_line('-', line);
+            }
+            for line in &self.new[hunk.new_lines.start.value..hunk.new_lines.end.value] {
+                push_line('+', line);
+            }
+            old_line = hunk.old_lines.end.value;
+        }
+        for line in &self.old[old_line..old_end] {
+            push_line(' ', line);
+        }
+    }
+}
+
+
+
+// =============
+// === Merge ===
+// =============
+
+/// The marker starting a conflict, followed by our version.
+pub const CONFLICT_START: &str = "<<<<<<< ours\n";
+/// The marker separating our and their versions of a conflict.
+pub const CONFLICT_SEPARATOR: &str = "=======\n";
+/// The marker ending a conflict, preceded by their version.
+pub const CONFLICT_END: &str = ">>>>>>> theirs\n";
+
+/// A region changed differently by both merged texts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The conflicting lines of the base text.
+    pub base_lines: Range<Line>,
+    /// The range of the conflict, including the markers, in the merged text.
+    pub range:      Range<Byte>,
+}
+
+/// The result of a three-way merge.
+#[derive(Clone, Debug, Default)]
+pub struct Merge {
+    /// The merged text. Conflicting regions contain both versions, surrounded with markers.
+    pub text:      Rope,
+    /// The conflicts, in order of their positions.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Check if the texts were merged without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Which of the merged texts a hunk comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Merge the changes done to the `base` text in `ours` and `theirs` texts.
+///
+/// The regions changed by only one side are taken from that side. The regions changed by both
+/// sides are taken if both changes are the same, otherwise they are reported as conflicts. The
+/// changes touching at the region boundaries do not conflict, unless one of them is an insertion.
+///
+/// ```
+/// # use enso_text::*;
+/// # use enso_text::diff::merge;
+/// let base = Rope::from("a\nb\nc\n");
+/// let ours = Rope::from("A\nb\nc\n");
+/// let theirs = Rope::from("a\nb\nC\n");
+/// let merged = merge(&base, &ours, &theirs);
+/// assert!(merged.is_clean());
+/// assert_eq!(merged.text.to_string(), "A\nb\nC\n");
+/// ```
+pub fn merge(base: &Rope, ours: &Rope, theirs: &Rope) -> Merge {
+    let base = rope_lines(base);
+    let ours = rope_lines(ours);
+    let theirs = rope_lines(theirs);
+    merge_lines(&as_strs(&base), &as_strs(&ours), &as_strs(&theirs))
+}
+
+fn merge_lines(base: &[&str], ours: &[&str], theirs: &[&str]) -> Merge {
+    let our_hunks = hunks_of_lines(base, ours).into_iter().map(|hunk| (Side::Ours, hunk));
+    let their_hunks = hunks_of_lines(base, theirs).into_iter().map(|hunk| (Side::Theirs, hunk));
+    let mut hunks = our_hunks.chain(their_hunks).collect_vec();
+    hunks.sort_by_key(|(_, hunk)| (hunk.old_lines.start.value, hunk.old_lines.end.value));
+
+    let mut text = String::new();
+    let mut conflicts = vec![];
+    let mut base_line = 0;
+    let mut hunks = hunks.into_iter().peekable();
+    while let Some(first) = hunks.next() {
+        let mut cluster = vec![first];
+        let mut end = first.1.old_lines.end.value;
+        let mut is_empty = first.1.old_lines.start.value == end;
+        while let Some((_, next)) = hunks.peek() {
+            let start = next.old_lines.start.value;
+            let next_is_empty = start == next.old_lines.end.value;
+            let overlaps = start < end || start == end && (is_empty || next_is_empty);
+            if !overlaps {
+                break;
+            }
+            end = end.max(next.old_lines.end.value);
+            is_empty = is_empty && next_is_empty;
+            cluster.extend(hunks.next());
+        }
+        let start = cluster[0].1.old_lines.start.value;
+        text.extend(base[base_line..start].iter().copied());
+        base_line = end;
+
+        let version = |side: Side, lines: &[&str]| -> Option<String> {
+            let mut side_hunks = cluster.iter().filter(|(s, _)| *s == side).map(|(_, h)| h);
+            let first = side_hunks.next()?;
+            let last = side_hunks.last().unwrap_or(first);
+            let side_start = first.new_lines.start.value - (first.old_lines.start.value - start);
+            let side_end = last.new_lines.end.value + (end - last.old_lines.end.value);
+            Some(lines[side_start..side_end].concat())
+        };
+        match (version(Side::Ours, ours), version(Side::Theirs, theirs)) {
+            (Some(ours), Some(theirs)) if ours != theirs => {
+                let conflict_start = text.len();
+                let push_version = |text: &mut String, version: &str| {
+                    text.push_str(version);
+                    if !version.is_empty() && !version.ends_with('\n') {
+                        text.push('\n');
+                    }
+                };
+                text.push_str(CONFLICT_START);
+                push_version(&mut text, &ours);
+                text.push_str(CONFLICT_SEPARATOR);
+                push_version(&mut text, &theirs);
+                text.push_str(CONFLICT_END);
+                let base_lines = Range::new(Line(start), Line(end));
+                let range = Range::new(Byte(conflict_start), Byte(text.len()));
+                conflicts.push(Conflict { base_lines, range });
+            }
+            (Some(version), _) | (None, Some(version)) => text.push_str(&version),
+            (None, None) => {}
+        }
+    }
+    text.extend(base[base_line..].iter().copied());
+    Merge { text: text.into(), conflicts }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<&str> {
+        text.split_inclusive('\n').collect()
+    }
+
+    fn line_ranges(old: &str, new: &str) -> Vec<((usize, usize), (usize, usize))> {
+        let hunks = hunks_of_lines(&lines(old), &lines(new));
+        let range = |range: Range<Line>| (range.start.value, range.end.value);
+        hunks.into_iter().map(|h| (range(h.old_lines), range(h.new_lines))).collect()
+    }
+
+    fn apply(old: &str, new: &str) -> String {
+        let diff = Diff::from_lines(&lines(old), &lines(new));
+        let mut text = old.to_string();
+        for change in diff.changes() {
+            change.apply(&mut text).unwrap();
+        }
+        text
+    }
+
+    #[test]
+    fn diffing_lines() {
+        assert_eq!(line_ranges("a\nb\nc\n", "a\nb\nc\n"), vec![]);
+        assert_eq!(line_ranges("a\nb\nc\n", "a\nc\n"), vec![((1, 2), (1, 1))]);
+        assert_eq!(line_ranges("a\nc\n", "a\nb\nc\n"), vec![((1, 1), (1, 2))]);
+        assert_eq!(line_ranges("a\nb\nc\n", "a\nx\nc\ny\n"), vec![
+            ((1, 2), (1, 2)),
+            ((3, 3), (3, 4))
+        ]);
+        assert_eq!(line_ranges("", "a\n"), vec![((0, 0), (0, 1))]);
+        assert_eq!(line_ranges("a\nb", "a\nb\n"), vec![((1, 2), (1, 2))]);
+        assert_eq!(line_ranges("a\nb\nc\nd\n", "d\nb\nc\na\n"), vec![
+            ((0, 1), (0, 1)),
+            ((3, 4), (3, 4))
+        ]);
+    }
+
+    #[test]
+    fn diffing_long_texts() {
+        let old = (0..10000).map(|i| format!("{i}\n")).collect::<String>();
+        let new = (0..10000).map(|i| format!("{}\n", if i % 1000 == 0 { i + 10000 } else { i }));
+        let new = new.collect::<String>();
+        let hunks = line_ranges(&old, &new);
+        assert_eq!(hunks.len(), 10);
+        assert!(hunks.iter().all(|&(old, new)| old.1 - old.0 == 1 && new.1 - new.0 == 1));
+        assert_eq!(apply(&old, &new), new);
+        let old = (0..1000).map(|i| format!("{i}\n")).collect::<String>();
+        let reversed = (0..1000).rev().map(|i| format!("{i}\n")).collect::<String>();
+        assert_eq!(apply(&old, &reversed), reversed);
+    }
+
+    #[test]
+    fn applying_diff_changes() {
+        let texts = [
+            "",
+            "a\n",
+            "a\nb\nc\nd\ne\n",
+            "e\nd\nc\nb\na\n",
+            "a\nx\nc\ny\ne",
+            "x\ny\nz\n",
+            "a\na\nb\nb\na\na\n",
+        ];
+        for old in texts {
+            for new in texts {
+                assert_eq!(apply(old, new), new, "Diff of {old:?} and {new:?}.");
+            }
+        }
+    }
+
+    #[test]
+    fn rendering_unified_diff() {
+        let old = lines("1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+        let new = lines("1\n2\nthree\n4\n5\n6\n7\n8\n9\nten");
+        let diff = Diff::from_lines(&old, &new);
+        let expected = "@@ -2,3 +2,3 @@\n 2\n-3\n+three\n 4\n@@ -9 +9,2 @@\n 9\n+ten\n\\ No \
+                        newline at end of file\n";
+        assert_eq!(diff.unified(1), expected);
+        let expected = "@@ -1,9 +1,10 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n 7\n 8\n 9\n+ten\n\\ No \
+                        newline at end of file\n";
+        assert_eq!(diff.unified(3), expected);
+        assert_eq!(Diff::from_lines(&old, &old).unified(3), "");
+    }
+
+    #[test]
+    fn merging_without_conflicts() {
+        let merge = |base: &str, ours: &str, theirs: &str| {
+            let merged = merge_lines(&lines(base), &lines(ours), &lines(theirs));
+            assert!(merged.is_clean(), "Merging {ours:?} and {theirs:?} conflicts.");
+            merged.text.to_string()
+        };
+        assert_eq!(merge("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n"), "A\nb\nC\n");
+        assert_eq!(merge("a\nb\nc\n", "a\nB\nc\n", "a\nB\nc\n"), "a\nB\nc\n");
+        assert_eq!(merge("a\nb\nc\n", "a\nb\nc\n", "x\n"), "x\n");
+        assert_eq!(merge("a\nb\n", "a\nx\nb\n", "a\nb\ny\n"), "a\nx\nb\ny\n");
+        assert_eq!(merge("a\nb\nc\n", "a\nc\n", "a\nb\nc\nd\n"), "a\nc\nd\n");
+    }
+
+    #[test]
+    fn merging_with_conflicts() {
+        let merged = merge_lines(&lines("a\nb\nc\n"), &lines("a\nx\nc\n"), &lines("a\ny"));
+        let expected = "a\n<<<<<<< ours\nx\nc\n=======\ny\n>>>>>>> theirs\n";
+        assert_eq!(merged.text.to_string(), expected);
+        let range = Range::new(Byte(2), Byte(expected.len()));
+        assert_eq!(merged.conflicts, vec![Conflict {
+            base_lines: Range::new(Line(1), Line(3)),
+            range
+        }]);
+
+        // Insertions at the same place conflict.
+        let merged = merge_lines(&lines("a\n"), &lines("a\nx\n"), &lines("a\ny\n"));
+        assert_eq!(merged.text.to_string(), "a\n<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n");
+    }
+}
@@ -7,6 +7,8 @@
 //!   chars, or in bytes? Or maybe in _grapheme clusters_)?
 //! * An alternative [`Range`] with text-related trait implementations + copyable.
 //! * Interval tree structure [`Spans`] useful for text rich decorations.
+//! * A [`Searcher`] finding and replacing literal or regex patterns in the text.
+//! * Line-based [`diff`] and three-way merge of texts.
 //!
 //! To properly understand the implementation and its assumptions, you have to know a lot about
 //! text encoding in different formats and text rendering. Especially, these links are very useful:
@@ -100,9 +102,11 @@
 // === Export ===
 // ==============
 
+pub mod diff;
 pub mod index;
 pub mod range;
 pub mod rope;
+pub mod search;
 pub mod spans;
 pub mod text;
 pub mod unit;
@@ -112,6 +116,7 @@ pub use range::Range;
 pub use range::RangeBounds;
 pub use rope::metric;
 pub use rope::Cursor;
+pub use search::Searcher;
 pub use spans::Spans;
 pub use text::Change;
 pub use text::FromInContextSnapped;
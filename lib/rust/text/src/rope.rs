@@ -25,6 +25,7 @@ pub use xi_rope;
 pub use xi_rope::interval::Interval;
 pub use xi_rope::interval::IntervalBounds;
 pub use xi_rope::rope::Lines;
+pub use xi_rope::rope::LinesRaw;
 pub use xi_rope::Cursor;
 pub use xi_rope::DeltaBuilder;
 pub use xi_rope::Rope as XiRope;
@@ -0,0 +1,376 @@
+//! Searching for literal or regular-expression patterns in a [`Rope`], and replacing the matches.
+//!
+//! The rope is never flattened: it is scanned in windows of consecutive lines, so a match cannot
+//! span more lines than the pattern contains line breaks. In particular, a pattern without line
+//! breaks matches within single lines only. Empty matches are never reported.
+
+use crate::index::*;
+use crate::prelude::*;
+
+use crate::range::Range;
+use crate::rope;
+use crate::text::Change;
+use crate::text::Rope;
+
+use regex::Captures;
+use regex::Regex;
+use regex::RegexBuilder;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+
+
+// ===============
+// === Options ===
+// ===============
+
+/// The error of parsing the regular expression pattern.
+pub type Error = regex::Error;
+
+/// Search parameters.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// Whether the letter case must match.
+    pub case_sensitive: bool,
+    /// Whether matches must not be a part of a longer word, i.e. they must not be preceded nor
+    /// followed by an alphanumeric character or underscore.
+    pub whole_word:     bool,
+    /// Whether the pattern is a regular expression rather than a literal text.
+    pub regex:          bool,
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Remove the line terminator from the end of the line.
+fn trim_line_end(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+
+
+// ================
+// === Searcher ===
+// ================
+
+/// A compiled search pattern.
+///
+/// ```
+/// # use enso_text::*;
+/// # use enso_text::search::*;
+/// let rope = Rope::from("let foo = food\nfoo + Foo");
+/// let options = Options { whole_word: true, ..Options::default() };
+/// let searcher = Searcher::new("foo", options).unwrap();
+/// let matches = searcher.find_iter(&rope).map(|range| rope.sub(range).to_string());
+/// assert_eq!(matches.collect::<Vec<_>>(), vec!["foo", "foo", "Foo"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Searcher {
+    regex:        Regex,
+    options:      Options,
+    window_lines: usize,
+}
+
+impl Searcher {
+    /// Constructor. Fails if the pattern is not a valid regular expression.
+    pub fn new(pattern: &str, options: Options) -> Result<Self, Error> {
+        let line_breaks = pattern.matches('\n').count();
+        let escaped_line_breaks = if options.regex { pattern.matches("\\n").count() } else { 0 };
+        let window_lines = 1 + line_breaks + escaped_line_breaks;
+        let source = if options.regex { Cow::from(pattern) } else { regex::escape(pattern).into() };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()?;
+        Ok(Self { regex, options, window_lines })
+    }
+
+    /// Search for the literal text.
+    pub fn literal(text: &str, options: Options) -> Self {
+        let options = Options { regex: false, ..options };
+        Self::new(text, options).expect("Escaped literal is always a valid pattern.")
+    }
+
+    /// The options this searcher was created with.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Iterate over all matches in the rope.
+    pub fn find_iter<'a>(&'a self, rope: &'a Rope) -> Matches<'a> {
+        self.find_iter_from(rope, Byte(0))
+    }
+
+    /// Iterate over the matches in the rope starting at or after the given offset. The matches do
+    /// not overlap: a match starting before the end of the previous one is skipped.
+    pub fn find_iter_from<'a>(&'a self, rope: &'a Rope, from: Byte) -> Matches<'a> {
+        Matches { scan: Scan::new(self, rope, from, Box::new(|_: &Captures| ())) }
+    }
+
+    /// The first match starting at or after the given offset.
+    pub fn find_next(&self, rope: &Rope, from: Byte) -> Option<Range<Byte>> {
+        self.find_iter_from(rope, from).next()
+    }
+
+    /// The last match starting before the given offset.
+    pub fn find_prev(&self, rope: &Rope, before: Byte) -> Option<Range<Byte>> {
+        self.find_iter(rope).take_while(|range| range.start < before).last()
+    }
+
+    /// Replace all the matches with the `replacement`. In the regex mode, the `$name` and `$index`
+    /// references in the `replacement` are expanded to the matched capture groups.
+    ///
+    /// Returns the changes in reverse order of their positions, so applying them one by one keeps
+    /// the ranges of the following changes valid.
+    ///
+    /// ```
+    /// # use enso_text::*;
+    /// # use enso_text::search::*;
+    /// let mut rope = Rope::from("foo(1, 2)\nfoo(3, 4)");
+    /// let options = Options { regex: true, ..Options::default() };
+    /// let searcher = Searcher::new(r"foo\((\d), (\d)\)", options).unwrap();
+    /// for change in searcher.replace_all(&rope, "bar($2, $1)") {
+    ///     rope.apply_change(change);
+    /// }
+    /// assert_eq!(rope.to_string(), "bar(2, 1)\nbar(4, 3)");
+    /// ```
+    pub fn replace_all(&self, rope: &Rope, replacement: &str) -> Vec<Change<Byte, String>> {
+        let is_regex = self.options.regex;
+        let expand = move |captures: &Captures| {
+            if is_regex {
+                let mut text = String::new();
+                captures.expand(replacement, &mut text);
+                text
+            } else {
+                replacement.to_owned()
+            }
+        };
+        let scan = Scan::new(self, rope, Byte(0), Box::new(expand));
+        let mut changes = scan.map(|(range, text)| Change { range, text }).collect_vec();
+        changes.reverse();
+        changes
+    }
+}
+
+
+
+// ============
+// === Scan ===
+// ============
+
+/// The iterator over matches, mapping each found match's captures with the `map` function.
+///
+/// The lines are stored in a window of at most `window_lines` lines. All matches starting in the
+/// first line of the window are found at once, then the window is moved by one line.
+struct Scan<'a, T> {
+    searcher:      &'a Searcher,
+    lines:         rope::LinesRaw<'a>,
+    window:        VecDeque<Cow<'a, str>>,
+    window_offset: usize,
+    from:          usize,
+    found:         VecDeque<(Range<Byte>, T)>,
+    map:           Box<dyn FnMut(&Captures) -> T + 'a>,
+}
+
+impl<'a, T> Scan<'a, T> {
+    fn new(
+        searcher: &'a Searcher,
+        rope: &'a Rope,
+        from: Byte,
+        map: Box<dyn FnMut(&Captures) -> T + 'a>,
+    ) -> Self {
+        let window_offset = rope.line_offset_snapped(rope.line_snapped(from)).value;
+        let lines = rope.lines_raw(window_offset..);
+        let window = default();
+        let found = default();
+        Self { searcher, lines, window, window_offset, from: from.value, found, map }
+    }
+
+    /// Move the window by one line. Returns `false` if there are no more lines to search.
+    fn advance_window(&mut self) -> bool {
+        if let Some(first_line) = self.window.pop_front() {
+            self.window_offset += first_line.len();
+        }
+        while self.window.len() < self.searcher.window_lines {
+            match self.lines.next() {
+                Some(line) => self.window.push_back(line),
+                None => break,
+            }
+        }
+        !self.window.is_empty()
+    }
+
+    fn search_window(&mut self) {
+        let first_line_len = self.window.front().map_or(0, |line| line.len());
+        let text = match self.window.len() {
+            1 => Cow::from(trim_line_end(&self.window[0])),
+            _ => {
+                let mut text = self.window.iter().map(|line| line.as_ref()).collect::<String>();
+                text.truncate(trim_line_end(&text).len());
+                Cow::from(text)
+            }
+        };
+        for captures in self.searcher.regex.captures_iter(&text) {
+            let found = captures.get(0).expect("The whole match is always captured.");
+            if found.start() >= first_line_len {
+                break;
+            }
+            let start = self.window_offset + found.start();
+            let is_word_start =
+                || !text[..found.start()].chars().next_back().map_or(false, is_word_char);
+            let is_word_end = || !text[found.end()..].chars().next().map_or(false, is_word_char);
+            let is_whole_word = || is_word_start() && is_word_end();
+            let accepted = !found.range().is_empty()
+                && start >= self.from
+                && (!self.searcher.options.whole_word || is_whole_word());
+            if accepted {
+                let end = self.window_offset + found.end();
+                // A multi-line match may overlap the matches found in the next windows.
+                self.from = end;
+                self.found.push_back((Range::new(Byte(start), Byte(end)), (self.map)(&captures)));
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for Scan<'a, T> {
+    type Item = (Range<Byte>, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(found) = self.found.pop_front() {
+                return Some(found);
+            }
+            if !self.advance_window() {
+                return None;
+            }
+            self.search_window();
+        }
+    }
+}
+
+impl<'a, T> Debug for Scan<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scan")
+            .field("searcher", &self.searcher)
+            .field("window_offset", &self.window_offset)
+            .field("from", &self.from)
+            .finish()
+    }
+}
+
+
+
+// ===============
+// === Matches ===
+// ===============
+
+/// An iterator over ranges of the matches, in order of their positions. See
+/// [`Searcher::find_iter`].
+#[derive(Debug)]
+pub struct Matches<'a> {
+    scan: Scan<'a, ()>,
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Range<Byte>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scan.next().map(|(range, ())| range)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(text: &str, pattern: &str, options: Options) -> Vec<(usize, usize)> {
+        let rope = Rope::from(text);
+        let searcher = Searcher::new(pattern, options).unwrap();
+        searcher.find_iter(&rope).map(|range| (range.start.value, range.end.value)).collect()
+    }
+
+    #[test]
+    fn finding_literals() {
+        let text = "Foo foo(food)\nfoo_bar";
+        assert_eq!(find(text, "foo", default()), vec![(0, 3), (4, 7), (8, 11), (14, 17)]);
+        let case_sensitive = Options { case_sensitive: true, ..default() };
+        assert_eq!(find(text, "foo", case_sensitive), vec![(4, 7), (8, 11), (14, 17)]);
+        let whole_word = Options { whole_word: true, ..default() };
+        assert_eq!(find(text, "foo", whole_word), vec![(0, 3), (4, 7)]);
+        assert_eq!(find(text, "(", default()), vec![(7, 8)]);
+        assert_eq!(find(text, "", default()), vec![]);
+    }
+
+    #[test]
+    fn finding_regex() {
+        let regex = Options { regex: true, ..default() };
+        let text = "a1 b22\nc333";
+        assert_eq!(find(text, r"\d+", regex), vec![(1, 2), (4, 6), (8, 11)]);
+        assert_eq!(find(text, r"^\w", regex), vec![(0, 1), (7, 8)]);
+        assert_eq!(find(text, r"\d$", regex), vec![(5, 6), (10, 11)]);
+        // Matches do not span lines unless the pattern contains a line break.
+        assert_eq!(find(text, r"\d\s+\w", regex), vec![(1, 4)]);
+        assert_eq!(find(text, r"2\nc", regex), vec![(5, 8)]);
+        assert!(Searcher::new("(", regex).is_err());
+    }
+
+    #[test]
+    fn finding_multiline_literals() {
+        let text = "a\nb\r\na\nb\na";
+        assert_eq!(find(text, "a\nb", default()), vec![(0, 3), (5, 8)]);
+        assert_eq!(find(text, "\na", default()), vec![(4, 6), (8, 10)]);
+        // The matches never overlap.
+        assert_eq!(find("a\na\na", "a\na", default()), vec![(0, 3)]);
+        assert_eq!(find("a\na\na\na", "a\na", default()), vec![(0, 3), (4, 7)]);
+        let rope = Rope::from("a\na\na");
+        let changes = Searcher::literal("a\na", default()).replace_all(&rope, "b");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn finding_across_chunks() {
+        let text = "word ".repeat(2000) + "last";
+        let rope = Rope::from(&text);
+        let searcher = Searcher::literal("word", default());
+        let matches = searcher.find_iter(&rope).collect_vec();
+        assert_eq!(matches.len(), 2000);
+        assert!(matches.iter().enumerate().all(|(i, range)| range.start == Byte(i * 5)));
+        let searcher = Searcher::literal("d\nl", default());
+        assert_eq!(searcher.find_iter(&Rope::from(text.replace(" l", "\nl"))).count(), 1);
+    }
+
+    #[test]
+    fn finding_next_and_previous() {
+        let rope = Rope::from("ab\nab ab");
+        let searcher = Searcher::literal("ab", default());
+        let found = |range: Option<Range<Byte>>| range.map(|r| r.start.value);
+        assert_eq!(found(searcher.find_next(&rope, Byte(0))), Some(0));
+        assert_eq!(found(searcher.find_next(&rope, Byte(1))), Some(3));
+        assert_eq!(found(searcher.find_next(&rope, Byte(4))), Some(6));
+        assert_eq!(found(searcher.find_next(&rope, Byte(7))), None);
+        assert_eq!(found(searcher.find_prev(&rope, Byte(6))), Some(3));
+        assert_eq!(found(searcher.find_prev(&rope, Byte(0))), None);
+    }
+
+    #[test]
+    fn replacing() {
+        let mut rope = Rope::from("one two\none");
+        let searcher = Searcher::literal("one", default());
+        let changes = searcher.replace_all(&rope, "$1");
+        let ranges = changes.iter().map(|c| (c.range.start.value, c.range.end.value));
+        assert_eq!(ranges.collect_vec(), vec![(8, 11), (0, 3)]);
+        for change in changes {
+            rope.apply_change(change);
+        }
+        assert_eq!(rope.to_string(), "$1 two\n$1");
+    }
+}
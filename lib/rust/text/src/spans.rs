@@ -43,6 +43,12 @@ impl<T: Clone + Debug> Spans<T> {
         self.raw.edit(range.into_rope_interval(), builder.build())
     }
 
+    /// Replace the provided `range` with the given `spans`. The spans following the range are
+    /// shifted accordingly to the length of the inserted spans.
+    pub fn replace(&mut self, range: Range<Byte>, spans: Spans<T>) {
+        self.raw.edit(range.into_rope_interval(), spans.raw)
+    }
+
     /// Modify the parameter value in the given range.
     pub fn modify(&mut self, range: Range<Byte>, f: impl Fn(T) -> T) {
         let subseq = self.raw.subseq(range.into_rope_interval());